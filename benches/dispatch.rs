@@ -0,0 +1,31 @@
+// Instructions per second of the interpreter loop on programs without I/O in their hot loops, to
+// compare dispatch strategies and the way instructions are fetched : cargo bench --bench dispatch
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use sac::interpreter::{Config, Engine, Interpreter};
+
+const PROGRAMS: [(&str, &str); 3] = [
+    ("nested loops", "-[>-[>-[>+<-]<-]<-]"),
+    ("arithmetic", "-[>-[>-[>+>++>[-]+++<<<-]<-]<-]"),
+    // Short loop bodies, so that fetching the instructions weighs more.
+    ("tight loops", "-[>-[>-[>-[-]<-]<-]<-]"),
+];
+
+fn main() {
+    for engine in Engine::ALL.into_iter().filter(|engine| engine.is_available()) {
+        for (name, code) in PROGRAMS {
+            let mut interpreter = Interpreter::with_config(Config { engine, ..Config::default() });
+            interpreter.set_output(std::io::sink());
+            interpreter.load_program_from_str(code).unwrap();
+
+            let start = Instant::now();
+            interpreter.interpret().unwrap();
+            let elapsed = start.elapsed();
+            let steps = black_box(interpreter.steps());
+
+            println!("{:>8} {name:>12} : {steps:>12} steps in {elapsed:>10.2?}, {:>8.1} M steps/s", engine.name(), steps as f64 / elapsed.as_secs_f64() / 1e6);
+        }
+    }
+}
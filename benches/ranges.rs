@@ -0,0 +1,31 @@
+// Time taken by a synthetic program moving a block of 64 cells back and forth 100 times, cell by
+// cell at O0 and with range operations at O1 : cargo bench --bench ranges
+
+use std::time::Instant;
+
+use sac::interpreter::{Config, Interpreter};
+use sac::optimizer::OptLevel;
+
+fn block_copy() -> String {
+    let cells = 64;
+    let fill = vec!["[-]+++"; cells].join(">");
+    let right = vec!["[->+<]"; cells].join("<");
+    let left = vec!["[-<+>]"; cells].join(">");
+    // Cell 1 counts the rounds, the block is set on cells 3 to 66, moved right by one cell, then back.
+    format!("++++++++++[>++++++++++<-]>[>>{fill}{right}>{left}{}-]", "<".repeat(cells + 2))
+}
+
+fn main() {
+    let code = block_copy();
+
+    for opt_level in OptLevel::ALL {
+        let mut interpreter = Interpreter::with_config(Config { opt_level, ..Config::default() });
+        interpreter.load_program_from_str(&code).unwrap();
+
+        let start = Instant::now();
+        interpreter.interpret().unwrap();
+        let elapsed = start.elapsed();
+
+        println!("{opt_level:?} : {:>10} steps in {elapsed:>10.2?}", interpreter.steps());
+    }
+}
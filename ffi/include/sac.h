@@ -0,0 +1,140 @@
+#ifndef SAC_H
+#define SAC_H
+
+/* Generated with cbindgen, do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+#define SAC_OK 0
+
+#define SAC_ERR_NULL_POINTER 1
+
+#define SAC_ERR_INVALID_UTF8 2
+
+#define SAC_ERR_IO 3
+
+#define SAC_ERR_UNMATCHED_BRACKET 4
+
+#define SAC_ERR_POINTER_UNDERFLOW 5
+
+#define SAC_ERR_POINTER_OVERFLOW 6
+
+#define SAC_ERR_OUT_OF_RANGE 7
+
+#define SAC_ERR_PANIC 8
+
+#define SAC_ERR_END_OF_INPUT 9
+
+#define SAC_ERR_CANCELLED 10
+
+#define SAC_ERR_LOOP_CAP_EXCEEDED 11
+
+#define SAC_ERR_STEP_LIMIT_EXCEEDED 12
+
+#define SAC_ERR_OUTPUT_LIMIT_EXCEEDED 13
+
+#define SAC_ERR_TIME_LIMIT_EXCEEDED 14
+
+#define SAC_ERR_OUT_OF_GAS 15
+
+#define SAC_ERR_IO_FORBIDDEN 16
+
+#define SAC_ERR_INPUT_TIMEOUT 17
+
+#define SAC_ERR_NO_PROGRESS 18
+
+#define SAC_ERR_EDIT_AT_INSTRUCTION_POINTER 19
+
+#define SAC_ERR_INVALID_UTF8_INPUT 20
+
+#define SAC_ERR_INVALID_UTF8_OUTPUT 21
+
+#define SAC_ERR_CELL_UNDERFLOW 22
+
+#define SAC_ERR_CELL_OVERFLOW 23
+
+#define SAC_ERR_PROGRAM_TOO_LARGE 24
+
+typedef struct SacInterpreter SacInterpreter;
+
+typedef struct SacConfig {
+  size_t tape_size;
+} SacConfig;
+
+typedef struct SacError {
+  int32_t code;
+} SacError;
+
+/**
+ * Creates an interpreter. `config` may be null to use the defaults.
+ *
+ * # Safety
+ * `config` must be null or point to a valid `SacConfig`.
+ */
+struct SacInterpreter *sac_interpreter_new(const struct SacConfig *config);
+
+/**
+ * Loads `len` bytes of UTF-8 source code, replacing any previous program.
+ *
+ * # Safety
+ * `handle` must come from `sac_interpreter_new` and `source` must point to `len` readable bytes.
+ */
+int32_t sac_load_str(struct SacInterpreter *handle,
+                     const char *source,
+                     size_t len,
+                     struct SacError *error);
+
+/**
+ * Runs the loaded program until it halts or fails.
+ *
+ * # Safety
+ * `handle` must come from `sac_interpreter_new`.
+ */
+int32_t sac_run(struct SacInterpreter *handle, struct SacError *error);
+
+/**
+ * Replaces the bytes fed to `,` instructions with a copy of `data`.
+ *
+ * # Safety
+ * `handle` must come from `sac_interpreter_new` and `data` must point to `len` readable bytes.
+ */
+int32_t sac_set_input_buffer(struct SacInterpreter *handle, const uint8_t *data, size_t len);
+
+/**
+ * Moves up to `len` bytes of pending program output into `buf` and returns how many were written.
+ *
+ * # Safety
+ * `handle` must come from `sac_interpreter_new` and `buf` must point to `len` writable bytes.
+ */
+size_t sac_read_output(struct SacInterpreter *handle, uint8_t *buf, size_t len);
+
+/**
+ * Stores the value of the cell at `index` into `value`.
+ *
+ * # Safety
+ * `handle` must come from `sac_interpreter_new` and `value` must be writable.
+ */
+int32_t sac_get_cell(const struct SacInterpreter *handle, size_t index, uint8_t *value);
+
+/**
+ * Copies the message of the last error raised on this thread into `buf` (NUL-terminated, truncated
+ * to `len`) and returns the full message length.
+ *
+ * # Safety
+ * `buf` must be null or point to `len` writable bytes.
+ */
+size_t sac_error_message(char *buf, size_t len);
+
+/**
+ * Destroys an interpreter. Passing null is a no-op.
+ *
+ * # Safety
+ * `handle` must come from `sac_interpreter_new` and must not be used afterwards.
+ */
+void sac_free(struct SacInterpreter *handle);
+
+#endif  /* SAC_H */
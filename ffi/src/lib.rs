@@ -0,0 +1,257 @@
+use std::cell::RefCell;
+use std::ffi::c_char;
+use std::io::{self, Cursor};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::slice;
+use std::str;
+
+use sac::buffer::OutputBuffer;
+use sac::error::{LoadError, RuntimeError};
+use sac::interpreter::{Config, Interpreter};
+
+pub const SAC_OK: i32 = 0;
+pub const SAC_ERR_NULL_POINTER: i32 = 1;
+pub const SAC_ERR_INVALID_UTF8: i32 = 2;
+pub const SAC_ERR_IO: i32 = 3;
+pub const SAC_ERR_UNMATCHED_BRACKET: i32 = 4;
+pub const SAC_ERR_POINTER_UNDERFLOW: i32 = 5;
+pub const SAC_ERR_POINTER_OVERFLOW: i32 = 6;
+pub const SAC_ERR_OUT_OF_RANGE: i32 = 7;
+pub const SAC_ERR_PANIC: i32 = 8;
+pub const SAC_ERR_END_OF_INPUT: i32 = 9;
+pub const SAC_ERR_CANCELLED: i32 = 10;
+pub const SAC_ERR_LOOP_CAP_EXCEEDED: i32 = 11;
+pub const SAC_ERR_STEP_LIMIT_EXCEEDED: i32 = 12;
+pub const SAC_ERR_OUTPUT_LIMIT_EXCEEDED: i32 = 13;
+pub const SAC_ERR_TIME_LIMIT_EXCEEDED: i32 = 14;
+pub const SAC_ERR_OUT_OF_GAS: i32 = 15;
+pub const SAC_ERR_IO_FORBIDDEN: i32 = 16;
+pub const SAC_ERR_INPUT_TIMEOUT: i32 = 17;
+pub const SAC_ERR_NO_PROGRESS: i32 = 18;
+pub const SAC_ERR_EDIT_AT_INSTRUCTION_POINTER: i32 = 19;
+pub const SAC_ERR_INVALID_UTF8_INPUT: i32 = 20;
+pub const SAC_ERR_INVALID_UTF8_OUTPUT: i32 = 21;
+pub const SAC_ERR_CELL_UNDERFLOW: i32 = 22;
+pub const SAC_ERR_CELL_OVERFLOW: i32 = 23;
+pub const SAC_ERR_PROGRAM_TOO_LARGE: i32 = 24;
+
+#[repr(C)]
+pub struct SacConfig {
+    pub tape_size: usize,
+}
+
+#[repr(C)]
+pub struct SacError {
+    pub code: i32,
+}
+
+pub struct SacInterpreter {
+    interpreter: Interpreter,
+    output: OutputBuffer,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<String> = const { RefCell::new(String::new()) };
+}
+
+type FfiResult<T> = Result<T, (i32, String)>;
+
+fn load_error(e: LoadError) -> (i32, String) {
+    let code = match e {
+        LoadError::Io(_) => SAC_ERR_IO,
+        LoadError::InvalidUtf8 { .. } => SAC_ERR_INVALID_UTF8,
+        LoadError::UnmatchedBracket { .. } => SAC_ERR_UNMATCHED_BRACKET,
+        LoadError::IoForbidden { .. } => SAC_ERR_IO_FORBIDDEN,
+        LoadError::EditAtInstructionPointer { .. } => SAC_ERR_EDIT_AT_INSTRUCTION_POINTER,
+        LoadError::ProgramTooLarge { .. } => SAC_ERR_PROGRAM_TOO_LARGE,
+    };
+    (code, e.to_string())
+}
+
+fn runtime_error(e: RuntimeError) -> (i32, String) {
+    let code = match e {
+        RuntimeError::Io(_) => SAC_ERR_IO,
+        RuntimeError::EndOfInput { .. } => SAC_ERR_END_OF_INPUT,
+        RuntimeError::InputTimeout { .. } => SAC_ERR_INPUT_TIMEOUT,
+        RuntimeError::Cancelled { .. } => SAC_ERR_CANCELLED,
+        RuntimeError::LoopCapExceeded { .. } => SAC_ERR_LOOP_CAP_EXCEEDED,
+        RuntimeError::StepLimitExceeded { .. } => SAC_ERR_STEP_LIMIT_EXCEEDED,
+        RuntimeError::OutputLimitExceeded { .. } => SAC_ERR_OUTPUT_LIMIT_EXCEEDED,
+        RuntimeError::TimeLimitExceeded { .. } => SAC_ERR_TIME_LIMIT_EXCEEDED,
+        RuntimeError::OutOfGas { .. } => SAC_ERR_OUT_OF_GAS,
+        RuntimeError::PointerUnderflow { .. } => SAC_ERR_POINTER_UNDERFLOW,
+        RuntimeError::PointerOverflow { .. } => SAC_ERR_POINTER_OVERFLOW,
+        RuntimeError::NoProgress { .. } => SAC_ERR_NO_PROGRESS,
+        RuntimeError::InvalidUtf8Input { .. } => SAC_ERR_INVALID_UTF8_INPUT,
+        RuntimeError::InvalidUtf8Output { .. } => SAC_ERR_INVALID_UTF8_OUTPUT,
+        RuntimeError::CellUnderflow { .. } => SAC_ERR_CELL_UNDERFLOW,
+        RuntimeError::CellOverflow { .. } => SAC_ERR_CELL_OVERFLOW,
+    };
+    (code, e.to_string())
+}
+
+fn null_pointer(name: &str) -> (i32, String) {
+    (SAC_ERR_NULL_POINTER, format!("Argument `{name}` is null"))
+}
+
+// Every entry point goes through here so that a panic never unwinds into the caller.
+fn call<T, F: FnOnce() -> FfiResult<T>>(error: *mut SacError, f: F) -> Result<T, i32> {
+    let result = panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|payload| {
+        let message = if let Some(s) = payload.downcast_ref::<&str>() {
+            s.to_string()
+        } else if let Some(s) = payload.downcast_ref::<String>() {
+            s.clone()
+        } else {
+            String::from("Unknown panic")
+        };
+        Err((SAC_ERR_PANIC, format!("Interpreter panicked : {message}")))
+    });
+
+    let result = result.map_err(|(code, message)| {
+        LAST_ERROR.with(|last| *last.borrow_mut() = message);
+        code
+    });
+
+    if !error.is_null() {
+        unsafe { (*error).code = *result.as_ref().err().unwrap_or(&SAC_OK); }
+    }
+
+    result
+}
+
+fn status<F: FnOnce() -> FfiResult<()>>(error: *mut SacError, f: F) -> i32 {
+    call(error, f).err().unwrap_or(SAC_OK)
+}
+
+/// Creates an interpreter. `config` may be null to use the defaults.
+///
+/// # Safety
+/// `config` must be null or point to a valid `SacConfig`.
+#[no_mangle]
+pub unsafe extern "C" fn sac_interpreter_new(config: *const SacConfig) -> *mut SacInterpreter {
+    call(ptr::null_mut(), || {
+        let mut rust_config = Config::default();
+        if !config.is_null() {
+            rust_config.tape_size = (*config).tape_size;
+        }
+
+        let output = OutputBuffer::new();
+        let mut interpreter = Interpreter::with_config(rust_config);
+        interpreter.set_input(io::empty());
+        interpreter.set_output(output.clone());
+
+        Ok(Box::into_raw(Box::new(SacInterpreter { interpreter, output })))
+    }).unwrap_or(ptr::null_mut())
+}
+
+/// Loads `len` bytes of UTF-8 source code, replacing any previous program.
+///
+/// # Safety
+/// `handle` must come from `sac_interpreter_new` and `source` must point to `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn sac_load_str(handle: *mut SacInterpreter, source: *const c_char, len: usize, error: *mut SacError) -> i32 {
+    status(error, || {
+        let handle = handle.as_mut().ok_or_else(|| null_pointer("handle"))?;
+        if source.is_null() {
+            return Err(null_pointer("source"));
+        }
+
+        let bytes = slice::from_raw_parts(source as *const u8, len);
+        let code = str::from_utf8(bytes).map_err(|e| (SAC_ERR_INVALID_UTF8, format!("Program is not valid UTF-8 : {e}")))?;
+
+        handle.interpreter.load_program_from_str(code).map_err(load_error)
+    })
+}
+
+/// Runs the loaded program until it halts or fails.
+///
+/// # Safety
+/// `handle` must come from `sac_interpreter_new`.
+#[no_mangle]
+pub unsafe extern "C" fn sac_run(handle: *mut SacInterpreter, error: *mut SacError) -> i32 {
+    status(error, || {
+        let handle = handle.as_mut().ok_or_else(|| null_pointer("handle"))?;
+        handle.interpreter.interpret().map_err(runtime_error)
+    })
+}
+
+/// Replaces the bytes fed to `,` instructions with a copy of `data`.
+///
+/// # Safety
+/// `handle` must come from `sac_interpreter_new` and `data` must point to `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn sac_set_input_buffer(handle: *mut SacInterpreter, data: *const u8, len: usize) -> i32 {
+    status(ptr::null_mut(), || {
+        let handle = handle.as_mut().ok_or_else(|| null_pointer("handle"))?;
+        if data.is_null() && len > 0 {
+            return Err(null_pointer("data"));
+        }
+
+        let data = if len == 0 { Vec::new() } else { slice::from_raw_parts(data, len).to_vec() };
+        handle.interpreter.set_input(Cursor::new(data));
+        Ok(())
+    })
+}
+
+/// Moves up to `len` bytes of pending program output into `buf` and returns how many were written.
+///
+/// # Safety
+/// `handle` must come from `sac_interpreter_new` and `buf` must point to `len` writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn sac_read_output(handle: *mut SacInterpreter, buf: *mut u8, len: usize) -> usize {
+    call(ptr::null_mut(), || {
+        let handle = handle.as_mut().ok_or_else(|| null_pointer("handle"))?;
+        if buf.is_null() {
+            return Err(null_pointer("buf"));
+        }
+
+        Ok(handle.output.read_into(slice::from_raw_parts_mut(buf, len)))
+    }).unwrap_or(0)
+}
+
+/// Stores the value of the cell at `index` into `value`.
+///
+/// # Safety
+/// `handle` must come from `sac_interpreter_new` and `value` must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn sac_get_cell(handle: *const SacInterpreter, index: usize, value: *mut u8) -> i32 {
+    status(ptr::null_mut(), || {
+        let handle = handle.as_ref().ok_or_else(|| null_pointer("handle"))?;
+        if value.is_null() {
+            return Err(null_pointer("value"));
+        }
+
+        *value = handle.interpreter.peek(index).ok_or_else(|| (SAC_ERR_OUT_OF_RANGE, format!("Cell {index} is outside of the tape")))?;
+        Ok(())
+    })
+}
+
+/// Copies the message of the last error raised on this thread into `buf` (NUL-terminated, truncated
+/// to `len`) and returns the full message length.
+///
+/// # Safety
+/// `buf` must be null or point to `len` writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn sac_error_message(buf: *mut c_char, len: usize) -> usize {
+    panic::catch_unwind(AssertUnwindSafe(|| LAST_ERROR.with(|last| {
+        let message = last.borrow();
+        if !buf.is_null() && len > 0 {
+            let count = message.len().min(len - 1);
+            ptr::copy_nonoverlapping(message.as_ptr(), buf as *mut u8, count);
+            *buf.add(count) = 0;
+        }
+        message.len()
+    }))).unwrap_or(0)
+}
+
+/// Destroys an interpreter. Passing null is a no-op.
+///
+/// # Safety
+/// `handle` must come from `sac_interpreter_new` and must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn sac_free(handle: *mut SacInterpreter) {
+    if !handle.is_null() {
+        let _ = panic::catch_unwind(AssertUnwindSafe(|| drop(Box::from_raw(handle))));
+    }
+}
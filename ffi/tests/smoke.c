@@ -0,0 +1,54 @@
+#include <stdio.h>
+#include <string.h>
+
+#include "sac.h"
+
+static int fail(const char *what) {
+    char message[256];
+    sac_error_message(message, sizeof(message));
+    fprintf(stderr, "%s : %s\n", what, message);
+    return 1;
+}
+
+int main(void) {
+    const char *hello = "++++++++[>++++[>++>+++>+++>+<<<<-]>+>+>->>+[<]<-]>>.>---.+++++++..+++.>>.<-.<.+++.------.--------.>>+.>++.";
+    const char *echo = ",[.,]";
+    SacConfig config = { 1000 };
+    SacError error = { SAC_OK };
+    char output[64] = { 0 };
+    uint8_t cell = 0;
+
+    SacInterpreter *interpreter = sac_interpreter_new(&config);
+    if (interpreter == NULL) return fail("sac_interpreter_new");
+
+    if (sac_load_str(interpreter, hello, strlen(hello), &error) != SAC_OK) return fail("sac_load_str");
+    if (sac_run(interpreter, &error) != SAC_OK) return fail("sac_run");
+
+    size_t count = sac_read_output(interpreter, (uint8_t *)output, sizeof(output) - 1);
+    if (count != 13 || strcmp(output, "Hello World!\n") != 0) return fail("sac_read_output");
+
+    if (sac_get_cell(interpreter, 1, &cell) != SAC_OK || cell != 0) return fail("sac_get_cell");
+    if (sac_get_cell(interpreter, 1000, &cell) != SAC_ERR_OUT_OF_RANGE) return fail("sac_get_cell out of range");
+
+    if (sac_load_str(interpreter, echo, strlen(echo), &error) != SAC_OK) return fail("sac_load_str echo");
+    if (sac_set_input_buffer(interpreter, (const uint8_t *)"abc", 3) != SAC_OK) return fail("sac_set_input_buffer");
+    if (sac_run(interpreter, &error) != SAC_ERR_END_OF_INPUT) return fail("echo end of input");
+    memset(output, 0, sizeof(output));
+    sac_read_output(interpreter, (uint8_t *)output, sizeof(output) - 1);
+    if (strcmp(output, "abc") != 0) return fail("echo output");
+
+    if (sac_load_str(interpreter, "[[]", 3, &error) != SAC_ERR_UNMATCHED_BRACKET || error.code != SAC_ERR_UNMATCHED_BRACKET) {
+        return fail("unmatched bracket");
+    }
+
+    if (sac_load_str(interpreter, "<", 1, &error) != SAC_OK) return fail("sac_load_str underflow");
+    if (sac_run(interpreter, &error) != SAC_ERR_POINTER_UNDERFLOW) return fail("pointer underflow");
+
+    if (sac_run(NULL, &error) != SAC_ERR_NULL_POINTER) return fail("null handle");
+
+    sac_free(interpreter);
+    sac_free(NULL);
+
+    printf("ok\n");
+    return 0;
+}
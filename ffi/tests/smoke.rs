@@ -0,0 +1,45 @@
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+fn library_dir() -> PathBuf {
+    let deps = env::current_exe().unwrap().parent().unwrap().to_path_buf();
+    [deps.clone(), deps.parent().unwrap().to_path_buf()]
+        .into_iter()
+        .find(|dir| dir.join("libsac_ffi.so").exists() || dir.join("libsac_ffi.dylib").exists())
+        .expect("the sac-ffi cdylib was not built")
+}
+
+fn has_c_compiler() -> bool {
+    Command::new("cc").arg("--version").output().map(|o| o.status.success()).unwrap_or(false)
+}
+
+#[test]
+fn c_smoke_test() {
+    if !has_c_compiler() {
+        eprintln!("No C compiler found, skipping the FFI smoke test");
+        return;
+    }
+
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let lib_dir = library_dir();
+    let binary = env::temp_dir().join(format!("sac_ffi_smoke_{}", std::process::id()));
+
+    let status = Command::new("cc")
+        .arg(root.join("tests/smoke.c"))
+        .arg("-I").arg(root.join("include"))
+        .arg("-L").arg(&lib_dir)
+        .arg(format!("-Wl,-rpath,{}", lib_dir.display()))
+        .arg("-lsac_ffi")
+        .arg("-o").arg(&binary)
+        .status()
+        .unwrap();
+    assert!(status.success(), "failed to compile the C smoke test");
+
+    // Make sure the freshly built library wins over any stale copy cargo put on the search path.
+    let output = Command::new(&binary).env("LD_LIBRARY_PATH", &lib_dir).env("DYLD_LIBRARY_PATH", &lib_dir).output().unwrap();
+    let _ = std::fs::remove_file(&binary);
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(output.stdout, b"ok\n");
+}
@@ -0,0 +1 @@
+Clear then +[[-]
@@ -0,0 +1,28 @@
+#![no_main]
+
+use std::io::Cursor;
+
+use libfuzzer_sys::fuzz_target;
+use sac::buffer::OutputBuffer;
+use sac::interpreter::{Config, Interpreter};
+
+// The first byte picks how the rest is turned into a program : as is, wrapped in deep nesting, or
+// with every command stretched into an enormous run. Runs are bounded and use in-memory I/O.
+fuzz_target!(|data: &[u8]| {
+    let Some((&mode, rest)) = data.split_first() else { return };
+    let code = String::from_utf8_lossy(rest);
+
+    let code = match mode % 3 {
+        0 => code.into_owned(),
+        1 => format!("{}{code}{}", "[".repeat(10_000), "]".repeat(10_000)),
+        _ => code.chars().map(|c| c.to_string().repeat(1000)).collect(),
+    };
+
+    let mut interpreter = Interpreter::with_config(Config { tape_size: 1024, ..Config::default() });
+    interpreter.set_input(Cursor::new(rest.to_vec()));
+    interpreter.set_output(OutputBuffer::new());
+    interpreter.set_step_limit(10_000);
+    if interpreter.load_program_from_str(&code).is_ok() {
+        let _ = interpreter.interpret();
+    }
+});
@@ -0,0 +1,13 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use sac::interpreter::{Config, Interpreter};
+
+// Any byte string must load or be rejected with an error, never panic.
+fuzz_target!(|data: &[u8]| {
+    let mut interpreter = Interpreter::with_config(Config { tape_size: 16, ..Config::default() });
+    if interpreter.load_program_from_bytes(data).is_ok() {
+        let _ = interpreter.loops();
+        let _ = interpreter.listing();
+    }
+});
@@ -0,0 +1,639 @@
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::interpreter::{IRInstruction, IRInstructionKind, Interpreter, Label, Span};
+use crate::io::OnEndOfInput;
+
+// Static checks on a loaded program. The pointer analysis tracks the range of cells the memory
+// pointer can be on, relative to cell 0. Loops are entered once with the range they start from,
+// and their exit range is widened according to the net movement of their body, so scanning loops
+// such as `[<]` leave the pointer unknown on one side and never cause a warning.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Edge {
+    Left,  // Moving left of cell 0.
+    Right, // Moving past the end of the tape.
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BoundsWarning {
+    pub instruction: usize,
+    pub span: Span,
+    pub edge: Edge,
+    // Every run reaching the instruction fails there. Otherwise some path may, which includes a
+    // loop body that is never actually entered.
+    pub definite: bool,
+}
+
+impl fmt::Display for BoundsWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let certainty = if self.definite { "will" } else { "may" };
+        let edge = match self.edge {
+            Edge::Left => "left of cell 0",
+            Edge::Right => "past the end of the tape",
+        };
+        write!(f, "The memory pointer {certainty} move {edge} at {} (instruction {})", self.span, self.instruction)
+    }
+}
+
+// A label defined again further down the source, the later definition being the one used.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DuplicateLabel {
+    pub label: Label,
+    pub later: Span,
+}
+
+impl fmt::Display for DuplicateLabel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Label {} at {} is defined again at {}, this definition is ignored", self.label.name, self.label.span, self.later)
+    }
+}
+
+pub fn duplicate_labels(interpreter: &Interpreter) -> Vec<DuplicateLabel> {
+    let labels = interpreter.labels();
+    labels.iter().enumerate().filter_map(|(index, label)| {
+        let later = labels[index + 1..].iter().find(|later| later.name == label.name)?;
+        Some(DuplicateLabel { label: label.clone(), later: later.span })
+    }).collect()
+}
+
+// Pointer positions, `None` bounds being unknown.
+#[derive(Clone, Copy)]
+struct Interval {
+    low: Option<i64>,
+    high: Option<i64>,
+}
+
+impl Interval {
+    const UNKNOWN: Interval = Interval { low: None, high: None };
+
+    fn shift(self, by: i64) -> Interval {
+        Interval { low: self.low.map(|l| l + by), high: self.high.map(|h| h + by) }
+    }
+
+    fn hull(self, other: Interval) -> Interval {
+        Interval { low: self.low.zip(other.low).map(|(a, b)| a.min(b)), high: self.high.zip(other.high).map(|(a, b)| a.max(b)) }
+    }
+}
+
+pub fn pointer_bounds(interpreter: &Interpreter) -> Vec<BoundsWarning> {
+    walk_pointer(interpreter.program(), interpreter.tape_size()).0
+}
+
+// Cells added to the size inferred by `Config::infer_tape_size`.
+const TAPE_SLACK: usize = 16;
+
+// Size of the tape `program` needs out of `tape_size` cells : the cells up to the rightmost
+// position the pointer analysis found, plus some slack, or `None` when it found no bound or the
+// tape is already smaller. Runs on a tape of that size behave the same, bounds errors included, as
+// the pointer never goes further without failing on both.
+pub(crate) fn inferred_tape_size(program: &[IRInstruction], tape_size: usize) -> Option<usize> {
+    let high = walk_pointer(program, tape_size).1.high?;
+    Some(high as usize + 1 + TAPE_SLACK).filter(|&size| size < tape_size)
+}
+
+// Warnings, and the lowest and highest cells the pointer may reach, `None` when unknown.
+fn walk_pointer(program: &[IRInstruction], tape_size: usize) -> (Vec<BoundsWarning>, Interval) {
+    let last_cell = tape_size as i64 - 1;
+    let displacements = displacements(program);
+    let mut warnings = Vec::new();
+
+    let mut at = Interval { low: Some(0), high: Some(0) };
+    let mut reach = at;
+    let mut loops = Vec::new(); // IR index of the `[` and range of the loops being walked.
+    let mut index = 0usize;
+
+    // When a loop body moves the pointer, its later iterations drift away on a side that is
+    // unknown once the loop exits, so walking the first one is enough.
+    while index < program.len() {
+        let inst = program[index];
+        let moved = match inst.kind {
+            IRInstructionKind::IncrementPointer => at.shift(inst.operand.unwrap() as i64),
+            IRInstructionKind::DecrementPointer => at.shift(-(inst.operand.unwrap() as i64)),
+            IRInstructionKind::Custom(_) => Interval::UNKNOWN, // Handlers may move the pointer anywhere.
+            IRInstructionKind::JumpIfZero | IRInstructionKind::If => {
+                loops.push((index, at));
+                index += 1;
+                continue;
+            },
+            // The body ran once or not at all.
+            IRInstructionKind::EndIf => {
+                let (_, entry) = loops.pop().unwrap();
+                at = entry.hull(at);
+                reach = reach.hull(at);
+                index += 1;
+                continue;
+            },
+            IRInstructionKind::JumpIfNotZero => {
+                let (open, entry) = loops.pop().unwrap();
+                at = match displacements[open] {
+                    Some(0) => entry,
+                    Some(d) if d > 0 => Interval { high: None, ..entry },
+                    Some(_) => Interval { low: None, ..entry },
+                    None => Interval::UNKNOWN,
+                };
+                reach = reach.hull(at);
+                index += 1;
+                continue;
+            },
+            _ => {
+                index += 1;
+                continue;
+            },
+        };
+
+        match check(&mut warnings, inst, index, moved, last_cell, !loops.is_empty()) {
+            Some(valid) => {
+                at = valid;
+                reach = reach.hull(at);
+                index += 1;
+            },
+            // Every path failed : the rest of the loop body is unreachable, and after a top level
+            // instruction the rest of the program.
+            None => match loops.last() {
+                Some(&(open, _)) => index = program[open].operand.unwrap(),
+                None => break,
+            },
+        }
+    }
+
+    (warnings, reach)
+}
+
+// Records a warning when `at` crosses an edge, then keeps the positions that are still valid.
+fn check(warnings: &mut Vec<BoundsWarning>, inst: IRInstruction, instruction: usize, at: Interval, last_cell: i64, in_loop: bool) -> Option<Interval> {
+    let mut valid = at;
+
+    if let Some(low) = at.low.filter(|&l| l < 0) {
+        let definite = at.high.is_some_and(|h| h < 0);
+        warnings.push(BoundsWarning { instruction, span: inst.span, edge: Edge::Left, definite: definite && !in_loop });
+        if definite {
+            return None;
+        }
+        valid.low = Some(low.max(0));
+    }
+    if let Some(high) = at.high.filter(|&h| h > last_cell) {
+        let definite = at.low.is_some_and(|l| l > last_cell);
+        warnings.push(BoundsWarning { instruction, span: inst.span, edge: Edge::Right, definite: definite && !in_loop });
+        if definite {
+            return None;
+        }
+        valid.high = Some(high.min(last_cell));
+    }
+
+    Some(valid)
+}
+
+// Net movement of each loop body, indexed by the IR index of its `[`, when all paths agree on it.
+pub(crate) fn displacements(program: &[IRInstruction]) -> Vec<Option<i64>> {
+    let mut displacements = vec![None; program.len()];
+    let mut stack = vec![(0usize, Some(0i64))]; // The bottom entry stands for the whole program.
+
+    for (index, inst) in program.iter().enumerate() {
+        let net = &mut stack.last_mut().unwrap().1;
+        match inst.kind {
+            IRInstructionKind::IncrementPointer => *net = net.map(|n| n + inst.operand.unwrap() as i64),
+            IRInstructionKind::DecrementPointer => *net = net.map(|n| n - inst.operand.unwrap() as i64),
+            IRInstructionKind::Custom(_) => *net = None,
+            IRInstructionKind::JumpIfZero | IRInstructionKind::If => stack.push((index, Some(0))),
+            IRInstructionKind::JumpIfNotZero | IRInstructionKind::EndIf => {
+                let (open, body) = stack.pop().unwrap();
+                displacements[open] = body;
+                // A loop that moves the pointer runs an unknown number of times.
+                if body != Some(0) {
+                    stack.last_mut().unwrap().1 = None;
+                }
+            },
+            _ => (),
+        }
+    }
+
+    displacements
+}
+
+// Source patterns that are almost always mistakes, reported by `lint` unless allowed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Lint {
+    EmptyLoop,       // `[]` reached with a cell that may not be 0.
+    DeadLoop,        // A loop right after a loop, whose cell is always 0.
+    DeadStore,       // `+` or `-` overwritten by `,` or `[-]` before being read.
+    InvariantLoop,   // A loop body that never changes the cell it tests.
+    UnreachableCode, // Instructions after a loop that never ends.
+    WrappedProgram,  // The whole program is one loop, which cell 0 at 0 never enters.
+    EndOfInputLoop,  // A loop ended by `,`, which the end of input doesn't set to 0.
+}
+
+impl Lint {
+    pub const ALL: [Lint; 7] = [Lint::EmptyLoop, Lint::DeadLoop, Lint::DeadStore, Lint::InvariantLoop, Lint::UnreachableCode, Lint::WrappedProgram, Lint::EndOfInputLoop];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Lint::EmptyLoop => "empty-loop",
+            Lint::DeadLoop => "dead-loop",
+            Lint::DeadStore => "dead-store",
+            Lint::InvariantLoop => "invariant-loop",
+            Lint::UnreachableCode => "unreachable-code",
+            Lint::WrappedProgram => "wrapped-program",
+            Lint::EndOfInputLoop => "end-of-input-loop",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Lint::EmptyLoop => "An empty loop never ends once entered",
+            Lint::DeadLoop => "A loop right after a loop is never entered",
+            Lint::DeadStore => "A value is overwritten before being read",
+            Lint::InvariantLoop => "A loop body never changes the cell it tests",
+            Lint::UnreachableCode => "Instructions after a loop that never ends are unreachable",
+            Lint::WrappedProgram => "A program wholly inside a loop never runs, as cell 0 starts at 0",
+            Lint::EndOfInputLoop => "A loop ended by reading a 0 never ends once the input is exhausted",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Lint> {
+        Lint::ALL.into_iter().find(|lint| lint.name() == name)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LintWarning {
+    pub lint: Lint,
+    pub instruction: usize,
+    pub span: Span, // The whole loop for loop lints.
+}
+
+impl fmt::Display for LintWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (span, instruction) = (self.span, self.instruction);
+        match self.lint {
+            Lint::EmptyLoop => write!(f, "Empty loop at {span} (instruction {instruction}) never ends once entered")?,
+            Lint::DeadLoop => write!(f, "Loop at {span} (instruction {instruction}) directly follows a loop, so it is never entered")?,
+            Lint::DeadStore => write!(f, "Value written at {span} (instruction {instruction}) is overwritten before being read")?,
+            Lint::InvariantLoop => write!(f, "Loop at {span} (instruction {instruction}) never changes the cell it tests, so it never ends once entered")?,
+            Lint::UnreachableCode => write!(f, "Instructions from {span} (instruction {instruction}) are unreachable, a loop before them never ends")?,
+            Lint::WrappedProgram => write!(f, "The whole program is the loop at {span} (instruction {instruction}), which never runs as cell 0 starts at 0")?,
+            Lint::EndOfInputLoop => write!(f, "Loop at {span} (instruction {instruction}) waits for a 0 from the input, so it never ends if the input runs out first")?,
+        }
+        write!(f, " [{}]", self.lint.name())
+    }
+}
+
+// Lints of the loaded program, in program order, leaving out those in `allow`.
+pub fn lint(interpreter: &Interpreter, allow: &[Lint]) -> Vec<LintWarning> {
+    let program = interpreter.program();
+    let invariant = invariant_loops(program);
+    let mut warnings = Vec::new();
+    let loop_span = |open: usize| Span { end: program[program[open].operand.unwrap()].span.end, ..program[open].span };
+
+    // A loop with a body, possibly folded into a `SetZero`, from the first instruction to the last.
+    let wrapped = match program {
+        [inst] => inst.kind == IRInstructionKind::SetZero,
+        [first, _, .., _] => first.kind.opens() && first.operand == Some(program.len() - 1),
+        _ => false,
+    };
+    if wrapped {
+        warnings.push(LintWarning { lint: Lint::WrappedProgram, instruction: 0, span: Span { end: program[program.len() - 1].span.end, ..program[0].span } });
+    }
+
+    let on_end_of_input = interpreter.on_end_of_input();
+    for (index, inst) in program.iter().enumerate() {
+        if inst.kind == IRInstructionKind::JumpIfZero && hangs_at_end_of_input(&program[index + 1..inst.operand.unwrap()], on_end_of_input) {
+            warnings.push(LintWarning { lint: Lint::EndOfInputLoop, instruction: index, span: loop_span(index) });
+        }
+    }
+
+    for (index, inst) in program.iter().enumerate() {
+        // Ranges are left out, the instructions they stand for follow them.
+        let next = (index + 1..program.len()).find(|&i| !program[i].kind.is_range());
+        let previous = program[..index].iter().rposition(|previous| !previous.kind.is_range()).map(|i| program[i].kind);
+        match inst.kind {
+            IRInstructionKind::JumpIfZero | IRInstructionKind::If if matches!(previous, Some(IRInstructionKind::JumpIfNotZero | IRInstructionKind::EndIf | IRInstructionKind::SetZero)) => {
+                warnings.push(LintWarning { lint: Lint::DeadLoop, instruction: index, span: loop_span(index) });
+            },
+            IRInstructionKind::IncrementByte | IRInstructionKind::DecrementByte => {
+                let overwritten = match next.map(|next| (next, program[next].kind)) {
+                    Some((_, IRInstructionKind::ReadInputToByte | IRInstructionKind::SetZero | IRInstructionKind::SetConst)) => true,
+                    Some((next, IRInstructionKind::JumpIfZero)) => is_clear_loop(&program[next..]),
+                    _ => false,
+                };
+                if overwritten {
+                    warnings.push(LintWarning { lint: Lint::DeadStore, instruction: index, span: inst.span });
+                }
+            },
+            _ => (),
+        }
+    }
+
+    // Value of the current cell, as far as it is known. Until the first write every cell is 0.
+    let mut cell = Some(0u8);
+    let mut untouched = true;
+
+    for (index, inst) in program.iter().enumerate() {
+        match inst.kind {
+            IRInstructionKind::IncrementByte => cell = cell.map(|c| c.wrapping_add(inst.operand.unwrap() as u8)),
+            IRInstructionKind::DecrementByte => cell = cell.map(|c| c.wrapping_sub(inst.operand.unwrap() as u8)),
+            IRInstructionKind::IncrementPointer | IRInstructionKind::DecrementPointer => cell = untouched.then_some(0),
+            IRInstructionKind::SetZero | IRInstructionKind::JumpIfNotZero | IRInstructionKind::EndIf => cell = Some(0),
+            IRInstructionKind::SetConst => cell = Some(inst.operand.unwrap() as u8),
+            IRInstructionKind::ReadInputToByte | IRInstructionKind::Custom(_) => cell = None,
+            IRInstructionKind::JumpIfZero | IRInstructionKind::If => {
+                let close = inst.operand.unwrap();
+                let lint = if close == index + 1 { Lint::EmptyLoop } else { Lint::InvariantLoop };
+                if invariant[index] && cell != Some(0) {
+                    warnings.push(LintWarning { lint, instruction: index, span: loop_span(index) });
+                }
+                if invariant[index] && cell.is_some_and(|c| c != 0) {
+                    let after = (close + 1..program.len()).find(|&i| !program[i].kind.closes());
+                    if let Some(after) = after {
+                        warnings.push(LintWarning { lint: Lint::UnreachableCode, instruction: after, span: program[after].span });
+                    }
+                    break;
+                }
+                cell = None; // Nonzero, but unknown.
+            },
+            IRInstructionKind::PrintByteAsChar | IRInstructionKind::Breakpoint | IRInstructionKind::FillRange { .. } | IRInstructionKind::CopyRange { .. } => (),
+        }
+        untouched &= matches!(inst.kind, IRInstructionKind::IncrementPointer | IRInstructionKind::DecrementPointer | IRInstructionKind::PrintByteAsChar | IRInstructionKind::Breakpoint);
+    }
+
+    warnings.retain(|w| !allow.contains(&w.lint));
+    warnings.sort_by_key(|w| w.instruction);
+    warnings
+}
+
+// Whether a loop with `body` keeps going forever once the input is exhausted, as in `,[.,]` : its
+// cell is last written by `,`, which leaves it at 255 then, or as it was, nonzero since the loop
+// was entered, when nothing else writes it. Only bodies without loops are looked at.
+fn hangs_at_end_of_input(body: &[IRInstruction], on_end_of_input: OnEndOfInput) -> bool {
+    let (mut offset, mut read, mut written) = (0isize, false, false);
+    for inst in body {
+        match inst.kind {
+            IRInstructionKind::IncrementPointer => offset += inst.operand.unwrap() as isize,
+            IRInstructionKind::DecrementPointer => offset -= inst.operand.unwrap() as isize,
+            IRInstructionKind::ReadInputToByte if offset == 0 => read = true,
+            IRInstructionKind::IncrementByte | IRInstructionKind::DecrementByte | IRInstructionKind::SetZero | IRInstructionKind::SetConst if offset == 0 => (read, written) = (false, true),
+            IRInstructionKind::ReadInputToByte
+            | IRInstructionKind::IncrementByte
+            | IRInstructionKind::DecrementByte
+            | IRInstructionKind::SetZero
+            | IRInstructionKind::SetConst
+            | IRInstructionKind::PrintByteAsChar
+            | IRInstructionKind::Breakpoint => (),
+            _ => return false,
+        }
+    }
+    offset == 0 && read && match on_end_of_input {
+        OnEndOfInput::MinusOne => true,
+        OnEndOfInput::Unchanged => !written,
+        OnEndOfInput::Error | OnEndOfInput::Zero => false,
+    }
+}
+
+// `[`, an odd streak of `+` or `-`, `]` : the loops folded to `SetZero`.
+pub(crate) fn is_clear_loop(program: &[IRInstruction]) -> bool {
+    matches!(program, [open, body, close, ..]
+        if open.kind == IRInstructionKind::JumpIfZero
+            && matches!(body.kind, IRInstructionKind::IncrementByte | IRInstructionKind::DecrementByte)
+            && body.operand.unwrap() % 2 == 1
+            && close.kind == IRInstructionKind::JumpIfNotZero)
+}
+
+// Whether each loop, indexed by the IR index of its `[`, ends every iteration on the cell it tests
+// without ever writing it. Positions are counted from an origin that moves each time the pointer
+// becomes unknown, which invalidates the loops open at that point.
+fn invariant_loops(program: &[IRInstruction]) -> Vec<bool> {
+    let displacements = displacements(program);
+    let mut invariant = vec![false; program.len()];
+    let mut last_write = BTreeMap::new(); // IR index of the last write of each (origin, position).
+    let mut open_loops = Vec::new(); // IR index, origin and position of each open loop.
+    let (mut origin, mut position) = (0usize, 0i64);
+
+    for (index, inst) in program.iter().enumerate() {
+        match inst.kind {
+            IRInstructionKind::IncrementPointer => position += inst.operand.unwrap() as i64,
+            IRInstructionKind::DecrementPointer => position -= inst.operand.unwrap() as i64,
+            IRInstructionKind::IncrementByte | IRInstructionKind::DecrementByte | IRInstructionKind::SetZero | IRInstructionKind::SetConst | IRInstructionKind::ReadInputToByte => {
+                last_write.insert((origin, position), index);
+            },
+            IRInstructionKind::Custom(_) => (origin, position) = (origin + 1, 0),
+            IRInstructionKind::JumpIfZero | IRInstructionKind::If => open_loops.push((index, origin, position)),
+            IRInstructionKind::JumpIfNotZero | IRInstructionKind::EndIf => {
+                let (open, loop_origin, start) = open_loops.pop().unwrap();
+                invariant[open] = (loop_origin, start) == (origin, position) && last_write.get(&(origin, position)).is_none_or(|&write| write < open);
+                if displacements[open] != Some(0) {
+                    (origin, position) = (origin + 1, 0);
+                }
+            },
+            IRInstructionKind::PrintByteAsChar | IRInstructionKind::Breakpoint | IRInstructionKind::FillRange { .. } | IRInstructionKind::CopyRange { .. } => (),
+        }
+    }
+
+    invariant
+}
+
+// Static profile of a loaded program.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Analysis {
+    pub source_bytes: usize,
+    pub commands: Vec<(char, usize)>, // Each of the eight commands, then any other token used.
+    pub instructions: Vec<(&'static str, usize)>, // Compiled instructions by kind, in order of first use.
+    pub loops: usize,
+    pub max_depth: usize,
+    pub longest_run: usize, // Longest streak of a single `><+-` command.
+    pub inputs: usize,
+    pub outputs: usize,
+    // Lowest and highest cells the pointer may reach, `None` when the pointer analysis can't
+    // bound that side.
+    pub reach: (Option<i64>, Option<i64>),
+    // Size of the tape with `Config::infer_tape_size`, `None` when the tape can't be shrunk.
+    pub inferred_tape_size: Option<usize>,
+    // Labels defined, in order, without the definitions overridden by a later one of the same name.
+    pub labels: Vec<Label>,
+}
+
+impl Analysis {
+    pub fn command_count(&self) -> usize {
+        self.commands.iter().map(|(_, count)| count).sum()
+    }
+
+    pub fn instruction_count(&self) -> usize {
+        self.instructions.iter().map(|(_, count)| count).sum()
+    }
+}
+
+pub fn analyze(interpreter: &Interpreter) -> Analysis {
+    let program = interpreter.program();
+    let source = interpreter.source();
+
+    let mut commands: Vec<(char, usize)> = "><+-.,[]".chars().map(|c| (c, 0)).collect();
+    for &c in source.iter().filter(|&&c| interpreter.is_command(c)) {
+        match commands.iter_mut().find(|(command, _)| *command == c) {
+            Some(entry) => entry.1 += 1,
+            None => commands.push((c, 1)),
+        }
+    }
+
+    let mut instructions: Vec<(&'static str, usize)> = Vec::new();
+    let (mut depth, mut max_depth, mut longest_run) = (0usize, 0usize, 0usize);
+    for inst in program {
+        match instructions.iter_mut().find(|(name, _)| *name == inst.kind.name()) {
+            Some(entry) => entry.1 += 1,
+            None => instructions.push((inst.kind.name(), 1)),
+        }
+        match inst.kind {
+            IRInstructionKind::JumpIfZero | IRInstructionKind::If => {
+                depth += 1;
+                max_depth = max_depth.max(depth);
+            },
+            IRInstructionKind::JumpIfNotZero | IRInstructionKind::EndIf => depth -= 1,
+            IRInstructionKind::IncrementPointer | IRInstructionKind::DecrementPointer | IRInstructionKind::IncrementByte | IRInstructionKind::DecrementByte => {
+                longest_run = longest_run.max(inst.operand.unwrap());
+            },
+            _ => (),
+        }
+    }
+
+    let count = |kind| program.iter().filter(|inst| inst.kind == kind).count();
+    let reach = walk_pointer(program, interpreter.tape_size()).1;
+    let mut labels = interpreter.labels();
+    let duplicates = duplicate_labels(interpreter);
+    labels.retain(|label| !duplicates.iter().any(|duplicate| duplicate.label == *label));
+
+    Analysis {
+        source_bytes: source.iter().map(|c| c.len_utf8()).sum(),
+        commands,
+        instructions,
+        loops: count(IRInstructionKind::JumpIfZero) + count(IRInstructionKind::If),
+        max_depth,
+        longest_run,
+        inputs: count(IRInstructionKind::ReadInputToByte),
+        outputs: count(IRInstructionKind::PrintByteAsChar),
+        reach: (reach.low, reach.high),
+        inferred_tape_size: inferred_tape_size(program, interpreter.tape_size()),
+        labels,
+    }
+}
+
+// Straight-line run of instructions `start..end` : only the first one is jumped to, and only the
+// last one jumps.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BasicBlock {
+    pub start: usize,
+    pub end: usize,
+    pub edges: Vec<BlockEdge>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BlockEdge {
+    pub to: Option<usize>, // Index of the block, `None` for the end of the program.
+    pub kind: EdgeKind,
+    pub back: bool, // Back to the start of a loop.
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EdgeKind {
+    Fallthrough,
+    Zero,    // Taken when the cell is 0, past a loop.
+    NonZero, // Taken when the cell isn't 0, into a loop body.
+    Skip,    // Past the instructions a `FillRange` or `CopyRange` stands for, when it applies.
+}
+
+// Splits the program into basic blocks, in program order, the edges of each block in the order of
+// `EdgeKind`.
+pub fn basic_blocks(program: &[IRInstruction]) -> Vec<BasicBlock> {
+    // Where each instruction ending a block may go to, as (instruction index, kind).
+    let targets = |index: usize, inst: &IRInstruction| -> Vec<(usize, EdgeKind)> {
+        match inst.kind {
+            kind if kind.opens() => vec![(index + 1, EdgeKind::NonZero), (inst.operand.unwrap() + 1, EdgeKind::Zero)],
+            IRInstructionKind::JumpIfNotZero => vec![(index + 1, EdgeKind::Zero), (inst.operand.unwrap() + 1, EdgeKind::NonZero)],
+            kind if kind.is_range() => vec![(index + 1, EdgeKind::Fallthrough), (index + inst.operand.unwrap() + 1, EdgeKind::Skip)],
+            _ => Vec::new(),
+        }
+    };
+
+    let mut leaders = vec![false; program.len() + 1];
+    leaders[0] = true;
+    for (index, inst) in program.iter().enumerate() {
+        for (target, _) in targets(index, inst) {
+            leaders[target] = true;
+        }
+        if inst.kind.closes() {
+            leaders[index + 1] = true;
+        }
+    }
+
+    let starts: Vec<usize> = (0..program.len()).filter(|&index| leaders[index]).collect();
+    let block = |instruction: usize| starts.binary_search(&instruction).ok();
+    starts.iter().enumerate().map(|(number, &start)| {
+        let end = starts.get(number + 1).copied().unwrap_or(program.len());
+        let mut edges: Vec<BlockEdge> = match targets(end - 1, &program[end - 1])[..] {
+            [] => vec![BlockEdge { to: block(end), kind: EdgeKind::Fallthrough, back: false }],
+            ref targets => targets.iter().map(|&(target, kind)| BlockEdge { to: block(target), kind, back: target <= start }).collect(),
+        };
+        edges.sort_by_key(|edge| edge.kind as u8);
+        BasicBlock { start, end, edges }
+    }).collect()
+}
+
+const DOT_LISTING_LINES: usize = 8;
+
+// Graphviz digraph of the basic blocks of the loaded program, each labeled with its instructions
+// and where it starts in the source. Back edges are dashed. With `runs`, how many times each
+// instruction ran, blocks are shaded by how often they ran.
+pub fn control_flow_dot(interpreter: &Interpreter, runs: Option<&[u64]>) -> String {
+    let program = interpreter.program();
+    let blocks = basic_blocks(program);
+    let block_runs = |block: &BasicBlock| runs.map(|runs| runs.get(block.start).copied().unwrap_or(0));
+    let most = blocks.iter().filter_map(block_runs).max().unwrap_or(0);
+
+    let mut dot = String::from("digraph cfg {\n    node [shape=box, fontname=\"monospace\"];\n    start [shape=oval];\n    end [shape=oval];\n");
+    for (number, block) in blocks.iter().enumerate() {
+        let span = program[block.start].span;
+        let mut label = format!("#{}..{} at {}:{}\\l", block.start, block.end, span.line, span.column);
+        for inst in program[block.start..block.end].iter().take(DOT_LISTING_LINES) {
+            label.push_str(&escape_dot(&format!("{inst}")));
+            label.push_str("\\l");
+        }
+        if block.end - block.start > DOT_LISTING_LINES {
+            label.push_str(&format!("... {} more\\l", block.end - block.start - DOT_LISTING_LINES));
+        }
+        let mut attributes = String::new();
+        if let Some(count) = block_runs(block) {
+            label.push_str(&format!("runs : {count}\\l"));
+            // On a log scale, from white for blocks that never ran to red for the hottest ones.
+            let heat = (count + 1).ilog2() as f64 / (most + 1).ilog2().max(1) as f64;
+            let other = 255 - (heat * 200.0) as u8;
+            attributes = format!(", style=filled, fillcolor=\"#ff{other:02x}{other:02x}\"");
+        }
+        dot.push_str(&format!("    b{number} [label=\"{label}\"{attributes}];\n"));
+    }
+
+    let node = |to: Option<usize>| to.map_or(String::from("end"), |number| format!("b{number}"));
+    dot.push_str(&format!("    start -> {};\n", node((!blocks.is_empty()).then_some(0))));
+    for (number, block) in blocks.iter().enumerate() {
+        for edge in &block.edges {
+            let label = match edge.kind {
+                EdgeKind::Fallthrough => String::new(),
+                EdgeKind::Zero => String::from("label=\"zero\""),
+                EdgeKind::NonZero => String::from("label=\"nonzero\""),
+                EdgeKind::Skip => String::from("label=\"skip\""),
+            };
+            let style = if edge.back { ", style=dashed, color=\"#1f77b4\"" } else { "" };
+            let attributes = format!("{label}{style}");
+            match attributes.trim_start_matches(", ") {
+                "" => dot.push_str(&format!("    b{number} -> {};\n", node(edge.to))),
+                attributes => dot.push_str(&format!("    b{number} -> {} [{attributes}];\n", node(edge.to))),
+            }
+        }
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+fn escape_dot(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
@@ -0,0 +1,185 @@
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use core::future::Future;
+use core::pin::{pin, Pin};
+use core::task::{Context, Poll};
+use std::io::{self, Read};
+use std::sync::Mutex;
+
+use crate::buffer::OutputBuffer;
+use crate::cancel::CancelToken;
+use crate::error::RuntimeError;
+use crate::interpreter::Interpreter;
+use crate::io::{InputSource, IoError, OutputSink};
+
+// Async counterparts of `InputSource` and `OutputSink`. Adapting a tokio `AsyncRead` or
+// `AsyncWrite` takes a newtype calling `read_u8` / `write_all`.
+pub trait AsyncInputSource {
+    // Resolves to the next byte, or `None` once the input is exhausted.
+    fn read_byte(&mut self) -> impl Future<Output = Result<Option<u8>, IoError>>;
+}
+
+pub trait AsyncOutputSink {
+    fn write_bytes(&mut self, bytes: &[u8]) -> impl Future<Output = Result<(), IoError>>;
+
+    fn flush(&mut self) -> impl Future<Output = Result<(), IoError>> {
+        async { Ok(()) }
+    }
+}
+
+pub const DEFAULT_YIELD_INTERVAL: usize = 4096;
+
+// Why `run_slice` gave control back.
+enum Slice {
+    Halted,
+    Printed,
+    NeedsInput,
+    Yield,
+}
+
+impl Interpreter {
+    // Same semantics as `interpret`, but `,` and `.` await their streams, and control goes back
+    // to the executor every `yield_interval` instructions so a busy program can't starve it.
+    // Steps run as they do in `interpret`, on in-memory streams standing for the async ones : the
+    // output printed by a step is awaited before the next one, and a `,` waits for its byte.
+    pub async fn interpret_async<I, O>(&mut self, input: &mut I, output: &mut O, yield_interval: usize) -> Result<(), RuntimeError>
+    where
+        I: AsyncInputSource,
+        O: AsyncOutputSink,
+    {
+        let (slot, printed) = (InputSlot::default(), OutputBuffer::new());
+        let streams = Streams::replace(self, Box::new(slot.clone()), Box::new(printed.clone()));
+        let this = &mut *streams.interpreter;
+        let token = this.cancel_token().cloned();
+        let mut since_yield = 0u64;
+
+        loop {
+            let (before, budget) = (this.steps(), (yield_interval as u64).saturating_sub(since_yield).max(1));
+            let slice = this.reported(|this| this.run_slice(budget, &printed));
+            since_yield += this.steps() - before;
+
+            // What was printed before an error is still written, as `interpret` flushes it.
+            let bytes = printed.take();
+            if !bytes.is_empty() {
+                Cancellable { token: token.as_ref(), future: pin!(output.write_bytes(&bytes)) }.await.map_err(|Cancelled| this.cancelled())??;
+                Cancellable { token: token.as_ref(), future: pin!(output.flush()) }.await.map_err(|Cancelled| this.cancelled())??;
+            }
+
+            match slice? {
+                Slice::Halted => return Ok(()),
+                Slice::NeedsInput => {
+                    let byte = Cancellable { token: token.as_ref(), future: pin!(input.read_byte()) }.await.map_err(|Cancelled| this.cancelled())??;
+                    slot.fill(byte);
+                },
+                Slice::Printed | Slice::Yield => (),
+            }
+
+            if since_yield >= yield_interval as u64 {
+                since_yield = 0;
+                YieldNow(false).await;
+            }
+        }
+    }
+
+    // Runs at most `budget` steps, stopping early after a step that printed or at a `,` whose byte
+    // isn't there yet, which the next slice retries.
+    fn run_slice(&mut self, budget: u64, printed: &OutputBuffer) -> Result<Slice, RuntimeError> {
+        for _ in 0..budget {
+            if self.is_halted() {
+                return Ok(Slice::Halted);
+            }
+            match self.step_once() {
+                Ok(()) if !printed.is_empty() => return Ok(Slice::Printed),
+                Ok(()) => (),
+                Err(RuntimeError::Io(e)) if e.kind() == io::ErrorKind::WouldBlock => return Ok(Slice::NeedsInput),
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(if self.is_halted() { Slice::Halted } else { Slice::Yield })
+    }
+}
+
+// The interpreter's own streams, put back once the run is over or its future dropped.
+struct Streams<'a> {
+    interpreter: &'a mut Interpreter,
+    saved: Option<(Box<dyn InputSource + Send>, Box<dyn OutputSink + Send>)>,
+}
+
+impl Streams<'_> {
+    fn replace(interpreter: &mut Interpreter, input: Box<dyn InputSource + Send>, output: Box<dyn OutputSink + Send>) -> Streams<'_> {
+        let saved = Some(interpreter.replace_streams(input, output));
+        Streams { interpreter, saved }
+    }
+}
+
+impl Drop for Streams<'_> {
+    fn drop(&mut self) {
+        if let Some((input, output)) = self.saved.take() {
+            self.interpreter.replace_streams(input, output);
+        }
+    }
+}
+
+// Input of the interpreter during an async run : the byte the async source gave for the pending
+// `,`, `None` at the end of the input, and `WouldBlock` until it was read.
+#[derive(Clone, Default)]
+struct InputSlot(Arc<Mutex<Option<Option<u8>>>>);
+
+impl InputSlot {
+    fn fill(&self, byte: Option<u8>) {
+        *self.0.lock().unwrap_or_else(|e| e.into_inner()) = Some(byte);
+    }
+}
+
+impl Read for InputSlot {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.0.lock().unwrap_or_else(|e| e.into_inner()).take() {
+            Some(Some(byte)) if !buf.is_empty() => {
+                buf[0] = byte;
+                Ok(1)
+            },
+            Some(_) => Ok(0),
+            None => Err(io::ErrorKind::WouldBlock.into()),
+        }
+    }
+}
+
+// Resolves to `Cancelled` as soon as the token fires, even while the wrapped read or write is
+// still pending. Only the token is held, not the interpreter, which isn't `Sync`.
+struct Cancellable<'a, F> {
+    token: Option<&'a CancelToken>,
+    future: Pin<&'a mut F>,
+}
+
+struct Cancelled;
+
+impl<F: Future> Future for Cancellable<'_, F> {
+    type Output = Result<F::Output, Cancelled>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if let Some(token) = self.token {
+            token.register(cx.waker());
+            if token.is_cancelled() {
+                return Poll::Ready(Err(Cancelled));
+            }
+        }
+
+        self.future.as_mut().poll(cx).map(Ok)
+    }
+}
+
+struct YieldNow(bool);
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.0 {
+            return Poll::Ready(());
+        }
+
+        self.0 = true;
+        cx.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
@@ -0,0 +1,91 @@
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::cell::UnsafeCell;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+#[cfg(not(feature = "std"))]
+use crate::io::{IoError, OutputSink};
+
+// In-memory output sink: clones share the same bytes, so one clone can be handed to the
+// interpreter while another one collects what the program printed, from any thread.
+#[derive(Clone, Default)]
+pub struct OutputBuffer(Arc<Shared>);
+
+// Bytes behind a lock of their own, as there is no `Mutex` without std. It is only held to copy
+// bytes in or out, so waiting for it by spinning is enough.
+#[derive(Default)]
+struct Shared {
+    locked: AtomicBool,
+    bytes: UnsafeCell<Vec<u8>>,
+}
+
+// SAFETY: the bytes are only reached through `with`, which holds the lock.
+unsafe impl Sync for Shared {}
+
+impl Shared {
+    fn with<T>(&self, f: impl FnOnce(&mut Vec<u8>) -> T) -> T {
+        struct Unlock<'a>(&'a AtomicBool);
+
+        impl Drop for Unlock<'_> {
+            fn drop(&mut self) {
+                self.0.store(false, Ordering::Release);
+            }
+        }
+
+        while self.locked.compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed).is_err() {
+            core::hint::spin_loop();
+        }
+        let _unlock = Unlock(&self.locked);
+        // SAFETY: the lock is held until `_unlock` is dropped, after `f` is done with the bytes.
+        f(unsafe { &mut *self.bytes.get() })
+    }
+}
+
+impl OutputBuffer {
+    pub fn new() -> OutputBuffer {
+        OutputBuffer::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.with(|bytes| bytes.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.with(|bytes| bytes.is_empty())
+    }
+
+    // Removes and returns everything written so far.
+    pub fn take(&self) -> Vec<u8> {
+        self.0.with(core::mem::take)
+    }
+
+    // Moves the oldest pending bytes into `buf`, returning how many were moved.
+    pub fn read_into(&self, buf: &mut [u8]) -> usize {
+        self.0.with(|bytes| {
+            let count = buf.len().min(bytes.len());
+            buf[..count].copy_from_slice(&bytes[..count]);
+            bytes.drain(..count);
+            count
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::io::Write for OutputBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.with(|bytes| bytes.extend_from_slice(buf));
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl OutputSink for OutputBuffer {
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), IoError> {
+        self.0.with(|shared| shared.extend_from_slice(bytes));
+        Ok(())
+    }
+}
@@ -0,0 +1,111 @@
+use alloc::vec::Vec;
+
+use crate::error::BuildError;
+use crate::interpreter::{IRInstruction, IRInstructionKind, Program, Span};
+
+// Emits a `Program` directly, for compilers targeting the IR rather than brainfuck source. There is
+// no source : instruction `n` gets the span `n..n + 1`, so that errors still locate it. The program
+// goes through `Pipeline::run` and `Interpreter::load_ir` as any other.
+#[derive(Clone, Debug, Default)]
+pub struct ProgramBuilder {
+    instructions: Vec<IRInstruction>,
+    open: Vec<usize>, // `[` of the loops not closed yet, innermost last.
+    error: Option<BuildError>, // First misnested loop, reported by `build`.
+}
+
+// An open loop, handed back to `loop_end` to close it. Loops must be closed innermost first.
+#[must_use = "loops must be closed with `loop_end`"]
+#[derive(Debug, PartialEq, Eq)]
+pub struct LoopToken {
+    start: usize,
+}
+
+impl ProgramBuilder {
+    pub fn new() -> ProgramBuilder {
+        ProgramBuilder::default()
+    }
+
+    // Adds `n` to the current cell, wrapping, as `+` or `-` repeated.
+    pub fn add(&mut self, n: i32) -> &mut ProgramBuilder {
+        let amount = (n.unsigned_abs() % 256) as usize;
+        match n {
+            _ if amount == 0 => self,
+            1.. => self.push(IRInstructionKind::IncrementByte, Some(amount)),
+            _ => self.push(IRInstructionKind::DecrementByte, Some(amount)),
+        }
+    }
+
+    // Moves the memory pointer `n` cells right, or left for a negative `n`.
+    pub fn move_ptr(&mut self, n: isize) -> &mut ProgramBuilder {
+        match n {
+            0 => self,
+            1.. => self.push(IRInstructionKind::IncrementPointer, Some(n.unsigned_abs())),
+            _ => self.push(IRInstructionKind::DecrementPointer, Some(n.unsigned_abs())),
+        }
+    }
+
+    pub fn output(&mut self) -> &mut ProgramBuilder {
+        self.push(IRInstructionKind::PrintByteAsChar, None)
+    }
+
+    pub fn input(&mut self) -> &mut ProgramBuilder {
+        self.push(IRInstructionKind::ReadInputToByte, None)
+    }
+
+    pub fn set_zero(&mut self) -> &mut ProgramBuilder {
+        self.push(IRInstructionKind::SetZero, None)
+    }
+
+    pub fn loop_start(&mut self) -> LoopToken {
+        let start = self.instructions.len();
+        self.open.push(start);
+        self.push(IRInstructionKind::JumpIfZero, None);
+        LoopToken { start }
+    }
+
+    // Closing a loop other than the innermost one makes `build` fail.
+    pub fn loop_end(&mut self, token: LoopToken) -> &mut ProgramBuilder {
+        if self.open.last() != Some(&token.start) {
+            self.error.get_or_insert(BuildError::MisnestedLoop { instruction: token.start });
+            self.open.retain(|&start| start != token.start);
+        } else {
+            self.open.pop();
+        }
+        self.push(IRInstructionKind::JumpIfNotZero, Some(token.start))
+    }
+
+    pub fn len(&self) -> usize {
+        self.instructions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.instructions.is_empty()
+    }
+
+    // The program, with every `[` pointing to its `]`.
+    pub fn build(mut self) -> Result<Program, BuildError> {
+        if let Some(e) = self.error {
+            return Err(e);
+        }
+        if let Some(&instruction) = self.open.first() {
+            return Err(BuildError::UnclosedLoop { instruction });
+        }
+        for index in 0..self.instructions.len() {
+            if self.instructions[index].kind == IRInstructionKind::JumpIfNotZero {
+                let start = self.instructions[index].operand.unwrap();
+                self.instructions[start].operand = Some(index);
+            }
+        }
+
+        let program = Program { instructions: self.instructions, segments: Vec::new() };
+        debug_assert_eq!(program.validate(), Ok(()));
+        Ok(program)
+    }
+
+    fn push(&mut self, kind: IRInstructionKind, operand: Option<usize>) -> &mut ProgramBuilder {
+        let index = self.instructions.len();
+        let span = Span { start: index, end: index + 1, line: 1, column: index + 1 };
+        self.instructions.push(IRInstruction { kind, operand, span });
+        self
+    }
+}
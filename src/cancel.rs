@@ -0,0 +1,44 @@
+use alloc::sync::Arc;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+// Cloneable flag used to stop a running program from another thread, a signal handler or an
+// async task. The interpreter checks it before every instruction.
+#[derive(Clone, Debug, Default)]
+pub struct CancelToken {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    cancelled: AtomicBool,
+    #[cfg(feature = "async")]
+    wakers: std::sync::Mutex<alloc::vec::Vec<core::task::Waker>>,
+}
+
+impl CancelToken {
+    pub fn new() -> CancelToken {
+        CancelToken::default()
+    }
+
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::SeqCst);
+
+        #[cfg(feature = "async")]
+        for waker in self.inner.wakers.lock().unwrap_or_else(|e| e.into_inner()).drain(..) {
+            waker.wake();
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::Relaxed)
+    }
+
+    // Wakes `waker` on cancellation, so a task blocked on I/O still notices it.
+    #[cfg(feature = "async")]
+    pub(crate) fn register(&self, waker: &core::task::Waker) {
+        let mut wakers = self.inner.wakers.lock().unwrap_or_else(|e| e.into_inner());
+        if !wakers.iter().any(|w| w.will_wake(waker)) {
+            wakers.push(waker.clone());
+        }
+    }
+}
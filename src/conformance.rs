@@ -0,0 +1,105 @@
+use std::fmt;
+use std::io::Cursor;
+
+use crate::buffer::OutputBuffer;
+use crate::error::RuntimeError;
+use crate::interpreter::{Config, Interpreter};
+
+// Small probe programs revealing the semantics brainfuck implementations disagree on, so that
+// users porting programs can check them against the current configuration.
+
+const NESTING_PROBE_DEPTH: usize = 10_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Cells {
+    Wrapping { bits: u32 },
+    Saturating,
+    Trapping,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Eof {
+    Error,
+    Unchanged,
+    Zero,
+    MinusOne,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Edge {
+    Error,
+    Open, // Moving past the edge is allowed, the tape wraps or grows.
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Report {
+    pub cells: Cells,
+    pub eof: Eof,
+    pub tape_cells: usize,
+    pub left_edge: Edge,
+    pub right_edge: Edge,
+    pub nesting_ok: bool, // Whether `NESTING_PROBE_DEPTH` nested loops ran.
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.cells {
+            Cells::Wrapping { bits } => write!(f, "cells: {bits}-bit wrapping; ")?,
+            Cells::Saturating => write!(f, "cells: saturating; ")?,
+            Cells::Trapping => write!(f, "cells: error on overflow; ")?,
+        }
+        let eof = match self.eof {
+            Eof::Error => "error",
+            Eof::Unchanged => "unchanged",
+            Eof::Zero => "0",
+            Eof::MinusOne => "-1",
+        };
+        let edge = |edge| if edge == Edge::Error { "bounded" } else { "open" };
+        write!(f, "EOF: {eof}; tape: {} cells, left edge {}, right edge {}; ", self.tape_cells, edge(self.left_edge), edge(self.right_edge))?;
+        if self.nesting_ok {
+            write!(f, "nesting: {NESTING_PROBE_DEPTH} levels OK")
+        } else {
+            write!(f, "nesting: fails below {NESTING_PROBE_DEPTH} levels")
+        }
+    }
+}
+
+// Runs `code` with no input, returning the interpreter for inspection along with the result.
+fn probe(config: Config, code: &str) -> (Interpreter, Result<(), RuntimeError>) {
+    let mut interpreter = Interpreter::with_config(config);
+    interpreter.set_input(Cursor::new(Vec::new()));
+    interpreter.set_output(OutputBuffer::new());
+    interpreter.set_step_limit(1_000_000);
+    interpreter.load_program_from_str(code).expect("probe programs are balanced");
+    let result = interpreter.interpret();
+    (interpreter, result)
+}
+
+pub fn run(config: Config) -> Report {
+    let cells = match probe(config, "-") {
+        (_, Err(_)) => Cells::Trapping,
+        (interpreter, Ok(())) => match interpreter.peek(0) {
+            Some(0) => Cells::Saturating,
+            Some(max) => Cells::Wrapping { bits: 8 - max.leading_zeros() },
+            None => Cells::Trapping,
+        },
+    };
+
+    let eof = match probe(config, "+++++,") {
+        (_, Err(_)) => Eof::Error,
+        (interpreter, Ok(())) => match interpreter.peek(0) {
+            Some(5) => Eof::Unchanged,
+            Some(0) => Eof::Zero,
+            _ => Eof::MinusOne,
+        },
+    };
+
+    let left_edge = if probe(config, "<").1.is_err() { Edge::Error } else { Edge::Open };
+    let (tape, _) = probe(config, "");
+    let right_edge = if probe(config, &">".repeat(tape.tape_size())).1.is_err() { Edge::Error } else { Edge::Open };
+
+    let nesting = format!("+{}-{}", "[".repeat(NESTING_PROBE_DEPTH), "]".repeat(NESTING_PROBE_DEPTH));
+    let nesting_ok = probe(config, &nesting).1.is_ok();
+
+    Report { cells, eof, tape_cells: tape.tape_size(), left_edge, right_edge, nesting_ok }
+}
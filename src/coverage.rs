@@ -0,0 +1,104 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::interpreter::{Interpreter, Span};
+
+// Reports on the coverage recorded by `Interpreter::set_coverage_tracking` : the regions of the
+// source that never ran, the source with them marked, and an lcov export, which coverage tools can
+// merge across runs. All of them are `None` without coverage tracking.
+
+const RESET: &str = "\x1b[0m";
+const UNCOVERED: &str = "\x1b[1;31m";
+
+// Spans of the runs of consecutive instructions that never ran, in order.
+pub fn uncovered_regions(interpreter: &Interpreter) -> Option<Vec<Span>> {
+    let coverage = interpreter.coverage()?;
+    let program = interpreter.program();
+    let mut regions: Vec<Span> = Vec::new();
+    let mut previous = None;
+    for index in coverage.uncovered() {
+        let span = program[index].span;
+        match regions.last_mut() {
+            Some(region) if previous.is_some_and(|previous| previous + 1 == index) => region.end = region.end.max(span.end),
+            _ => regions.push(span),
+        }
+        previous = Some(index);
+    }
+    Some(regions)
+}
+
+// How many instructions ran, then one line per region that never ran with its first line of code.
+pub fn summary(interpreter: &Interpreter) -> Option<String> {
+    let coverage = interpreter.coverage()?;
+    let source = interpreter.source();
+    let mut summary = format!("Coverage : {} of {} instructions run ({:.1} %)", coverage.covered_count(), coverage.len(), coverage.percent());
+    for region in uncovered_regions(interpreter)? {
+        let code: String = source[region.start..region.end].iter().take_while(|&&c| c != '\n').filter(|&&c| interpreter.is_command(c)).collect();
+        summary.push_str(&format!("\nNot run : {region} : {code}"));
+    }
+    Some(summary)
+}
+
+// The source with the commands that never ran in red, or without colors, marked with `^` on a line
+// of their own below theirs.
+pub fn annotate(interpreter: &Interpreter, color: bool) -> Option<String> {
+    let coverage = interpreter.coverage()?;
+    let source = interpreter.source();
+    let mut uncovered = vec![false; source.len()];
+    for index in coverage.uncovered() {
+        let span = interpreter.program()[index].span;
+        for offset in span.start..span.end {
+            uncovered[offset] = interpreter.is_command(source[offset]);
+        }
+    }
+
+    let mut annotated = String::with_capacity(source.len() * 2);
+    let mut start = 0;
+    for line in source.split_inclusive(|&c| c == '\n') {
+        let marks = &uncovered[start..start + line.len()];
+        let text = line.strip_suffix(&['\n']).unwrap_or(line);
+        if color {
+            for (&c, &mark) in text.iter().zip(marks) {
+                match mark {
+                    true => annotated.push_str(&format!("{UNCOVERED}{c}{RESET}")),
+                    false => annotated.push(c),
+                }
+            }
+            annotated.push('\n');
+        } else {
+            annotated.extend(text);
+            annotated.push('\n');
+            if marks.contains(&true) {
+                // Tabs are kept, so that the marks line up with the characters above them.
+                let marker: String = text.iter().zip(marks).map(|(&c, &mark)| if mark { '^' } else if c == '\t' { '\t' } else { ' ' }).collect();
+                annotated.push_str(marker.trim_end());
+                annotated.push('\n');
+            }
+        }
+        start += line.len();
+    }
+    Some(annotated)
+}
+
+// lcov tracefile of the program at `path` : each line with code, run as many times as the
+// instruction starting on it that ran the most.
+pub fn to_lcov(interpreter: &Interpreter, path: &str) -> Option<String> {
+    let coverage = interpreter.coverage()?;
+    let mut lines: Vec<(usize, u64)> = Vec::new();
+    for (index, inst) in interpreter.program().iter().enumerate() {
+        let count = coverage.count(index);
+        match lines.last_mut() {
+            Some((line, most)) if *line == inst.span.line => *most = (*most).max(count),
+            _ => lines.push((inst.span.line, count)),
+        }
+    }
+
+    let mut lcov = format!("TN:\nSF:{path}\n");
+    for (line, count) in &lines {
+        lcov.push_str(&format!("DA:{line},{count}\n"));
+    }
+    lcov.push_str(&format!("LH:{}\nLF:{}\nend_of_record\n", lines.iter().filter(|(_, count)| *count > 0).count(), lines.len()));
+    Some(lcov)
+}
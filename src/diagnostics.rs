@@ -0,0 +1,126 @@
+use alloc::string::{String, ToString};
+#[cfg(feature = "sarif")]
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::analysis::{self, Edge, Lint};
+use crate::error::LoadError;
+use crate::interpreter::{Config, Interpreter, Span};
+#[cfg(feature = "sarif")]
+use crate::json::Value;
+
+// Every problem `check` knows about in one list : bracket errors, lints, pointer bounds warnings
+// and duplicate labels, each with the rule it comes from and its location in the source.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub rule: &'static str,
+    pub severity: Severity,
+    pub message: String,
+    pub span: Span,
+    // Position right after the last character of the span, as SARIF regions end.
+    pub end_line: usize,
+    pub end_column: usize,
+}
+
+pub const UNMATCHED_BRACKET: &str = "unmatched-bracket";
+pub const POINTER_UNDERFLOW: &str = "pointer-underflow";
+pub const POINTER_OVERFLOW: &str = "pointer-overflow";
+pub const DUPLICATE_LABEL: &str = "duplicate-label";
+
+// Collects the diagnostics of `code`, lints in `allow` left out. Unmatched brackets are reported
+// one by one and blanked out, so that the rest of the program is still linted and analyzed.
+pub fn collect(code: &str, config: Config, allow: &[Lint]) -> Vec<Diagnostic> {
+    let mut source: Vec<char> = code.chars().collect();
+    let mut diagnostics = Vec::new();
+    let mut interpreter = Interpreter::with_config(config);
+    let diagnostic = |rule, severity, message, span: Span| {
+        let (end_line, end_column) = end_of(code.chars().skip(span.start).take(span.end - span.start), span, config.tab_width);
+        Diagnostic { rule, severity, message, span, end_line, end_column }
+    };
+
+    loop {
+        match interpreter.load_program_from_str(&source.iter().collect::<String>()) {
+            Ok(()) => break,
+            Err(e @ LoadError::UnmatchedBracket { span, .. }) => {
+                diagnostics.push(diagnostic(UNMATCHED_BRACKET, Severity::Error, e.to_string(), span));
+                source[span.start] = ' ';
+            },
+            Err(_) => unreachable!("programs given as text only fail on brackets"),
+        }
+    }
+
+    for warning in analysis::lint(&interpreter, allow) {
+        diagnostics.push(diagnostic(warning.lint.name(), Severity::Warning, warning.to_string(), warning.span));
+    }
+    for warning in analysis::pointer_bounds(&interpreter) {
+        let rule = if warning.edge == Edge::Left { POINTER_UNDERFLOW } else { POINTER_OVERFLOW };
+        diagnostics.push(diagnostic(rule, Severity::Warning, warning.to_string(), warning.span));
+    }
+    for duplicate in analysis::duplicate_labels(&interpreter) {
+        diagnostics.push(diagnostic(DUPLICATE_LABEL, Severity::Warning, duplicate.to_string(), duplicate.label.span));
+    }
+
+    diagnostics
+}
+
+// Line and column following the characters of `span`, counted as the lexer does.
+fn end_of(chars: impl Iterator<Item = char>, span: Span, tab_width: usize) -> (usize, usize) {
+    let (mut line, mut column) = (span.line, span.column);
+    for c in chars {
+        match c {
+            '\n' => (line, column) = (line + 1, 1),
+            '\t' => column += tab_width - (column - 1) % tab_width,
+            _ => column += 1,
+        }
+    }
+    (line, column)
+}
+
+// SARIF 2.1.0 log of `diagnostics` found in the file at `uri`, for code scanning tools.
+#[cfg(feature = "sarif")]
+pub fn diagnostics_to_sarif(diagnostics: &[Diagnostic], uri: &str) -> Value {
+    let object = |members: Vec<(&str, Value)>| Value::Object(members.into_iter().map(|(k, v)| (k.to_string(), v)).collect());
+    let text = |text: &str| object(vec![("text", Value::from(text))]);
+
+    let mut rules = vec![(UNMATCHED_BRACKET, "Brackets must be balanced")];
+    rules.extend(Lint::ALL.map(|lint| (lint.name(), lint.description())));
+    rules.push((POINTER_UNDERFLOW, "The memory pointer moves left of cell 0"));
+    rules.push((POINTER_OVERFLOW, "The memory pointer moves past the end of the tape"));
+    rules.push((DUPLICATE_LABEL, "A label is defined again later, which overrides it"));
+
+    let results: Vec<Value> = diagnostics.iter().map(|diagnostic| {
+        let span = diagnostic.span;
+        let region = object(vec![
+            ("startLine", Value::from(span.line)),
+            ("startColumn", Value::from(span.column)),
+            ("endLine", Value::from(diagnostic.end_line)),
+            ("endColumn", Value::from(diagnostic.end_column)),
+            ("charOffset", Value::from(span.start)),
+            ("charLength", Value::from(span.end - span.start)),
+        ]);
+        let location = object(vec![("physicalLocation", object(vec![("artifactLocation", object(vec![("uri", Value::from(uri))])), ("region", region)]))]);
+        object(vec![
+            ("ruleId", Value::from(diagnostic.rule)),
+            ("ruleIndex", Value::from(rules.iter().position(|(id, _)| *id == diagnostic.rule).unwrap())),
+            ("level", Value::from(if diagnostic.severity == Severity::Error { "error" } else { "warning" })),
+            ("message", text(&diagnostic.message)),
+            ("locations", Value::Array(vec![location])),
+        ])
+    }).collect();
+
+    let rules = rules.into_iter().map(|(id, description)| object(vec![("id", Value::from(id)), ("shortDescription", text(description))]));
+    let driver = object(vec![("name", Value::from("sac")), ("version", Value::from(env!("CARGO_PKG_VERSION"))), ("rules", Value::Array(rules.collect()))]);
+
+    object(vec![
+        ("$schema", Value::from("https://json.schemastore.org/sarif-2.1.0.json")),
+        ("version", Value::from("2.1.0")),
+        ("runs", Value::Array(vec![object(vec![("tool", object(vec![("driver", driver)])), ("results", Value::Array(results))])])),
+    ])
+}
@@ -0,0 +1,278 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::{BufReader, Cursor, Read, Write};
+use std::mem;
+
+use crate::buffer::OutputBuffer;
+use crate::error::{LoadError, RuntimeError};
+use crate::interpreter::{Config, Interpreter, Span};
+use crate::verify::execute;
+
+// Runs two programs side by side on the same input, comparing their outputs byte by byte as they
+// are printed, so that neither output is ever held in memory.
+
+const CONTEXT_BYTES: usize = 8;
+
+#[derive(Debug)]
+pub enum Comparison {
+    Identical { bytes: u64 },
+    OutputDiffers(OutputDivergence),
+    // Same output, but only one of the programs failed, or they failed differently.
+    StatusDiffers { bytes: u64, old: Option<RuntimeError>, new: Option<RuntimeError> },
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct OutputDivergence {
+    pub offset: u64,
+    pub context: Vec<u8>, // The bytes before `offset`, common to both outputs.
+    pub old: Vec<u8>,     // Bytes from `offset` on, empty when the output ended there.
+    pub new: Vec<u8>,
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let status = |error: &Option<RuntimeError>| error.as_ref().map_or(String::from("halted"), ToString::to_string);
+        match self {
+            Comparison::Identical { bytes } => write!(f, "identical ({bytes} bytes)"),
+            Comparison::OutputDiffers(divergence) => write!(f, "{divergence}"),
+            Comparison::StatusDiffers { bytes, old, new } => {
+                write!(f, "Same output ({bytes} bytes) but halt statuses differ\n  old : {}\n  new : {}", status(old), status(new))
+            },
+        }
+    }
+}
+
+impl fmt::Display for OutputDivergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let byte = |bytes: &[u8]| bytes.first().map_or(String::from("end of output"), |b| format!("{b:#04x}"));
+        writeln!(f, "Outputs differ at byte {} : expected {}, got {}", self.offset, byte(&self.old), byte(&self.new))?;
+        write!(f, "  old : {}\n  new : {}", window(&self.context, &self.old), window(&self.context, &self.new))
+    }
+}
+
+// Hex dump of the context and the diverging bytes, the first of them bracketed, followed by the
+// same bytes as printable characters.
+fn window(context: &[u8], rest: &[u8]) -> String {
+    let mut hex: Vec<String> = context.iter().map(|b| format!("{b:02x}")).collect();
+    match rest.split_first() {
+        Some((first, after)) => {
+            hex.push(format!("[{first:02x}]"));
+            hex.extend(after.iter().map(|b| format!("{b:02x}")));
+        },
+        None => hex.push(String::from("[]")),
+    }
+
+    let printable: String = context.iter().chain(rest).map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' }).collect();
+    format!("{:<width$} |{printable}|", hex.join(" "), width = (2 * CONTEXT_BYTES + 1) * 3 + 1)
+}
+
+struct Side {
+    interpreter: Interpreter,
+    output: OutputBuffer,
+    status: Option<Result<(), RuntimeError>>,
+}
+
+impl Side {
+    fn new(code: &str, input: &[u8], config: Config) -> Result<Side, LoadError> {
+        let output = OutputBuffer::new();
+        let mut interpreter = Interpreter::with_config(config);
+        interpreter.set_input(Cursor::new(input.to_vec()));
+        interpreter.set_output(output.clone());
+        interpreter.load_program_from_str(code)?;
+        Ok(Side { interpreter, output, status: None })
+    }
+
+    // Runs until the next output byte, `None` once the program halted.
+    fn next_byte(&mut self) -> Option<u8> {
+        let mut byte = [0u8];
+        loop {
+            if self.output.read_into(&mut byte) == 1 {
+                return Some(byte[0]);
+            }
+            if self.status.is_some() {
+                return None;
+            }
+            if self.interpreter.is_halted() {
+                self.status = Some(Ok(()));
+            } else if let Err(e) = self.interpreter.step() {
+                self.status = Some(Err(e));
+            }
+        }
+    }
+
+    fn take(&mut self, count: usize) -> Vec<u8> {
+        (0..count).map_while(|_| self.next_byte()).collect()
+    }
+}
+
+// Runs both programs with the same input and configuration until their outputs diverge or both
+// halt.
+pub fn diff(old: &str, new: &str, input: &[u8], config: Config) -> Result<Comparison, LoadError> {
+    let mut old = Side::new(old, input, config)?;
+    let mut new = Side::new(new, input, config)?;
+    let mut context = VecDeque::with_capacity(CONTEXT_BYTES);
+    let mut offset = 0u64;
+
+    loop {
+        match (old.next_byte(), new.next_byte()) {
+            (None, None) => break,
+            (Some(a), Some(b)) if a == b => {
+                if context.len() == CONTEXT_BYTES {
+                    context.pop_front();
+                }
+                context.push_back(a);
+                offset += 1;
+            },
+            (a, b) => {
+                let rest = |first: Option<u8>, side: &mut Side| first.into_iter().chain(side.take(CONTEXT_BYTES)).collect();
+                let divergence = OutputDivergence { offset, context: context.into(), old: rest(a, &mut old), new: rest(b, &mut new) };
+                return Ok(Comparison::OutputDiffers(divergence));
+            },
+        }
+    }
+
+    let (old, new) = (old.status.and_then(Result::err), new.status.and_then(Result::err));
+    Ok(if same_status(&old, &new) { Comparison::Identical { bytes: offset } } else { Comparison::StatusDiffers { bytes: offset, old, new } })
+}
+
+// Spans differ between two programs, so errors only need to be of the same kind.
+fn same_status(a: &Option<RuntimeError>, b: &Option<RuntimeError>) -> bool {
+    match (a, b) {
+        (None, None) => true,
+        (Some(a), Some(b)) => mem::discriminant(a) == mem::discriminant(b),
+        _ => false,
+    }
+}
+
+// Outcome of running two programs on the same set of inputs. This is black box testing, programs
+// agreeing on the inputs tried may still differ on others.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Equivalence {
+    Equivalent,
+    // Index of the first input on which the outputs differ, or only one program failed, or each in
+    // its own way.
+    Differs { input: usize },
+    // Index of the first input on which a program reached the step limit before any difference
+    // showed, when no input made them differ.
+    Inconclusive { input: usize },
+}
+
+impl Equivalence {
+    pub fn is_equivalent(&self) -> bool {
+        *self == Equivalence::Equivalent
+    }
+}
+
+// Runs both programs on each input, at most `step_limit` instructions each time.
+pub fn programs_equivalent(a: &str, b: &str, inputs: &[&[u8]], config: Config, step_limit: u64) -> Result<Equivalence, LoadError> {
+    let run = |code: &str, input: &[u8]| {
+        let mut interpreter = Interpreter::with_config(config);
+        interpreter.set_step_limit(step_limit);
+        execute(&mut interpreter, code, input)
+    };
+    let timed_out = |error: &Option<RuntimeError>| matches!(error, Some(RuntimeError::StepLimitExceeded { .. }));
+    let mut inconclusive = None;
+
+    for (index, input) in inputs.iter().enumerate() {
+        let (a, b) = (run(a, input)?, run(b, input)?);
+        if timed_out(&a.error) || timed_out(&b.error) {
+            // A program stopped early may still print what the other one did.
+            let common = a.output.len().min(b.output.len());
+            let prefix_differs = a.output[..common] != b.output[..common];
+            let shorter_halted = (a.output.len() < b.output.len() && !timed_out(&a.error)) || (b.output.len() < a.output.len() && !timed_out(&b.error));
+            if prefix_differs || shorter_halted {
+                return Ok(Equivalence::Differs { input: index });
+            }
+            inconclusive.get_or_insert(index);
+            continue;
+        }
+        if a.output != b.output || !same_status(&a.error, &b.error) {
+            return Ok(Equivalence::Differs { input: index });
+        }
+    }
+
+    Ok(inconclusive.map_or(Equivalence::Equivalent, |input| Equivalence::Inconclusive { input }))
+}
+
+// Byte of output differing from a reference output. `expected` is `None` for bytes printed past its
+// end, and `got` for the bytes of the reference the program never printed.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Mismatch {
+    pub offset: u64,
+    pub expected: Option<u8>,
+    pub got: Option<u8>,
+    pub span: Option<Span>, // The `.` which printed `got`.
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let byte = |byte: Option<u8>| byte.map_or(String::from("end of output"), |b| format!("{b:#04x}"));
+        write!(f, "Output differs at byte {} : expected {}, got {}", self.offset, byte(self.expected), byte(self.got))?;
+        match self.span {
+            Some(span) => write!(f, ", printed at {span}"),
+            None => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct CheckedRun {
+    pub bytes: u64, // Printed by the program.
+    pub mismatches: Vec<Mismatch>,
+    pub error: Option<RuntimeError>,
+}
+
+// Runs the loaded program, checking every byte it prints against `expected` as soon as it is printed,
+// and passing it on to `output`. The run stops at the first mismatch unless `keep_going`, in which
+// case every differing byte is reported. Failures to read `expected` or to write `output` end the
+// run with an I/O error.
+pub fn compare_against<R: Read, W: Write>(interpreter: &mut Interpreter, expected: R, mut output: W, keep_going: bool) -> CheckedRun {
+    let printed = OutputBuffer::new();
+    interpreter.set_output(printed.clone());
+    // Each byte must come out with the `.` printing it.
+    interpreter.set_output_buffer_size(0);
+    let mut expected = BufReader::new(expected).bytes();
+    let mut run = CheckedRun { bytes: 0, mismatches: Vec::new(), error: None };
+
+    while !interpreter.is_halted() && (keep_going || run.mismatches.is_empty()) {
+        let instruction = interpreter.instruction_pointer();
+        if let Err(e) = interpreter.step() {
+            run.error = Some(e);
+            break;
+        }
+        if printed.is_empty() {
+            continue;
+        }
+
+        let bytes = printed.take();
+        if let Err(e) = output.write_all(&bytes).and_then(|()| output.flush()) {
+            run.error = Some(RuntimeError::Io(e));
+            return run;
+        }
+        let span = interpreter.program()[instruction].span;
+        for got in bytes {
+            let expected = match expected.next().transpose() {
+                Ok(byte) => byte,
+                Err(e) => {
+                    run.error = Some(RuntimeError::Io(e));
+                    return run;
+                },
+            };
+            if expected != Some(got) && (keep_going || run.mismatches.is_empty()) {
+                run.mismatches.push(Mismatch { offset: run.bytes, expected, got: Some(got), span: Some(span) });
+            }
+            run.bytes += 1;
+        }
+    }
+
+    // Bytes of the reference left over once the program halted or failed.
+    if run.mismatches.is_empty() || keep_going {
+        match expected.next().transpose() {
+            Ok(Some(byte)) => run.mismatches.push(Mismatch { offset: run.bytes, expected: Some(byte), got: None, span: None }),
+            Ok(None) => (),
+            Err(e) => run.error = run.error.take().or(Some(RuntimeError::Io(e))),
+        }
+    }
+
+    run
+}
@@ -0,0 +1,92 @@
+use alloc::string::String;
+use core::fmt;
+
+// Generates programs printing a given text. Cells are printed as the character with the same code
+// point, so any text made of characters up to U+00FF can be printed, one cell value per character.
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EncodeStyle {
+    // Runs of `+` or `-` from one character to the next.
+    Naive,
+    // Multiplication loops on a scratch cell when they are shorter than runs.
+    #[default]
+    Loops,
+}
+
+impl EncodeStyle {
+    pub fn from_name(name: &str) -> Option<EncodeStyle> {
+        match name {
+            "naive" => Some(EncodeStyle::Naive),
+            "loops" => Some(EncodeStyle::Loops),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Unprintable {
+    pub character: char,
+    pub index: usize, // In characters.
+}
+
+impl fmt::Display for Unprintable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Character {:?} at index {} can't be printed, cells only go up to U+00FF", self.character, self.index)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Unprintable {}
+
+// Program printing `text`. The generated program only uses cell 0, and cell 1 as the loop counter
+// of the loops style.
+pub fn encode(text: &str, style: EncodeStyle) -> Result<String, Unprintable> {
+    let mut code = String::new();
+    let mut cell = 0u8;
+
+    for (index, character) in text.chars().enumerate() {
+        let target = u8::try_from(character).map_err(|_| Unprintable { character, index })?;
+
+        let mut best = adjust(target.wrapping_sub(cell), style);
+        if style == EncodeStyle::Loops {
+            let cleared = ["[-]", &adjust(target, style)].concat();
+            if cell != 0 && cleared.len() < best.len() {
+                best = cleared;
+            }
+        }
+
+        code.push_str(&best);
+        code.push('.');
+        cell = target;
+    }
+
+    Ok(code)
+}
+
+// Shortest code adding `delta` to cell 0, wrapping around.
+fn adjust(delta: u8, style: EncodeStyle) -> String {
+    let (sign, opposite, amount) = if delta <= 128 { ('+', '-', delta as usize) } else { ('-', '+', 256 - delta as usize) };
+    let mut best: String = core::iter::repeat_n(sign, amount).collect();
+
+    if style == EncodeStyle::Loops {
+        // `>` counter `[<` step `>-]<` remainder : counter * step + remainder = amount.
+        for counter in 2..=amount {
+            let step = amount / counter;
+            for step in [step, step + 1] {
+                let remainder = amount as isize - (counter * step) as isize;
+                if step == 0 || counter + step + remainder.unsigned_abs() + 7 >= best.len() {
+                    continue;
+                }
+                let mut code = String::from(">");
+                code.extend(core::iter::repeat_n('+', counter));
+                code.push_str("[<");
+                code.extend(core::iter::repeat_n(sign, step));
+                code.push_str(">-]<");
+                code.extend(core::iter::repeat_n(if remainder >= 0 { sign } else { opposite }, remainder.unsigned_abs()));
+                best = code;
+            }
+        }
+    }
+
+    best
+}
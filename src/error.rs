@@ -0,0 +1,278 @@
+use core::fmt;
+
+use crate::interpreter::Span;
+use crate::io::IoError;
+
+#[derive(Debug)]
+pub enum LoadError {
+    #[cfg(feature = "std")]
+    Io(std::io::Error),
+    InvalidUtf8 { offset: usize },
+    UnmatchedBracket { instruction: usize, span: Span },
+    IoForbidden { instruction: usize, span: Span }, // `.` or `,` with `Config::forbid_io`.
+    EditAtInstructionPointer { instruction: usize, span: Span }, // See `Interpreter::edit_program`.
+    ProgramTooLarge { limit: usize }, // Over `Config::max_source_bytes`.
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(feature = "std")]
+            LoadError::Io(e) => write!(f, "Unable to read the program : {e}"),
+            LoadError::InvalidUtf8 { offset } => write!(f, "The program is not valid UTF-8 (byte {offset})"),
+            LoadError::UnmatchedBracket { instruction, span } => write!(f, "Unmatched bracket at {span} (instruction {instruction})"),
+            LoadError::IoForbidden { instruction, span } => write!(f, "I/O is forbidden, found at {span} (instruction {instruction})"),
+            LoadError::EditAtInstructionPointer { instruction, span } => {
+                write!(f, "Cannot edit the code of the next instruction to run, at {span} (instruction {instruction})")
+            },
+            LoadError::ProgramTooLarge { limit } => write!(f, "The program is larger than {limit} bytes"),
+        }
+    }
+}
+
+impl core::error::Error for LoadError {}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for LoadError {
+    fn from(e: std::io::Error) -> LoadError {
+        LoadError::Io(e)
+    }
+}
+
+// Broken invariant of a `Program`, as found by `Program::validate`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InvalidProgram {
+    pub instruction: usize,
+    pub reason: &'static str,
+}
+
+impl fmt::Display for InvalidProgram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid program at instruction {} : {}", self.instruction, self.reason)
+    }
+}
+
+impl core::error::Error for InvalidProgram {}
+
+// Why `ProgramBuilder::build` couldn't make a program, with the index of the `[` at fault.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BuildError {
+    UnclosedLoop { instruction: usize },
+    MisnestedLoop { instruction: usize }, // Closed while a loop inside it was still open.
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildError::UnclosedLoop { instruction } => write!(f, "Loop starting at instruction {instruction} is never closed"),
+            BuildError::MisnestedLoop { instruction } => write!(f, "Loop starting at instruction {instruction} is closed before the loops inside it"),
+        }
+    }
+}
+
+impl core::error::Error for BuildError {}
+
+// Why `Interpreter::tape_as_string` couldn't read a string from the tape.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TapeStringError {
+    OutOfRange { end: usize, tape_size: usize },
+    InvalidUtf8 { cell: usize }, // First cell of the invalid sequence.
+}
+
+impl fmt::Display for TapeStringError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TapeStringError::OutOfRange { end, tape_size } => write!(f, "Cells up to {end} are past the end of the tape ({tape_size} cells)"),
+            TapeStringError::InvalidUtf8 { cell } => write!(f, "The tape is not valid UTF-8 (cell {cell})"),
+        }
+    }
+}
+
+impl core::error::Error for TapeStringError {}
+
+// Why `image::to_pgm` or `Interpreter::tape_as_pgm` couldn't make an image.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImageError {
+    Empty, // A width or height of 0.
+    SizeMismatch { width: usize, height: usize, bytes: usize },
+    OutOfRange { end: usize, tape_size: usize },
+}
+
+impl fmt::Display for ImageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImageError::Empty => write!(f, "Images need a width and a height of at least 1 pixel"),
+            ImageError::SizeMismatch { width, height, bytes } => {
+                write!(f, "A {width}x{height} image needs {} bytes, got {bytes}", width.saturating_mul(*height))
+            },
+            ImageError::OutOfRange { end, tape_size } => write!(f, "Cells up to {end} are past the end of the tape ({tape_size} cells)"),
+        }
+    }
+}
+
+impl core::error::Error for ImageError {}
+
+#[derive(Debug)]
+pub enum RuntimeError {
+    Io(IoError),
+    EndOfInput { instruction: usize, span: Span },
+    InputTimeout { instruction: usize, span: Span }, // A `TimeoutInput` waited too long, with `OnInputTimeout::Error`.
+    Cancelled { instruction: usize, span: Span },
+    LoopCapExceeded { loop_index: usize, span: Span },
+    StepLimitExceeded { instruction: usize, span: Span },
+    OutputLimitExceeded { instruction: usize, span: Span },
+    TimeLimitExceeded { instruction: usize, span: Span },
+    OutOfGas { instruction: usize, span: Span },
+    PointerUnderflow { instruction: usize, span: Span },
+    PointerOverflow { instruction: usize, span: Span },
+    CellUnderflow { instruction: usize, span: Span, cell: usize }, // With `WrapPolicy::Trap`.
+    CellOverflow { instruction: usize, span: Span, cell: usize },  // Also with `WrapPolicy::Trap`.
+    NoProgress { loop_index: usize, span: Span, period: u64 }, // Found by hang detection.
+    InvalidUtf8Input { instruction: usize, span: Span },  // With `InvalidUtf8::Fail`.
+    InvalidUtf8Output { instruction: usize, span: Span }, // Also with `InvalidUtf8::Fail`.
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuntimeError::Io(e) => write!(f, "I/O failure : {e}"),
+            RuntimeError::EndOfInput { instruction, span } => write!(f, "No input left to read at {span} (instruction {instruction})"),
+            RuntimeError::InputTimeout { instruction, span } => write!(f, "No input arrived in time at {span} (instruction {instruction})"),
+            RuntimeError::Cancelled { instruction, span } => write!(f, "Execution cancelled at {span} (instruction {instruction})"),
+            RuntimeError::LoopCapExceeded { loop_index, span } => {
+                write!(f, "Loop starting at {span} (instruction {loop_index}) exceeded its iteration cap")
+            },
+            RuntimeError::StepLimitExceeded { instruction, span } => write!(f, "Step limit reached at {span} (instruction {instruction})"),
+            RuntimeError::OutputLimitExceeded { instruction, span } => write!(f, "Output limit reached at {span} (instruction {instruction})"),
+            RuntimeError::TimeLimitExceeded { instruction, span } => write!(f, "Time limit reached at {span} (instruction {instruction})"),
+            RuntimeError::OutOfGas { instruction, span } => write!(f, "Out of gas at {span} (instruction {instruction})"),
+            RuntimeError::PointerUnderflow { instruction, span } => {
+                write!(f, "Memory pointer underflow at {span} (instruction {instruction}) : the pointer cannot move left of cell 0")
+            },
+            RuntimeError::PointerOverflow { instruction, span } => {
+                write!(f, "Memory pointer overflow at {span} (instruction {instruction}) : the pointer cannot move past the end of the tape")
+            },
+            RuntimeError::CellUnderflow { instruction, span, cell } => {
+                write!(f, "Cell underflow at {span} (instruction {instruction}) : cell {cell} would go below 0")
+            },
+            RuntimeError::CellOverflow { instruction, span, cell } => {
+                write!(f, "Cell overflow at {span} (instruction {instruction}) : cell {cell} would go past 255")
+            },
+            RuntimeError::NoProgress { loop_index, span, period } => {
+                write!(f, "Loop starting at {span} (instruction {loop_index}) never ends : no progress possible, state repeats every ")?;
+                match period {
+                    1 => write!(f, "step"),
+                    _ => write!(f, "{period} steps"),
+                }
+            },
+            RuntimeError::InvalidUtf8Input { instruction, span } => write!(f, "Invalid UTF-8 in the input read at {span} (instruction {instruction})"),
+            RuntimeError::InvalidUtf8Output { instruction, span } => write!(f, "Invalid UTF-8 printed at {span} (instruction {instruction})"),
+        }
+    }
+}
+
+impl RuntimeError {
+    // Names of the variants, as returned by `kind`.
+    pub const KINDS: [&'static str; 16] = [
+        "io",
+        "end-of-input",
+        "input-timeout",
+        "cancelled",
+        "loop-cap-exceeded",
+        "step-limit-exceeded",
+        "output-limit-exceeded",
+        "time-limit-exceeded",
+        "out-of-gas",
+        "pointer-underflow",
+        "pointer-overflow",
+        "no-progress",
+        "invalid-utf8-input",
+        "invalid-utf8-output",
+        "cell-underflow",
+        "cell-overflow",
+    ];
+
+    pub fn kind(&self) -> &'static str {
+        let index = match self {
+            RuntimeError::Io(_) => 0,
+            RuntimeError::EndOfInput { .. } => 1,
+            RuntimeError::InputTimeout { .. } => 2,
+            RuntimeError::Cancelled { .. } => 3,
+            RuntimeError::LoopCapExceeded { .. } => 4,
+            RuntimeError::StepLimitExceeded { .. } => 5,
+            RuntimeError::OutputLimitExceeded { .. } => 6,
+            RuntimeError::TimeLimitExceeded { .. } => 7,
+            RuntimeError::OutOfGas { .. } => 8,
+            RuntimeError::PointerUnderflow { .. } => 9,
+            RuntimeError::PointerOverflow { .. } => 10,
+            RuntimeError::NoProgress { .. } => 11,
+            RuntimeError::InvalidUtf8Input { .. } => 12,
+            RuntimeError::InvalidUtf8Output { .. } => 13,
+            RuntimeError::CellUnderflow { .. } => 14,
+            RuntimeError::CellOverflow { .. } => 15,
+        };
+        RuntimeError::KINDS[index]
+    }
+
+    // Source location of the instruction that failed, if any.
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            RuntimeError::Io(_) => None,
+            RuntimeError::EndOfInput { span, .. }
+            | RuntimeError::InputTimeout { span, .. }
+            | RuntimeError::Cancelled { span, .. }
+            | RuntimeError::LoopCapExceeded { span, .. }
+            | RuntimeError::StepLimitExceeded { span, .. }
+            | RuntimeError::OutputLimitExceeded { span, .. }
+            | RuntimeError::TimeLimitExceeded { span, .. }
+            | RuntimeError::OutOfGas { span, .. }
+            | RuntimeError::PointerUnderflow { span, .. }
+            | RuntimeError::PointerOverflow { span, .. }
+            | RuntimeError::CellUnderflow { span, .. }
+            | RuntimeError::CellOverflow { span, .. }
+            | RuntimeError::NoProgress { span, .. }
+            | RuntimeError::InvalidUtf8Input { span, .. }
+            | RuntimeError::InvalidUtf8Output { span, .. } => Some(*span),
+        }
+    }
+}
+
+impl core::error::Error for RuntimeError {}
+
+impl From<IoError> for RuntimeError {
+    fn from(e: IoError) -> RuntimeError {
+        RuntimeError::Io(e)
+    }
+}
+
+// Traces that can't be read, or replayed on the loaded program.
+#[derive(Debug)]
+pub enum TraceError {
+    NotATrace,
+    UnsupportedVersion { version: u32 },
+    Truncated,
+    OtherProgram, // Recorded for another program, or with other optimizations.
+    OutOfProgram { step: u64, instruction: usize },
+    Runtime(RuntimeError), // The replayed run failed, as the recorded one did.
+}
+
+impl fmt::Display for TraceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TraceError::NotATrace => write!(f, "Not a trace"),
+            TraceError::UnsupportedVersion { version } => write!(f, "Trace format version {version} is not supported"),
+            TraceError::Truncated => write!(f, "The trace is truncated"),
+            TraceError::OtherProgram => write!(f, "The trace was recorded for another program, or with other optimizations"),
+            TraceError::OutOfProgram { step, instruction } => write!(f, "Step {step} of the trace runs instruction {instruction}, past the end of the program"),
+            TraceError::Runtime(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl core::error::Error for TraceError {}
+
+impl From<RuntimeError> for TraceError {
+    fn from(e: RuntimeError) -> TraceError {
+        TraceError::Runtime(e)
+    }
+}
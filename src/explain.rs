@@ -0,0 +1,292 @@
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::analysis::{displacements, is_clear_loop};
+use crate::interpreter::{IRInstruction, IRInstructionKind, Interpreter};
+
+// Outline of a program in plain English, one line per segment : runs of arithmetic and moves, I/O
+// and loops, with loop bodies indented below their loop. Clear loops and multiplication loops are
+// summed up in one line, as the optimizer sees them.
+
+// What is known of the tape at some point of the program. Positions are counted from an origin,
+// which is cell 0 until a loop or a custom instruction leaves the pointer somewhere unknown.
+#[derive(Clone)]
+struct Knowledge {
+    origin_known: bool,
+    pointer: i64,
+    values: BTreeMap<i64, Option<u8>>, // `None` for cells written with unknown values.
+    zero_elsewhere: bool,              // Whether the cells missing from `values` hold 0.
+}
+
+impl Knowledge {
+    fn value(&self, cell: i64) -> Option<u8> {
+        match self.values.get(&cell) {
+            Some(&value) => value,
+            None if self.zero_elsewhere => Some(0),
+            None => None,
+        }
+    }
+
+    // Name of the cell `offset` cells away from the pointer.
+    fn name(&self, offset: i64) -> String {
+        match offset {
+            _ if self.origin_known => format!("cell {}", self.pointer + offset),
+            0 => String::from("the current cell"),
+            1 => String::from("the next cell"),
+            -1 => String::from("the previous cell"),
+            2.. => format!("the cell {offset} to the right"),
+            _ => format!("the cell {} to the left", -offset),
+        }
+    }
+
+    // Nothing known but the value of the current cell, as after a loop.
+    fn forget(&mut self, keep_position: bool) {
+        if !keep_position {
+            self.origin_known = false;
+            self.pointer = 0;
+        }
+        self.values.clear();
+        self.zero_elsewhere = false;
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Effect {
+    Add(u8),
+    Set(u8),
+}
+
+// Effects of a run of arithmetic and moves on each cell it touches, in the order they are first
+// touched, with offsets from the pointer before the run.
+#[derive(Default)]
+struct Run {
+    effects: Vec<(i64, Effect)>,
+    at: i64,
+}
+
+impl Run {
+    fn push(&mut self, inst: IRInstruction) -> bool {
+        let effect = match inst.kind {
+            IRInstructionKind::IncrementPointer => {
+                self.at += inst.operand.unwrap() as i64;
+                return true;
+            },
+            IRInstructionKind::DecrementPointer => {
+                self.at -= inst.operand.unwrap() as i64;
+                return true;
+            },
+            IRInstructionKind::IncrementByte => Effect::Add(inst.operand.unwrap() as u8),
+            IRInstructionKind::DecrementByte => Effect::Add((inst.operand.unwrap() as u8).wrapping_neg()),
+            IRInstructionKind::SetZero => Effect::Set(0),
+            IRInstructionKind::SetConst => Effect::Set(inst.operand.unwrap() as u8),
+            _ => return false,
+        };
+        self.apply(effect);
+        true
+    }
+
+    fn apply(&mut self, effect: Effect) {
+        let at = self.at;
+        match self.effects.iter_mut().find(|(offset, _)| *offset == at) {
+            Some((_, current)) => {
+                *current = match (*current, effect) {
+                    (Effect::Add(a), Effect::Add(b)) => Effect::Add(a.wrapping_add(b)),
+                    (Effect::Set(a), Effect::Add(b)) => Effect::Set(a.wrapping_add(b)),
+                    (_, set) => set,
+                }
+            },
+            None => self.effects.push((at, effect)),
+        }
+    }
+
+    // Clauses describing the run, such as "set cell 0 to 8, move to cell 1".
+    fn describe(&self, state: &mut Knowledge) -> Vec<String> {
+        let mut clauses = Vec::new();
+
+        for &(offset, effect) in &self.effects {
+            let cell = state.pointer + offset;
+            let known = state.value(cell);
+            let value = match effect {
+                Effect::Set(value) => Some(value),
+                Effect::Add(delta) => known.map(|k| k.wrapping_add(delta)),
+            };
+
+            match (effect, value) {
+                (Effect::Add(0), _) => (),
+                (_, Some(value)) => clauses.push(format!("set {} to {value}", state.name(offset))),
+                (Effect::Add(delta), None) => clauses.push(adjust("add", "subtract", delta, &state.name(offset))),
+                (Effect::Set(_), None) => unreachable!(),
+            }
+            state.values.insert(cell, value);
+        }
+
+        if self.at != 0 {
+            clauses.push(if state.origin_known {
+                format!("move to cell {}", state.pointer + self.at)
+            } else if self.at > 0 {
+                format!("move right by {}", self.at)
+            } else {
+                format!("move left by {}", -self.at)
+            });
+            state.pointer += self.at;
+        }
+
+        clauses
+    }
+}
+
+// "add 3 to cell 1", or "subtract 3 from cell 1" when the delta wraps below 0.
+fn adjust(add: &str, subtract: &str, delta: u8, cell: &str) -> String {
+    if delta <= 128 {
+        format!("{add} {delta} to {cell}")
+    } else {
+        format!("{subtract} {} from {cell}", delta.wrapping_neg())
+    }
+}
+
+// Effects of a loop body made only of arithmetic and moves, ending on the cell it tests, which it
+// changes by 1 : a multiplication loop, adding a multiple of the tested cell to other cells.
+fn multiplication(body: &[IRInstruction]) -> Option<Run> {
+    let mut run = Run::default();
+    for &inst in body {
+        if matches!(inst.kind, IRInstructionKind::SetZero | IRInstructionKind::SetConst) || !run.push(inst) {
+            return None;
+        }
+    }
+    let counter = run.effects.iter().find(|(offset, _)| *offset == 0).map(|&(_, effect)| effect);
+    (run.at == 0 && matches!(counter, Some(Effect::Add(1 | 255)))).then_some(run)
+}
+
+// Outline of the loaded program, two spaces of indentation per loop level. Bodies of loops nested
+// deeper than `max_depth` are left out.
+pub fn explain(interpreter: &Interpreter, max_depth: Option<usize>) -> String {
+    let program = interpreter.program();
+    let displacements = displacements(program);
+    let mut lines = Vec::new();
+    let mut state = Knowledge { origin_known: true, pointer: 0, values: BTreeMap::new(), zero_elsewhere: true };
+    let mut parents: Vec<Knowledge> = Vec::new(); // States before the loops being described.
+    let mut run = Run::default();
+    let mut index = 0usize;
+
+    let mut line = |depth: usize, text: String| lines.push(format!("{:indent$}{text}", "", indent = 2 * depth));
+
+    while index <= program.len() {
+        let depth = parents.len();
+
+        if let Some(&inst) = program.get(index) {
+            // The instructions a range stands for follow it, and are described instead.
+            if inst.kind.is_range() || run.push(inst) {
+                index += 1;
+                continue;
+            }
+            if is_clear_loop(&program[index..]) {
+                run.apply(Effect::Set(0));
+                index += 3;
+                continue;
+            }
+        }
+
+        let clauses = run.describe(&mut state);
+        if !clauses.is_empty() {
+            line(depth, clauses.join(", "));
+        }
+        run = Run::default();
+
+        let Some(&inst) = program.get(index) else { break };
+        let span = inst.span;
+        match inst.kind {
+            IRInstructionKind::PrintByteAsChar => {
+                let count = program[index..].iter().take_while(|i| i.kind == IRInstructionKind::PrintByteAsChar).count();
+                let character = state.value(state.pointer).filter(u8::is_ascii).map(|v| format!(" ({:?})", v as char)).unwrap_or_default();
+                let times = if count > 1 { format!(" {count} times") } else { String::new() };
+                line(depth, format!("print {}{character}{times}", state.name(0)));
+                index += count;
+                continue;
+            },
+            IRInstructionKind::ReadInputToByte => {
+                line(depth, format!("read a byte into {}", state.name(0)));
+                state.values.insert(state.pointer, None);
+            },
+            IRInstructionKind::Custom(token) => {
+                line(depth, format!("run the custom instruction {token:?} on {}", state.name(0)));
+                state.forget(false);
+            },
+            IRInstructionKind::Breakpoint => line(depth, String::from("breakpoint")),
+            IRInstructionKind::JumpIfZero | IRInstructionKind::If => {
+                let close = inst.operand.unwrap();
+                let body = &program[index + 1..close];
+                let displacement = displacements[index];
+                let once = inst.kind == IRInstructionKind::If;
+                let header = if once { format!("loop at {span} : runs once") } else { format!("loop at {span} : runs") };
+                let test = format!("{} {} ≠ 0", if once { "if" } else { "while" }, state.name(0));
+                let each = if once { "" } else { " each iteration" };
+
+                if let Some(multiplication) = multiplication(body) {
+                    let counter = multiplication.effects.iter().find(|(offset, _)| *offset == 0).unwrap().1;
+                    let iterations = state.value(state.pointer).map(|c| if counter == Effect::Add(255) { c } else { c.wrapping_neg() });
+                    let times = iterations.map(|n| format!(" {n} times")).unwrap_or_default();
+
+                    let mut changes: Vec<String> = multiplication
+                        .effects
+                        .iter()
+                        .filter(|&&(offset, _)| offset != 0)
+                        .chain(multiplication.effects.iter().filter(|&&(offset, _)| offset == 0))
+                        .filter_map(|&(offset, effect)| match effect {
+                            Effect::Add(0) | Effect::Set(_) => None,
+                            Effect::Add(delta) => Some(adjust("adds", "subtracts", delta, &state.name(offset))),
+                        })
+                        .collect();
+                    let last = changes.pop().unwrap();
+                    let changes = if changes.is_empty() { last } else { format!("{} and {last}", changes.join(", ")) };
+                    line(depth, format!("{header}{times} {test}, each iteration {changes}"));
+
+                    for &(offset, effect) in &multiplication.effects {
+                        let cell = state.pointer + offset;
+                        let value = match (effect, iterations, state.value(cell)) {
+                            _ if offset == 0 => Some(0),
+                            (Effect::Add(delta), Some(n), Some(value)) => Some(value.wrapping_add(delta.wrapping_mul(n))),
+                            _ => None,
+                        };
+                        state.values.insert(cell, value);
+                    }
+                    index = close + 1;
+                    continue;
+                }
+
+                let moving = match displacement {
+                    Some(0) => String::new(),
+                    Some(d) if d > 0 => format!(", moving right by {d}{each}"),
+                    Some(d) => format!(", moving left by {}{each}", -d),
+                    None => String::from(", moving the pointer"),
+                };
+                let scan = matches!(body, [only] if matches!(only.kind, IRInstructionKind::IncrementPointer | IRInstructionKind::DecrementPointer));
+
+                if scan || max_depth.is_some_and(|max| depth >= max) {
+                    let hidden = if scan { "" } else { " (body not shown)" };
+                    line(depth, format!("{header} {test}{moving}{hidden}"));
+                    state.forget(displacement == Some(0));
+                    state.values.insert(state.pointer, Some(0));
+                    index = close + 1;
+                    continue;
+                }
+
+                line(depth, format!("{header} {test}{moving}"));
+                let mut inside = state.clone();
+                inside.forget(displacement == Some(0));
+                parents.push(core::mem::replace(&mut state, inside));
+            },
+            IRInstructionKind::JumpIfNotZero | IRInstructionKind::EndIf => {
+                let displacement = displacements[inst.operand.unwrap()];
+                state = parents.pop().unwrap();
+                state.forget(displacement == Some(0));
+                state.values.insert(state.pointer, Some(0));
+            },
+            _ => unreachable!("runs are handled above"),
+        }
+        index += 1;
+    }
+
+    lines.join("\n")
+}
@@ -0,0 +1,268 @@
+use std::io;
+
+// Minimal gzip reader (RFC 1952 members wrapping RFC 1951 deflate streams), only what is
+// needed to load compressed programs.
+
+const MAX_BITS: usize = 15;
+
+const LENGTH_BASE: [u16; 29] = [3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258];
+const LENGTH_EXTRA: [u8; 29] = [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0];
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145, 8193, 12289,
+    16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13];
+const CODE_LENGTH_ORDER: [usize; 19] = [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15];
+
+const FLAG_HCRC: u8 = 0x02;
+const FLAG_EXTRA: u8 = 0x04;
+const FLAG_NAME: u8 = 0x08;
+const FLAG_COMMENT: u8 = 0x10;
+
+fn corrupt(what: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("corrupt gzip stream ({what})"))
+}
+
+pub fn is_gzip(bytes: &[u8]) -> bool {
+    bytes.starts_with(&[0x1f, 0x8b])
+}
+
+pub use crate::hash::crc32;
+
+// Decompresses every member of a gzip file, checking their CRC and size trailers.
+pub fn decompress(bytes: &[u8]) -> io::Result<Vec<u8>> {
+    decompress_at_most(bytes, usize::MAX)
+}
+
+// As `decompress`, failing with `io::ErrorKind::FileTooLarge` as soon as the output grows past
+// `limit` bytes, so that a small file can't expand to fill the memory.
+pub fn decompress_at_most(bytes: &[u8], limit: usize) -> io::Result<Vec<u8>> {
+    let mut output = Vec::new();
+    let mut position = 0usize;
+
+    while position < bytes.len() {
+        let member = &bytes[position..];
+        if member.len() < 18 || !is_gzip(member) || member[2] != 8 {
+            return Err(corrupt("bad header"));
+        }
+
+        let flags = member[3];
+        let mut header_end = 10usize;
+        if flags & FLAG_EXTRA != 0 {
+            let extra = member.get(header_end..header_end + 2).ok_or_else(|| corrupt("truncated header"))?;
+            header_end += 2 + u16::from_le_bytes([extra[0], extra[1]]) as usize;
+        }
+        for flag in [FLAG_NAME, FLAG_COMMENT] {
+            if flags & flag != 0 {
+                let terminator = member.get(header_end..).and_then(|rest| rest.iter().position(|&b| b == 0)).ok_or_else(|| corrupt("truncated header"))?;
+                header_end += terminator + 1;
+            }
+        }
+        if flags & FLAG_HCRC != 0 {
+            header_end += 2;
+        }
+
+        let start = output.len();
+        let mut reader = BitReader { data: member.get(header_end..).ok_or_else(|| corrupt("truncated header"))?, position: 0, bit_buffer: 0, bit_count: 0 };
+        inflate(&mut reader, &mut output, limit)?;
+
+        let trailer_start = header_end + reader.position;
+        let trailer = member.get(trailer_start..trailer_start + 8).ok_or_else(|| corrupt("truncated trailer"))?;
+        let crc = u32::from_le_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]);
+        let size = u32::from_le_bytes([trailer[4], trailer[5], trailer[6], trailer[7]]);
+        if crc != crc32(&output[start..]) || size != (output.len() - start) as u32 {
+            return Err(corrupt("checksum mismatch"));
+        }
+
+        position += trailer_start + 8;
+    }
+
+    Ok(output)
+}
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+    bit_buffer: u32,
+    bit_count: u32,
+}
+
+impl BitReader<'_> {
+    fn bits(&mut self, count: u32) -> io::Result<u32> {
+        while self.bit_count < count {
+            let byte = *self.data.get(self.position).ok_or_else(|| corrupt("unexpected end of data"))?;
+            self.position += 1;
+            self.bit_buffer |= (byte as u32) << self.bit_count;
+            self.bit_count += 8;
+        }
+
+        let value = self.bit_buffer & ((1u32 << count) - 1);
+        self.bit_buffer >>= count;
+        self.bit_count -= count;
+        Ok(value)
+    }
+
+    fn align(&mut self) {
+        self.bit_buffer = 0;
+        self.bit_count = 0;
+    }
+}
+
+struct Huffman {
+    counts: [u16; MAX_BITS + 1],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Huffman {
+        let mut counts = [0u16; MAX_BITS + 1];
+        for &length in lengths {
+            counts[length as usize] += 1;
+        }
+        counts[0] = 0;
+
+        let mut offsets = [0u16; MAX_BITS + 2];
+        for length in 1..=MAX_BITS {
+            offsets[length + 1] = offsets[length] + counts[length];
+        }
+
+        let mut symbols = vec![0u16; lengths.len()];
+        for (symbol, &length) in lengths.iter().enumerate() {
+            if length != 0 {
+                symbols[offsets[length as usize] as usize] = symbol as u16;
+                offsets[length as usize] += 1;
+            }
+        }
+
+        Huffman { counts, symbols }
+    }
+
+    // Canonical codes are read one bit at a time, which is plenty fast for program sources.
+    fn decode(&self, reader: &mut BitReader) -> io::Result<u16> {
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+
+        for length in 1..=MAX_BITS {
+            code |= reader.bits(1)? as i32;
+            let count = self.counts[length] as i32;
+            if code - count < first {
+                return self.symbols.get((index + code - first) as usize).copied().ok_or_else(|| corrupt("bad code"));
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+
+        Err(corrupt("bad code"))
+    }
+}
+
+fn too_large(limit: usize) -> io::Error {
+    io::Error::new(io::ErrorKind::FileTooLarge, format!("decompresses to more than {limit} bytes"))
+}
+
+fn inflate(reader: &mut BitReader, output: &mut Vec<u8>, limit: usize) -> io::Result<()> {
+    loop {
+        let last = reader.bits(1)? == 1;
+
+        match reader.bits(2)? {
+            0 => {
+                reader.align();
+                let header = reader.data.get(reader.position..reader.position + 4).ok_or_else(|| corrupt("truncated stored block"))?;
+                let length = u16::from_le_bytes([header[0], header[1]]);
+                if length != !u16::from_le_bytes([header[2], header[3]]) {
+                    return Err(corrupt("bad stored block length"));
+                }
+                reader.position += 4;
+                let block = reader.data.get(reader.position..reader.position + length as usize).ok_or_else(|| corrupt("truncated stored block"))?;
+                if output.len() + block.len() > limit {
+                    return Err(too_large(limit));
+                }
+                output.extend_from_slice(block);
+                reader.position += length as usize;
+            },
+            1 => {
+                let mut lengths = [0u8; 288];
+                lengths[..144].fill(8);
+                lengths[144..256].fill(9);
+                lengths[256..280].fill(7);
+                lengths[280..].fill(8);
+                inflate_block(reader, output, &Huffman::new(&lengths), &Huffman::new(&[5; 30]), limit)?;
+            },
+            2 => {
+                let (literals, distances) = read_dynamic_tables(reader)?;
+                inflate_block(reader, output, &literals, &distances, limit)?;
+            },
+            _ => return Err(corrupt("bad block type")),
+        }
+
+        if last {
+            return Ok(());
+        }
+    }
+}
+
+fn read_dynamic_tables(reader: &mut BitReader) -> io::Result<(Huffman, Huffman)> {
+    let literal_count = reader.bits(5)? as usize + 257;
+    let distance_count = reader.bits(5)? as usize + 1;
+    let code_length_count = reader.bits(4)? as usize + 4;
+
+    let mut code_lengths = [0u8; 19];
+    for &symbol in &CODE_LENGTH_ORDER[..code_length_count] {
+        code_lengths[symbol] = reader.bits(3)? as u8;
+    }
+    let code_length_huffman = Huffman::new(&code_lengths);
+
+    let mut lengths = Vec::with_capacity(literal_count + distance_count);
+    while lengths.len() < literal_count + distance_count {
+        let symbol = code_length_huffman.decode(reader)?;
+        let (value, repeat) = match symbol {
+            0..=15 => (symbol as u8, 1),
+            16 => (*lengths.last().ok_or_else(|| corrupt("repeat without a previous length"))?, 3 + reader.bits(2)?),
+            17 => (0, 3 + reader.bits(3)?),
+            _ => (0, 11 + reader.bits(7)?),
+        };
+        lengths.extend(std::iter::repeat_n(value, repeat as usize));
+    }
+
+    if lengths.len() != literal_count + distance_count {
+        return Err(corrupt("too many code lengths"));
+    }
+
+    Ok((Huffman::new(&lengths[..literal_count]), Huffman::new(&lengths[literal_count..])))
+}
+
+fn inflate_block(reader: &mut BitReader, output: &mut Vec<u8>, literals: &Huffman, distances: &Huffman, limit: usize) -> io::Result<()> {
+    loop {
+        if output.len() > limit {
+            return Err(too_large(limit));
+        }
+        let symbol = literals.decode(reader)? as usize;
+
+        match symbol {
+            0..=255 => output.push(symbol as u8),
+            256 => return Ok(()),
+            _ => {
+                let index = symbol - 257;
+                if index >= LENGTH_BASE.len() {
+                    return Err(corrupt("bad length code"));
+                }
+                let length = LENGTH_BASE[index] as usize + reader.bits(LENGTH_EXTRA[index] as u32)? as usize;
+
+                let index = distances.decode(reader)? as usize;
+                if index >= DISTANCE_BASE.len() {
+                    return Err(corrupt("bad distance code"));
+                }
+                let distance = DISTANCE_BASE[index] as usize + reader.bits(DISTANCE_EXTRA[index] as u32)? as usize;
+                if distance > output.len() {
+                    return Err(corrupt("distance too far back"));
+                }
+
+                // Copies byte by byte on purpose, overlapping references repeat recent output.
+                let start = output.len() - distance;
+                for i in 0..length {
+                    output.push(output[start + i]);
+                }
+            },
+        }
+    }
+}
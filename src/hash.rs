@@ -0,0 +1,276 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{self, Write as _};
+
+// Streaming digests of program output, so that huge outputs can be checked against a known good run
+// without being stored.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    Sha256,
+    Crc32,
+}
+
+impl HashAlgorithm {
+    pub fn from_name(name: &str) -> Option<HashAlgorithm> {
+        match name {
+            "sha256" => Some(HashAlgorithm::Sha256),
+            "crc32" => Some(HashAlgorithm::Crc32),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            HashAlgorithm::Sha256 => "sha256",
+            HashAlgorithm::Crc32 => "crc32",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Digest {
+    pub algorithm: HashAlgorithm,
+    pub hex: String,
+    pub bytes: u64, // Length of the hashed data.
+}
+
+impl fmt::Display for Digest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} ({} bytes)", self.algorithm.name(), self.hex, self.bytes)
+    }
+}
+
+#[derive(Clone)]
+pub struct Hasher {
+    state: State,
+    bytes: u64,
+}
+
+#[derive(Clone)]
+enum State {
+    Sha256(Sha256),
+    Crc32(Crc32),
+}
+
+impl Hasher {
+    pub fn new(algorithm: HashAlgorithm) -> Hasher {
+        let state = match algorithm {
+            HashAlgorithm::Sha256 => State::Sha256(Sha256::new()),
+            HashAlgorithm::Crc32 => State::Crc32(Crc32::new()),
+        };
+        Hasher { state, bytes: 0 }
+    }
+
+    pub fn update(&mut self, bytes: &[u8]) {
+        self.bytes += bytes.len() as u64;
+        match &mut self.state {
+            State::Sha256(sha) => sha.update(bytes),
+            State::Crc32(crc) => crc.update(bytes),
+        }
+    }
+
+    // Digest of everything hashed so far. Hashing can go on afterwards.
+    pub fn digest(&self) -> Digest {
+        let (algorithm, raw) = match &self.state {
+            State::Sha256(sha) => (HashAlgorithm::Sha256, sha.clone().finish().to_vec()),
+            State::Crc32(crc) => (HashAlgorithm::Crc32, crc.finish().to_be_bytes().to_vec()),
+        };
+        Digest { algorithm, hex: hex(&raw), bytes: self.bytes }
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    let mut hex = String::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        let _ = write!(hex, "{byte:02x}");
+    }
+    hex
+}
+
+// CRC-32 as used by gzip and zip (reflected polynomial 0xedb88320).
+#[derive(Clone)]
+pub struct Crc32(u32);
+
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut index = 0;
+    while index < 256 {
+        let mut crc = index as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xedb8_8320 } else { crc >> 1 };
+            bit += 1;
+        }
+        table[index] = crc;
+        index += 1;
+    }
+    table
+};
+
+impl Default for Crc32 {
+    fn default() -> Crc32 {
+        Crc32::new()
+    }
+}
+
+impl Crc32 {
+    pub fn new() -> Crc32 {
+        Crc32(!0)
+    }
+
+    pub fn update(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 >> 8) ^ CRC32_TABLE[((self.0 ^ byte as u32) & 0xff) as usize];
+        }
+    }
+
+    pub fn finish(&self) -> u32 {
+        !self.0
+    }
+}
+
+pub fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = Crc32::new();
+    crc.update(bytes);
+    crc.finish()
+}
+
+// SHA-256 (FIPS 180-4).
+#[derive(Clone)]
+pub struct Sha256 {
+    state: [u32; 8],
+    block: Vec<u8>, // Bytes waiting for a full 64 byte block.
+    length: u64,
+}
+
+const SHA256_K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5, 0xd807aa98, 0x12835b01, 0x243185be,
+    0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174, 0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa,
+    0x5cb0a9dc, 0x76f988da, 0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967, 0x27b70a85,
+    0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85, 0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3,
+    0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070, 0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f,
+    0x682e6ff3, 0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+impl Default for Sha256 {
+    fn default() -> Sha256 {
+        Sha256::new()
+    }
+}
+
+impl Sha256 {
+    pub fn new() -> Sha256 {
+        Sha256 {
+            state: [0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19],
+            block: Vec::with_capacity(64),
+            length: 0,
+        }
+    }
+
+    pub fn update(&mut self, mut bytes: &[u8]) {
+        self.length += bytes.len() as u64;
+
+        if !self.block.is_empty() {
+            let taken = bytes.len().min(64 - self.block.len());
+            self.block.extend_from_slice(&bytes[..taken]);
+            bytes = &bytes[taken..];
+            if self.block.len() < 64 {
+                return;
+            }
+            let block: [u8; 64] = self.block[..].try_into().unwrap();
+            self.compress(&block);
+            self.block.clear();
+        }
+
+        let mut blocks = bytes.chunks_exact(64);
+        for block in &mut blocks {
+            self.compress(block.try_into().unwrap());
+        }
+        self.block.extend_from_slice(blocks.remainder());
+    }
+
+    pub fn finish(mut self) -> [u8; 32] {
+        let bits = self.length.wrapping_mul(8);
+        let padding = if self.block.len() < 56 { 56 - self.block.len() } else { 120 - self.block.len() };
+        let mut tail = alloc::vec![0u8; padding + 8];
+        tail[0] = 0x80;
+        tail[padding..].copy_from_slice(&bits.to_be_bytes());
+        let length = self.length;
+        self.update(&tail);
+        self.length = length;
+
+        let mut digest = [0u8; 32];
+        for (chunk, word) in digest.chunks_exact_mut(4).zip(self.state) {
+            chunk.copy_from_slice(&word.to_be_bytes());
+        }
+        digest
+    }
+
+    fn compress(&mut self, block: &[u8; 64]) {
+        let mut w = [0u32; 64];
+        for (i, word) in block.chunks_exact(4).enumerate() {
+            w[i] = u32::from_be_bytes(word.try_into().unwrap());
+        }
+        for i in 16..64 {
+            let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+            let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+            w[i] = w[i - 16].wrapping_add(s0).wrapping_add(w[i - 7]).wrapping_add(s1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = self.state;
+        for i in 0..64 {
+            let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let choice = (e & f) ^ (!e & g);
+            let t1 = h.wrapping_add(s1).wrapping_add(choice).wrapping_add(SHA256_K[i]).wrapping_add(w[i]);
+            let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let majority = (a & b) ^ (a & c) ^ (b & c);
+            let t2 = s0.wrapping_add(majority);
+            (h, g, f, e, d, c, b, a) = (g, f, e, d.wrapping_add(t1), c, b, a, t1.wrapping_add(t2));
+        }
+
+        for (state, value) in self.state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+            *state = state.wrapping_add(value);
+        }
+    }
+}
+
+// `Write` adapter hashing everything written through it before passing it on. Clones share the
+// writer and the hasher, so one clone can be handed to the interpreter while another one reads the
+// digest once the program halted. Wrap `std::io::sink()` to only hash.
+#[cfg(feature = "std")]
+pub struct HashingWriter<W> {
+    shared: std::sync::Arc<std::sync::Mutex<(W, Hasher)>>,
+}
+
+#[cfg(feature = "std")]
+impl<W> Clone for HashingWriter<W> {
+    fn clone(&self) -> HashingWriter<W> {
+        HashingWriter { shared: self.shared.clone() }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: std::io::Write> HashingWriter<W> {
+    pub fn new(inner: W, algorithm: HashAlgorithm) -> HashingWriter<W> {
+        HashingWriter { shared: std::sync::Arc::new(std::sync::Mutex::new((inner, Hasher::new(algorithm)))) }
+    }
+
+    pub fn digest(&self) -> Digest {
+        self.shared.lock().unwrap_or_else(|e| e.into_inner()).1.digest()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: std::io::Write> std::io::Write for HashingWriter<W> {
+    fn write(&mut self, bytes: &[u8]) -> std::io::Result<usize> {
+        let (inner, hasher) = &mut *self.shared.lock().unwrap_or_else(|e| e.into_inner());
+        let written = inner.write(bytes)?;
+        hasher.update(&bytes[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.shared.lock().unwrap_or_else(|e| e.into_inner()).0.flush()
+    }
+}
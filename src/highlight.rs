@@ -0,0 +1,105 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+// Source listing with ANSI colors : commands colored by category, brackets by nesting depth and
+// comments dimmed. Unbalanced brackets don't stop the listing, they are shown in red.
+
+const RESET: &str = "\x1b[0m";
+const COMMENT: &str = "\x1b[2m";
+const POINTER: &str = "\x1b[36m";
+const ARITHMETIC: &str = "\x1b[32m";
+const IO: &str = "\x1b[33m";
+const ORPHAN: &str = "\x1b[1;31m";
+const BRACKETS: [&str; 4] = ["\x1b[95m", "\x1b[94m", "\x1b[96m", "\x1b[93m"];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HighlightOptions {
+    pub color: bool,        // Without colors, the source is printed as is.
+    pub line_numbers: bool, // Gutter with the line number before every line.
+}
+
+impl Default for HighlightOptions {
+    fn default() -> HighlightOptions {
+        HighlightOptions { color: true, line_numbers: false }
+    }
+}
+
+pub fn highlight(code: &str, options: HighlightOptions) -> String {
+    let chars: Vec<char> = code.chars().collect();
+    let depths = bracket_depths(&chars);
+    let lines = code.lines().count().max(1);
+    let width = lines.to_string().len();
+
+    let mut highlighted = String::with_capacity(code.len() * 2);
+    let mut style = None;
+    let mut line = 1;
+    let mut line_start = true;
+
+    for (index, &c) in chars.iter().enumerate() {
+        if line_start && options.line_numbers {
+            let gutter = format!("{line:>width$} | ");
+            if options.color {
+                highlighted.push_str(COMMENT);
+                highlighted.push_str(&gutter);
+                highlighted.push_str(RESET);
+            } else {
+                highlighted.push_str(&gutter);
+            }
+        }
+        line_start = c == '\n';
+
+        if options.color {
+            // Styles never span lines, so that every line can be read on its own.
+            let next = match c {
+                '\n' | '\r' => None,
+                '<' | '>' => Some(POINTER),
+                '+' | '-' => Some(ARITHMETIC),
+                '.' | ',' => Some(IO),
+                '[' | ']' => Some(depths[index].map_or(ORPHAN, |depth| BRACKETS[depth % BRACKETS.len()])),
+                _ if c.is_whitespace() => style,
+                _ => Some(COMMENT),
+            };
+            if next != style {
+                if style.is_some() {
+                    highlighted.push_str(RESET);
+                }
+                if let Some(next) = next {
+                    highlighted.push_str(next);
+                }
+                style = next;
+            }
+        }
+
+        highlighted.push(c);
+        if c == '\n' {
+            line += 1;
+        }
+    }
+
+    if style.is_some() {
+        highlighted.push_str(RESET);
+    }
+    highlighted
+}
+
+// Nesting depth of every bracket, `None` for brackets without a match and for other characters.
+fn bracket_depths(chars: &[char]) -> Vec<Option<usize>> {
+    let mut depths = alloc::vec![None; chars.len()];
+    let mut open = Vec::new();
+
+    for (index, &c) in chars.iter().enumerate() {
+        match c {
+            '[' => open.push(index),
+            ']' => {
+                if let Some(start) = open.pop() {
+                    depths[start] = Some(open.len());
+                    depths[index] = Some(open.len());
+                }
+            },
+            _ => (),
+        }
+    }
+
+    depths
+}
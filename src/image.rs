@@ -0,0 +1,20 @@
+use alloc::format;
+use alloc::vec::Vec;
+
+use crate::error::ImageError;
+
+// Binary PGM (Netpbm grayscale) images, for programs drawing on the tape or printing their pixels :
+// one byte per pixel, row after row from the top left, 0 black and 255 white.
+
+pub fn to_pgm(pixels: &[u8], width: usize, height: usize) -> Result<Vec<u8>, ImageError> {
+    if width == 0 || height == 0 {
+        return Err(ImageError::Empty);
+    }
+    if width.checked_mul(height) != Some(pixels.len()) {
+        return Err(ImageError::SizeMismatch { width, height, bytes: pixels.len() });
+    }
+
+    let mut image = format!("P5\n{width} {height}\n255\n").into_bytes();
+    image.extend_from_slice(pixels);
+    Ok(image)
+}
@@ -0,0 +1,86 @@
+use core::fmt;
+use std::io::Write;
+use std::sync::Mutex;
+
+// Spans and events for the phases of loading and running programs, reported to a `Subscriber` set
+// with `Interpreter::set_subscriber`. A span is entered before its phase and exited after it, with
+// the fields known by then, so the spans entered in between are its children. Forwarding them to
+// the `tracing` crate takes a newtype entering a `tracing::Span` in `enter` and leaving it in `exit`.
+//
+// Spans :
+// - `load` (`source_bytes`, then `instructions`), around the parsing of a program
+// - `jumps` (`instructions`), pairing the brackets, before and after the passes, inside `load`
+// - `pass` (`name` and `instructions_before`, then `instructions_after`), inside `load`
+// - `execute` (`steps` run so far, then `steps` run in the span), for `interpret`, `resume`, ...
+//
+// Events : `steps` (`steps`) every million steps of a run, and `runtime_error` (`kind`, `line` and
+// `column` when the error has a span, `message`).
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Value<'a> {
+    U64(u64),
+    Str(&'a str),
+}
+
+impl fmt::Display for Value<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::U64(n) => write!(f, "{n}"),
+            Value::Str(s) => write!(f, "{s:?}"),
+        }
+    }
+}
+
+pub type Fields<'a> = [(&'static str, Value<'a>)];
+
+pub trait Subscriber: Send + Sync {
+    fn enter(&self, span: &'static str, fields: &Fields<'_>);
+    fn exit(&self, span: &'static str, fields: &Fields<'_>);
+    fn event(&self, name: &'static str, fields: &Fields<'_>);
+}
+
+// Writes a line per span entered or exited and per event, indented by the spans they are in, as
+// `sac -vv` does on stderr.
+pub struct WriterSubscriber<W> {
+    state: Mutex<(W, usize)>, // With the depth.
+}
+
+impl<W: Write + Send> WriterSubscriber<W> {
+    pub fn new(writer: W) -> WriterSubscriber<W> {
+        WriterSubscriber { state: Mutex::new((writer, 0)) }
+    }
+
+    fn write(&self, depth: usize, mark: &str, name: &str, fields: &Fields<'_>) {
+        let mut line = format!("{:1$}{mark} {name}", "", 2 * depth);
+        for (field, value) in fields {
+            line += &format!(" {field}={value}");
+        }
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let _ = writeln!(state.0, "{line}");
+    }
+}
+
+impl<W: Write + Send> Subscriber for WriterSubscriber<W> {
+    fn enter(&self, span: &'static str, fields: &Fields<'_>) {
+        let depth = {
+            let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+            state.1 += 1;
+            state.1 - 1
+        };
+        self.write(depth, "→", span, fields);
+    }
+
+    fn exit(&self, span: &'static str, fields: &Fields<'_>) {
+        let depth = {
+            let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+            state.1 = state.1.saturating_sub(1);
+            state.1
+        };
+        self.write(depth, "←", span, fields);
+    }
+
+    fn event(&self, name: &'static str, fields: &Fields<'_>) {
+        let depth = self.state.lock().unwrap_or_else(|e| e.into_inner()).1;
+        self.write(depth, "·", name, fields);
+    }
+}
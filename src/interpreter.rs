@@ -1,17 +1,53 @@
-use std::collections::HashMap;
-use std::fs::File;
-use std::io::{self, Read, Write};
+use alloc::boxed::Box;
+use alloc::collections::VecDeque;
+use alloc::format;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::{self, Write};
+use core::ops::Range;
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant};
+
+use crate::analysis;
+use crate::buffer::OutputBuffer;
+use crate::cancel::CancelToken;
+use crate::error::{ImageError, InvalidProgram, LoadError, RuntimeError, TapeStringError, TraceError};
+#[cfg(feature = "gzip")]
+use crate::gzip;
+use crate::io::{Bell, DecimalNumber, InputMode, InputSource, InvalidUtf8, IoError, NonAscii, OnEndOfInput, OutputSink, PrngInput, Utf8Decoder};
+#[cfg(feature = "std")]
+use crate::io::{OnInputTimeout, TimeoutInput};
+#[cfg(feature = "tracing")]
+use crate::instrument::{Fields, Subscriber, Value};
+use crate::metrics::{MetricKey, Metrics};
+use crate::optimizer::{OptLevel, PassReport, Pipeline};
+use crate::tape::{Tape, TapeBacking, TapeMode, WrapPolicy};
+use crate::trace::{Recorder, Trace};
 
 struct Lexer {
     code: Vec<char>,
+    extra_tokens: Vec<char>, // Breakpoint and custom instruction tokens.
+    tab_width: usize,
     position_in_code: usize,
+    line: usize,
+    column: usize,
+    last_line: usize,
+    last_column: usize,
 }
 
 impl Lexer {
-    pub fn new() -> Lexer {
+    pub fn new(extra_tokens: Vec<char>, tab_width: usize) -> Lexer {
         Lexer {
             code: Vec::new(),
+            extra_tokens,
+            tab_width: tab_width.max(1),
             position_in_code: 0,
+            line: 1,
+            column: 1,
+            last_line: 1,
+            last_column: 1,
         }
     }
 
@@ -23,30 +59,44 @@ impl Lexer {
 
     fn is_valid_instruction(&self, inst: char) -> bool {
         let valid = "><+-.,[]";
-        if valid.contains(inst) {
-            return true;
+        valid.contains(inst) || self.extra_tokens.contains(&inst)
+    }
+
+    fn advance(&mut self) {
+        if self.code[self.position_in_code] == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else if self.code[self.position_in_code] == '\t' {
+            self.column += self.tab_width - (self.column - 1) % self.tab_width;
         } else {
-            return false;
+            self.column += 1;
         }
+        self.position_in_code += 1;
     }
 
-    pub fn next(&mut self) -> char {
+    // Next instruction character, `None` at the end of the code.
+    pub fn next(&mut self) -> Option<char> {
         while self.position_in_code < self.code.len() && !self.is_valid_instruction(self.code[self.position_in_code]) {
-            self.position_in_code += 1;
+            self.advance();
         }
 
-        if self.position_in_code >= self.code.len() {
-            return '@'; // EOF character.
-        }
+        let r = *self.code.get(self.position_in_code)?;
+        self.last_line = self.line;
+        self.last_column = self.column;
+        self.advance();
+        Some(r)
+    }
 
-        let r = self.code[self.position_in_code];
-        self.position_in_code += 1;
-        return r;
+    // Offset in the source of the character last returned by `next`.
+    fn last_position(&self) -> usize {
+        self.position_in_code - 1
     }
 }
 
-#[derive(Clone, Copy, PartialEq)]
-enum IRInstructionKind {
+// Instructions of the intermediate representation run by the interpreter, see `Program` for the
+// operand each kind expects.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IRInstructionKind {
     IncrementPointer,
     DecrementPointer,
     IncrementByte,
@@ -55,142 +105,2812 @@ enum IRInstructionKind {
     ReadInputToByte,
     JumpIfZero,
     JumpIfNotZero,
+    // A loop whose body always leaves its cell at 0, so that it runs at most once. Paired like
+    // brackets, `EndIf` only marks the end of the body.
+    If,
+    EndIf,
+    // Sets `len` cells from the memory pointer plus `start_offset` to `value`, as a run of `[-]>[-]>`
+    // would. The operand is the number of instructions it stands for, which follow it.
+    FillRange { start_offset: isize, len: usize, value: u8 },
+    // Adds `len` cells from the memory pointer plus `src_offset` to those from `dst_offset` and
+    // clears them, as a run of transfer loops such as `[-<+>]>` would, cell after cell from the
+    // pointer on. The operand is the number of instructions it stands for, which follow it.
+    CopyRange { src_offset: isize, dst_offset: isize, len: usize },
+    SetZero,
+    SetConst, // Operand is the value.
+    Breakpoint,
+    Custom(char), // Operand is the index of the handler.
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IRInstruction {
+    pub kind: IRInstructionKind,
+    pub operand: Option<usize>, // Streak length, or the matching bracket for jumps.
+    pub span: Span,
+}
+
+// Program compiled by `Interpreter::compile`. Every run gets a fresh interpreter and tape, so runs
+// don't see each other, and clones can run on other threads.
+#[derive(Clone, Debug)]
+pub struct CompiledProgram {
+    program: Vec<IRInstruction>,
+    config: Config,
+    input_mode: InputMode,
+    non_ascii: NonAscii,
+    bell: Bell,
+    utf8_io: Option<InvalidUtf8>,
+    output_map: Option<Box<[u8; 256]>>,
+}
+
+impl CompiledProgram {
+    // Output of a run on `input`, until the program halts or fails.
+    pub fn run(&self, input: &[u8]) -> Result<Vec<u8>, RuntimeError> {
+        let output = OutputBuffer::new();
+        let mut interpreter = Interpreter::with_config(self.config);
+        interpreter.input_mode = self.input_mode;
+        interpreter.non_ascii = self.non_ascii;
+        interpreter.bell = self.bell;
+        interpreter.utf8_io = self.utf8_io;
+        interpreter.output_map = self.output_map.clone();
+        #[cfg(feature = "std")]
+        interpreter.set_input(std::io::Cursor::new(input.to_vec()));
+        #[cfg(not(feature = "std"))]
+        interpreter.set_input(VecDeque::from(input.to_vec()));
+        interpreter.set_output(output.clone());
+        interpreter.program = self.program.clone();
+        interpreter.size_tape();
+        interpreter.prepare();
+
+        interpreter.interpret()?;
+        Ok(output.take())
+    }
+}
+
+fn listing(program: &[IRInstruction]) -> String {
+    let mut listing = String::new();
+    let mut depth = 0usize;
+
+    for (index, inst) in program.iter().enumerate() {
+        if inst.kind.closes() {
+            depth -= 1;
+        }
+        let text = format!("{:indent$}{inst}", "", indent = 2 * depth);
+        let _ = writeln!(listing, "{index:>6}  {text:<32} ; {} ({}..{})", inst.span, inst.span.start, inst.span.end);
+        if inst.kind.opens() {
+            depth += 1;
+        }
+    }
+
+    listing
+}
+
+// A program as the optimization passes see it. Every pass must leave it valid, which `validate`
+// checks :
+// - `[` and `]` are balanced, and their operands hold the index of the matching bracket. Passes
+//   moving instructions around call `link` to pair the brackets again. `If` and `EndIf` count as
+//   brackets, and can be paired with `[` and `]` too, though no pass does that.
+// - `><+-` have the length of their streak as operand, at least 1.
+// - `SetConst` has its value as operand, 1 to 255, 0 being `SetZero`.
+// - `FillRange` and `CopyRange` cover at least 1 cell, and have the number of instructions they
+//   stand for as operand. Those follow them, and must be left as they are.
+// - `Custom` has the index of its handler as operand, other kinds have none.
+// - Spans start in source order, so that positions in the source map back to instructions. In a
+//   program made of segments, spans are in the source of their segment, and the order starts over
+//   with each segment.
+// - Segments start in order, the first one at instruction 0.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Program {
+    pub instructions: Vec<IRInstruction>,
+    // Programs `concat` glued together, empty for a program from a single source. Passes don't
+    // update them, so programs are optimized before being concatenated. As passes don't assume the
+    // tape starts cleared (see `Pass`), each part runs as it would on its own tape.
+    pub segments: Vec<Segment>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Segment {
+    pub start: usize, // Index of its first instruction.
+    pub name: Option<String>, // Of the source its spans are in, such as a file name.
+}
+
+impl Program {
+    // The programs one after the other, with their jumps rebased. Each program becomes a segment, or
+    // keeps its own if it has some, so that every span can still be traced back to its source.
+    pub fn concat(programs: &[&Program]) -> Program {
+        let mut program = Program::default();
+        for part in programs {
+            program.append(part);
+        }
+        program
+    }
+
+    // Programs without instructions are left out, they have no spans to trace back.
+    pub fn append(&mut self, other: &Program) {
+        if other.instructions.is_empty() {
+            return;
+        }
+        let offset = self.instructions.len();
+        match offset {
+            0 => self.segments.clear(),
+            _ if self.segments.is_empty() => self.segments.push(Segment::default()),
+            _ => (),
+        }
+        match &other.segments[..] {
+            [] => self.segments.push(Segment { start: offset, name: None }),
+            segments => self.segments.extend(segments.iter().map(|segment| Segment { start: offset + segment.start, ..segment.clone() })),
+        }
+
+        self.instructions.extend(other.instructions.iter().map(|&inst| match inst.kind {
+            kind if kind.opens() || kind.closes() => IRInstruction { operand: inst.operand.map(|target| target + offset), ..inst },
+            _ => inst,
+        }));
+        if cfg!(debug_assertions) {
+            if let Err(e) = self.validate() {
+                panic!("appended an invalid program : {e}");
+            }
+        }
+    }
+
+    // Segment instruction `index` belongs to, `None` for a program from a single source.
+    pub fn segment(&self, index: usize) -> Option<&Segment> {
+        self.segments.get(self.segments.partition_point(|segment| segment.start <= index).checked_sub(1)?)
+    }
+
+    // Gives the program a segment named `name`, for `concat`. Its segments are replaced.
+    pub fn named(mut self, name: &str) -> Program {
+        self.segments = vec![Segment { start: 0, name: Some(String::from(name)) }];
+        self
+    }
+
+    // Pairs every bracket with its match, failing on the first one without any.
+    pub fn link(&mut self) -> Result<(), InvalidProgram> {
+        let mut open = Vec::new();
+        for index in 0..self.instructions.len() {
+            match self.instructions[index].kind {
+                kind if kind.opens() => open.push(index),
+                kind if kind.closes() => {
+                    let target = open.pop().ok_or(InvalidProgram { instruction: index, reason: "unmatched ]" })?;
+                    self.instructions[index].operand = Some(target);
+                    self.instructions[target].operand = Some(index);
+                },
+                _ => (),
+            }
+        }
+
+        match open.pop() {
+            Some(index) => Err(InvalidProgram { instruction: index, reason: "unmatched [" }),
+            None => Ok(()),
+        }
+    }
+
+    // Same format as `Interpreter::listing`.
+    pub fn listing(&self) -> String {
+        listing(&self.instructions)
+    }
+
+    pub fn validate(&self) -> Result<(), InvalidProgram> {
+        let mut open = Vec::new();
+        let mut previous_start = 0;
+        if let Some((index, _)) = self.segments.iter().enumerate().find(|&(index, segment)| {
+            segment.start > self.instructions.len() || if index == 0 { segment.start != 0 } else { segment.start <= self.segments[index - 1].start }
+        }) {
+            return Err(InvalidProgram { instruction: self.segments[index].start, reason: "segment out of order" });
+        }
+
+        for (index, inst) in self.instructions.iter().enumerate() {
+            let invalid = |reason| Err(InvalidProgram { instruction: index, reason });
+            if self.segments.iter().any(|segment| segment.start == index) {
+                previous_start = 0;
+            }
+            match (inst.kind, inst.operand) {
+                (IRInstructionKind::IncrementPointer | IRInstructionKind::DecrementPointer, Some(1..))
+                | (IRInstructionKind::IncrementByte | IRInstructionKind::DecrementByte, Some(1..))
+                | (IRInstructionKind::SetConst, Some(1..=255))
+                | (IRInstructionKind::Custom(_), Some(_))
+                | (IRInstructionKind::PrintByteAsChar | IRInstructionKind::ReadInputToByte | IRInstructionKind::SetZero | IRInstructionKind::Breakpoint, None) => (),
+                (IRInstructionKind::FillRange { len: 1.., .. } | IRInstructionKind::CopyRange { len: 1.., .. }, Some(covered @ 1..)) if index + covered < self.instructions.len() => (),
+                (kind, _) if kind.opens() => open.push(index),
+                (kind, operand) if kind.closes() => match open.pop() {
+                    Some(target) if operand == Some(target) && self.instructions[target].operand == Some(index) => (),
+                    Some(_) => return invalid("bracket not linked to its match"),
+                    None => return invalid("unmatched ]"),
+                },
+                _ => return invalid("operand out of range"),
+            }
+            if inst.span.start < previous_start {
+                return invalid("span out of source order");
+            }
+            previous_start = inst.span.start;
+        }
+
+        match open.pop() {
+            Some(index) => Err(InvalidProgram { instruction: index, reason: "unmatched [" }),
+            None => Ok(()),
+        }
+    }
+}
+
+impl IRInstructionKind {
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            IRInstructionKind::IncrementPointer => "IncrementPointer",
+            IRInstructionKind::DecrementPointer => "DecrementPointer",
+            IRInstructionKind::IncrementByte => "IncrementByte",
+            IRInstructionKind::DecrementByte => "DecrementByte",
+            IRInstructionKind::PrintByteAsChar => "PrintByteAsChar",
+            IRInstructionKind::ReadInputToByte => "ReadInputToByte",
+            IRInstructionKind::JumpIfZero => "JumpIfZero",
+            IRInstructionKind::JumpIfNotZero => "JumpIfNotZero",
+            IRInstructionKind::If => "If",
+            IRInstructionKind::EndIf => "EndIf",
+            IRInstructionKind::FillRange { .. } => "FillRange",
+            IRInstructionKind::CopyRange { .. } => "CopyRange",
+            IRInstructionKind::SetZero => "SetZero",
+            IRInstructionKind::SetConst => "SetConst",
+            IRInstructionKind::Breakpoint => "Breakpoint",
+            IRInstructionKind::Custom(_) => "Custom",
+        }
+    }
+
+    // `[` or `If`, the start of a body ended by the instruction its operand points to.
+    pub(crate) fn opens(self) -> bool {
+        matches!(self, IRInstructionKind::JumpIfZero | IRInstructionKind::If)
+    }
+
+    // `]` or `EndIf`.
+    pub(crate) fn closes(self) -> bool {
+        matches!(self, IRInstructionKind::JumpIfNotZero | IRInstructionKind::EndIf)
+    }
+
+    // `FillRange` or `CopyRange`, whose effect is also that of the instructions after them.
+    pub(crate) fn is_range(self) -> bool {
+        matches!(self, IRInstructionKind::FillRange { .. } | IRInstructionKind::CopyRange { .. })
+    }
+}
+
+impl fmt::Display for IRInstruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self.kind {
+            IRInstructionKind::Custom(token) => return write!(f, "Custom {token:?}"),
+            kind => kind.name(),
+        };
+
+        match (self.kind, self.operand) {
+            (kind, Some(target)) if kind.opens() || kind.closes() => write!(f, "{name} -> {target}"),
+            (IRInstructionKind::FillRange { start_offset, len, value }, Some(covered)) => write!(f, "{name} {start_offset:+} x{len} = {value} (covers {covered})"),
+            (IRInstructionKind::CopyRange { src_offset, dst_offset, len }, Some(covered)) => write!(f, "{name} {src_offset:+} x{len} -> {dst_offset:+} (covers {covered})"),
+            (_, Some(operand)) => write!(f, "{name} {operand}"),
+            (_, None) => f.write_str(name),
+        }
+    }
+}
+
+// Character offsets of an instruction in the source code, `end` being exclusive.
+// `line` and `column` locate `start` and are 1-based.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LoopInfo {
+    pub open: usize,
+    pub close: usize,
+    pub depth: usize, // 1 for a top level loop.
+    pub span: Span,
+    pub contains_io: bool,
+}
+
+// Cells around the memory pointer, as returned by `tape_window`. The pointer is at `cursor`, the
+// middle of the window, and `first_cell` is the tape index of `cells[0]`, negative when the window
+// starts left of the tape. Positions past either edge of the tape hold 0.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TapeWindow {
+    pub cells: Vec<u8>,
+    pub cursor: usize,
+    pub first_cell: isize,
+}
+
+// Loop events reported to the hook set with `set_loop_hook`, `loop_index` being the IR index of
+// the loop's `[` and `cell` the value its condition was evaluated on. `Enter` fires each time `[`
+// is evaluated, `Exit` each time `]` is, whether or not the loop goes on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LoopEvent {
+    Enter { loop_index: usize, cell: u8 },
+    Exit { loop_index: usize, cell: u8 },
+}
+
+// `@label: name` on a line of its own names the code after it, for breakpoints. Names are made of
+// letters, digits and `_`, so that the line stays a comment.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Label {
+    pub name: String,
+    pub span: Span, // Of the `@label: name` text.
+}
+
+// Labels of `source`, in order. A name defined twice keeps its last definition.
+fn labels(source: &[char], tab_width: usize) -> Vec<Label> {
+    let mut labels = Vec::new();
+    let (mut start, mut line) = (0, 1);
+    for text in source.split(|&c| c == '\n') {
+        let indent = text.iter().take_while(|c| c.is_whitespace()).count();
+        let trimmed = text[indent..].iter().rev().skip_while(|c| c.is_whitespace()).count() + indent;
+        let definition: String = text[indent..trimmed].iter().collect();
+        if let Some(name) = definition.strip_prefix("@label:").map(str::trim_start) {
+            if !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+                let column = 1 + text[..indent].iter().fold(0, |column, &c| if c == '\t' { column + tab_width - column % tab_width } else { column + 1 });
+                let span = Span { start: start + indent, end: start + trimmed, line, column };
+                labels.push(Label { name: String::from(name), span });
+            }
+        }
+        start += text.len() + 1;
+        line += 1;
+    }
+    labels
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pause {
+    Halted,
+    Breakpoint { instruction: usize, span: Span },
+}
+
+// Where `fast_forward_loop` stopped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FastForward {
+    // All the iterations ran, `cell` being the value the loop's test found after the last one : the
+    // instruction pointer is at the start of the body when the loop goes on, past it otherwise.
+    Paused { cell: u8 },
+    Exited { iterations: u64 }, // The loop ended after fewer iterations than asked for.
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StepStatus {
+    Running, // There are instructions left to step through.
+    Halted,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StepOutcome {
+    Completed(RunSummary),
+    BudgetExhausted { steps_run: u64 },
+    NeedsInput, // The `,` is retried by the next call.
+}
+
+// How many times each instruction of the loaded program ran, by IR index. The instructions a
+// `FillRange` or `CopyRange` stands for run along with it, whether it skips them or falls through.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Coverage {
+    counts: Vec<u64>, // Runs of each instruction.
+}
+
+impl Coverage {
+    fn new(len: usize) -> Coverage {
+        Coverage { counts: vec![0; len] }
+    }
+
+    fn mark(&mut self, index: usize) {
+        self.counts[index] += 1;
+    }
+
+    // Number of instructions in the program.
+    pub fn len(&self) -> usize {
+        self.counts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    // Times instruction `index` ran, 0 past the end of the program.
+    pub fn count(&self, index: usize) -> u64 {
+        self.counts.get(index).copied().unwrap_or(0)
+    }
+
+    pub fn is_covered(&self, index: usize) -> bool {
+        self.count(index) > 0
+    }
+
+    pub fn covered_count(&self) -> usize {
+        self.counts.iter().filter(|&&count| count > 0).count()
+    }
+
+    pub fn uncovered(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.len()).filter(|&index| !self.is_covered(index))
+    }
+
+    // Share of the instructions run, 100 for an empty program.
+    pub fn percent(&self) -> f64 {
+        if self.is_empty() {
+            return 100.0;
+        }
+        100.0 * self.covered_count() as f64 / self.len() as f64
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RunSummary {
+    pub steps: u64, // Since the program was loaded.
+}
+
+// State handed to an `InstructionHandler`. Moving `pointer` off the tape is handled as `>` past the
+// last cell would be, once the handler returns.
+pub struct Machine<'a> {
+    pub tape: &'a mut [u8],
+    pub pointer: &'a mut usize,
+    pub input: &'a mut dyn InputSource,
+    pub output: &'a mut dyn OutputSink,
+}
+
+// Executes a custom token registered with `register_handler`. Output written here is not counted
+// by the output limit.
+pub trait InstructionHandler {
+    fn execute(&mut self, machine: Machine<'_>) -> Result<(), IoError>;
 }
 
+// State before an instruction run by `step` : every instruction writes at most the cell under the
+// memory pointer.
 #[derive(Clone, Copy)]
-struct IRInstruction {
-    kind: IRInstructionKind,
-    operand: Option<u8>,
+struct Undo {
+    instruction_pointer: usize,
+    memory_pointer: usize,
+    cell: u8,
+}
+
+// State saved by hang detection. Cells are saved lazily : `cells` holds the values the cells from
+// `first_cell` had when the state was saved, and is widened just before an instruction writes
+// past it, so the cells it doesn't cover still hold their saved values.
+struct SavedState {
+    step: u64,
+    instruction_pointer: usize,
+    memory_pointer: usize,
+    first_cell: usize,
+    cells: VecDeque<u8>,
+}
+
+struct HangDetector {
+    interval: u64,
+    saved: Option<SavedState>,
+}
+
+impl HangDetector {
+    // Makes `cells` cover `written`, before the instruction writes it.
+    fn widen(&mut self, memory: &[u8], written: Range<usize>) {
+        let Some(saved) = &mut self.saved else { return };
+        if written.is_empty() {
+            return;
+        }
+        if saved.cells.is_empty() {
+            saved.first_cell = written.start;
+        }
+        while written.start < saved.first_cell {
+            saved.first_cell -= 1;
+            saved.cells.push_front(memory[saved.first_cell]);
+        }
+        while saved.first_cell + saved.cells.len() < written.end {
+            saved.cells.push_back(memory[saved.first_cell + saved.cells.len()]);
+        }
+    }
+
+    // Period of the cycle the machine is in, if it is back to the saved state. Otherwise the state is
+    // saved again every `interval` steps.
+    fn check(&mut self, step: u64, instruction_pointer: usize, memory_pointer: usize, memory: &[u8]) -> Option<u64> {
+        match &self.saved {
+            Some(saved) if step - saved.step < self.interval => {
+                let same = saved.instruction_pointer == instruction_pointer
+                    && saved.memory_pointer == memory_pointer
+                    && saved.cells.iter().eq(&memory[saved.first_cell..saved.first_cell + saved.cells.len()]);
+                same.then_some(step - saved.step)
+            },
+            _ => {
+                self.saved = Some(SavedState { step, instruction_pointer, memory_pointer, first_cell: 0, cells: VecDeque::new() });
+                None
+            },
+        }
+    }
+}
+
+pub const TOTAL_MEMORY_SIZE: usize = 100000; // 100000 cells.
+
+// Gas charged for each kind of instruction, set with `set_gas`. A run of `><+-` is one instruction,
+// charged once or once per command depending on `per_command`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct GasSchedule {
+    pub pointer_move: u64,
+    pub arithmetic: u64,
+    pub set_zero: u64, // Also charged for setting a cell to a constant.
+    pub output: u64,
+    pub input: u64,
+    pub jump: u64, // Both `[` and `]`, each time they are evaluated.
+    pub breakpoint: u64,
+    pub custom: u64,
+    pub per_command: bool,
+}
+
+impl Default for GasSchedule {
+    fn default() -> GasSchedule {
+        GasSchedule { pointer_move: 1, arithmetic: 1, set_zero: 1, output: 1, input: 1, jump: 1, breakpoint: 0, custom: 1, per_command: true }
+    }
+}
+
+impl GasSchedule {
+    fn cost(&self, inst: &IRInstruction) -> u64 {
+        let streak = |cost: u64| if self.per_command { cost.saturating_mul(inst.operand.unwrap() as u64) } else { cost };
+        match inst.kind {
+            IRInstructionKind::IncrementPointer | IRInstructionKind::DecrementPointer => streak(self.pointer_move),
+            IRInstructionKind::IncrementByte | IRInstructionKind::DecrementByte => streak(self.arithmetic),
+            IRInstructionKind::SetZero | IRInstructionKind::SetConst => self.set_zero,
+            IRInstructionKind::FillRange { len, .. } | IRInstructionKind::CopyRange { len, .. } => self.set_zero.saturating_mul(len as u64),
+            IRInstructionKind::PrintByteAsChar => self.output,
+            IRInstructionKind::ReadInputToByte => self.input,
+            IRInstructionKind::JumpIfZero | IRInstructionKind::JumpIfNotZero | IRInstructionKind::If | IRInstructionKind::EndIf => self.jump,
+            IRInstructionKind::Breakpoint => self.breakpoint,
+            IRInstructionKind::Custom(_) => self.custom,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Config {
+    pub tape_size: usize,
+    pub opt_level: OptLevel,
+    // Source character compiled to a breakpoint, honored by `resume` in debug mode. The eight
+    // commands and `@`, which starts labels, can't be used.
+    pub breakpoint_token: Option<char>,
+    // Columns a tab advances to the next tab stop by, when computing the column of instructions.
+    pub tab_width: usize,
+    pub tape_backing: TapeBacking,
+    // Rejects programs containing `.` or `,` when they are loaded, so that they can only compute into
+    // the tape, which is then read with `peek`.
+    pub forbid_io: bool,
+    // Shrinks the tape of each loaded program to the cells it can reach, plus some slack, when the
+    // pointer analysis bounds them. `tape_size` stays the upper limit, and the size of the tape of
+    // programs it can't bound. Only with `TapeMode::Error`, as the other modes tell where the edges are.
+    pub infer_tape_size: bool,
+    pub tape_mode: TapeMode,
+    pub wrap_policy: WrapPolicy,
+    pub on_end_of_input: OnEndOfInput,
+    // Backend running the instructions, see `Executor`.
+    pub engine: Engine,
+    // Rejects larger programs when they are loaded. Files are checked before they are read, so
+    // that a huge file isn't read into memory only to be refused.
+    pub max_source_bytes: Option<usize>,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            tape_size: TOTAL_MEMORY_SIZE,
+            opt_level: OptLevel::default(),
+            breakpoint_token: None,
+            tab_width: 1,
+            tape_backing: TapeBacking::Heap,
+            forbid_io: false,
+            infer_tape_size: false,
+            tape_mode: TapeMode::default(),
+            wrap_policy: WrapPolicy::default(),
+            on_end_of_input: OnEndOfInput::default(),
+            engine: Engine::default(),
+            max_source_bytes: None,
+        }
+    }
+}
+
+// Settings matching well-known interpreters, for programs written against one of them. Cells are
+// bytes wrapping around in all of them : as sac's cells wrap too, a signed cell holding -1 behaves as
+// one holding 255, and there is no sign to set.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CompatProfile {
+    // The original 30000 cells, failing past either edge, and `,` storing 0 at end of input.
+    Classic30k,
+    // A mapped tape of 16 Mi cells, more than such programs use, and `,` leaving the cell unchanged
+    // at end of input.
+    Unbounded,
+}
+
+impl CompatProfile {
+    pub fn from_name(name: &str) -> Option<CompatProfile> {
+        match name {
+            "classic30k" => Some(CompatProfile::Classic30k),
+            "unbounded" => Some(CompatProfile::Unbounded),
+            _ => None,
+        }
+    }
+
+    // Sets the tape, cell and end of input settings of `config`, leaving the others as they are.
+    pub fn apply(self, config: &mut Config) {
+        (config.tape_size, config.tape_backing, config.on_end_of_input) = match self {
+            CompatProfile::Classic30k => (30000, TapeBacking::Heap, OnEndOfInput::Zero),
+            CompatProfile::Unbounded => (1 << 24, TapeBacking::Mapped, OnEndOfInput::Unchanged),
+        };
+        config.tape_mode = TapeMode::Error;
+        config.wrap_policy = WrapPolicy::Wrap;
+        config.infer_tape_size = false;
+    }
+
+    pub fn config(self) -> Config {
+        let mut config = Config::default();
+        self.apply(&mut config);
+        config
+    }
 }
 
-const TOTAL_MEMORY_SIZE: usize = 100000; // 100000 cells.
+#[cfg(feature = "std")]
+const TAPE_MAGIC: &[u8] = b"SACTAPE1";
+
+#[cfg(feature = "std")]
+const CHECKPOINT_MAGIC: &[u8] = b"SACCKPT1";
+
+#[cfg(feature = "std")]
+const TIME_CHECK_INTERVAL: u64 = 4096;
+
+// Steps between two reports to `metrics` during long runs.
+const METRICS_INTERVAL: u64 = 1 << 20;
 
+// Most steps `run_inline` takes in a row, so that cancellation and the step limit are still
+// checked by `step_once` in between.
+const INLINE_STEPS: u64 = 1 << 16;
+
+// Interpreters are `Send`, so that a run can move to another thread or async task : the streams,
+// hooks, handlers and passes given to them have to be `Send` too.
 pub struct Interpreter {
     instruction_pointer: usize,
     memory_pointer: usize,
-    memory: [u8; TOTAL_MEMORY_SIZE],
+    memory: Tape,
     program: Vec<IRInstruction>,
-    jump_map: HashMap<usize, usize>,
+    opt_level: OptLevel,
+    breakpoint_token: Option<char>,
+    tab_width: usize,
+    forbid_io: bool,
+    tape_size: usize, // As configured, the tape may be smaller with `infer_tape_size`.
+    infer_tape_size: bool,
+    tape_mode: TapeMode,
+    wrap_policy: WrapPolicy,
+    on_end_of_input: OnEndOfInput,
+    engine: Engine, // After fallbacks.
+    max_source_bytes: Option<usize>,
+    debug_mode: bool,
+    breakpoints: Vec<usize>, // Instructions `resume` stops before, in debug mode.
+    handlers: Vec<(char, Box<dyn InstructionHandler + Send>)>,
     lexer: Lexer,
+    input: Box<dyn InputSource + Send>,
+    input_mode: InputMode,
+    non_ascii: NonAscii,
+    bell: Bell,
+    utf8_io: Option<InvalidUtf8>,
+    output_map: Option<Box<[u8; 256]>>, // `None` for the identity.
+    utf8_input: Utf8Decoder,
+    utf8_read: VecDeque<u8>, // Input decoded by `utf8_input`, not given to `,` yet.
+    utf8_output: Utf8Decoder,
+    utf8_output_at: usize, // `.` which printed the last byte held by `utf8_output`.
+    output: Box<dyn OutputSink + Send>,
+    cancel_token: Option<CancelToken>,
+    loop_hook: Option<Box<dyn FnMut(LoopEvent) + Send>>,
+    loop_iteration_cap: Option<usize>,
+    loop_iterations: Vec<usize>, // Iterations of the current run of each loop, indexed by its `]`.
+    steps: u64,
+    step_limit: Option<u64>,
+    output_bytes: usize,
+    output_limit: Option<usize>,
+    output_buffer: Vec<u8>, // Output held back until it reaches `output_buffer_size` bytes.
+    output_buffer_size: usize,
+    history: VecDeque<Undo>,
+    history_limit: usize,
+    hang_detector: Option<HangDetector>,
+    pending_input: Option<DecimalNumber>, // `,` already run whose input couldn't be read yet.
+    input_bytes: u64, // Read since the program was loaded.
+    output_written: u64, // Bytes, since the program was loaded.
+    metrics: Option<Arc<dyn Metrics>>,
+    metrics_reported: [u64; 3], // Steps, input and output bytes already reported to `metrics`.
+    #[cfg(feature = "tracing")]
+    subscriber: Option<Arc<dyn Subscriber>>,
+    touched: Option<Vec<u64>>, // Bitmap of the cells written since the program was loaded.
+    read: Option<Vec<u64>>, // Bitmap of the cells read, along with `touched`.
+    coverage: Option<Coverage>,
+    trace: Option<Recorder>,
+    gas_schedule: Option<GasSchedule>,
+    gas_costs: Vec<u64>, // Cost of each instruction under `gas_schedule`.
+    dispatch: Vec<Handler>, // Handler of each instruction, looked up once per program.
+    pipeline: Option<Pipeline>, // Replaces the passes of `opt_level`.
+    pass_reports: Vec<PassReport>,
+    gas_used: u64,
+    gas_limit: u64,
+    #[cfg(feature = "std")]
+    time_limit: Option<Duration>,
+    #[cfg(feature = "std")]
+    deadline: Option<Instant>,
+    #[cfg(feature = "std")]
+    run_time: Duration, // Spent running since the program was loaded.
+}
+
+impl Default for Interpreter {
+    fn default() -> Interpreter {
+        Interpreter::new()
+    }
 }
 
 impl Interpreter {
     pub fn new() -> Interpreter {
+        Interpreter::with_config(Config::default())
+    }
+
+    pub fn with_config(config: Config) -> Interpreter {
         Interpreter {
             instruction_pointer: 0,
             memory_pointer: 0,
-            memory: [0; TOTAL_MEMORY_SIZE],
+            memory: Tape::new(config.tape_size, config.tape_backing),
             program: Vec::new(),
-            jump_map: HashMap::new(),
-            lexer: Lexer::new(),
+            opt_level: config.opt_level,
+            breakpoint_token: config.breakpoint_token.filter(|&c| !is_reserved(c)),
+            tab_width: config.tab_width,
+            forbid_io: config.forbid_io,
+            tape_size: config.tape_size,
+            infer_tape_size: config.infer_tape_size,
+            tape_mode: config.tape_mode,
+            wrap_policy: config.wrap_policy,
+            on_end_of_input: config.on_end_of_input,
+            engine: executor(config.engine).engine(),
+            max_source_bytes: config.max_source_bytes,
+            debug_mode: false,
+            breakpoints: Vec::new(),
+            handlers: Vec::new(),
+            lexer: Lexer::new(Vec::new(), config.tab_width),
+            #[cfg(feature = "std")]
+            input: Box::new(std::io::stdin()),
+            #[cfg(feature = "std")]
+            output: Box::new(std::io::stdout()),
+            #[cfg(not(feature = "std"))]
+            input: Box::new(crate::io::NoInput),
+            #[cfg(not(feature = "std"))]
+            output: Box::new(crate::io::NoOutput),
+            input_mode: InputMode::default(),
+            non_ascii: NonAscii::default(),
+            bell: Bell::default(),
+            utf8_io: None,
+            output_map: None,
+            utf8_input: Utf8Decoder::default(),
+            utf8_read: VecDeque::new(),
+            utf8_output: Utf8Decoder::default(),
+            utf8_output_at: 0,
+            cancel_token: None,
+            loop_hook: None,
+            loop_iteration_cap: None,
+            loop_iterations: Vec::new(),
+            steps: 0,
+            step_limit: None,
+            output_bytes: 0,
+            output_limit: None,
+            output_buffer: Vec::new(),
+            output_buffer_size: 0,
+            history: VecDeque::new(),
+            pending_input: None,
+            input_bytes: 0,
+            output_written: 0,
+            metrics: None,
+            metrics_reported: [0; 3],
+            #[cfg(feature = "tracing")]
+            subscriber: None,
+            history_limit: 0,
+            hang_detector: None,
+            touched: None,
+            read: None,
+            coverage: None,
+            trace: None,
+            gas_schedule: None,
+            gas_costs: Vec::new(),
+            dispatch: Vec::new(),
+            pipeline: None,
+            pass_reports: Vec::new(),
+            gas_used: 0,
+            gas_limit: 0,
+            #[cfg(feature = "std")]
+            time_limit: None,
+            #[cfg(feature = "std")]
+            deadline: None,
+            #[cfg(feature = "std")]
+            run_time: Duration::ZERO,
         }
     }
 
-    pub fn load_program(&mut self, program_path: &str) {
-        let mut program_file = File::open(program_path).expect("[ERROR] Unable to open the program !");
+    pub fn set_input<I: InputSource + Send + 'static>(&mut self, input: I) {
+        self.input = Box::new(input);
+    }
 
-        let mut program_buffer = String::new();
+    // Writes the tape up to its last non-zero cell, preceded by a magic and the cell count, so that
+    // another program can pick it up with `load_tape`. Pointers are not saved.
+    #[cfg(feature = "std")]
+    pub fn save_tape(&self, path: &str) -> std::io::Result<()> {
+        let used = self.memory.iter().rposition(|&cell| cell != 0).map_or(0, |last| last + 1);
 
-        program_file.read_to_string(&mut program_buffer).expect("[ERROR] Unable to read the program !");
+        let mut bytes = Vec::with_capacity(TAPE_MAGIC.len() + 8 + used);
+        bytes.extend_from_slice(TAPE_MAGIC);
+        bytes.extend_from_slice(&(used as u64).to_le_bytes());
+        bytes.extend_from_slice(&self.memory[..used]);
+        std::fs::write(path, bytes)
+    }
 
-        self.lexer.fill(program_buffer.as_str());
+    // Replaces the tape with one saved by `save_tape`, the remaining cells being cleared. Call it
+    // after loading the program, which resets the tape.
+    #[cfg(feature = "std")]
+    pub fn load_tape(&mut self, path: &str) -> std::io::Result<()> {
+        use std::io::{Error, ErrorKind};
 
-        let mut c = self.lexer.next();
+        let bytes = std::fs::read(path)?;
+        let invalid = |message: String| Error::new(ErrorKind::InvalidData, message);
 
-        while c != '@' {
-            let inst: IRInstruction;
-            match c {
-                '>' | '<' | '+' | '-' => {
-                    let k: IRInstructionKind;
-                    if c == '>' { k = IRInstructionKind::IncrementPointer; }
-                    else if c == '<' { k = IRInstructionKind::DecrementPointer; }
-                    else if c == '+' { k = IRInstructionKind::IncrementByte; }
-                    else { k = IRInstructionKind::DecrementByte; }
+        let header = bytes.get(..TAPE_MAGIC.len() + 8).filter(|h| h.starts_with(TAPE_MAGIC)).ok_or_else(|| invalid(format!("{path} is not a saved tape")))?;
+        let length = u64::from_le_bytes(header[TAPE_MAGIC.len()..].try_into().unwrap()) as usize;
+        let cells = &bytes[header.len()..];
+        if cells.len() != length {
+            return Err(invalid(format!("{path} is truncated")));
+        }
+        if length > self.memory.len() {
+            return Err(invalid(format!("the saved tape has {length} cells, more than the {} of this interpreter", self.memory.len())));
+        }
 
-                    let mut s = self.lexer.next();
-                    let mut streak = 1u8;
+        self.memory.clear();
+        self.history.clear();
+        self.memory[..length].copy_from_slice(cells);
+        Ok(())
+    }
 
-                    while c == s {
-                        streak += 1;
-                        s = self.lexer.next();
-                    }
+    // Saves where the program is, so that a run killed later can go on from here with
+    // `load_checkpoint` : a hash of the compiled program, the pointers, the step count, the number of
+    // input bytes read and the tape up to its last non-zero cell. The file is written next to `path`
+    // and renamed over it, so that a crash while saving leaves the previous checkpoint intact. Call it
+    // between steps, with no `,` waiting for its input.
+    #[cfg(feature = "std")]
+    pub fn save_checkpoint(&self, path: &str) -> std::io::Result<()> {
+        let used = self.memory.iter().rposition(|&cell| cell != 0).map_or(0, |last| last + 1);
 
-                    inst = IRInstruction { kind: k, operand: Some(streak) };
-                    c = s;
-                },
-                '.' | ',' | '[' | ']' => {
-                    let k: IRInstructionKind;
-                    if c == '.' { k = IRInstructionKind::PrintByteAsChar; }
-                    else if c == ',' { k = IRInstructionKind::ReadInputToByte; }
-                    else if c == '[' { k = IRInstructionKind::JumpIfZero; }
-                    else { k = IRInstructionKind::JumpIfNotZero; }
+        let mut bytes = Vec::with_capacity(CHECKPOINT_MAGIC.len() + 32 + 5 * 8 + used);
+        bytes.extend_from_slice(CHECKPOINT_MAGIC);
+        bytes.extend_from_slice(&self.program_hash());
+        for value in [self.instruction_pointer as u64, self.memory_pointer as u64, self.steps, self.input_bytes, used as u64] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        bytes.extend_from_slice(&self.memory[..used]);
 
-                    inst = IRInstruction { kind: k, operand: None };
-                    c = self.lexer.next();
-                },
-                _ => continue,
-            }
+        let temporary = format!("{path}.tmp");
+        std::fs::write(&temporary, bytes)?;
+        std::fs::rename(&temporary, path)
+    }
 
-            self.program.push(inst);
+    // Picks up a run from a checkpoint saved by `save_checkpoint` for the same compiled program. Call
+    // it after loading the program and setting the input, which must be the one the checkpointed run
+    // was given : the bytes it had already read are skipped. Other run state, such as limits and
+    // loop iteration counts, starts over.
+    #[cfg(feature = "std")]
+    pub fn load_checkpoint(&mut self, path: &str) -> std::io::Result<()> {
+        use std::io::{Error, ErrorKind};
+
+        let bytes = std::fs::read(path)?;
+        let invalid = |message: String| Error::new(ErrorKind::InvalidData, message);
+
+        let header_length = CHECKPOINT_MAGIC.len() + 32 + 5 * 8;
+        let header = bytes.get(..header_length).filter(|h| h.starts_with(CHECKPOINT_MAGIC)).ok_or_else(|| invalid(format!("{path} is not a checkpoint")))?;
+        if header[CHECKPOINT_MAGIC.len()..CHECKPOINT_MAGIC.len() + 32] != self.program_hash() {
+            return Err(invalid(format!("{path} was saved for another program, or with other optimizations")));
+        }
+        let field = |index: usize| {
+            let start = CHECKPOINT_MAGIC.len() + 32 + index * 8;
+            u64::from_le_bytes(header[start..start + 8].try_into().unwrap())
+        };
+        let (instruction_pointer, memory_pointer, steps, input_bytes, used) = (field(0) as usize, field(1) as usize, field(2), field(3), field(4) as usize);
+        let cells = &bytes[header_length..];
+        if cells.len() != used {
+            return Err(invalid(format!("{path} is truncated")));
+        }
+        if used > self.memory.len() || memory_pointer >= self.memory.len() || instruction_pointer > self.program.len() {
+            return Err(invalid(format!("{path} doesn't fit this interpreter")));
+        }
+
+        for _ in 0..input_bytes {
+            if self.input.read_byte()?.is_none() {
+                return Err(Error::new(ErrorKind::UnexpectedEof, format!("the input ended before the {input_bytes} bytes read before the checkpoint")));
+            }
         }
+
+        self.memory.clear();
+        self.memory[..used].copy_from_slice(cells);
+        (self.instruction_pointer, self.memory_pointer, self.steps, self.input_bytes) = (instruction_pointer, memory_pointer, steps, input_bytes);
+        self.history.clear();
+        self.pending_input = None;
+        self.reset_loop_iterations();
+        Ok(())
     }
 
-    fn precompute_jumps(&mut self) {
-        let mut stack = Vec::new();
+    fn program_hash(&self) -> [u8; 32] {
+        let mut sha = crate::hash::Sha256::new();
+        sha.update(self.listing().as_bytes());
+        sha.finish()
+    }
 
-        let mut local_instruction_pointer = 0usize;
+    // Records a `Trace` of the runs of the programs loaded from now on, starting over at each load,
+    // for `take_trace`. Editing the program stops the recording. Input read by custom instructions
+    // or by `interpret_async` isn't recorded.
+    pub fn set_trace_recording(&mut self, enabled: bool) {
+        self.trace = enabled.then(Recorder::default);
+    }
 
-        while local_instruction_pointer < self.program.len() {
-            let inst = self.program[local_instruction_pointer];
+    // The trace recorded since the program was loaded, which ends the recording.
+    pub fn take_trace(&mut self) -> Option<Trace> {
+        let hash = self.program_hash();
+        self.trace.take().map(|trace| trace.finish(hash))
+    }
 
-            match inst.kind {
-                IRInstructionKind::JumpIfZero => stack.push(local_instruction_pointer),
-                IRInstructionKind::JumpIfNotZero => {
-                    let target = stack.pop().unwrap();
-                    self.jump_map.insert(local_instruction_pointer, target);
-                    self.jump_map.insert(target, local_instruction_pointer);
-                },
-                _ => (), // Other instructions aren't jump related.
-            }
+    // Runs the loaded program again as `trace` recorded it, following its steps rather than the jumps
+    // and reading its input instead of this interpreter's. Call it right after loading the program the
+    // trace was recorded for, configured as it was. A failed run ends its trace with the instruction
+    // it failed on, which fails the replay the same way, unless a limit or cancellation stopped it.
+    pub fn replay(&mut self, trace: &Trace) -> Result<(), TraceError> {
+        if trace.program_hash != self.program_hash() {
+            return Err(TraceError::OtherProgram);
+        }
+        #[cfg(feature = "std")]
+        self.set_input(std::io::Cursor::new(trace.input().to_vec()));
+        #[cfg(not(feature = "std"))]
+        self.set_input(VecDeque::from(trace.input().to_vec()));
 
-            local_instruction_pointer += 1;
+        for (step, instruction) in trace.instructions().enumerate() {
+            if instruction >= self.program.len() {
+                return Err(TraceError::OutOfProgram { step: step as u64, instruction });
+            }
+            self.instruction_pointer = instruction;
+            self.step_once()?;
         }
+        self.flush_output()?;
+        Ok(())
+    }
+
+    pub fn set_input_mode(&mut self, mode: InputMode) {
+        self.input_mode = mode;
     }
 
-    pub fn interpret(&mut self) {
-        self.precompute_jumps();
+    pub fn set_non_ascii(&mut self, mode: NonAscii) {
+        self.non_ascii = mode;
+    }
 
-        while self.instruction_pointer < self.program.len() {
-            let inst = self.program[self.instruction_pointer];
+    pub fn set_bell(&mut self, mode: Bell) {
+        self.bell = mode;
+    }
 
-            match inst.kind {
-                IRInstructionKind::IncrementPointer => self.memory_pointer += inst.operand.unwrap() as usize,
-                IRInstructionKind::DecrementPointer => self.memory_pointer -= inst.operand.unwrap() as usize,
-                IRInstructionKind::IncrementByte => self.memory[self.memory_pointer] += inst.operand.unwrap(),
-                IRInstructionKind::DecrementByte => self.memory[self.memory_pointer] -= inst.operand.unwrap(),
-                IRInstructionKind::PrintByteAsChar => {
-                    let byte_as_char = self.memory[self.memory_pointer] as char;
-                    print!("{byte_as_char}");
-                    io::stdout().flush().unwrap();
-                },
-                IRInstructionKind::ReadInputToByte => {
-                    let mut input: [u8; 1] = [0; 1];
-                    io::stdin().read_exact(&mut input).expect("[ERROR] Unable to read stdin !");
-                    self.memory[self.memory_pointer] = input[0];
-                },
-                IRInstructionKind::JumpIfZero => {
-                    if self.memory[self.memory_pointer] == 0 {
-                        self.instruction_pointer = *self.jump_map.get(&self.instruction_pointer).unwrap();
-                    }
-                },
-                IRInstructionKind::JumpIfNotZero => {
-                    if self.memory[self.memory_pointer] != 0 {
-                        self.instruction_pointer = *self.jump_map.get(&self.instruction_pointer).unwrap();
-                    }
-                }
-            }
+    // `.` prints `map[cell]` instead of the cell, before `set_non_ascii`, `set_bell` and UTF-8 output
+    // apply, e.g. to apply ROT13 to the output. The identity map, the default, turns it off.
+    pub fn set_output_map(&mut self, map: [u8; 256]) {
+        self.output_map = (map != core::array::from_fn(|byte| byte as u8)).then(|| Box::new(map));
+    }
 
-            self.instruction_pointer += 1;
-        }
+    // Byte a `.` prints from the current cell.
+    fn printed_byte(&self) -> u8 {
+        let cell = self.memory[self.memory_pointer];
+        self.output_map.as_ref().map_or(cell, |map| map[cell as usize])
+    }
+
+    // Text I/O : `,` reads the input as UTF-8, and `.` prints cells as the bytes of UTF-8 text rather
+    // than as code points, so `non_ascii` doesn't apply. Each `,` still gets one byte, but an end of
+    // input only comes between characters. Printed characters are held back until complete, which
+    // lets a program echo its input byte by byte, and one still incomplete when the program halts is
+    // invalid. `interpret_async` ignores this.
+    pub fn set_utf8_io(&mut self, invalid: Option<InvalidUtf8>) {
+        self.utf8_io = invalid;
+        self.reset_utf8();
+    }
+
+    fn reset_utf8(&mut self) {
+        self.utf8_input = Utf8Decoder::default();
+        self.utf8_read.clear();
+        self.utf8_output = Utf8Decoder::default();
+    }
+
+    // Feeds `,` from a seeded pseudo-random stream, so that runs can be reproduced from the seed.
+    pub fn set_input_prng(&mut self, seed: u64) {
+        self.input = Box::new(PrngInput::new(seed));
+    }
+
+    // Feeds `,` from `input` through a `TimeoutInput`, so that a read waiting longer than `timeout`
+    // gives up as `on_timeout` says.
+    #[cfg(feature = "std")]
+    pub fn set_input_with_timeout<R: std::io::Read + Send + 'static>(&mut self, input: R, timeout: Duration, on_timeout: OnInputTimeout) {
+        self.input = Box::new(TimeoutInput::new(input, timeout, on_timeout));
+    }
+
+    // Holds back up to `bytes` of output before writing it to the sink in one go, instead of writing
+    // and flushing every `.` on its own. The buffer is also written out before every `,`, when the
+    // program halts or fails and by `flush_output`. Once full, it is written out before taking more,
+    // so a slow sink blocks the program instead of letting the buffer grow. 0, the default, turns the
+    // buffer off.
+    pub fn set_output_buffer_size(&mut self, bytes: usize) {
+        self.output_buffer_size = bytes;
+        self.output_buffer = Vec::with_capacity(bytes);
+    }
+
+    // Writes out the output held back by `set_output_buffer_size`, if any, and flushes the sink.
+    pub fn flush_output(&mut self) -> Result<(), RuntimeError> {
+        if self.output_buffer.is_empty() {
+            return Ok(());
+        }
+        let result = self.output.write_bytes(&self.output_buffer);
+        self.output_buffer.clear();
+        result?;
+        self.output.flush()?;
+        Ok(())
+    }
+
+    pub fn set_output<O: OutputSink + Send + 'static>(&mut self, output: O) {
+        self.output = Box::new(output);
+    }
+
+    // IR index of the next instruction to run.
+    pub fn instruction_pointer(&self) -> usize {
+        self.instruction_pointer
+    }
+
+    pub fn memory_pointer(&self) -> usize {
+        self.memory_pointer
+    }
+
+    // Keeps track of the cells written by the program, which `working_set_size` and
+    // `high_water_mark` report, and of those it reads. Cells accessed by custom instructions are not seen.
+    pub fn set_cell_tracking(&mut self, enabled: bool) {
+        self.touched = enabled.then(|| vec![0; self.memory.len().div_ceil(64)]);
+        self.read = self.touched.clone();
+    }
+
+    // Bitmaps of the cells read and written since the program was loaded, without cell tracking
+    // `None`. Cell n is bit n % 64 of word n / 64. A cell is read when it is printed, tested by a
+    // loop, added to others by a transfer loop or targeted by `,`, and written by arithmetic, clears
+    // and input. `+` and `-` only count as writes, even though the new value depends on the old
+    // one. Loops folded by the optimizer read the cells they would have tested, so that the sets
+    // don't depend on the optimization level.
+    pub fn read_set(&self) -> Option<&[u64]> {
+        self.read.as_deref()
+    }
+
+    pub fn write_set(&self) -> Option<&[u64]> {
+        self.touched.as_deref()
+    }
+
+    // Number of distinct cells written at least once, 0 without cell tracking.
+    pub fn working_set_size(&self) -> usize {
+        self.touched.iter().flatten().map(|bits| bits.count_ones() as usize).sum()
+    }
+
+    // Highest cell written, if any, `None` without cell tracking.
+    pub fn high_water_mark(&self) -> Option<usize> {
+        let touched = self.touched.as_ref()?;
+        let (word, bits) = touched.iter().enumerate().rev().find(|(_, bits)| **bits != 0)?;
+        Some(word * 64 + 63 - bits.leading_zeros() as usize)
+    }
+
+    fn touch(&mut self) {
+        self.touch_cell(self.memory_pointer);
+    }
+
+    fn touch_cell(&mut self, cell: usize) {
+        if let Some(touched) = &mut self.touched {
+            touched[cell / 64] |= 1 << (cell % 64);
+        }
+    }
+
+    fn read_cell(&mut self, cell: usize) {
+        if let Some(read) = &mut self.read {
+            read[cell / 64] |= 1 << (cell % 64);
+        }
+    }
+
+    // Keeps track of the instructions run since the program was loaded or edited, which `coverage`
+    // reports.
+    pub fn set_coverage_tracking(&mut self, enabled: bool) {
+        self.coverage = enabled.then(|| Coverage::new(self.program.len()));
+    }
+
+    // `None` without coverage tracking.
+    pub fn coverage(&self) -> Option<&Coverage> {
+        self.coverage.as_ref()
+    }
+
+    pub fn tape_size(&self) -> usize {
+        self.memory.len()
+    }
+
+    // Backend running the program, `Config::engine` unless this build doesn't have it.
+    pub fn engine(&self) -> Engine {
+        self.engine
+    }
+
+    pub fn on_end_of_input(&self) -> OnEndOfInput {
+        self.on_end_of_input
+    }
+
+    pub fn peek(&self, index: usize) -> Option<u8> {
+        self.memory.get(index).copied()
+    }
+
+    pub fn peek_range(&self, range: Range<usize>) -> Option<&[u8]> {
+        self.memory.get(range)
+    }
+
+    // The cells of `range` decoded as UTF-8, for programs leaving their result on the tape. With
+    // `stop_at_zero`, the string ends at the first 0 cell, as in C.
+    pub fn tape_as_string(&self, range: Range<usize>, stop_at_zero: bool) -> Result<String, TapeStringError> {
+        let start = range.start;
+        let cells = self.memory.get(range.clone()).ok_or(TapeStringError::OutOfRange { end: range.end, tape_size: self.memory.len() })?;
+        let cells = match stop_at_zero {
+            true => cells.split(|&cell| cell == 0).next().unwrap(),
+            false => cells,
+        };
+        core::str::from_utf8(cells).map(String::from).map_err(|e| TapeStringError::InvalidUtf8 { cell: start + e.valid_up_to() })
+    }
+
+    // `width` × `height` cells from `start` as a PGM image, see `image::to_pgm`. Printed pixels are
+    // collected with `run_to_output`, which gives the bytes before `.` encodes them.
+    pub fn tape_as_pgm(&self, start: usize, width: usize, height: usize) -> Result<Vec<u8>, ImageError> {
+        let end = start.saturating_add(width.saturating_mul(height));
+        let cells = self.memory.get(start..end).ok_or(ImageError::OutOfRange { end, tape_size: self.memory.len() })?;
+        crate::image::to_pgm(cells, width, height)
+    }
+
+    // `size` cells centered on the memory pointer, for visualizers scrolling along with it. Even sizes
+    // have one more cell left of the pointer than right of it.
+    pub fn tape_window(&self, size: usize) -> TapeWindow {
+        let cursor = size / 2;
+        let first_cell = self.memory_pointer as isize - cursor as isize;
+        let mut cells = vec![0; size];
+        let start = first_cell.max(0) as usize;
+        let end = (first_cell + size as isize).clamp(0, self.memory.len() as isize) as usize;
+        if start < end {
+            let offset = (start as isize - first_cell) as usize;
+            cells[offset..offset + end - start].copy_from_slice(&self.memory[start..end]);
+        }
+        TapeWindow { cells, cursor, first_cell }
+    }
+
+    #[cfg(feature = "std")]
+    pub fn load_program(&mut self, program_path: &str) -> Result<(), LoadError> {
+        let code = read_program_at_most(program_path, self.max_source_bytes)?;
+
+        self.load_program_from_str(code.as_str())
+    }
+
+    pub fn load_program_from_bytes(&mut self, code: &[u8]) -> Result<(), LoadError> {
+        let code = core::str::from_utf8(code).map_err(|e| LoadError::InvalidUtf8 { offset: e.valid_up_to() })?;
+
+        self.load_program_from_str(code)
+    }
+
+    // Handles `token` with `handler` in the programs loaded from now on, replacing any previous
+    // handler of the same token. The eight commands, `@` and the breakpoint token are ignored.
+    pub fn register_handler<H: InstructionHandler + Send + 'static>(&mut self, token: char, handler: H) {
+        if is_reserved(token) || Some(token) == self.breakpoint_token {
+            return;
+        }
+        match self.handlers.iter_mut().find(|(t, _)| *t == token) {
+            Some(entry) => entry.1 = Box::new(handler),
+            None => self.handlers.push((token, Box::new(handler))),
+        }
+    }
+
+    pub fn load_program_from_str(&mut self, code: &str) -> Result<(), LoadError> {
+        if let Some(limit) = self.max_source_bytes.filter(|&limit| code.len() > limit) {
+            return Err(LoadError::ProgramTooLarge { limit });
+        }
+        self.report_metrics();
+        self.metrics_reported = [0; 3];
+        self.output_written = 0;
+        self.instruction_pointer = 0;
+        self.memory_pointer = 0;
+        self.memory.clear();
+        self.history.clear();
+        self.pending_input = None;
+        self.input_bytes = 0;
+        self.breakpoints.clear();
+        self.reset_utf8();
+        if let Some(trace) = &mut self.trace {
+            *trace = Recorder::default();
+        }
+        self.reset_limits();
+        self.forget_saved_state();
+        if let Some(touched) = &mut self.touched {
+            touched.fill(0);
+        }
+        if let Some(read) = &mut self.read {
+            read.fill(0);
+        }
+
+        #[cfg(feature = "std")]
+        let start = Instant::now();
+        #[cfg(feature = "tracing")]
+        self.enter("load", &[("source_bytes", Value::U64(code.len() as u64))]);
+        let loaded = self.compile_source(code).map(|()| {
+            self.size_tape();
+            self.prepare();
+        });
+        #[cfg(feature = "tracing")]
+        self.exit("load", &[("instructions", Value::U64(self.program.len() as u64))]);
+        loaded?;
+        #[cfg(feature = "std")]
+        if let Some(metrics) = &self.metrics {
+            metrics.incr(MetricKey::ParseMicros, start.elapsed().as_micros() as u64);
+        }
+
+        Ok(())
+    }
+
+    // Loads `program` as it is, without parsing nor optimizing it, e.g. one built with
+    // `Program::concat`. There is no source then : `source`-based reports such as labels see an
+    // empty program, and errors point to spans in the sources of the segments.
+    pub fn load_ir(&mut self, program: &Program) -> Result<(), InvalidProgram> {
+        program.validate()?;
+        for (index, inst) in program.instructions.iter().enumerate() {
+            let invalid = |reason| Err(InvalidProgram { instruction: index, reason });
+            match inst.kind {
+                IRInstructionKind::Custom(_) if inst.operand.unwrap() >= self.handlers.len() => return invalid("no such custom instruction"),
+                IRInstructionKind::PrintByteAsChar | IRInstructionKind::ReadInputToByte if self.forbid_io => return invalid("I/O is forbidden"),
+                _ => (),
+            }
+        }
+
+        self.load_program_from_str("").unwrap();
+        self.program = program.instructions.clone();
+        self.pass_reports.clear();
+        self.size_tape();
+        self.prepare();
+        Ok(())
+    }
+
+    // The loaded program, as optimized, for `Program::concat` or `load_ir`.
+    pub fn to_program(&self) -> Program {
+        Program { instructions: self.program.clone(), segments: Vec::new() }
+    }
+
+    // Live editing : replaces the source of the loaded program with `code`, keeping the tape, the
+    // memory pointer and the counts of the limits, so that a paused program goes on running the
+    // new code. The instruction pointer keeps its place in the source : after an edit at or past
+    // it, whatever code is now there runs next, and an edit before it shifts it along with the
+    // code. An edit overlapping the next instruction to run, or merging it with the code before
+    // it as in a run of `+`, is refused with `EditAtInstructionPointer`. On errors the program is
+    // left as it was. Loop counts and the undo history start over, and the tape keeps its size.
+    pub fn edit_program(&mut self, code: &str) -> Result<(), LoadError> {
+        let old_program = core::mem::take(&mut self.program);
+        let old_lexer = core::mem::replace(&mut self.lexer, Lexer::new(Vec::new(), self.tab_width));
+        let old_reports = core::mem::take(&mut self.pass_reports);
+
+        let moved = self.compile_source(code).and_then(|()| self.moved_instruction_pointer(&old_program, &old_lexer.code));
+        let instruction_pointer = match moved {
+            Ok(instruction_pointer) => instruction_pointer,
+            Err(e) => {
+                (self.program, self.lexer, self.pass_reports) = (old_program, old_lexer, old_reports);
+                return Err(e);
+            },
+        };
+
+        self.instruction_pointer = instruction_pointer;
+        if self.program.get(instruction_pointer).is_none_or(|inst| inst.kind != IRInstructionKind::ReadInputToByte) {
+            self.pending_input = None;
+        }
+        self.history.clear();
+        self.breakpoints.clear();
+        self.forget_saved_state();
+        self.trace = None;
+        self.prepare();
+
+        Ok(())
+    }
+
+    // Where the instruction pointer goes in the newly compiled program, see `edit_program`.
+    fn moved_instruction_pointer(&self, old_program: &[IRInstruction], old_source: &[char]) -> Result<usize, LoadError> {
+        let new_source = &self.lexer.code;
+        let prefix = old_source.iter().zip(new_source).take_while(|(a, b)| a == b).count();
+        let suffix = old_source[prefix..].iter().rev().zip(new_source[prefix..].iter().rev()).take_while(|(a, b)| a == b).count();
+
+        let refused = |instruction: usize, span: Span| Err(LoadError::EditAtInstructionPointer { instruction, span });
+        // Programs loaded with `load_ir` have spans past their empty source.
+        let at = old_program.get(self.instruction_pointer).map_or(old_source.len(), |inst| inst.span.start.min(old_source.len()));
+        let at = if at <= prefix {
+            at
+        } else if at >= old_source.len() - suffix {
+            new_source.len() - (old_source.len() - at)
+        } else {
+            return refused(self.instruction_pointer, old_program[self.instruction_pointer].span);
+        };
+
+        // The instructions a range stands for start inside its span, so ranges are left out.
+        let instruction_pointer = self.program.partition_point(|inst| inst.span.start < at);
+        match self.program[..instruction_pointer].iter().enumerate().rev().find(|(_, inst)| !inst.kind.is_range()) {
+            Some((index, inst)) if inst.span.end > at => refused(index, inst.span),
+            _ => Ok(instruction_pointer),
+        }
+    }
+
+    // Lexes `code` into `program`, then links and optimizes it.
+    fn compile_source(&mut self, code: &str) -> Result<(), LoadError> {
+        let extra_tokens = self.breakpoint_token.into_iter().chain(self.handlers.iter().map(|(t, _)| *t)).collect();
+        self.lexer = Lexer::new(extra_tokens, self.tab_width);
+        self.program.clear();
+        self.lexer.fill(code);
+
+        let mut next = self.lexer.next();
+
+        while let Some(c) = next {
+            let inst: IRInstruction;
+            let start = self.lexer.last_position();
+            let (line, column) = (self.lexer.last_line, self.lexer.last_column);
+            match c {
+                '>' | '<' | '+' | '-' => {
+                    let k: IRInstructionKind;
+                    if c == '>' { k = IRInstructionKind::IncrementPointer; }
+                    else if c == '<' { k = IRInstructionKind::DecrementPointer; }
+                    else if c == '+' { k = IRInstructionKind::IncrementByte; }
+                    else { k = IRInstructionKind::DecrementByte; }
+
+                    let mut s = self.lexer.next();
+                    let mut streak = 1usize;
+                    let mut end = start + 1;
+
+                    while s == Some(c) {
+                        streak += 1;
+                        end = self.lexer.last_position() + 1;
+                        s = self.lexer.next();
+                    }
+
+                    inst = IRInstruction { kind: k, operand: Some(streak), span: Span { start, end, line, column } };
+                    next = s;
+                },
+                '.' | ',' | '[' | ']' => {
+                    let k: IRInstructionKind;
+                    if c == '.' { k = IRInstructionKind::PrintByteAsChar; }
+                    else if c == ',' { k = IRInstructionKind::ReadInputToByte; }
+                    else if c == '[' { k = IRInstructionKind::JumpIfZero; }
+                    else { k = IRInstructionKind::JumpIfNotZero; }
+
+                    inst = IRInstruction { kind: k, operand: None, span: Span { start, end: start + 1, line, column } };
+                    next = self.lexer.next();
+                },
+                _ if Some(c) == self.breakpoint_token => {
+                    inst = IRInstruction { kind: IRInstructionKind::Breakpoint, operand: None, span: Span { start, end: start + 1, line, column } };
+                    next = self.lexer.next();
+                },
+                _ => {
+                    let handler = self.handlers.iter().position(|(t, _)| *t == c).unwrap();
+                    inst = IRInstruction { kind: IRInstructionKind::Custom(c), operand: Some(handler), span: Span { start, end: start + 1, line, column } };
+                    next = self.lexer.next();
+                },
+            }
+
+            self.program.push(inst);
+        }
+
+        if self.forbid_io {
+            if let Some(instruction) = self.program.iter().position(|inst| matches!(inst.kind, IRInstructionKind::PrintByteAsChar | IRInstructionKind::ReadInputToByte)) {
+                let inst = self.program[instruction];
+                self.program.clear();
+                return Err(LoadError::IoForbidden { instruction, span: inst.span });
+            }
+        }
+
+        // Brackets are checked on the source before any pass gets to rewrite loops.
+        self.precompute_jumps()?;
+        let mut program = Program { instructions: core::mem::take(&mut self.program), segments: Vec::new() };
+        let mut default_pipeline = None;
+        let pipeline = match &mut self.pipeline {
+            Some(pipeline) => pipeline,
+            None if self.wrap_policy == WrapPolicy::Trap => default_pipeline.insert(Pipeline::for_level(OptLevel::O0)),
+            None => default_pipeline.insert(Pipeline::for_level(self.opt_level)),
+        };
+        #[cfg(not(feature = "tracing"))]
+        let reports = pipeline.run(&mut program);
+        #[cfg(feature = "tracing")]
+        let reports = match &self.subscriber {
+            Some(subscriber) => pipeline.run_with(
+                &mut program,
+                |name, before| subscriber.enter("pass", &[("name", Value::Str(name)), ("instructions_before", Value::U64(before as u64))]),
+                |report| subscriber.exit("pass", &[("instructions_after", Value::U64(report.instructions_after as u64))]),
+            ),
+            None => pipeline.run(&mut program),
+        };
+        self.pass_reports = reports;
+        self.program = program.instructions;
+        self.precompute_jumps()
+    }
+
+    fn size_tape(&mut self) {
+        let size = match self.infer_tape_size && self.tape_mode == TapeMode::Error {
+            true => analysis::inferred_tape_size(&self.program, self.tape_size).unwrap_or(self.tape_size),
+            false => self.tape_size,
+        };
+        if size != self.memory.len() {
+            self.memory = Tape::new(size, self.memory.backing());
+            if self.touched.is_some() {
+                self.set_cell_tracking(true);
+            }
+        }
+    }
+
+    // Tables derived from the program, once its jumps are linked.
+    fn prepare(&mut self) {
+        if let Some(coverage) = &mut self.coverage {
+            *coverage = Coverage::new(self.program.len());
+        }
+        self.reset_loop_iterations();
+        self.precompute_gas_costs();
+        self.dispatch = executor(self.engine).compile(&self.program);
+    }
+
+    // The loaded program, ready to be run many times without being parsed again. Programs using
+    // custom instructions can't be compiled, as their handlers stay with this interpreter.
+    pub fn compile(&self) -> Option<CompiledProgram> {
+        if self.program.iter().any(|inst| matches!(inst.kind, IRInstructionKind::Custom(_))) {
+            return None;
+        }
+        let config = Config {
+            tape_size: self.tape_size,
+            opt_level: self.opt_level,
+            breakpoint_token: self.breakpoint_token,
+            tab_width: self.tab_width,
+            tape_backing: self.memory.backing(),
+            forbid_io: self.forbid_io,
+            infer_tape_size: self.infer_tape_size,
+            tape_mode: self.tape_mode,
+            wrap_policy: self.wrap_policy,
+            on_end_of_input: self.on_end_of_input,
+            engine: self.engine,
+            max_source_bytes: self.max_source_bytes,
+        };
+        Some(CompiledProgram { program: self.program.clone(), config, input_mode: self.input_mode, non_ascii: self.non_ascii, bell: self.bell, utf8_io: self.utf8_io, output_map: self.output_map.clone() })
+    }
+
+    // Brackets are paired with a heap stack, so nesting depth is only bounded by memory (tested
+    // with 100000 nested loops).
+    fn precompute_jumps(&mut self) -> Result<(), LoadError> {
+        #[cfg(feature = "tracing")]
+        self.enter("jumps", &[("instructions", Value::U64(self.program.len() as u64))]);
+        let paired = self.pair_brackets();
+        #[cfg(feature = "tracing")]
+        self.exit("jumps", &[]);
+        paired
+    }
+
+    fn pair_brackets(&mut self) -> Result<(), LoadError> {
+        let mut stack = Vec::new();
+
+        let mut local_instruction_pointer = 0usize;
+
+        while local_instruction_pointer < self.program.len() {
+            let inst = self.program[local_instruction_pointer];
+
+            match inst.kind {
+                kind if kind.opens() => stack.push(local_instruction_pointer),
+                kind if kind.closes() => {
+                    let target = stack.pop().ok_or(LoadError::UnmatchedBracket { instruction: local_instruction_pointer, span: inst.span })?;
+                    self.program[local_instruction_pointer].operand = Some(target);
+                    self.program[target].operand = Some(local_instruction_pointer);
+                },
+                _ => (), // Other instructions aren't jump related.
+            }
+
+            local_instruction_pointer += 1;
+        }
+
+        match stack.pop() {
+            Some(unmatched) => Err(LoadError::UnmatchedBracket { instruction: unmatched, span: self.program[unmatched].span }),
+            None => Ok(()),
+        }
+    }
+
+    // Index of the `[` or `]` paired with the bracket at `ir_index`, `None` for other instructions.
+    pub fn matching_bracket(&self, ir_index: usize) -> Option<usize> {
+        let inst = self.program.get(ir_index)?;
+        match inst.kind {
+            kind if kind.opens() || kind.closes() => inst.operand,
+            _ => None,
+        }
+    }
+
+    // Same as `matching_bracket`, but from and to character offsets in the source code.
+    pub fn matching_bracket_at_offset(&self, offset: usize) -> Option<usize> {
+        // A `CopyRange` starts where the `[` after it does.
+        let first = self.program.partition_point(|inst| inst.span.start < offset);
+        let ir_index = (first..self.program.len()).take_while(|&i| self.program[i].span.start == offset).find(|&i| self.matching_bracket(i).is_some())?;
+        let partner = self.matching_bracket(ir_index)?;
+        Some(self.program[partner].span.start)
+    }
+
+    pub fn loops(&self) -> Vec<LoopInfo> {
+        let mut loops = Vec::new();
+        let mut depth = 0usize;
+
+        // Running count of I/O instructions, so that deeply nested loops don't rescan their bodies.
+        let mut io_before = Vec::with_capacity(self.program.len() + 1);
+        io_before.push(0usize);
+        for inst in &self.program {
+            let is_io = matches!(inst.kind, IRInstructionKind::PrintByteAsChar | IRInstructionKind::ReadInputToByte);
+            io_before.push(io_before.last().unwrap() + is_io as usize);
+        }
+
+        for (index, inst) in self.program.iter().enumerate() {
+            match inst.kind {
+                kind if kind.opens() => {
+                    depth += 1;
+                    let close = inst.operand.unwrap();
+                    let contains_io = io_before[close] > io_before[index];
+
+                    loops.push(LoopInfo {
+                        open: index,
+                        close,
+                        depth,
+                        span: Span { end: self.program[close].span.end, ..inst.span },
+                        contains_io,
+                    });
+                },
+                kind if kind.closes() => depth -= 1,
+                _ => (),
+            }
+        }
+
+        loops
+    }
+
+    // Passes run on the programs loaded from now on, instead of those of the configured `opt_level`.
+    pub fn set_pipeline(&mut self, pipeline: Pipeline) {
+        self.pipeline = Some(pipeline);
+    }
+
+    // What each pass did to the loaded program.
+    pub fn pass_reports(&self) -> &[PassReport] {
+        &self.pass_reports
+    }
+
+    // Numbered listing of the optimized program, one instruction per line with its jump target and
+    // source location, indented by loop depth.
+    pub fn listing(&self) -> String {
+        listing(&self.program)
+    }
+
+    // Brainfuck source equivalent to the compiled program, comments dropped.
+    pub fn to_source(&self) -> String {
+        let mut source = String::new();
+
+        for inst in &self.program {
+            let (command, count) = match inst.kind {
+                IRInstructionKind::IncrementPointer => ('>', inst.operand.unwrap()),
+                IRInstructionKind::DecrementPointer => ('<', inst.operand.unwrap()),
+                IRInstructionKind::IncrementByte => ('+', inst.operand.unwrap()),
+                IRInstructionKind::DecrementByte => ('-', inst.operand.unwrap()),
+                IRInstructionKind::PrintByteAsChar => ('.', 1),
+                IRInstructionKind::ReadInputToByte => (',', 1),
+                IRInstructionKind::JumpIfZero | IRInstructionKind::If => ('[', 1),
+                IRInstructionKind::JumpIfNotZero | IRInstructionKind::EndIf => (']', 1),
+                IRInstructionKind::SetZero => {
+                    source.push_str("[-]");
+                    continue;
+                },
+                IRInstructionKind::SetConst => {
+                    source.push_str("[-]");
+                    match inst.operand.unwrap() {
+                        value @ ..=128 => ('+', value),
+                        value => ('-', 256 - value),
+                    }
+                },
+                IRInstructionKind::Breakpoint => (self.breakpoint_token.unwrap(), 1),
+                IRInstructionKind::Custom(token) => (token, 1),
+                IRInstructionKind::FillRange { .. } | IRInstructionKind::CopyRange { .. } => continue, // The instructions after it are printed.
+            };
+            source.extend(core::iter::repeat_n(command, count));
+        }
+
+        source
+    }
+
+    #[cfg(feature = "std")]
+    pub fn write_listing(&self, path: &str) -> std::io::Result<()> {
+        std::fs::write(path, self.listing())
+    }
+
+    pub(crate) fn program(&self) -> &[IRInstruction] {
+        &self.program
+    }
+
+    // Source of the loaded program.
+    pub(crate) fn source(&self) -> &[char] {
+        &self.lexer.code
+    }
+
+    // Labels defined in the source of the loaded program, in order, see `Label`.
+    pub fn labels(&self) -> Vec<Label> {
+        labels(&self.lexer.code, self.tab_width)
+    }
+
+    // First instruction of the code after label `name`, `None` when it isn't defined or no code
+    // follows it. Code merged with the code before the label, as in a run of `+` around it,
+    // starts at the instruction it merged into.
+    pub fn label_instruction(&self, name: &str) -> Option<usize> {
+        let label = self.labels().into_iter().rev().find(|label| label.name == name)?;
+        let instruction = self.program.partition_point(|inst| inst.span.end <= label.span.end);
+        // The instructions a range stands for are skipped, it is the one to stop at.
+        let range = self.program[..instruction].iter().enumerate().rev().find(|(index, inst)| inst.kind.is_range() && index + inst.operand.unwrap() >= instruction);
+        let instruction = range.map_or(instruction, |(index, _)| index);
+        (instruction < self.program.len()).then_some(instruction)
+    }
+
+    // Whether `c` is compiled to an instruction rather than being a comment.
+    pub(crate) fn is_command(&self, c: char) -> bool {
+        self.lexer.is_valid_instruction(c)
+    }
+
+    // Whether the program ran to its end : nothing is left to step through.
+    pub fn is_halted(&self) -> bool {
+        self.instruction_pointer >= self.program.len()
+    }
+
+    pub fn set_cancel_token(&mut self, token: CancelToken) {
+        self.cancel_token = Some(token);
+    }
+
+    pub fn set_loop_hook<F: FnMut(LoopEvent) + Send + 'static>(&mut self, hook: F) {
+        self.loop_hook = Some(Box::new(hook));
+    }
+
+    pub fn clear_loop_hook(&mut self) {
+        self.loop_hook = None;
+    }
+
+    // Aborts with `LoopCapExceeded` when a single run of any loop iterates more than `cap` times.
+    // Each time a loop is entered anew its count starts over, so only runaway loops are caught.
+    pub fn set_loop_iteration_cap(&mut self, cap: usize) {
+        self.loop_iteration_cap = Some(cap);
+        self.reset_loop_iterations();
+    }
+
+    fn reset_loop_iterations(&mut self) {
+        self.loop_iterations.clear();
+        if self.loop_iteration_cap.is_some() {
+            self.loop_iterations.resize(self.program.len(), 0);
+        }
+    }
+
+    // Limits for running untrusted programs, counted from the moment the program is loaded. Every
+    // executed IR instruction is a step, and output is measured in bytes once encoded.
+    pub fn set_step_limit(&mut self, limit: u64) {
+        self.step_limit = Some(limit);
+    }
+
+    pub fn set_output_limit(&mut self, bytes: usize) {
+        self.output_limit = Some(bytes);
+    }
+
+    // The deadline starts with the first step, and is only checked every few thousand steps.
+    #[cfg(feature = "std")]
+    pub fn set_time_limit(&mut self, limit: Duration) {
+        self.time_limit = Some(limit);
+        self.deadline = None;
+    }
+
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    // Time spent in the calls running the program since it was loaded, the time the program waits
+    // for input included.
+    #[cfg(feature = "std")]
+    pub fn run_time(&self) -> Duration {
+        self.run_time
+    }
+
+    // Throughput of the runs since the program was loaded : `steps` over `run_time`, `None` before
+    // anything ran. Steps count IR instructions, so the figure grows with the optimization level.
+    #[cfg(feature = "std")]
+    pub fn instructions_per_second(&self) -> Option<f64> {
+        let seconds = self.run_time.as_secs_f64();
+        (self.steps > 0 && seconds > 0.0).then(|| self.steps as f64 / seconds)
+    }
+
+    // Metered execution : each instruction is charged its cost under `schedule`, and running out of
+    // gas stops the program before the instruction that couldn't be paid for. `add_gas` then lets it
+    // resume where it stopped. Gas used is counted from the moment the program is loaded.
+    pub fn set_gas(&mut self, limit: u64, schedule: GasSchedule) {
+        self.gas_schedule = Some(schedule);
+        self.gas_limit = limit;
+        self.precompute_gas_costs();
+    }
+
+    pub fn add_gas(&mut self, gas: u64) {
+        self.gas_limit = self.gas_limit.saturating_add(gas);
+    }
+
+    pub fn gas_used(&self) -> u64 {
+        self.gas_used
+    }
+
+    fn precompute_gas_costs(&mut self) {
+        self.gas_costs.clear();
+        if let Some(schedule) = self.gas_schedule {
+            self.gas_costs.extend(self.program.iter().map(|inst| schedule.cost(inst)));
+        }
+    }
+
+    fn reset_limits(&mut self) {
+        self.steps = 0;
+        self.output_bytes = 0;
+        self.gas_used = 0;
+        #[cfg(feature = "std")]
+        {
+            self.deadline = None;
+            self.run_time = Duration::ZERO;
+        }
+    }
+
+    fn check_limits(&mut self, inst: &IRInstruction) -> Result<(), RuntimeError> {
+        let (instruction, span) = (self.instruction_pointer, inst.span);
+
+        if self.step_limit.is_some_and(|limit| self.steps >= limit) {
+            return Err(RuntimeError::StepLimitExceeded { instruction, span });
+        }
+
+        let gas = self.gas_costs.get(instruction).copied().unwrap_or(0);
+        if self.gas_schedule.is_some() && self.gas_used + gas > self.gas_limit {
+            return Err(RuntimeError::OutOfGas { instruction, span });
+        }
+
+        #[cfg(feature = "std")]
+        if let Some(limit) = self.time_limit {
+            let deadline = *self.deadline.get_or_insert_with(|| Instant::now() + limit);
+            if self.steps.is_multiple_of(TIME_CHECK_INTERVAL) && Instant::now() >= deadline {
+                return Err(RuntimeError::TimeLimitExceeded { instruction, span });
+            }
+        }
+
+        if let (Some(limit), IRInstructionKind::PrintByteAsChar) = (self.output_limit, inst.kind) {
+            let length = match self.utf8_io {
+                Some(_) => 1,
+                None => encode_output(self.printed_byte(), self.non_ascii, self.bell, &mut [0; 4]).len(),
+            };
+            if self.output_bytes + length > limit {
+                return Err(RuntimeError::OutputLimitExceeded { instruction, span });
+            }
+            self.output_bytes += length;
+        }
+
+        self.steps += 1;
+        self.gas_used += gas;
+
+        Ok(())
+    }
+
+    pub fn interpret(&mut self) -> Result<(), RuntimeError> {
+        self.reported(|this| {
+            let inline = this.runs_inline();
+            while !this.is_halted() {
+                if inline {
+                    this.run_inline();
+                }
+                this.step_once()?;
+            }
+            Ok(())
+        })
+    }
+
+    // Whether nothing needs to see each step, so that `Engine::Match` can run them with `run_inline`.
+    fn runs_inline(&self) -> bool {
+        let observed = self.trace.is_some() || self.coverage.is_some() || self.hang_detector.is_some() || self.loop_hook.is_some() || self.touched.is_some() || self.metrics.is_some();
+        #[cfg(feature = "tracing")]
+        let observed = observed || self.subscriber.is_some();
+        let limited = self.gas_schedule.is_some() || self.loop_iteration_cap.is_some() || self.history_limit > 0 || self.pending_input.is_some();
+        #[cfg(feature = "std")]
+        let limited = limited || self.time_limit.is_some();
+        self.engine == Engine::Match && !observed && !limited
+    }
+
+    // `Engine::Match`'s loop : runs the instructions needing nothing but the tape in a row, with a
+    // `match` of their own. It stops before any other instruction (I/O, custom instructions), or
+    // one that would move off the tape or trap, leaving it to `step_once`.
+    fn run_inline(&mut self) {
+        let steps = self.step_limit.map_or(INLINE_STEPS, |limit| limit.saturating_sub(self.steps).min(INLINE_STEPS));
+        let (mut ip, mut mp, start) = (self.instruction_pointer, self.memory_pointer, self.steps);
+        let trap = self.wrap_policy == WrapPolicy::Trap;
+
+        while self.steps - start < steps {
+            let Some(&inst) = self.program.get(ip) else { break };
+            match inst.kind {
+                IRInstructionKind::IncrementPointer => match mp + inst.operand.unwrap() {
+                    target if target < self.memory.len() => mp = target,
+                    _ => break,
+                },
+                IRInstructionKind::DecrementPointer => match mp.checked_sub(inst.operand.unwrap()) {
+                    Some(target) => mp = target,
+                    None => break,
+                },
+                IRInstructionKind::IncrementByte if !trap => self.memory[mp] = self.memory[mp].wrapping_add(inst.operand.unwrap() as u8),
+                IRInstructionKind::DecrementByte if !trap => self.memory[mp] = self.memory[mp].wrapping_sub(inst.operand.unwrap() as u8),
+                IRInstructionKind::SetZero => self.memory[mp] = 0,
+                IRInstructionKind::SetConst => self.memory[mp] = inst.operand.unwrap() as u8,
+                IRInstructionKind::JumpIfZero | IRInstructionKind::If => {
+                    if self.memory[mp] == 0 {
+                        ip = inst.operand.unwrap();
+                    }
+                },
+                IRInstructionKind::JumpIfNotZero => {
+                    if self.memory[mp] != 0 {
+                        ip = inst.operand.unwrap();
+                    }
+                },
+                IRInstructionKind::EndIf | IRInstructionKind::Breakpoint => (),
+                _ => break,
+            }
+            ip += 1;
+            self.steps += 1;
+        }
+        (self.instruction_pointer, self.memory_pointer) = (ip, mp);
+    }
+
+    // In debug mode, `resume` stops right after the breakpoints compiled from `breakpoint_token`.
+    pub fn set_debug_mode(&mut self, enabled: bool) {
+        self.debug_mode = enabled;
+    }
+
+    // In debug mode, `resume` also stops before running `instruction`, with `Pause::Breakpoint`.
+    // Breakpoints are cleared when a program is loaded or edited.
+    pub fn add_breakpoint(&mut self, instruction: usize) {
+        if let Err(index) = self.breakpoints.binary_search(&instruction) {
+            self.breakpoints.insert(index, instruction);
+        }
+    }
+
+    // `add_breakpoint` at the code after label `name`, returning its instruction, `None` when
+    // `label_instruction` finds none.
+    pub fn add_label_breakpoint(&mut self, name: &str) -> Option<usize> {
+        let instruction = self.label_instruction(name)?;
+        self.add_breakpoint(instruction);
+        Some(instruction)
+    }
+
+    pub fn clear_breakpoints(&mut self) {
+        self.breakpoints.clear();
+    }
+
+    // Runs until the program halts or, in debug mode, passes a breakpoint or reaches one added
+    // with `add_breakpoint`. The instruction `resume` starts from never stops it.
+    pub fn resume(&mut self) -> Result<Pause, RuntimeError> {
+        self.reported(|this| {
+            let mut first = true;
+            while !this.is_halted() {
+                let instruction = this.instruction_pointer;
+                if this.debug_mode && !first && this.breakpoints.binary_search(&instruction).is_ok() {
+                    this.flush_output()?;
+                    return Ok(Pause::Breakpoint { instruction, span: this.program[instruction].span });
+                }
+                first = false;
+                let at_breakpoint = this.program[instruction].kind == IRInstructionKind::Breakpoint;
+                this.step_once()?;
+                if at_breakpoint && this.debug_mode {
+                    this.flush_output()?;
+                    return Ok(Pause::Breakpoint { instruction, span: this.program[instruction].span });
+                }
+            }
+            Ok(Pause::Halted)
+        })
+    }
+
+    // Runs until the program prints a byte, which still goes to the output, and returns it. `None`
+    // once the program halted.
+    pub fn run_to_output(&mut self) -> Result<Option<u8>, RuntimeError> {
+        self.reported(|this| {
+            while !this.is_halted() {
+                let printing = this.program[this.instruction_pointer].kind == IRInstructionKind::PrintByteAsChar;
+                let byte = this.printed_byte();
+                this.step_once()?;
+                if printing {
+                    this.flush_output()?;
+                    return Ok(Some(byte));
+                }
+            }
+            Ok(None)
+        })
+    }
+
+    // Runs `n` full iterations of the loop whose `[` is under the instruction pointer, or was just
+    // evaluated, as where it paused before. It stops right after the test ending the last one, so
+    // that it goes on from there without evaluating anything twice, or after the loop when it ends
+    // sooner. Breakpoints don't stop it. `None` when no loop starts there.
+    pub fn fast_forward_loop(&mut self, n: u64) -> Result<Option<FastForward>, RuntimeError> {
+        let opens = |index: usize| self.program.get(index).filter(|inst| inst.kind.opens()).map(|inst| (index, inst.operand.unwrap()));
+        let Some((open, close)) = opens(self.instruction_pointer).or_else(|| opens(self.instruction_pointer.checked_sub(1)?)) else {
+            return Ok(None);
+        };
+        self.reported(|this| {
+            let mut iterations = 0;
+            while iterations < n {
+                let at = this.instruction_pointer;
+                this.step_once()?;
+                iterations += (at == close) as u64;
+                if !(open..=close).contains(&this.instruction_pointer) && iterations < n {
+                    this.flush_output()?;
+                    return Ok(Some(FastForward::Exited { iterations }));
+                }
+            }
+            this.flush_output()?;
+            Ok(Some(FastForward::Paused { cell: this.memory[this.memory_pointer] }))
+        })
+    }
+
+    // Executes the instruction under the instruction pointer, doing nothing once the program halted,
+    // and tells whether the program halted with it.
+    pub fn step(&mut self) -> Result<StepStatus, RuntimeError> {
+        let result = self.step_once();
+        if result.is_err() || self.is_halted() {
+            self.report_metrics();
+        }
+        if let (Err(e), Some(metrics)) = (&result, &self.metrics) {
+            metrics.incr(MetricKey::RuntimeErrors(e.kind()), 1);
+        }
+        #[cfg(feature = "tracing")]
+        if let Err(e) = &result {
+            self.runtime_error_event(e);
+        }
+        result.map(|()| if self.is_halted() { StepStatus::Halted } else { StepStatus::Running })
+    }
+
+    pub(crate) fn step_once(&mut self) -> Result<(), RuntimeError> {
+        let mut result = self.run_step();
+        if self.utf8_output.is_pending() && result.is_ok() && self.is_halted() {
+            result = self.finish_utf8_output();
+        }
+        if !self.output_buffer.is_empty() && (result.is_err() || self.is_halted()) {
+            let flushed = self.flush_output();
+            return result.and(flushed);
+        }
+        result
+    }
+
+    fn run_step(&mut self) -> Result<(), RuntimeError> {
+        let undo = (self.history_limit > 0 && !self.is_halted()).then(|| Undo {
+            instruction_pointer: self.instruction_pointer,
+            memory_pointer: self.memory_pointer,
+            cell: self.memory.get(self.memory_pointer).copied().unwrap_or(0),
+        });
+
+        if let Some(mut detector) = self.hang_detector.take() {
+            detector.widen(&self.memory, self.written_cells());
+            self.hang_detector = Some(detector);
+        }
+        let custom = self.hang_detector.is_some() && matches!(self.program.get(self.instruction_pointer), Some(inst) if matches!(inst.kind, IRInstructionKind::Custom(_)));
+
+        // After a failed read, the `,` already went through the limits and only needs its input.
+        let (effect, mut number) = match self.pending_input.take() {
+            Some(number) => (Effect::Input, number),
+            None => (self.execute()?, DecimalNumber::default()),
+        };
+
+        if self.steps.is_multiple_of(METRICS_INTERVAL) {
+            self.report_metrics();
+            #[cfg(feature = "tracing")]
+            self.event("steps", &[("steps", Value::U64(self.steps))]);
+        }
+
+        // I/O is progress, and custom instructions may keep state of their own.
+        if !matches!(effect, Effect::None) || custom {
+            self.forget_saved_state();
+        } else if let Some(detector) = self.hang_detector.as_mut().filter(|_| self.instruction_pointer < self.program.len()) {
+            if let Some(period) = detector.check(self.steps, self.instruction_pointer, self.memory_pointer, &self.memory) {
+                return Err(self.no_progress(period));
+            }
+        }
+
+        match effect {
+            Effect::None => (),
+            Effect::Output(byte) if self.utf8_io.is_some() => self.print_utf8(byte)?,
+            Effect::Output(byte) => {
+                let mut encoded = [0; 4];
+                let bytes = encode_output(byte, self.non_ascii, self.bell, &mut encoded);
+                self.output_written += bytes.len() as u64;
+                if self.output_buffer.len() + bytes.len() > self.output_buffer_size {
+                    self.flush_output()?;
+                }
+                if bytes.len() > self.output_buffer_size {
+                    self.output.write_bytes(bytes)?;
+                    self.output.flush()?;
+                } else {
+                    self.output_buffer.extend_from_slice(bytes);
+                }
+            },
+            Effect::Input => {
+                // Prompts must be out before waiting for the answer.
+                if let Err(e) = self.flush_output() {
+                    self.pending_input = Some(number);
+                    return Err(e);
+                }
+                let byte = loop {
+                    let byte = match self.read_input() {
+                        Ok(byte) => byte,
+                        Err(e) => {
+                            self.pending_input = Some(number);
+                            return Err(e);
+                        },
+                    };
+                    match self.input_mode {
+                        InputMode::RawByte => break byte,
+                        InputMode::DecimalNumber => if let Some(value) = number.push(byte) { break value; },
+                    }
+                };
+                self.complete_input(byte)?;
+            },
+        }
+
+        if let Some(undo) = undo {
+            // A custom instruction may write any cell, so there is no going back past it.
+            if matches!(self.program[undo.instruction_pointer].kind, IRInstructionKind::Custom(_)) {
+                self.history.clear();
+            } else {
+                if self.history.len() == self.history_limit {
+                    self.history.pop_front();
+                }
+                self.history.push_back(undo);
+            }
+        }
+
+        Ok(())
+    }
+
+    // Next byte for `,`, decoded as UTF-8 under `utf8_io`.
+    fn read_input(&mut self) -> Result<Option<u8>, RuntimeError> {
+        let Some(invalid) = self.utf8_io else {
+            return self.read_raw_input();
+        };
+        loop {
+            if let Some(byte) = self.utf8_read.pop_front() {
+                return Ok(Some(byte));
+            }
+            let valid = match self.read_raw_input()? {
+                Some(byte) => self.utf8_input.push(byte, &mut self.utf8_read),
+                None if self.utf8_input.is_pending() => self.utf8_input.finish(&mut self.utf8_read),
+                None => return Ok(None),
+            };
+            if !valid && invalid == InvalidUtf8::Fail {
+                self.utf8_read.clear();
+                return Err(RuntimeError::InvalidUtf8Input { instruction: self.instruction_pointer, span: self.program[self.instruction_pointer].span });
+            }
+        }
+    }
+
+    fn read_raw_input(&mut self) -> Result<Option<u8>, RuntimeError> {
+        match self.input.read_byte() {
+            Ok(byte) => {
+                self.input_bytes += byte.is_some() as u64;
+                if let (Some(trace), Some(byte)) = (&mut self.trace, byte) {
+                    trace.input(byte);
+                }
+                Ok(byte)
+            },
+            #[cfg(feature = "std")]
+            Err(e) if e.kind() == std::io::ErrorKind::TimedOut => {
+                Err(RuntimeError::InputTimeout { instruction: self.instruction_pointer, span: self.program[self.instruction_pointer].span })
+            },
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    // Prints a cell under `utf8_io`, once the `.` ran.
+    fn print_utf8(&mut self, byte: u8) -> Result<(), RuntimeError> {
+        let byte = match (byte, self.bell) {
+            (7, Bell::Suppress) => return Ok(()),
+            (7, Bell::Replace(replacement)) => replacement,
+            _ => byte,
+        };
+        self.utf8_output_at = self.instruction_pointer - 1;
+        let before = self.output_buffer.len();
+        let valid = self.utf8_output.push(byte, &mut self.output_buffer);
+        self.utf8_printed(before, valid)
+    }
+
+    // A character left incomplete when the program halts is invalid.
+    fn finish_utf8_output(&mut self) -> Result<(), RuntimeError> {
+        if !self.utf8_output.is_pending() {
+            return Ok(());
+        }
+        let before = self.output_buffer.len();
+        let valid = self.utf8_output.finish(&mut self.output_buffer);
+        self.utf8_printed(before, valid)
+    }
+
+    fn utf8_printed(&mut self, before: usize, valid: bool) -> Result<(), RuntimeError> {
+        if !valid && self.utf8_io == Some(InvalidUtf8::Fail) {
+            self.output_buffer.truncate(before);
+            let instruction = self.utf8_output_at;
+            return Err(RuntimeError::InvalidUtf8Output { instruction, span: self.program[instruction].span });
+        }
+        self.output_written += (self.output_buffer.len() - before) as u64;
+        if self.output_buffer.len() > self.output_buffer_size {
+            self.flush_output()?;
+        }
+        Ok(())
+    }
+
+    // Runs at most `max_steps` instructions, so that an embedder can interleave the program with its
+    // own work. All state is kept between calls, and output is flushed before returning. An input
+    // reporting `WouldBlock` ends the call with `NeedsInput` instead of an error.
+    pub fn run_for(&mut self, max_steps: u64) -> Result<StepOutcome, RuntimeError> {
+        self.reported(|this| {
+            let mut steps_run = 0;
+
+            while !this.is_halted() {
+                if steps_run == max_steps {
+                    this.flush_output()?;
+                    return Ok(StepOutcome::BudgetExhausted { steps_run });
+                }
+                match this.step_once() {
+                    Ok(()) => steps_run += 1,
+                    #[cfg(feature = "std")]
+                    Err(RuntimeError::Io(e)) if e.kind() == std::io::ErrorKind::WouldBlock => return Ok(StepOutcome::NeedsInput),
+                    Err(e) => return Err(e),
+                }
+            }
+
+            Ok(StepOutcome::Completed(RunSummary { steps: this.steps }))
+        })
+    }
+
+    // Counters go to `metrics` at the end of each load and run, see `Metrics`.
+    pub fn set_metrics(&mut self, metrics: Arc<dyn Metrics>) {
+        self.metrics = Some(metrics);
+    }
+
+    // Spans and events of the loads and runs go to `subscriber`, see `instrument`.
+    #[cfg(feature = "tracing")]
+    pub fn set_subscriber(&mut self, subscriber: Arc<dyn Subscriber>) {
+        self.subscriber = Some(subscriber);
+    }
+
+    #[cfg(feature = "tracing")]
+    fn enter(&self, span: &'static str, fields: &Fields<'_>) {
+        if let Some(subscriber) = &self.subscriber {
+            subscriber.enter(span, fields);
+        }
+    }
+
+    #[cfg(feature = "tracing")]
+    fn exit(&self, span: &'static str, fields: &Fields<'_>) {
+        if let Some(subscriber) = &self.subscriber {
+            subscriber.exit(span, fields);
+        }
+    }
+
+    #[cfg(feature = "tracing")]
+    fn event(&self, name: &'static str, fields: &Fields<'_>) {
+        if let Some(subscriber) = &self.subscriber {
+            subscriber.event(name, fields);
+        }
+    }
+
+    #[cfg(feature = "tracing")]
+    fn runtime_error_event(&self, e: &RuntimeError) {
+        let Some(subscriber) = &self.subscriber else { return };
+        let message = e.to_string();
+        let (kind, message) = (("kind", Value::Str(e.kind())), ("message", Value::Str(&message)));
+        match e.span() {
+            Some(span) => subscriber.event("runtime_error", &[kind, ("line", Value::U64(span.line as u64)), ("column", Value::U64(span.column as u64)), message]),
+            None => subscriber.event("runtime_error", &[kind, message]),
+        }
+    }
+
+    // Runs `run`, adding its time to `run_time`, then reports its counts, time and error.
+    pub(crate) fn reported<T>(&mut self, run: impl FnOnce(&mut Interpreter) -> Result<T, RuntimeError>) -> Result<T, RuntimeError> {
+        #[cfg(feature = "std")]
+        let start = Instant::now();
+        #[cfg(feature = "tracing")]
+        let steps = self.steps;
+        #[cfg(feature = "tracing")]
+        self.enter("execute", &[("steps", Value::U64(steps))]);
+        let result = run(self);
+        #[cfg(feature = "tracing")]
+        {
+            if let Err(e) = &result {
+                self.runtime_error_event(e);
+            }
+            self.exit("execute", &[("steps", Value::U64(self.steps - steps))]);
+        }
+        #[cfg(feature = "std")]
+        {
+            self.run_time += start.elapsed();
+        }
+        if self.metrics.is_none() {
+            return result;
+        }
+
+        self.report_metrics();
+        let metrics = self.metrics.as_ref().unwrap();
+        #[cfg(feature = "std")]
+        metrics.incr(MetricKey::RunMicros, start.elapsed().as_micros() as u64);
+        if let Err(e) = &result {
+            metrics.incr(MetricKey::RuntimeErrors(e.kind()), 1);
+        }
+        result
+    }
+
+    // Reports the steps and bytes counted since the last report.
+    #[cold]
+    fn report_metrics(&mut self) {
+        let Some(metrics) = &self.metrics else { return };
+        let keys = [MetricKey::Steps, MetricKey::InputBytes, MetricKey::OutputBytes];
+        let counts = [self.steps, self.input_bytes, self.output_written];
+
+        for ((key, count), reported) in keys.into_iter().zip(counts).zip(&mut self.metrics_reported) {
+            if count > *reported {
+                metrics.incr(key, count - *reported);
+            }
+            *reported = count;
+        }
+    }
+
+    // Keeps the state needed to undo the last `limit` instructions run by `step`, or by `interpret`
+    // and `resume` which go through it. 0, the default, turns the history off.
+    pub fn set_history_limit(&mut self, limit: usize) {
+        self.history_limit = limit;
+        while self.history.len() > limit {
+            self.history.pop_front();
+        }
+    }
+
+    // Hang detection : every `interval` steps the state of the machine is saved, and running stops
+    // with `NoProgress` when it comes back to that state with no input read and no output written
+    // in between, as it would then go round forever. Only cycles shorter than `interval` steps are
+    // caught, and the whole state is compared, so a program that could still halt is never stopped.
+    pub fn set_hang_detection(&mut self, interval: Option<u64>) {
+        self.hang_detector = interval.map(|interval| HangDetector { interval: interval.max(1), saved: None });
+    }
+
+    fn forget_saved_state(&mut self) {
+        if let Some(detector) = &mut self.hang_detector {
+            detector.saved = None;
+        }
+    }
+
+    // Cells the instruction under the instruction pointer may write.
+    fn written_cells(&self) -> Range<usize> {
+        let Some(inst) = self.program.get(self.instruction_pointer) else {
+            return 0..0;
+        };
+        let range = |offset: isize, len: usize| self.range_start(offset, len).map_or(0..0, |start| start..start + len);
+
+        match inst.kind {
+            IRInstructionKind::IncrementByte
+            | IRInstructionKind::DecrementByte
+            | IRInstructionKind::SetZero
+            | IRInstructionKind::SetConst
+            | IRInstructionKind::ReadInputToByte
+            | IRInstructionKind::Custom(_) => self.memory_pointer..self.memory_pointer + 1,
+            IRInstructionKind::FillRange { start_offset, len, .. } => range(start_offset, len),
+            IRInstructionKind::CopyRange { src_offset, dst_offset, len } => {
+                let (src, dst) = (range(src_offset, len), range(dst_offset, len));
+                if src.is_empty() || dst.is_empty() { 0..0 } else { src.start.min(dst.start)..src.end.max(dst.end) }
+            },
+            _ => 0..0,
+        }
+    }
+
+    // The innermost loop around the instruction pointer is the one going round.
+    fn no_progress(&self, period: u64) -> RuntimeError {
+        let ip = self.instruction_pointer.min(self.program.len().saturating_sub(1));
+        let open = (0..=ip).rev().find(|&i| self.program[i].kind.opens() && self.program[i].operand.unwrap() >= ip);
+        match open {
+            Some(open) => {
+                let span = Span { end: self.program[self.program[open].operand.unwrap()].span.end, ..self.program[open].span };
+                RuntimeError::NoProgress { loop_index: open, span, period }
+            },
+            None => RuntimeError::NoProgress { loop_index: ip, span: self.program[ip].span, period },
+        }
+    }
+
+    // Undoes the last instruction run, restoring the instruction pointer, the memory pointer and
+    // the cell it wrote. Printed output and consumed input stay as they are, and limits keep their
+    // counts. Returns false when the history is empty.
+    pub fn step_back(&mut self) -> bool {
+        let Some(undo) = self.history.pop_back() else {
+            return false;
+        };
+        self.pending_input = None;
+        self.forget_saved_state();
+        self.instruction_pointer = undo.instruction_pointer;
+        self.memory_pointer = undo.memory_pointer;
+        if let Some(cell) = self.memory.get_mut(undo.memory_pointer) {
+            *cell = undo.cell;
+        }
+        true
+    }
+
+    // Runs the current instruction up to its I/O: a `.` is executed and hands back the byte to
+    // print, while a `,` is left pending until `complete_input` provides the byte. Both the
+    // blocking and the async drivers are built on top of this.
+    pub(crate) fn execute(&mut self) -> Result<Effect, RuntimeError> {
+        let Some(&inst) = self.program.get(self.instruction_pointer) else {
+            return Ok(Effect::None);
+        };
+
+        if self.is_cancelled() {
+            return Err(self.cancelled());
+        }
+
+        self.check_limits(&inst)?;
+
+        let at = self.instruction_pointer;
+        if let Some(trace) = &mut self.trace {
+            trace.step(at);
+        }
+        let effect = match self.engine {
+            Engine::Match => self.match_instruction(inst)?,
+            _ => (self.dispatch[at])(self, inst)?,
+        };
+        if let Some(coverage) = &mut self.coverage {
+            coverage.mark(at);
+            // The instructions a range stands for ran as part of it when it skipped them.
+            if inst.kind.is_range() {
+                (at + 1..=self.instruction_pointer).for_each(|index| coverage.mark(index));
+            }
+        }
+        if !matches!(effect, Effect::Input) {
+            self.instruction_pointer += 1;
+        }
+
+        Ok(effect)
+    }
+
+    // The `Match` engine, inlined into `execute` so that the handlers are too.
+    #[inline(always)]
+    fn match_instruction(&mut self, inst: IRInstruction) -> Result<Effect, RuntimeError> {
+        match inst.kind {
+            IRInstructionKind::IncrementPointer => self.increment_pointer(inst),
+            IRInstructionKind::DecrementPointer => self.decrement_pointer(inst),
+            IRInstructionKind::IncrementByte => self.increment_byte(inst),
+            IRInstructionKind::DecrementByte => self.decrement_byte(inst),
+            IRInstructionKind::PrintByteAsChar => self.print_byte(inst),
+            IRInstructionKind::ReadInputToByte => self.read_byte(inst),
+            IRInstructionKind::JumpIfZero | IRInstructionKind::If => self.jump_if_zero(inst),
+            IRInstructionKind::JumpIfNotZero => self.jump_if_not_zero(inst),
+            IRInstructionKind::EndIf => self.end_if(inst),
+            IRInstructionKind::FillRange { .. } => self.fill_range(inst),
+            IRInstructionKind::CopyRange { .. } => self.copy_range(inst),
+            IRInstructionKind::SetZero => self.set_zero(inst),
+            IRInstructionKind::SetConst => self.set_const(inst),
+            IRInstructionKind::Breakpoint => self.breakpoint(inst),
+            IRInstructionKind::Custom(_) => self.custom(inst),
+        }
+    }
+
+    fn increment_pointer(&mut self, inst: IRInstruction) -> Result<Effect, RuntimeError> {
+        let target = self.memory_pointer + inst.operand.unwrap();
+        if target >= self.memory.len() {
+            return self.past_right_edge(inst, target);
+        }
+        self.memory_pointer = target;
+        Ok(Effect::None)
+    }
+
+    fn decrement_pointer(&mut self, inst: IRInstruction) -> Result<Effect, RuntimeError> {
+        match self.memory_pointer.checked_sub(inst.operand.unwrap()) {
+            Some(target) => self.memory_pointer = target,
+            None => return self.past_left_edge(inst),
+        }
+        Ok(Effect::None)
+    }
+
+    // The pointer would go to `target`, past the last cell, as `tape_mode` handles.
+    #[cold]
+    fn past_right_edge(&mut self, inst: IRInstruction, target: usize) -> Result<Effect, RuntimeError> {
+        self.memory_pointer = match self.tape_mode {
+            TapeMode::Error => return Err(RuntimeError::PointerOverflow { instruction: self.instruction_pointer, span: inst.span }),
+            TapeMode::Saturate => self.memory.len() - 1,
+            TapeMode::Wrap => target % self.memory.len(),
+        };
+        Ok(Effect::None)
+    }
+
+    #[cold]
+    fn past_left_edge(&mut self, inst: IRInstruction) -> Result<Effect, RuntimeError> {
+        let len = self.memory.len();
+        self.memory_pointer = match self.tape_mode {
+            TapeMode::Error => return Err(RuntimeError::PointerUnderflow { instruction: self.instruction_pointer, span: inst.span }),
+            TapeMode::Saturate => 0,
+            TapeMode::Wrap => (self.memory_pointer + len - inst.operand.unwrap() % len) % len,
+        };
+        Ok(Effect::None)
+    }
+
+    fn increment_byte(&mut self, inst: IRInstruction) -> Result<Effect, RuntimeError> {
+        if self.memory[self.memory_pointer] as usize + inst.operand.unwrap() > 255 && self.wrap_policy == WrapPolicy::Trap {
+            return Err(RuntimeError::CellOverflow { instruction: self.instruction_pointer, span: inst.span, cell: self.memory_pointer });
+        }
+        self.memory[self.memory_pointer] = self.memory[self.memory_pointer].wrapping_add(inst.operand.unwrap() as u8);
+        self.touch();
+        Ok(Effect::None)
+    }
+
+    fn decrement_byte(&mut self, inst: IRInstruction) -> Result<Effect, RuntimeError> {
+        if (self.memory[self.memory_pointer] as usize) < inst.operand.unwrap() && self.wrap_policy == WrapPolicy::Trap {
+            return Err(RuntimeError::CellUnderflow { instruction: self.instruction_pointer, span: inst.span, cell: self.memory_pointer });
+        }
+        self.memory[self.memory_pointer] = self.memory[self.memory_pointer].wrapping_sub(inst.operand.unwrap() as u8);
+        self.touch();
+        Ok(Effect::None)
+    }
+
+    fn set_zero(&mut self, _: IRInstruction) -> Result<Effect, RuntimeError> {
+        self.read_cell(self.memory_pointer);
+        self.memory[self.memory_pointer] = 0;
+        self.touch();
+        Ok(Effect::None)
+    }
+
+    fn set_const(&mut self, inst: IRInstruction) -> Result<Effect, RuntimeError> {
+        self.read_cell(self.memory_pointer);
+        self.memory[self.memory_pointer] = inst.operand.unwrap() as u8;
+        self.touch();
+        Ok(Effect::None)
+    }
+
+    // First cell of the range of `len` cells at `offset` from the memory pointer, when it is within
+    // the tape. The history only undoes one cell per step, so ranges aren't used while it is on.
+    fn range_start(&self, offset: isize, len: usize) -> Option<usize> {
+        if self.history_limit > 0 {
+            return None;
+        }
+        self.memory_pointer.checked_add_signed(offset).filter(|&start| start + len <= self.memory.len())
+    }
+
+    // Moves past the instructions a range stands for, leaving the pointer where they would : at the
+    // end of the range away from where it started.
+    fn skip_range(&mut self, inst: IRInstruction, start: usize, offset: isize, len: usize) {
+        self.memory_pointer = if offset < 0 { start } else { start + len - 1 };
+        self.instruction_pointer += inst.operand.unwrap();
+    }
+
+    // Ranges reaching out of the tape fall through to the instructions they stand for, which fail
+    // where the program would.
+    fn fill_range(&mut self, inst: IRInstruction) -> Result<Effect, RuntimeError> {
+        let IRInstructionKind::FillRange { start_offset, len, value } = inst.kind else { unreachable!() };
+        if let Some(start) = self.range_start(start_offset, len) {
+            self.memory[start..start + len].fill(value);
+            if self.touched.is_some() {
+                (start..start + len).for_each(|cell| {
+                    self.read_cell(cell);
+                    self.touch_cell(cell);
+                });
+            }
+            self.skip_range(inst, start, start_offset, len);
+        }
+        Ok(Effect::None)
+    }
+
+    // Overlapping ranges are shifted by `copy_within`, as the cells of the destination the transfer
+    // loops reach after the first ones were cleared by them beforehand.
+    fn copy_range(&mut self, inst: IRInstruction) -> Result<Effect, RuntimeError> {
+        let IRInstructionKind::CopyRange { src_offset, dst_offset, len } = inst.kind else { unreachable!() };
+        let (Some(src), Some(dst)) = (self.range_start(src_offset, len), self.range_start(dst_offset, len)) else {
+            return Ok(Effect::None);
+        };
+        if self.touched.is_some() {
+            (src..src + len).for_each(|cell| self.read_cell(cell));
+            for i in 0..len {
+                if self.memory[src + i] != 0 {
+                    self.touch_cell(src + i);
+                    self.touch_cell(dst + i);
+                }
+            }
+        }
+
+        let shift = src.abs_diff(dst);
+        // Cells of the source added to the destination, copied to it, and cleared without being copied over.
+        let (added, copied, cleared) = if shift >= len {
+            (0..len, 0..0, src..src + len)
+        } else if dst < src {
+            (0..shift, shift..len, src + len - shift..src + len)
+        } else {
+            (len - shift..len, 0..len - shift, src..src + shift)
+        };
+        for i in added {
+            self.memory[dst + i] = self.memory[dst + i].wrapping_add(self.memory[src + i]);
+        }
+        self.memory.copy_within(src + copied.start..src + copied.end, dst + copied.start);
+        self.memory[cleared].fill(0);
+
+        self.skip_range(inst, src, src_offset, len);
+        Ok(Effect::None)
+    }
+
+    // Only `resume` stops there.
+    fn breakpoint(&mut self, _: IRInstruction) -> Result<Effect, RuntimeError> {
+        Ok(Effect::None)
+    }
+
+    fn custom(&mut self, inst: IRInstruction) -> Result<Effect, RuntimeError> {
+        let handler = &mut self.handlers[inst.operand.unwrap()].1;
+        handler.execute(Machine { tape: &mut self.memory, pointer: &mut self.memory_pointer, input: &mut *self.input, output: &mut *self.output })?;
+        if self.memory_pointer >= self.memory.len() {
+            return self.past_right_edge(inst, self.memory_pointer);
+        }
+        Ok(Effect::None)
+    }
+
+    fn print_byte(&mut self, _: IRInstruction) -> Result<Effect, RuntimeError> {
+        self.read_cell(self.memory_pointer);
+        Ok(Effect::Output(self.printed_byte()))
+    }
+
+    fn read_byte(&mut self, _: IRInstruction) -> Result<Effect, RuntimeError> {
+        Ok(Effect::Input)
+    }
+
+    fn jump_if_zero(&mut self, inst: IRInstruction) -> Result<Effect, RuntimeError> {
+        if let Some(hook) = &mut self.loop_hook {
+            hook(LoopEvent::Enter { loop_index: self.instruction_pointer, cell: self.memory[self.memory_pointer] });
+        }
+        self.read_cell(self.memory_pointer);
+        if self.memory[self.memory_pointer] == 0 {
+            self.instruction_pointer = inst.operand.unwrap();
+        } else if self.loop_iteration_cap.is_some() {
+            self.loop_iterations[inst.operand.unwrap()] = 1;
+        }
+        Ok(Effect::None)
+    }
+
+    fn jump_if_not_zero(&mut self, inst: IRInstruction) -> Result<Effect, RuntimeError> {
+        if let Some(hook) = &mut self.loop_hook {
+            hook(LoopEvent::Exit { loop_index: inst.operand.unwrap(), cell: self.memory[self.memory_pointer] });
+        }
+        self.read_cell(self.memory_pointer);
+        if self.memory[self.memory_pointer] != 0 {
+            if let Some(cap) = self.loop_iteration_cap {
+                let iterations = &mut self.loop_iterations[self.instruction_pointer];
+                *iterations += 1;
+                if *iterations > cap {
+                    let loop_index = inst.operand.unwrap();
+                    return Err(RuntimeError::LoopCapExceeded { loop_index, span: self.program[loop_index].span });
+                }
+            }
+            self.instruction_pointer = inst.operand.unwrap();
+        }
+        Ok(Effect::None)
+    }
+
+    // The cell is always 0 here, so unlike `]` there is nothing to test.
+    fn end_if(&mut self, inst: IRInstruction) -> Result<Effect, RuntimeError> {
+        if let Some(hook) = &mut self.loop_hook {
+            hook(LoopEvent::Exit { loop_index: inst.operand.unwrap(), cell: self.memory[self.memory_pointer] });
+        }
+        Ok(Effect::None)
+    }
+
+    // Finishes the `,` left pending by `execute`, `None` meaning the input is exhausted.
+    pub(crate) fn complete_input(&mut self, byte: Option<u8>) -> Result<(), RuntimeError> {
+        let input = match (byte, self.on_end_of_input) {
+            (Some(byte), _) => Some(byte),
+            (None, OnEndOfInput::Error) => return Err(RuntimeError::EndOfInput { instruction: self.instruction_pointer, span: self.program[self.instruction_pointer].span }),
+            (None, OnEndOfInput::Zero) => Some(0),
+            (None, OnEndOfInput::MinusOne) => Some(255),
+            (None, OnEndOfInput::Unchanged) => None,
+        };
+        self.read_cell(self.memory_pointer);
+        if let Some(input) = input {
+            self.memory[self.memory_pointer] = input;
+            self.touch();
+        }
+        self.instruction_pointer += 1;
+        Ok(())
+    }
+
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.cancel_token.as_ref().is_some_and(CancelToken::is_cancelled)
+    }
+
+    pub(crate) fn cancelled(&self) -> RuntimeError {
+        let span = self.program.get(self.instruction_pointer).map(|inst| inst.span).unwrap_or_default();
+        RuntimeError::Cancelled { instruction: self.instruction_pointer, span }
+    }
+
+    #[cfg(feature = "async")]
+    pub(crate) fn cancel_token(&self) -> Option<&CancelToken> {
+        self.cancel_token.as_ref()
+    }
+
+    // Swaps the streams for `input` and `output`, returning the ones set before.
+    #[cfg(feature = "async")]
+    pub(crate) fn replace_streams(&mut self, input: Box<dyn InputSource + Send>, output: Box<dyn OutputSink + Send>) -> (Box<dyn InputSource + Send>, Box<dyn OutputSink + Send>) {
+        (core::mem::replace(&mut self.input, input), core::mem::replace(&mut self.output, output))
+    }
+}
+
+// Reads a program's source from a file, decompressing it first when the gzip feature is enabled.
+#[cfg(feature = "std")]
+pub fn read_program(program_path: &str) -> Result<String, LoadError> {
+    read_program_at_most(program_path, None)
+}
+
+// As `read_program`, failing with `LoadError::ProgramTooLarge` past `max_bytes`. The size of
+// regular files is checked before reading them, and at most one byte past the limit is read from
+// the others (pipes, devices). Compressed programs stop being decompressed as soon as they grow
+// past it.
+#[cfg(feature = "std")]
+pub fn read_program_at_most(program_path: &str, max_bytes: Option<usize>) -> Result<String, LoadError> {
+    use std::io::Read;
+
+    let mut program_file = std::fs::File::open(program_path)?;
+
+    let mut program_buffer = Vec::new();
+
+    match max_bytes {
+        Some(limit) => {
+            if program_file.metadata()?.len() > limit as u64 {
+                return Err(LoadError::ProgramTooLarge { limit });
+            }
+            program_file.take(limit as u64 + 1).read_to_end(&mut program_buffer)?;
+            if program_buffer.len() > limit {
+                return Err(LoadError::ProgramTooLarge { limit });
+            }
+        },
+        None => {
+            program_file.read_to_end(&mut program_buffer)?;
+        },
+    }
+
+    #[cfg(feature = "gzip")]
+    if program_path.ends_with(".gz") || gzip::is_gzip(&program_buffer) {
+        program_buffer = gzip::decompress_at_most(&program_buffer, max_bytes.unwrap_or(usize::MAX)).map_err(|e| match (e.kind(), max_bytes) {
+            (std::io::ErrorKind::FileTooLarge, Some(limit)) => LoadError::ProgramTooLarge { limit },
+            _ => LoadError::Io(e),
+        })?;
+    }
+
+    let mut program = String::from_utf8(program_buffer).map_err(|e| LoadError::InvalidUtf8 { offset: e.utf8_error().valid_up_to() })?;
+
+    // The `#!` line of a script run as `#!/usr/bin/env sac`, whose path and arguments may hold
+    // commands. Its newline is kept, so that the lines after it keep their numbers.
+    if program.starts_with("#!") {
+        program.drain(..program.find('\n').unwrap_or(program.len()));
+    }
+    Ok(program)
+}
+
+// The eight commands, and `@` which starts labels (see `labels`).
+fn is_reserved(token: char) -> bool {
+    token == '@' || "><+-.,[]".contains(token)
+}
+
+// Runs one instruction, `execute` then moving past it unless it waits for input. Jumps leave the
+// instruction pointer on the matching bracket. With `Engine::FnTable` there is one handler per
+// kind, looked up when the program is loaded rather than matched on each step, which keeps the hot
+// loop to one indirect call.
+pub type Handler = fn(&mut Interpreter, IRInstruction) -> Result<Effect, RuntimeError>;
+
+fn handler(kind: IRInstructionKind) -> Handler {
+    match kind {
+        IRInstructionKind::IncrementPointer => Interpreter::increment_pointer,
+        IRInstructionKind::DecrementPointer => Interpreter::decrement_pointer,
+        IRInstructionKind::IncrementByte => Interpreter::increment_byte,
+        IRInstructionKind::DecrementByte => Interpreter::decrement_byte,
+        IRInstructionKind::PrintByteAsChar => Interpreter::print_byte,
+        IRInstructionKind::ReadInputToByte => Interpreter::read_byte,
+        IRInstructionKind::JumpIfZero => Interpreter::jump_if_zero,
+        IRInstructionKind::JumpIfNotZero => Interpreter::jump_if_not_zero,
+        IRInstructionKind::If => Interpreter::jump_if_zero,
+        IRInstructionKind::EndIf => Interpreter::end_if,
+        IRInstructionKind::FillRange { .. } => Interpreter::fill_range,
+        IRInstructionKind::CopyRange { .. } => Interpreter::copy_range,
+        IRInstructionKind::SetZero => Interpreter::set_zero,
+        IRInstructionKind::SetConst => Interpreter::set_const,
+        IRInstructionKind::Breakpoint => Interpreter::breakpoint,
+        IRInstructionKind::Custom(_) => Interpreter::custom,
+    }
+}
+
+// What an instruction leaves to the interpreter once run by an `Executor`.
+pub enum Effect {
+    None,
+    Output(u8), // Byte to print, `.` having moved past.
+    Input,      // `,` waiting for its byte, the instruction pointer still on it.
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Engine {
+    FnTable, // Handlers looked up once per program, one indirect call per instruction.
+    #[default]
+    Match,   // A `match` inlined into the interpreter loop, see `run_inline`.
+    Jit,     // Not built into this crate yet : runs with `Match`.
+}
+
+impl Engine {
+    pub const ALL: [Engine; 3] = [Engine::FnTable, Engine::Match, Engine::Jit];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Engine::FnTable => "fn-table",
+            Engine::Match => "match",
+            Engine::Jit => "jit",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Engine> {
+        Engine::ALL.into_iter().find(|engine| engine.name() == name)
+    }
+
+    // Whether this build has the engine, rather than falling back to another one.
+    pub fn is_available(self) -> bool {
+        executor(self).engine() == self
+    }
+}
+
+// A backend carrying out instructions on the state of an `Interpreter`, picked with
+// `Config::engine`. Backends compile each loaded program to the handler of each instruction, and
+// the interpreter calls the handler of the instruction under the instruction pointer, except with
+// `Engine::Match`, whose `match` is part of the interpreter. Limits and cancellation are checked
+// before it, and I/O, breakpoints, hooks, the undo history and hang detection are handled around
+// it, so every backend gets them.
+pub trait Executor: Sync {
+    // The engine actually running, which differs from the one asked for after a fallback.
+    fn engine(&self) -> Engine;
+    fn compile(&self, program: &[IRInstruction]) -> Vec<Handler>;
+}
+
+struct FnTable;
+
+impl Executor for FnTable {
+    fn engine(&self) -> Engine {
+        Engine::FnTable
+    }
+
+    fn compile(&self, program: &[IRInstruction]) -> Vec<Handler> {
+        program.iter().map(|inst| handler(inst.kind)).collect()
+    }
+}
+
+struct Match;
+
+impl Executor for Match {
+    fn engine(&self) -> Engine {
+        Engine::Match
+    }
+
+    // No table : `execute` matches on the kind of each instruction itself.
+    fn compile(&self, _: &[IRInstruction]) -> Vec<Handler> {
+        Vec::new()
+    }
+}
+
+// The backend for `engine`, or the one it falls back to when this build doesn't have it.
+pub fn executor(engine: Engine) -> &'static dyn Executor {
+    match engine {
+        Engine::FnTable => &FnTable,
+        Engine::Match | Engine::Jit => &Match,
+    }
+}
+
+// Cells are printed as the character with the same code point, unless `non_ascii` says otherwise
+// for values above 127, or `bell` for 7.
+pub(crate) fn encode_output(byte: u8, non_ascii: NonAscii, bell: Bell, buffer: &mut [u8; 4]) -> &[u8] {
+    match (byte, bell) {
+        (7, Bell::Suppress) => return &[],
+        (7, Bell::Replace(replacement)) => {
+            buffer[0] = replacement;
+            return &buffer[..1];
+        },
+        _ => (),
+    }
+    match non_ascii {
+        NonAscii::Placeholder(placeholder) if !byte.is_ascii() => {
+            buffer[0] = placeholder;
+            &buffer[..1]
+        },
+        NonAscii::Escape if !byte.is_ascii() => {
+            const HEX: &[u8; 16] = b"0123456789abcdef";
+            *buffer = [b'\\', b'x', HEX[byte as usize >> 4], HEX[byte as usize & 0xf]];
+            buffer
+        },
+        _ => (byte as char).encode_utf8(buffer).as_bytes(),
     }
 }
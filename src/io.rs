@@ -0,0 +1,334 @@
+// Byte level I/O used by `,` and `.`. With std these are implemented for every `Read` and
+// `Write`, without it they are small enough to implement over a UART or a ring buffer.
+
+#[cfg(feature = "std")]
+pub type IoError = std::io::Error;
+
+#[cfg(not(feature = "std"))]
+#[derive(Debug)]
+pub struct IoError(pub &'static str);
+
+#[cfg(not(feature = "std"))]
+impl core::fmt::Display for IoError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+pub trait InputSource {
+    // Returns the next byte, or `None` once the input is exhausted.
+    fn read_byte(&mut self) -> Result<Option<u8>, IoError>;
+}
+
+pub trait OutputSink {
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), IoError>;
+
+    fn flush(&mut self) -> Result<(), IoError> {
+        Ok(())
+    }
+}
+
+// How `,` turns the input stream into a cell value.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum InputMode {
+    #[default]
+    RawByte,
+    // Reads a run of ASCII digits, skipping whatever comes before it, and stores the number modulo
+    // 256. The byte ending the number is consumed, and running out of input before any digit is an
+    // end of input, see `OnEndOfInput`.
+    DecimalNumber,
+}
+
+// How `.` prints cells above 127, which aren't ASCII characters.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NonAscii {
+    // The character with the same code point, encoded in UTF-8 on two bytes.
+    #[default]
+    Utf8,
+    // The given byte instead, such as `b'.'`, so that binary output can't garble a terminal.
+    Placeholder(u8),
+    // `\xNN`, the value in hexadecimal.
+    Escape,
+}
+
+// How `.` prints byte 7 (BEL), which makes some terminals beep.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Bell {
+    #[default]
+    PassThrough,
+    // Nothing is printed.
+    Suppress,
+    // The given byte instead.
+    Replace(u8),
+}
+
+// What `,` and `.` do with invalid UTF-8 once `Interpreter::set_utf8_io` is on.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum InvalidUtf8 {
+    // Each invalid sequence, including a character cut short, stands as U+FFFD.
+    #[default]
+    Replace,
+    // Fails with `RuntimeError::InvalidUtf8Input` or `InvalidUtf8Output`.
+    Fail,
+}
+
+const REPLACEMENT: &[u8] = "\u{fffd}".as_bytes();
+
+// Checks a stream of UTF-8 one byte at a time, holding back the bytes of a character until it is
+// complete. Overlong encodings, surrogates and code points past U+10FFFF are invalid.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct Utf8Decoder {
+    bytes: [u8; 4],
+    len: usize,
+    expected: usize,
+}
+
+impl Utf8Decoder {
+    // Appends to `out` the character `byte` completes, with U+FFFD for the invalid sequences it
+    // ends. Returns false for invalid sequences.
+    pub(crate) fn push(&mut self, byte: u8, out: &mut impl Extend<u8>) -> bool {
+        if self.len > 0 {
+            let continuation = match (self.len, self.bytes[0]) {
+                (1, 0xe0) => 0xa0..=0xbf,
+                (1, 0xed) => 0x80..=0x9f,
+                (1, 0xf0) => 0x90..=0xbf,
+                (1, 0xf4) => 0x80..=0x8f,
+                _ => 0x80..=0xbf,
+            };
+            if !continuation.contains(&byte) {
+                // The character is cut short, and `byte` may start the next one.
+                self.len = 0;
+                out.extend(REPLACEMENT.iter().copied());
+                self.push(byte, out);
+                return false;
+            }
+        } else {
+            self.expected = match byte {
+                0x00..=0x7f => 1,
+                0xc2..=0xdf => 2,
+                0xe0..=0xef => 3,
+                0xf0..=0xf4 => 4,
+                _ => {
+                    out.extend(REPLACEMENT.iter().copied());
+                    return false;
+                },
+            };
+        }
+
+        self.bytes[self.len] = byte;
+        self.len += 1;
+        if self.len == self.expected {
+            out.extend(self.bytes[..self.len].iter().copied());
+            self.len = 0;
+        }
+        true
+    }
+
+    // Ends the stream, a character left incomplete being invalid.
+    pub(crate) fn finish(&mut self, out: &mut impl Extend<u8>) -> bool {
+        if self.len == 0 {
+            return true;
+        }
+        self.len = 0;
+        out.extend(REPLACEMENT.iter().copied());
+        false
+    }
+
+    pub(crate) fn is_pending(&self) -> bool {
+        self.len > 0
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub(crate) struct DecimalNumber {
+    value: u8,
+    digits: usize,
+}
+
+impl DecimalNumber {
+    // Feeds the next input byte, returning the value once the number is complete, or `Some(None)`
+    // when the input ran out before any digit.
+    pub(crate) fn push(&mut self, byte: Option<u8>) -> Option<Option<u8>> {
+        match byte {
+            Some(digit @ b'0'..=b'9') => {
+                self.value = self.value.wrapping_mul(10).wrapping_add(digit - b'0');
+                self.digits += 1;
+                None
+            },
+            Some(_) if self.digits == 0 => None,
+            Some(_) => Some(Some(self.value)),
+            None => Some((self.digits > 0).then_some(self.value)),
+        }
+    }
+}
+
+// Endless stream of pseudo-random bytes (SplitMix64), the same seed always giving the same bytes.
+#[derive(Clone, Debug)]
+pub struct PrngInput {
+    state: u64,
+}
+
+impl PrngInput {
+    pub fn new(seed: u64) -> PrngInput {
+        PrngInput { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+impl InputSource for PrngInput {
+    fn read_byte(&mut self) -> Result<Option<u8>, IoError> {
+        Ok(Some((self.next_u64() >> 56) as u8))
+    }
+}
+
+// What a `,` does once the input is exhausted.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OnEndOfInput {
+    // Fails with `RuntimeError::EndOfInput`.
+    #[default]
+    Error,
+    Zero,
+    // Stores 255, the -1 of interpreters with signed cells.
+    MinusOne,
+    // Leaves the cell as it was.
+    Unchanged,
+}
+
+// What a `,` does once it waited longer than the timeout of a `TimeoutInput`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OnInputTimeout {
+    // As if the input ended, for this read only.
+    #[default]
+    Eof,
+    // Fails with `RuntimeError::InputTimeout`.
+    Error,
+}
+
+// Input read on a thread of its own, so that a `,` can stop waiting for it after `timeout`. Reads
+// going past the timeout return an end of input, or a `TimedOut` error, which the interpreter reports
+// as `RuntimeError::InputTimeout`. The thread keeps waiting on the reader and later reads get what it
+// received meanwhile.
+#[cfg(feature = "std")]
+pub struct TimeoutInput {
+    chunks: std::sync::mpsc::Receiver<std::io::Result<alloc::vec::Vec<u8>>>,
+    buffered: alloc::collections::VecDeque<u8>,
+    timeout: std::time::Duration,
+    on_timeout: OnInputTimeout,
+    ended: bool,
+}
+
+#[cfg(feature = "std")]
+impl TimeoutInput {
+    pub fn new<R: std::io::Read + Send + 'static>(mut reader: R, timeout: std::time::Duration, on_timeout: OnInputTimeout) -> TimeoutInput {
+        let (sender, chunks) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            let mut chunk = [0u8; 4096];
+            loop {
+                let result = match reader.read(&mut chunk) {
+                    Ok(0) => break,
+                    Ok(read) => Ok(chunk[..read].to_vec()),
+                    Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                    Err(e) => Err(e),
+                };
+                let failed = result.is_err();
+                if sender.send(result).is_err() || failed {
+                    break;
+                }
+            }
+        });
+        TimeoutInput { chunks, buffered: alloc::collections::VecDeque::new(), timeout, on_timeout, ended: false }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::io::Read for TimeoutInput {
+    fn read(&mut self, bytes: &mut [u8]) -> std::io::Result<usize> {
+        use std::sync::mpsc::RecvTimeoutError;
+
+        if self.buffered.is_empty() && !self.ended {
+            match self.chunks.recv_timeout(self.timeout) {
+                Ok(chunk) => self.buffered.extend(chunk?),
+                Err(RecvTimeoutError::Disconnected) => self.ended = true,
+                Err(RecvTimeoutError::Timeout) => match self.on_timeout {
+                    OnInputTimeout::Eof => return Ok(0),
+                    OnInputTimeout::Error => return Err(std::io::Error::new(std::io::ErrorKind::TimedOut, "no input within the timeout")),
+                },
+            }
+        }
+
+        let read = bytes.len().min(self.buffered.len());
+        for (byte, buffered) in bytes.iter_mut().zip(self.buffered.drain(..read)) {
+            *byte = buffered;
+        }
+        Ok(read)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: std::io::Read + ?Sized> InputSource for R {
+    fn read_byte(&mut self) -> Result<Option<u8>, IoError> {
+        let mut byte = [0u8; 1];
+        loop {
+            match self.read(&mut byte) {
+                Ok(0) => return Ok(None),
+                Ok(_) => return Ok(Some(byte[0])),
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: std::io::Write + ?Sized> OutputSink for W {
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), IoError> {
+        self.write_all(bytes)
+    }
+
+    fn flush(&mut self) -> Result<(), IoError> {
+        std::io::Write::flush(self)
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl InputSource for alloc::collections::VecDeque<u8> {
+    fn read_byte(&mut self) -> Result<Option<u8>, IoError> {
+        Ok(self.pop_front())
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl OutputSink for alloc::vec::Vec<u8> {
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), IoError> {
+        self.extend_from_slice(bytes);
+        Ok(())
+    }
+}
+
+// Defaults without std, where there is no terminal to talk to.
+#[cfg(not(feature = "std"))]
+pub(crate) struct NoInput;
+
+#[cfg(not(feature = "std"))]
+impl InputSource for NoInput {
+    fn read_byte(&mut self) -> Result<Option<u8>, IoError> {
+        Ok(None)
+    }
+}
+
+#[cfg(not(feature = "std"))]
+pub(crate) struct NoOutput;
+
+#[cfg(not(feature = "std"))]
+impl OutputSink for NoOutput {
+    fn write_bytes(&mut self, _bytes: &[u8]) -> Result<(), IoError> {
+        Ok(())
+    }
+}
@@ -0,0 +1,373 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{self, Write};
+
+// Just enough JSON for the tools built around the interpreter : a DOM that keeps object keys in
+// insertion order, a strict parser and a compact printer.
+
+const MAX_DEPTH: usize = 128;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Object(members) => members.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    // Only for non-negative integers that survive the round trip through `f64`.
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            Value::Number(n) if *n >= 0.0 && *n <= (1u64 << 53) as f64 && (*n as u64) as f64 == *n => Some(*n as u64),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Value]> {
+        match self {
+            Value::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Value {
+        Value::String(String::from(s))
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Value {
+        Value::String(s)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Value {
+        Value::Bool(b)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(n: f64) -> Value {
+        Value::Number(n)
+    }
+}
+
+impl From<u64> for Value {
+    fn from(n: u64) -> Value {
+        Value::Number(n as f64)
+    }
+}
+
+impl From<usize> for Value {
+    fn from(n: usize) -> Value {
+        Value::Number(n as f64)
+    }
+}
+
+impl From<Vec<Value>> for Value {
+    fn from(items: Vec<Value>) -> Value {
+        Value::Array(items)
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Null => f.write_str("null"),
+            Value::Bool(b) => write!(f, "{b}"),
+            Value::Number(n) if !n.is_finite() => f.write_str("null"),
+            Value::Number(n) if -1e15 < *n && *n < 1e15 && (*n as i64) as f64 == *n => write!(f, "{}", *n as i64),
+            Value::Number(n) => write!(f, "{n}"),
+            Value::String(s) => write_string(f, s),
+            Value::Array(items) => {
+                f.write_char('[')?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    write!(f, "{item}")?;
+                }
+                f.write_char(']')
+            },
+            Value::Object(members) => {
+                f.write_char('{')?;
+                for (i, (key, value)) in members.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    write_string(f, key)?;
+                    write!(f, ":{value}")?;
+                }
+                f.write_char('}')
+            },
+        }
+    }
+}
+
+fn write_string(f: &mut fmt::Formatter<'_>, s: &str) -> fmt::Result {
+    f.write_char('"')?;
+    for c in s.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => f.write_char(c)?,
+        }
+    }
+    f.write_char('"')
+}
+
+#[derive(Debug)]
+pub struct ParseError {
+    pub position: usize,
+    message: &'static str,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at byte {}", self.message, self.position)
+    }
+}
+
+impl core::error::Error for ParseError {}
+
+pub fn parse(text: &str) -> Result<Value, ParseError> {
+    let mut parser = Parser { bytes: text.as_bytes(), position: 0 };
+    let value = parser.value(0)?;
+    parser.skip_whitespace();
+    if parser.position != parser.bytes.len() {
+        return Err(parser.error("trailing characters"));
+    }
+    Ok(value)
+}
+
+struct Parser<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &'static str) -> ParseError {
+        ParseError { position: self.position, message }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.bytes.get(self.position) {
+            self.position += 1;
+        }
+    }
+
+    fn expect(&mut self, literal: &str) -> Result<(), ParseError> {
+        if self.bytes[self.position..].starts_with(literal.as_bytes()) {
+            self.position += literal.len();
+            Ok(())
+        } else {
+            Err(self.error("unexpected character"))
+        }
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Value, ParseError> {
+        if depth > MAX_DEPTH {
+            return Err(self.error("nesting too deep"));
+        }
+
+        self.skip_whitespace();
+        match self.bytes.get(self.position) {
+            None => Err(self.error("unexpected end of input")),
+            Some(b'n') => self.expect("null").map(|_| Value::Null),
+            Some(b't') => self.expect("true").map(|_| Value::Bool(true)),
+            Some(b'f') => self.expect("false").map(|_| Value::Bool(false)),
+            Some(b'"') => self.string().map(Value::String),
+            Some(b'[') => {
+                self.position += 1;
+                let mut items = Vec::new();
+                self.skip_whitespace();
+                if self.bytes.get(self.position) == Some(&b']') {
+                    self.position += 1;
+                    return Ok(Value::Array(items));
+                }
+                loop {
+                    items.push(self.value(depth + 1)?);
+                    self.skip_whitespace();
+                    match self.bytes.get(self.position) {
+                        Some(b',') => self.position += 1,
+                        Some(b']') => {
+                            self.position += 1;
+                            return Ok(Value::Array(items));
+                        },
+                        _ => return Err(self.error("expected `,` or `]`")),
+                    }
+                }
+            },
+            Some(b'{') => {
+                self.position += 1;
+                let mut members = Vec::new();
+                self.skip_whitespace();
+                if self.bytes.get(self.position) == Some(&b'}') {
+                    self.position += 1;
+                    return Ok(Value::Object(members));
+                }
+                loop {
+                    self.skip_whitespace();
+                    if self.bytes.get(self.position) != Some(&b'"') {
+                        return Err(self.error("expected a key"));
+                    }
+                    let key = self.string()?;
+                    self.skip_whitespace();
+                    self.expect(":")?;
+                    members.push((key, self.value(depth + 1)?));
+                    self.skip_whitespace();
+                    match self.bytes.get(self.position) {
+                        Some(b',') => self.position += 1,
+                        Some(b'}') => {
+                            self.position += 1;
+                            return Ok(Value::Object(members));
+                        },
+                        _ => return Err(self.error("expected `,` or `}`")),
+                    }
+                }
+            },
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            Some(_) => Err(self.error("unexpected character")),
+        }
+    }
+
+    fn number(&mut self) -> Result<Value, ParseError> {
+        let start = self.position;
+        if self.bytes[self.position] == b'-' {
+            self.position += 1;
+        }
+        let digits = |parser: &mut Self| {
+            let from = parser.position;
+            while parser.bytes.get(parser.position).is_some_and(u8::is_ascii_digit) {
+                parser.position += 1;
+            }
+            parser.position > from
+        };
+        if !digits(self) {
+            return Err(self.error("expected a digit"));
+        }
+        if self.bytes.get(self.position) == Some(&b'.') {
+            self.position += 1;
+            if !digits(self) {
+                return Err(self.error("expected a digit"));
+            }
+        }
+        if let Some(b'e' | b'E') = self.bytes.get(self.position) {
+            self.position += 1;
+            if let Some(b'+' | b'-') = self.bytes.get(self.position) {
+                self.position += 1;
+            }
+            if !digits(self) {
+                return Err(self.error("expected a digit"));
+            }
+        }
+
+        // The slice only holds ASCII digits and signs, so it is valid UTF-8 and a valid float.
+        let text = core::str::from_utf8(&self.bytes[start..self.position]).unwrap();
+        text.parse().map(Value::Number).map_err(|_| ParseError { position: start, message: "invalid number" })
+    }
+
+    fn string(&mut self) -> Result<String, ParseError> {
+        self.position += 1;
+        let mut s = String::new();
+
+        loop {
+            let start = self.position;
+            while let Some(&b) = self.bytes.get(self.position) {
+                if b == b'"' || b == b'\\' || b < 0x20 {
+                    break;
+                }
+                self.position += 1;
+            }
+            // Only ever split on ASCII bytes, so every run is valid UTF-8.
+            s.push_str(core::str::from_utf8(&self.bytes[start..self.position]).unwrap());
+
+            match self.bytes.get(self.position) {
+                Some(b'"') => {
+                    self.position += 1;
+                    return Ok(s);
+                },
+                Some(b'\\') => {
+                    self.position += 1;
+                    let escape = *self.bytes.get(self.position).ok_or_else(|| self.error("unexpected end of input"))?;
+                    self.position += 1;
+                    match escape {
+                        b'"' => s.push('"'),
+                        b'\\' => s.push('\\'),
+                        b'/' => s.push('/'),
+                        b'b' => s.push('\u{8}'),
+                        b'f' => s.push('\u{c}'),
+                        b'n' => s.push('\n'),
+                        b'r' => s.push('\r'),
+                        b't' => s.push('\t'),
+                        b'u' => s.push(self.unicode_escape()?),
+                        _ => return Err(self.error("invalid escape")),
+                    }
+                },
+                Some(_) => return Err(self.error("control character in string")),
+                None => return Err(self.error("unterminated string")),
+            }
+        }
+    }
+
+    fn hex4(&mut self) -> Result<u32, ParseError> {
+        let digits = self.bytes.get(self.position..self.position + 4).ok_or_else(|| self.error("truncated escape"))?;
+        let text = core::str::from_utf8(digits).map_err(|_| self.error("invalid escape"))?;
+        let value = u32::from_str_radix(text, 16).map_err(|_| self.error("invalid escape"))?;
+        self.position += 4;
+        Ok(value)
+    }
+
+    fn unicode_escape(&mut self) -> Result<char, ParseError> {
+        let high = self.hex4()?;
+        let code = if (0xd800..0xdc00).contains(&high) {
+            self.expect("\\u").map_err(|_| self.error("lone surrogate"))?;
+            let low = self.hex4()?;
+            if !(0xdc00..0xe000).contains(&low) {
+                return Err(self.error("lone surrogate"));
+            }
+            0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00)
+        } else {
+            high
+        };
+        char::from_u32(code).ok_or_else(|| self.error("lone surrogate"))
+    }
+}
@@ -1 +1,57 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+pub mod analysis;
+pub mod buffer;
+pub mod builder;
+pub mod cancel;
+pub mod coverage;
+pub mod diagnostics;
+pub mod encode;
+pub mod error;
+pub mod explain;
+pub mod hash;
+pub mod highlight;
+pub mod image;
 pub mod interpreter;
+pub mod io;
+pub mod json;
+pub mod metrics;
+pub mod optimizer;
+pub mod tape;
+pub mod testing;
+pub mod trace;
+
+#[cfg(feature = "std")]
+pub mod conformance;
+
+#[cfg(feature = "std")]
+pub mod diff;
+
+#[cfg(feature = "std")]
+pub mod line;
+
+#[cfg(feature = "std")]
+pub mod runner;
+
+#[cfg(feature = "std")]
+pub mod verify;
+
+#[cfg(feature = "async")]
+pub mod asynchronous;
+
+#[cfg(feature = "gzip")]
+pub mod gzip;
+
+#[cfg(feature = "tracing")]
+pub mod instrument;
+
+#[cfg(feature = "lsp")]
+pub mod lsp;
+
+#[cfg(feature = "playground")]
+pub mod playground;
+
+#[cfg(feature = "serve")]
+pub mod serve;
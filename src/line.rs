@@ -0,0 +1,405 @@
+use std::collections::VecDeque;
+use std::io::{self, Read, Write};
+use std::mem;
+
+// Line-edited input for `,` when a person types it : a whole line is read and edited first, then
+// handed to the program one byte at a time, followed by a newline.
+
+// Lines without their newline, `None` once the input ended.
+pub trait LineSource {
+    fn read_line(&mut self) -> io::Result<Option<String>>;
+}
+
+// Bytes of the lines of a `LineSource`, each followed by `\n`. Lines are only read once the previous
+// one was taken whole, so nothing is asked for before the program needs it.
+pub struct LineInput<S> {
+    source: S,
+    pending: VecDeque<u8>,
+    ended: bool,
+}
+
+impl<S: LineSource> LineInput<S> {
+    pub fn new(source: S) -> LineInput<S> {
+        LineInput { source, pending: VecDeque::new(), ended: false }
+    }
+
+    pub fn source(&self) -> &S {
+        &self.source
+    }
+}
+
+impl<S: LineSource> Read for LineInput<S> {
+    fn read(&mut self, bytes: &mut [u8]) -> io::Result<usize> {
+        if self.pending.is_empty() && !self.ended {
+            match self.source.read_line()? {
+                Some(line) => {
+                    self.pending.extend(line.bytes());
+                    self.pending.push_back(b'\n');
+                },
+                None => self.ended = true,
+            }
+        }
+
+        let read = bytes.len().min(self.pending.len());
+        for (byte, pending) in bytes.iter_mut().zip(self.pending.drain(..read)) {
+            *byte = pending;
+        }
+        Ok(read)
+    }
+}
+
+// Minimal line editor reading keys from `keys` and drawing the line on `echo`, after whatever is
+// already on the terminal line such as a prompt. Arrows, Home and End move in the line or through
+// the lines read before, Backspace and Delete erase, Ctrl-U and Ctrl-K clear before and after the
+// cursor, and Ctrl-D on an empty line ends the input. Characters are assumed one column wide. The
+// terminal is put in raw mode while a line is read, on Linux only : elsewhere it keeps its own
+// editing and echo, which the editor then repeats.
+pub struct LineEditor<R, W> {
+    keys: R,
+    echo: W,
+    history: Vec<String>,
+}
+
+#[derive(Default)]
+struct Line {
+    chars: Vec<char>,
+    cursor: usize,
+}
+
+impl<R: Read, W: Write> LineEditor<R, W> {
+    pub fn new(keys: R, echo: W) -> LineEditor<R, W> {
+        LineEditor { keys, echo, history: Vec::new() }
+    }
+
+    // Lines read so far, oldest first, without empty lines or repeats of the previous line.
+    pub fn history(&self) -> &[String] {
+        &self.history
+    }
+
+    fn byte(&mut self) -> io::Result<Option<u8>> {
+        let mut byte = [0u8; 1];
+        loop {
+            match self.keys.read(&mut byte) {
+                Ok(0) => return Ok(None),
+                Ok(_) => return Ok(Some(byte[0])),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    // The rest of a UTF-8 character starting with `first`.
+    fn char(&mut self, first: u8) -> io::Result<char> {
+        let length = match first {
+            0xc0..=0xdf => 2,
+            0xe0..=0xef => 3,
+            0xf0..=0xf7 => 4,
+            _ => 1,
+        };
+        let mut bytes = vec![first];
+        while bytes.len() < length {
+            match self.byte()? {
+                Some(byte) => bytes.push(byte),
+                None => break,
+            }
+        }
+        Ok(String::from_utf8_lossy(&bytes).chars().next().unwrap_or(char::REPLACEMENT_CHARACTER))
+    }
+
+    // Draws `line` over the one drawn with its cursor at `shown`.
+    fn redraw(&mut self, line: &Line, shown: usize) -> io::Result<()> {
+        if shown > 0 {
+            write!(self.echo, "\x1b[{shown}D")?;
+        }
+        let text: String = line.chars.iter().collect();
+        write!(self.echo, "{text}\x1b[K")?;
+        if line.chars.len() > line.cursor {
+            write!(self.echo, "\x1b[{}D", line.chars.len() - line.cursor)?;
+        }
+        self.echo.flush()
+    }
+
+    fn edit(&mut self) -> io::Result<Option<String>> {
+        let mut line = Line::default();
+        let mut browsing = self.history.len(); // Index in `history` of the line shown, if any.
+        let mut draft = Vec::new(); // The line being typed, while browsing the history.
+
+        loop {
+            let shown = line.cursor;
+            let Some(byte) = self.byte()? else {
+                if line.chars.is_empty() {
+                    return Ok(None);
+                }
+                break;
+            };
+            match byte {
+                b'\r' | b'\n' => break,
+                0x04 if line.chars.is_empty() => return Ok(None),
+                0x04 if line.cursor < line.chars.len() => {
+                    line.chars.remove(line.cursor);
+                },
+                0x7f | 0x08 if line.cursor > 0 => {
+                    line.cursor -= 1;
+                    line.chars.remove(line.cursor);
+                },
+                0x01 => line.cursor = 0,
+                0x05 => line.cursor = line.chars.len(),
+                0x15 => {
+                    line.chars.drain(..line.cursor);
+                    line.cursor = 0;
+                },
+                0x0b => line.chars.truncate(line.cursor),
+                0x1b => {
+                    if self.byte()? != Some(b'[') {
+                        continue;
+                    }
+                    match self.byte()? {
+                        Some(b'A') if browsing > 0 => {
+                            if browsing == self.history.len() {
+                                draft = line.chars.clone();
+                            }
+                            browsing -= 1;
+                            line.chars = self.history[browsing].chars().collect();
+                            line.cursor = line.chars.len();
+                        },
+                        Some(b'B') if browsing < self.history.len() => {
+                            browsing += 1;
+                            line.chars = match self.history.get(browsing) {
+                                Some(previous) => previous.chars().collect(),
+                                None => mem::take(&mut draft),
+                            };
+                            line.cursor = line.chars.len();
+                        },
+                        Some(b'C') => line.cursor = (line.cursor + 1).min(line.chars.len()),
+                        Some(b'D') => line.cursor = line.cursor.saturating_sub(1),
+                        Some(b'H') => line.cursor = 0,
+                        Some(b'F') => line.cursor = line.chars.len(),
+                        Some(b'3') if self.byte()? == Some(b'~') && line.cursor < line.chars.len() => {
+                            line.chars.remove(line.cursor);
+                        },
+                        _ => (),
+                    }
+                },
+                0x20..=0x7e | 0x80.. => {
+                    let c = self.char(byte)?;
+                    line.chars.insert(line.cursor, c);
+                    line.cursor += 1;
+                },
+                _ => continue,
+            }
+            self.redraw(&line, shown)?;
+        }
+
+        writeln!(self.echo)?;
+        self.echo.flush()?;
+        let text: String = line.chars.into_iter().collect();
+        if !text.is_empty() && self.history.last() != Some(&text) {
+            self.history.push(text.clone());
+        }
+        Ok(Some(text))
+    }
+}
+
+impl<R: Read, W: Write> LineSource for LineEditor<R, W> {
+    fn read_line(&mut self) -> io::Result<Option<String>> {
+        let _raw = RawMode::enable()?;
+        self.edit()
+    }
+}
+
+// Stdin without line buffering nor echo, until dropped, when stdin is a terminal : each key pressed
+// is read at once, keys sending escape sequences such as arrows as several bytes. Ctrl-C still
+// interrupts. Terminals are switched with termios on Linux, macOS and the BSDs, and with console
+// modes on Windows, `enable` fails on other platforms.
+pub struct RawMode {
+    saved: Option<terminal::Saved>,
+}
+
+// The termios struct and its flags differ between systems, and on Linux between architectures :
+// only the layouts written here are used, other targets go without raw mode.
+#[cfg(any(
+    all(target_os = "linux", any(target_arch = "x86", target_arch = "x86_64", target_arch = "arm", target_arch = "aarch64", target_arch = "riscv64")),
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "freebsd",
+    target_os = "openbsd",
+    target_os = "netbsd",
+    target_os = "dragonfly"
+))]
+mod terminal {
+    use std::ffi::c_int;
+    use std::mem;
+
+    pub const SUPPORTED: bool = true;
+
+    #[cfg(target_os = "linux")]
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    pub struct Saved {
+        iflag: u32,
+        oflag: u32,
+        cflag: u32,
+        lflag: u32,
+        line: u8,
+        cc: [u8; 32],
+        ispeed: u32,
+        ospeed: u32,
+    }
+
+    #[cfg(target_os = "linux")]
+    const ICANON: u32 = 0o2;
+    #[cfg(target_os = "linux")]
+    const ECHO: u32 = 0o10;
+    #[cfg(target_os = "linux")]
+    const VTIME: usize = 5;
+    #[cfg(target_os = "linux")]
+    const VMIN: usize = 6;
+
+    // `tcflag_t` and `speed_t` are `unsigned long` on Apple systems, `unsigned int` on the BSDs.
+    #[cfg(any(target_os = "macos", target_os = "ios"))]
+    type Flag = std::ffi::c_ulong;
+    #[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "ios")))]
+    type Flag = std::ffi::c_uint;
+
+    #[cfg(not(target_os = "linux"))]
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    pub struct Saved {
+        iflag: Flag,
+        oflag: Flag,
+        cflag: Flag,
+        lflag: Flag,
+        cc: [u8; 20],
+        ispeed: Flag,
+        ospeed: Flag,
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    const ICANON: Flag = 0x100;
+    #[cfg(not(target_os = "linux"))]
+    const ECHO: Flag = 0x8;
+    #[cfg(not(target_os = "linux"))]
+    const VMIN: usize = 16;
+    #[cfg(not(target_os = "linux"))]
+    const VTIME: usize = 17;
+
+    const TCSANOW: c_int = 0;
+
+    extern "C" {
+        fn tcgetattr(fd: c_int, termios: *mut Saved) -> c_int;
+        fn tcsetattr(fd: c_int, actions: c_int, termios: *const Saved) -> c_int;
+    }
+
+    // `None` when stdin isn't a terminal.
+    pub fn enter() -> Option<Saved> {
+        let mut saved = mem::MaybeUninit::<Saved>::uninit();
+        // SAFETY: tcgetattr fills the whole struct when it succeeds, and only reads it back after.
+        let saved = unsafe { (tcgetattr(0, saved.as_mut_ptr()) == 0).then(|| saved.assume_init()) }?;
+        let mut raw = saved;
+        raw.lflag &= !(ICANON | ECHO);
+        raw.cc[VMIN] = 1;
+        raw.cc[VTIME] = 0;
+        // SAFETY: `raw` is a valid termios struct, as read by tcgetattr.
+        unsafe { tcsetattr(0, TCSANOW, &raw) };
+        Some(saved)
+    }
+
+    pub fn restore(saved: &Saved) {
+        // SAFETY: `saved` was read by tcgetattr.
+        unsafe { tcsetattr(0, TCSANOW, saved) };
+    }
+}
+
+#[cfg(windows)]
+mod terminal {
+    use std::ffi::{c_int, c_void};
+
+    pub const SUPPORTED: bool = true;
+
+    pub struct Saved {
+        mode: u32,
+    }
+
+    const STD_INPUT_HANDLE: u32 = -10i32 as u32;
+    // Ctrl-C is still handled by the system, as `ENABLE_PROCESSED_INPUT` is left on.
+    const ENABLE_LINE_INPUT: u32 = 0x2;
+    const ENABLE_ECHO_INPUT: u32 = 0x4;
+    // Arrows and other special keys are read as the escape sequences terminals send elsewhere.
+    const ENABLE_VIRTUAL_TERMINAL_INPUT: u32 = 0x200;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetStdHandle(handle: u32) -> *mut c_void;
+        fn GetConsoleMode(console: *mut c_void, mode: *mut u32) -> c_int;
+        fn SetConsoleMode(console: *mut c_void, mode: u32) -> c_int;
+    }
+
+    // `None` when stdin isn't a console.
+    pub fn enter() -> Option<Saved> {
+        let mut mode = 0;
+        // SAFETY: GetStdHandle has no preconditions, GetConsoleMode only writes `mode` and fails on
+        // handles that aren't consoles.
+        let console = unsafe { GetStdHandle(STD_INPUT_HANDLE) };
+        if unsafe { GetConsoleMode(console, &mut mode) } == 0 {
+            return None;
+        }
+        let raw = mode & !(ENABLE_LINE_INPUT | ENABLE_ECHO_INPUT);
+        // SAFETY: `console` is a console handle. Consoles older than Windows 10 reject the virtual
+        // terminal flag, keys are then read without their escape sequences.
+        if unsafe { SetConsoleMode(console, raw | ENABLE_VIRTUAL_TERMINAL_INPUT) } == 0 {
+            unsafe { SetConsoleMode(console, raw) };
+        }
+        Some(Saved { mode })
+    }
+
+    pub fn restore(saved: &Saved) {
+        // SAFETY: the mode was read from this console by GetConsoleMode.
+        unsafe { SetConsoleMode(GetStdHandle(STD_INPUT_HANDLE), saved.mode) };
+    }
+}
+
+#[cfg(not(any(
+    all(target_os = "linux", any(target_arch = "x86", target_arch = "x86_64", target_arch = "arm", target_arch = "aarch64", target_arch = "riscv64")),
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "freebsd",
+    target_os = "openbsd",
+    target_os = "netbsd",
+    target_os = "dragonfly",
+    windows
+)))]
+mod terminal {
+    pub const SUPPORTED: bool = false;
+
+    pub enum Saved {}
+
+    pub fn enter() -> Option<Saved> {
+        None
+    }
+
+    pub fn restore(saved: &Saved) {
+        match *saved {}
+    }
+}
+
+impl RawMode {
+    pub fn enable() -> io::Result<RawMode> {
+        if !terminal::SUPPORTED {
+            return Err(io::Error::new(io::ErrorKind::Unsupported, "raw terminal input isn't supported on this platform"));
+        }
+        Ok(RawMode { saved: terminal::enter() })
+    }
+
+    // Whether stdin was switched, it isn't when it isn't a terminal.
+    pub fn is_active(&self) -> bool {
+        self.saved.is_some()
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        if let Some(saved) = &self.saved {
+            terminal::restore(saved);
+        }
+    }
+}
@@ -0,0 +1,222 @@
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+
+use crate::diagnostics::{self, Diagnostic, Severity};
+use crate::interpreter::{Config, Interpreter, LoopInfo, Span};
+use crate::json::{self, Value};
+use crate::optimizer::OptLevel;
+
+// Minimal language server over JSON-RPC : full document sync, the diagnostics of `check`, hover
+// on brackets and top-level loops as document symbols. Documents are re-parsed on every change,
+// which is cheap enough for brainfuck sources.
+
+const METHOD_NOT_FOUND: i64 = -32601;
+const SYMBOL_KIND_FUNCTION: u64 = 12;
+const SEVERITY_ERROR: u64 = 1;
+const SEVERITY_WARNING: u64 = 2;
+
+struct Document {
+    text: Vec<char>,
+    interpreter: Interpreter,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Document {
+    fn new(text: &str) -> Document {
+        // Programs are only parsed, never run, so there is no need for a real tape. Loops are
+        // reported as written, without folding.
+        let mut interpreter = Interpreter::with_config(Config { tape_size: 1, opt_level: OptLevel::O0, ..Config::default() });
+        let _ = interpreter.load_program_from_str(text);
+        let diagnostics = diagnostics::collect(text, Config::default(), &[]);
+        Document { text: text.chars().collect(), interpreter, diagnostics }
+    }
+
+    // LSP positions count UTF-16 code units from the start of the line, spans count characters.
+    fn line_and_character(&self, offset: usize) -> (usize, usize) {
+        let (mut line, mut character) = (0usize, 0usize);
+        for &c in &self.text[..offset.min(self.text.len())] {
+            if c == '\n' {
+                line += 1;
+                character = 0;
+            } else {
+                character += c.len_utf16();
+            }
+        }
+        (line, character)
+    }
+
+    fn position(&self, offset: usize) -> Value {
+        let (line, character) = self.line_and_character(offset);
+        object(vec![("line", Value::from(line)), ("character", Value::from(character))])
+    }
+
+    fn offset(&self, position: &Value) -> Option<usize> {
+        let line = position.get("line")?.as_u64()? as usize;
+        let character = position.get("character")?.as_u64()? as usize;
+
+        let mut offset = 0usize;
+        for _ in 0..line {
+            offset += self.text[offset..].iter().position(|&c| c == '\n')? + 1;
+        }
+        let mut units = 0usize;
+        while units < character {
+            units += self.text.get(offset)?.len_utf16();
+            offset += 1;
+        }
+        Some(offset)
+    }
+
+    fn range(&self, span: Span) -> Value {
+        object(vec![("start", self.position(span.start)), ("end", self.position(span.end))])
+    }
+
+    // Bracket errors, lints and pointer bounds warnings, as `check` reports them with the default
+    // configuration.
+    fn diagnostics(&self) -> Vec<Value> {
+        let severity = |severity| if severity == Severity::Error { SEVERITY_ERROR } else { SEVERITY_WARNING };
+        self.diagnostics.iter().map(|d| {
+            object(vec![
+                ("range", self.range(d.span)),
+                ("severity", Value::from(severity(d.severity))),
+                ("code", Value::from(d.rule)),
+                ("source", Value::from("sac")),
+                ("message", Value::from(d.message.as_str())),
+            ])
+        }).collect()
+    }
+
+    fn loop_at(&self, offset: usize) -> Option<LoopInfo> {
+        self.interpreter.loops().into_iter().find(|l| l.span.start == offset || l.span.end == offset + 1)
+    }
+
+    fn hover(&self, position: &Value) -> Value {
+        let Some(offset) = self.offset(position) else { return Value::Null };
+        let (Some(partner), Some(info)) = (self.interpreter.matching_bracket_at_offset(offset), self.loop_at(offset)) else {
+            return Value::Null;
+        };
+
+        let (line, character) = self.line_and_character(partner);
+        let bracket = if partner < offset { '[' } else { ']' };
+        let message = format!("Matches `{bracket}` at line {}, column {}\n\nLoop body : {} instructions", line + 1, character + 1, info.close - info.open - 1);
+
+        object(vec![
+            ("contents", object(vec![("kind", Value::from("markdown")), ("value", Value::from(message))])),
+            ("range", self.range(Span { start: offset, end: offset + 1, ..Span::default() })),
+        ])
+    }
+
+    fn symbols(&self) -> Value {
+        let symbols = self.interpreter.loops().into_iter().filter(|l| l.depth == 1).map(|l| {
+            object(vec![
+                ("name", Value::from(format!("Loop at line {}", l.span.line))),
+                ("detail", Value::from(format!("{} instructions", l.close - l.open - 1))),
+                ("kind", Value::from(SYMBOL_KIND_FUNCTION)),
+                ("range", self.range(l.span)),
+                ("selectionRange", self.range(Span { end: l.span.start + 1, ..l.span })),
+            ])
+        });
+        Value::Array(symbols.collect())
+    }
+}
+
+// Serves one client until it sends `exit` or closes the input.
+pub fn run<R: BufRead, W: Write>(mut input: R, mut output: W) -> io::Result<()> {
+    let mut documents = HashMap::new();
+
+    while let Some(message) = read_message(&mut input)? {
+        let method = message.get("method").and_then(Value::as_str).unwrap_or("");
+        let params = message.get("params").cloned().unwrap_or(Value::Null);
+        let uri = params.get("textDocument").and_then(|d| d.get("uri")).and_then(Value::as_str).unwrap_or("").to_string();
+
+        let result = match method {
+            "initialize" => object(vec![(
+                "capabilities",
+                object(vec![
+                    ("textDocumentSync", Value::from(1u64)),
+                    ("hoverProvider", Value::from(true)),
+                    ("documentSymbolProvider", Value::from(true)),
+                ]),
+            )]),
+            "textDocument/didOpen" | "textDocument/didChange" => {
+                let text = match method {
+                    "textDocument/didOpen" => params.get("textDocument").and_then(|d| d.get("text")),
+                    _ => params.get("contentChanges").and_then(Value::as_array).and_then(|c| c.last()).and_then(|c| c.get("text")),
+                };
+                if let Some(text) = text.and_then(Value::as_str) {
+                    let document = Document::new(text);
+                    publish_diagnostics(&mut output, &uri, document.diagnostics())?;
+                    documents.insert(uri, document);
+                }
+                continue;
+            },
+            "textDocument/didClose" => {
+                documents.remove(&uri);
+                publish_diagnostics(&mut output, &uri, Vec::new())?;
+                continue;
+            },
+            "textDocument/hover" => match (documents.get(&uri), params.get("position")) {
+                (Some(document), Some(position)) => document.hover(position),
+                _ => Value::Null,
+            },
+            "textDocument/documentSymbol" => documents.get(&uri).map_or(Value::Null, Document::symbols),
+            "shutdown" => Value::Null,
+            "exit" => return Ok(()),
+            _ => {
+                // Unknown notifications are ignored, unknown requests are answered with an error.
+                if let Some(id) = message.get("id") {
+                    let error = object(vec![("code", Value::Number(METHOD_NOT_FOUND as f64)), ("message", Value::from(format!("Unknown method {method}")))]);
+                    write_message(&mut output, &object(vec![("jsonrpc", Value::from("2.0")), ("id", id.clone()), ("error", error)]))?;
+                }
+                continue;
+            },
+        };
+
+        if let Some(id) = message.get("id") {
+            write_message(&mut output, &object(vec![("jsonrpc", Value::from("2.0")), ("id", id.clone()), ("result", result)]))?;
+        }
+    }
+
+    Ok(())
+}
+
+fn object(members: Vec<(&str, Value)>) -> Value {
+    Value::Object(members.into_iter().map(|(k, v)| (k.to_string(), v)).collect())
+}
+
+fn publish_diagnostics<W: Write>(output: &mut W, uri: &str, diagnostics: Vec<Value>) -> io::Result<()> {
+    let params = object(vec![("uri", Value::from(uri)), ("diagnostics", Value::Array(diagnostics))]);
+    write_message(output, &object(vec![("jsonrpc", Value::from("2.0")), ("method", Value::from("textDocument/publishDiagnostics")), ("params", params)]))
+}
+
+fn read_message<R: BufRead>(input: &mut R) -> io::Result<Option<Value>> {
+    let mut content_length = None;
+
+    loop {
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+
+    let length = content_length.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "missing Content-Length header"))?;
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+
+    let text = String::from_utf8(body).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    json::parse(&text).map(Some).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+fn write_message<W: Write>(output: &mut W, message: &Value) -> io::Result<()> {
+    let body = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{body}", body.len())?;
+    output.flush()
+}
@@ -1,5 +1,13 @@
 use std::{env, process};
-use sac::interpreter::Interpreter;
+use sac::interpreter::{executor, read_program_at_most, CompatProfile, Config, Engine, Interpreter};
+use sac::optimizer::OptLevel;
+use sac::tape::{TapeMode, WrapPolicy};
+use sac::line::RawMode;
+use sac::trace::Trace;
+use sac::verify;
+
+// Steps between two states saved by --detect-hangs.
+const HANG_CHECK_INTERVAL: u64 = 1_000_000;
 
 fn main() {
     let args: Vec<String> = env::args().collect();
@@ -10,11 +18,1133 @@ fn main() {
         process::exit(1);
     }
 
-    let program_path = &args[1];
+    if args[1] == "test" {
+        match test(&args[2..]) {
+            Ok(true) => return,
+            Ok(false) => process::exit(1),
+            Err(e) => {
+                eprintln!("[ERROR] {e} !");
+                process::exit(1);
+            },
+        }
+    }
+
+    if args[1] == "check" {
+        if let Err(e) = check(&args[2..]) {
+            eprintln!("[ERROR] {e} !");
+            process::exit(1);
+        }
+        return;
+    }
+
+    if args[1] == "diff" {
+        match diff(&args[2..]) {
+            Ok(comparison) => {
+                println!("{comparison}");
+                if !matches!(comparison, sac::diff::Comparison::Identical { .. }) {
+                    process::exit(1);
+                }
+            },
+            Err(e) => {
+                eprintln!("[ERROR] {e} !");
+                process::exit(1);
+            },
+        }
+        return;
+    }
+
+    if args[1] == "analyze" {
+        match analyze(&args[2..]) {
+            Ok(report) => println!("{report}"),
+            Err(e) => {
+                eprintln!("[ERROR] {e} !");
+                process::exit(1);
+            },
+        }
+        return;
+    }
+
+    if args[1] == "encode" {
+        match encode(&args[2..]) {
+            Ok(code) => println!("{code}"),
+            Err(e) => {
+                eprintln!("[ERROR] {e} !");
+                process::exit(1);
+            },
+        }
+        return;
+    }
+
+    if args[1] == "explain" {
+        match explain(&args[2..]) {
+            Ok(outline) => println!("{outline}"),
+            Err(e) => {
+                eprintln!("[ERROR] {e} !");
+                process::exit(1);
+            },
+        }
+        return;
+    }
+
+    if args[1] == "highlight" {
+        match highlight(&args[2..]) {
+            Ok(listing) => print!("{listing}"),
+            Err(e) => {
+                eprintln!("[ERROR] {e} !");
+                process::exit(1);
+            },
+        }
+        return;
+    }
+
+    if args[1] == "conformance" {
+        match conformance(&args[2..]) {
+            Ok(report) => println!("{report}"),
+            Err(e) => {
+                eprintln!("[ERROR] {e} !");
+                process::exit(1);
+            },
+        }
+        return;
+    }
+
+    #[cfg(feature = "lsp")]
+    if args[1] == "lsp" {
+        if let Err(e) = sac::lsp::run(std::io::stdin().lock(), std::io::stdout().lock()) {
+            eprintln!("[ERROR] {e} !");
+            process::exit(1);
+        }
+        return;
+    }
+
+    #[cfg(feature = "serve")]
+    if args[1] == "serve" {
+        if let Err(e) = serve(&args[2..]) {
+            eprintln!("[ERROR] {e} !");
+            process::exit(1);
+        }
+        return;
+    }
+
+    let result = run(&args[1..]);
+    restore_terminal();
+    if let Err(e) = result {
+        eprintln!("[ERROR] {e} !");
+        process::exit(1);
+    }
+}
+
+// ./sac [-O0|-O1|--opt-level N] [--verify|--disasm] [--non-ascii utf8|escape|CHAR] [--bell pass|suppress|CHAR] program.bf
+// ./sac [-O0|-O1|--opt-level N] [--utf8-io [--utf8-strict]] [--bell pass|suppress|CHAR] program.bf
+// ./sac [-O0|-O1|--opt-level N] [--no-line-editing|--raw-input] program.bf
+// ./sac [-O0|-O1|--opt-level N] [--record-trace FILE|--replay-trace FILE] program.bf
+// ./sac [-O0|-O1|--opt-level N] [--coverage] [--coverage-annotate] [--coverage-out FILE] program.bf
+// ./sac [-O0|-O1|--opt-level N] [--output-hash sha256|crc32] [--expect-hash HEX] [--quiet] program.bf
+// ./sac [-O0|-O1|--opt-level N] [--dump-tape-on-interrupt FILE] program.bf
+// ./sac [-O0|-O1|--opt-level N] [--input-timeout DURATION [--on-input-timeout eof|error]] program.bf
+// ./sac [-O0|-O1|--opt-level N] [--compare-against FILE [--compare-keep-going]] program.bf
+// ./sac [-O0|-O1|--opt-level N] [--output-buffer BYTES] [--infer-tape-size] [--tape-mode error|saturate|wrap] [--wrap-policy wrap|trap] [--detect-hangs] program.bf
+// ./sac [-O0|-O1|--opt-level N] [--compat classic30k|unbounded] program.bf...
+// ./sac [-O0|-O1|--opt-level N] [--checkpoint FILE [--checkpoint-every DURATION|STEPS]] [--resume FILE] program.bf
+// ./sac [-O0|-O1|--opt-level N] [--dump-ir-after-pass NAME|all [--dump-dir DIR]] [--verify|--disasm] program.bf
+// ./sac [-O0|-O1|--opt-level N] [--engine match|fn-table|jit] [--jobs N] program.bf...
+// ./sac [-O0|-O1|--opt-level N] [--max-program-bytes BYTES] program.bf...
+// ./sac [-O0|-O1|--opt-level N] [-vv] program.bf
+fn run(args: &[String]) -> Result<(), String> {
+    use sac::hash::{HashAlgorithm, HashingWriter};
+    use sac::io::{Bell, InvalidUtf8, NonAscii, OnInputTimeout};
+    use sac::line::{LineEditor, LineInput};
+    use std::io::IsTerminal;
+
+    let mut program_paths = Vec::new();
+    let mut jobs = 1usize;
+    let mut config = Config::default();
+    let mut non_ascii = NonAscii::default();
+    let mut bell = Bell::default();
+    let mut verify = false;
+    let mut disasm = false;
+    let mut hash = None;
+    let mut expected_hash: Option<String> = None;
+    let mut quiet = false;
+    let mut interrupt_dump = None;
+    let mut ir_dump = None;
+    let mut dump_dir = None;
+    let mut input_timeout = None;
+    let mut on_input_timeout = None;
+    let mut reference = None;
+    let mut keep_going = false;
+    let mut output_buffer = 0usize;
+    let mut checkpoint = None;
+    let mut checkpoint_every = None;
+    let mut resume = None;
+    let mut detect_hangs = false;
+    let mut utf8_io = false;
+    let mut utf8_strict = false;
+    let mut line_editing = true;
+    let mut raw_input = false;
+    let mut record_trace = None;
+    let mut replay_trace = None;
+    let mut coverage = CoverageOptions::default();
+    let mut verbose = false;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--verify" => verify = true,
+            "--disasm" => disasm = true,
+            "--infer-tape-size" => config.infer_tape_size = true,
+            "--detect-hangs" => detect_hangs = true,
+            "--utf8-io" => utf8_io = true,
+            "--utf8-strict" => utf8_strict = true,
+            "--no-line-editing" => line_editing = false,
+            "--raw-input" => raw_input = true,
+            "--coverage" => coverage.summary = true,
+            "--coverage-annotate" => coverage.annotate = true,
+            "--coverage-out" => coverage.out = Some(args.next().ok_or("Missing value for --coverage-out")?.as_str()),
+            "-O0" => config.opt_level = OptLevel::O0,
+            "-O1" => config.opt_level = OptLevel::O1,
+            "--compat" => {
+                let value = args.next().ok_or("Missing value for --compat")?;
+                CompatProfile::from_name(value).ok_or("--compat expects classic30k or unbounded")?.apply(&mut config);
+            },
+            "--tape-mode" => config.tape_mode = match args.next().map(String::as_str) {
+                Some("error") => TapeMode::Error,
+                Some("saturate") => TapeMode::Saturate,
+                Some("wrap") => TapeMode::Wrap,
+                _ => return Err(String::from("--tape-mode expects error, saturate or wrap")),
+            },
+            "--wrap-policy" => config.wrap_policy = match args.next().map(String::as_str) {
+                Some("wrap") => WrapPolicy::Wrap,
+                Some("trap") => WrapPolicy::Trap,
+                _ => return Err(String::from("--wrap-policy expects wrap or trap")),
+            },
+            "--opt-level" => config.opt_level = match args.next().map(String::as_str) {
+                Some("0") => OptLevel::O0,
+                Some("1") => OptLevel::O1,
+                _ => return Err(String::from("--opt-level expects 0 or 1")),
+            },
+            "--non-ascii" => non_ascii = match args.next().map(|value| value.as_bytes()) {
+                Some(b"utf8") => NonAscii::Utf8,
+                Some(b"escape") => NonAscii::Escape,
+                Some(&[placeholder]) if placeholder.is_ascii() => NonAscii::Placeholder(placeholder),
+                _ => return Err(String::from("--non-ascii expects utf8, escape or an ASCII placeholder character")),
+            },
+            "--bell" => bell = match args.next().map(|value| value.as_bytes()) {
+                Some(b"pass") => Bell::PassThrough,
+                Some(b"suppress") => Bell::Suppress,
+                Some(&[replacement]) if replacement.is_ascii() => Bell::Replace(replacement),
+                _ => return Err(String::from("--bell expects pass, suppress or an ASCII replacement character")),
+            },
+            "--engine" => {
+                let value = args.next().ok_or("Missing value for --engine")?;
+                config.engine = Engine::from_name(value).ok_or("--engine expects match, fn-table or jit")?;
+                if !config.engine.is_available() {
+                    eprintln!("[WARNING] The {value} engine isn't built into this sac, running with {}", executor(config.engine).engine().name());
+                }
+            },
+            "--jobs" => {
+                let value = args.next().ok_or("Missing value for --jobs")?;
+                jobs = value.parse().map_err(|_| format!("Invalid value for --jobs : {value}"))?;
+            },
+            "--output-hash" => {
+                let value = args.next().ok_or("Missing value for --output-hash")?;
+                hash = Some(HashAlgorithm::from_name(value).ok_or("--output-hash expects sha256 or crc32")?);
+            },
+            "--expect-hash" => expected_hash = Some(args.next().ok_or("Missing value for --expect-hash")?.to_ascii_lowercase()),
+            "--quiet" => quiet = true,
+            "-vv" => verbose = true,
+            "--dump-ir-after-pass" => ir_dump = Some(args.next().ok_or("Missing value for --dump-ir-after-pass")?.as_str()),
+            "--dump-dir" => dump_dir = Some(args.next().ok_or("Missing value for --dump-dir")?.as_str()),
+            "--input-timeout" => {
+                let value = args.next().ok_or("Missing value for --input-timeout")?;
+                input_timeout = Some(parse_duration(value).ok_or(format!("Invalid value for --input-timeout : {value}, expected a duration such as 10s or 500ms"))?);
+            },
+            "--on-input-timeout" => on_input_timeout = match args.next().map(String::as_str) {
+                Some("eof") => Some(OnInputTimeout::Eof),
+                Some("error") => Some(OnInputTimeout::Error),
+                _ => return Err(String::from("--on-input-timeout expects eof or error")),
+            },
+            "--compare-against" => reference = Some(args.next().ok_or("Missing value for --compare-against")?.as_str()),
+            "--compare-keep-going" => keep_going = true,
+            "--checkpoint" => checkpoint = Some(args.next().ok_or("Missing value for --checkpoint")?.as_str()),
+            "--checkpoint-every" => {
+                let value = args.next().ok_or("Missing value for --checkpoint-every")?;
+                checkpoint_every = Some(match parse_duration(value) {
+                    Some(duration) => Every::Duration(duration),
+                    None => Every::Steps(value.parse().ok().filter(|&steps| steps > 0).ok_or(format!("Invalid value for --checkpoint-every : {value}, expected a duration such as 30s or a number of steps"))?),
+                });
+            },
+            "--resume" => resume = Some(args.next().ok_or("Missing value for --resume")?.as_str()),
+            "--record-trace" => record_trace = Some(args.next().ok_or("Missing value for --record-trace")?.as_str()),
+            "--replay-trace" => replay_trace = Some(args.next().ok_or("Missing value for --replay-trace")?.as_str()),
+            "--output-buffer" => {
+                let value = args.next().ok_or("Missing value for --output-buffer")?;
+                output_buffer = value.parse().map_err(|_| format!("Invalid value for --output-buffer : {value}"))?;
+            },
+            "--max-program-bytes" => {
+                let value = args.next().ok_or("Missing value for --max-program-bytes")?;
+                config.max_source_bytes = Some(value.parse().map_err(|_| format!("Invalid value for --max-program-bytes : {value}"))?);
+            },
+            "--dump-tape-on-interrupt" => interrupt_dump = Some(args.next().ok_or("Missing value for --dump-tape-on-interrupt")?.as_str()),
+            _ if arg.starts_with('-') => return Err(format!("Unknown option {arg}")),
+            _ => program_paths.push(arg.as_str()),
+        }
+    }
+
+    let program_path = match program_paths[..] {
+        [] => return Err(String::from("No program provided")),
+        [program_path] => program_path,
+        _ if verify || disasm || non_ascii != NonAscii::default() || bell != Bell::default() || hash.is_some() || expected_hash.is_some() || quiet || interrupt_dump.is_some() || ir_dump.is_some() || input_timeout.is_some() || reference.is_some() || output_buffer > 0 || checkpoint.is_some() || resume.is_some() || detect_hangs || utf8_io || record_trace.is_some() || replay_trace.is_some() || raw_input || coverage.is_on() || verbose => {
+            return Err(String::from("--verify, --disasm, --non-ascii, --bell, --input-timeout, --compare-against, --output-buffer, --detect-hangs, --utf8-io, --raw-input, -vv, the checkpoint options, the trace options, the coverage options, the dump options and the output hash options only work on a single program"));
+        },
+        _ => return run_many(&program_paths, config, jobs),
+    };
 
-    let mut my_interpreter = Interpreter::new();
+    if on_input_timeout.is_some() && input_timeout.is_none() {
+        return Err(String::from("--on-input-timeout only works with --input-timeout"));
+    }
+    if utf8_strict && !utf8_io {
+        return Err(String::from("--utf8-strict only works with --utf8-io"));
+    }
+    if utf8_io && non_ascii != NonAscii::default() {
+        return Err(String::from("--non-ascii can't be used with --utf8-io, which prints UTF-8 as is"));
+    }
+    if keep_going && reference.is_none() {
+        return Err(String::from("--compare-keep-going only works with --compare-against"));
+    }
+    if reference.is_some() && (hash.is_some() || expected_hash.is_some() || quiet) {
+        return Err(String::from("--compare-against can't be used with the output hash options"));
+    }
+    if checkpoint_every.is_some() && checkpoint.is_none() {
+        return Err(String::from("--checkpoint-every only works with --checkpoint"));
+    }
+    if (checkpoint.is_some() || resume.is_some()) && (reference.is_some() || hash.is_some() || expected_hash.is_some() || verify) {
+        return Err(String::from("--checkpoint and --resume can't be used with --verify, --compare-against or the output hash options"));
+    }
+
+    if coverage.is_on() && reference.is_some() {
+        return Err(String::from("The coverage options can't be used with --compare-against"));
+    }
+    if (record_trace.is_some() || replay_trace.is_some()) && (verify || reference.is_some() || checkpoint.is_some() || resume.is_some()) {
+        return Err(String::from("--record-trace and --replay-trace can't be used with --verify, --compare-against or the checkpoint options"));
+    }
+    if replay_trace.is_some() && (record_trace.is_some() || hash.is_some() || expected_hash.is_some()) {
+        return Err(String::from("--replay-trace can't be used with --record-trace or the output hash options"));
+    }
+
+    match (ir_dump, dump_dir) {
+        (Some(selection), dump_dir) => dump_ir(program_path, config, selection, dump_dir)?,
+        (None, Some(_)) => return Err(String::from("--dump-dir only works with --dump-ir-after-pass")),
+        (None, None) => (),
+    }
+
+    // Without --output-hash, the algorithm is picked from the length of the expected digest.
+    let hash = match (hash, &expected_hash) {
+        (Some(hash), _) => Some(hash),
+        (None, Some(expected)) if expected.len() == 64 => Some(HashAlgorithm::Sha256),
+        (None, Some(expected)) if expected.len() == 8 => Some(HashAlgorithm::Crc32),
+        (None, Some(_)) => return Err(String::from("--expect-hash expects a sha256 or crc32 digest in hexadecimal")),
+        (None, None) => None,
+    };
+
+    if verify {
+        if non_ascii != NonAscii::default() || bell != Bell::default() || hash.is_some() || quiet || input_timeout.is_some() || reference.is_some() || detect_hangs || utf8_io || raw_input || coverage.is_on() || verbose {
+            return Err(String::from("--non-ascii, --bell, --input-timeout, --compare-against, --detect-hangs, --utf8-io, --raw-input, -vv, the coverage options and the output hash options can't be used with --verify"));
+        }
+        return run_verified(program_path, config);
+    }
+
+    let mut my_interpreter = Interpreter::with_config(config);
+    my_interpreter.set_non_ascii(non_ascii);
+    my_interpreter.set_bell(bell);
+    if utf8_io {
+        my_interpreter.set_utf8_io(Some(if utf8_strict { InvalidUtf8::Fail } else { InvalidUtf8::Replace }));
+    }
+    my_interpreter.set_output_buffer_size(output_buffer);
+    if detect_hangs {
+        my_interpreter.set_hang_detection(Some(HANG_CHECK_INTERVAL));
+    }
+    my_interpreter.set_trace_recording(record_trace.is_some());
+    my_interpreter.set_coverage_tracking(coverage.is_on());
+    if verbose {
+        trace_to_stderr(&mut my_interpreter)?;
+    }
+
+    my_interpreter.load_program(program_path).map_err(|e| e.to_string())?;
+
+    if disasm {
+        print!("{}", my_interpreter.listing());
+        return Ok(());
+    }
+
+    let interrupt = interrupt_token();
+    my_interpreter.set_cancel_token(interrupt.clone());
+    // Typed input is edited a line at a time, piped input goes to the program as is.
+    if raw_input && std::io::stdin().is_terminal() {
+        enter_raw_mode()?;
+    }
+    let stdin: Box<dyn std::io::Read + Send> = if line_editing && !raw_input && cfg!(target_os = "linux") && std::io::stdin().is_terminal() {
+        Box::new(LineInput::new(LineEditor::new(InterruptibleStdin(interrupt.clone()), std::io::stderr())))
+    } else {
+        Box::new(InterruptibleStdin(interrupt.clone()))
+    };
+    match input_timeout {
+        Some(timeout) => my_interpreter.set_input_with_timeout(stdin, timeout, on_input_timeout.unwrap_or_default()),
+        None => my_interpreter.set_input(stdin),
+    }
+    if let Some(path) = resume {
+        my_interpreter.load_checkpoint(path).map_err(|e| format!("Unable to resume from {path} : {e}"))?;
+    }
+
+    if let Some(path) = replay_trace {
+        let trace = Trace::load(path).map_err(|e| format!("Unable to read the trace {path} : {e}"))?;
+        let result = my_interpreter.replay(&trace);
+        report_coverage(&my_interpreter, coverage, program_path)?;
+        if interrupt.is_cancelled() {
+            interrupted(&my_interpreter, interrupt_dump);
+        }
+        return result.map_err(|e| e.to_string());
+    }
 
-    my_interpreter.load_program(program_path);
+    if let Some(path) = reference {
+        return compare_against(&mut my_interpreter, path, keep_going, interrupt_dump);
+    }
+
+    let Some(hash) = hash else {
+        if quiet {
+            my_interpreter.set_output(std::io::sink());
+        }
+        let result = match checkpoint {
+            Some(path) => run_checkpointed(&mut my_interpreter, path, checkpoint_every.unwrap_or(Every::Duration(std::time::Duration::from_secs(30)))),
+            None => my_interpreter.interpret(),
+        };
+        save_trace(&mut my_interpreter, record_trace)?;
+        report_coverage(&my_interpreter, coverage, program_path)?;
+        if interrupt.is_cancelled() {
+            interrupted(&my_interpreter, interrupt_dump);
+        }
+        return result.map_err(|e| e.to_string());
+    };
+
+    // The digest goes to stderr, so that it is never mixed with the output.
+    let writer: Box<dyn std::io::Write + Send> = if quiet { Box::new(std::io::sink()) } else { Box::new(std::io::stdout()) };
+    let hasher = HashingWriter::new(writer, hash);
+    my_interpreter.set_output(hasher.clone());
+    let result = my_interpreter.interpret();
+    save_trace(&mut my_interpreter, record_trace)?;
+    report_coverage(&my_interpreter, coverage, program_path)?;
+    if interrupt.is_cancelled() {
+        interrupted(&my_interpreter, interrupt_dump);
+    }
+    result.map_err(|e| e.to_string())?;
+
+    let digest = hasher.digest();
+    eprintln!("{digest}");
+    match expected_hash {
+        Some(expected) if expected != digest.hex => Err(format!("Output hash mismatch : expected {expected}, got {}", digest.hex)),
+        _ => Ok(()),
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+struct CoverageOptions<'a> {
+    summary: bool,
+    annotate: bool,
+    out: Option<&'a str>,
+}
+
+impl CoverageOptions<'_> {
+    fn is_on(self) -> bool {
+        self.summary || self.annotate || self.out.is_some()
+    }
+}
+
+// Prints the coverage of the run on stderr and writes its lcov file, whether the run succeeded or not.
+fn report_coverage(interpreter: &Interpreter, options: CoverageOptions, program_path: &str) -> Result<(), String> {
+    use std::io::IsTerminal;
+
+    if options.summary {
+        eprintln!("{}", sac::coverage::summary(interpreter).unwrap());
+    }
+    if options.annotate {
+        let color = env::var_os("NO_COLOR").is_none_or(|value| value.is_empty()) && std::io::stderr().is_terminal();
+        eprint!("{}", sac::coverage::annotate(interpreter, color).unwrap());
+    }
+    if let Some(path) = options.out {
+        let lcov = sac::coverage::to_lcov(interpreter, program_path).unwrap();
+        std::fs::write(path, lcov).map_err(|e| format!("Unable to write the coverage to {path} : {e}"))?;
+    }
+    Ok(())
+}
+
+// Prints the spans and events of the load and the run on stderr, for -vv.
+#[cfg(feature = "tracing")]
+fn trace_to_stderr(interpreter: &mut Interpreter) -> Result<(), String> {
+    interpreter.set_subscriber(std::sync::Arc::new(sac::instrument::WriterSubscriber::new(std::io::stderr())));
+    Ok(())
+}
+
+#[cfg(not(feature = "tracing"))]
+fn trace_to_stderr(_: &mut Interpreter) -> Result<(), String> {
+    Err(String::from("-vv needs sac to be built with the tracing feature"))
+}
+
+// Saves the trace recorded for --record-trace, whether the run succeeded or not.
+fn save_trace(interpreter: &mut Interpreter, path: Option<&str>) -> Result<(), String> {
+    match (path, interpreter.take_trace()) {
+        (Some(path), Some(trace)) => trace.save(path).map_err(|e| format!("Unable to write the trace to {path} : {e}")),
+        _ => Ok(()),
+    }
+}
+
+// Runs the program checking its output against the file at `path` as it is printed. Every mismatch
+// is reported with the position of the `.` that printed it, and the run fails if there is any.
+fn compare_against(interpreter: &mut Interpreter, path: &str, keep_going: bool, interrupt_dump: Option<&str>) -> Result<(), String> {
+    let expected = std::fs::File::open(path).map_err(|e| format!("Unable to read {path} : {e}"))?;
+    let run = sac::diff::compare_against(interpreter, expected, std::io::stdout(), keep_going);
+    if interrupt_token().is_cancelled() {
+        interrupted(interpreter, interrupt_dump);
+    }
+
+    match (run.error, &run.mismatches[..]) {
+        (None, []) => Ok(()),
+        (None, [mismatch]) => Err(mismatch.to_string()),
+        (error, mismatches) => {
+            for mismatch in mismatches {
+                eprintln!("[ERROR] {mismatch} !");
+            }
+            Err(error.map_or_else(|| format!("{} mismatches with {path}", mismatches.len()), |e| e.to_string()))
+        },
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Every {
+    Duration(std::time::Duration),
+    Steps(u64),
+}
+
+// Runs the program in slices, saving a checkpoint to `path` between two slices once `every` went
+// by. Failing to save one only warns, the run goes on.
+fn run_checkpointed(interpreter: &mut Interpreter, path: &str, every: Every) -> Result<(), sac::error::RuntimeError> {
+    use std::time::Instant;
+
+    use sac::interpreter::StepOutcome;
+
+    let slice = match every {
+        Every::Steps(steps) => steps.min(1 << 16),
+        Every::Duration(_) => 1 << 16,
+    };
+    let (mut saved_at, mut saved_steps) = (Instant::now(), interpreter.steps());
+
+    loop {
+        if let StepOutcome::Completed(_) = interpreter.run_for(slice)? {
+            return Ok(());
+        }
+        let due = match every {
+            Every::Duration(duration) => saved_at.elapsed() >= duration,
+            Every::Steps(steps) => interpreter.steps() - saved_steps >= steps,
+        };
+        if due {
+            if let Err(e) = interpreter.save_checkpoint(path) {
+                eprintln!("[WARNING] Unable to save the checkpoint to {path} : {e}");
+            }
+            (saved_at, saved_steps) = (Instant::now(), interpreter.steps());
+        }
+    }
+}
+
+// `500ms`, `10s` or `2m`.
+fn parse_duration(value: &str) -> Option<std::time::Duration> {
+    use std::time::Duration;
+
+    let split = value.find(|c: char| !c.is_ascii_digit())?;
+    let number: u64 = value[..split].parse().ok()?;
+    match &value[split..] {
+        "ms" => Some(Duration::from_millis(number)),
+        "s" => Some(Duration::from_secs(number)),
+        "m" => Some(Duration::from_secs(number.checked_mul(60)?)),
+        _ => None,
+    }
+}
+
+// Loads the program with a hook on the pipeline, to show the listing left by the pass named
+// `selection`, or by every pass with `all`. Each listing follows a summary of what the pass did, and
+// goes to stderr or to `DIR/NN-PASS.ir`.
+fn dump_ir(program_path: &str, config: Config, selection: &str, dir: Option<&str>) -> Result<(), String> {
+    use std::sync::{Arc, Mutex};
+
+    use sac::optimizer::Pipeline;
+
+    let mut pipeline = Pipeline::for_level(config.opt_level);
+    if selection != "all" && !pipeline.names().contains(&selection) {
+        return Err(format!("Unknown pass {selection}, the passes at this level are : {}", pipeline.names().join(", ")));
+    }
+
+    let dumps = Arc::new(Mutex::new(Vec::new()));
+    let sink = dumps.clone();
+    let selection = selection.to_string();
+    pipeline.on_pass(move |report, program| {
+        if selection == "all" || report.name == selection {
+            sink.lock().unwrap_or_else(|e| e.into_inner()).push((report.name.clone(), format!("; {report}\n{}", program.listing())));
+        }
+    });
+
+    let mut interpreter = Interpreter::with_config(config);
+    interpreter.set_pipeline(pipeline);
+    interpreter.load_program(program_path).map_err(|e| e.to_string())?;
+
+    if let Some(dir) = dir {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Unable to create {dir} : {e}"))?;
+    }
+    for (index, (name, dump)) in std::mem::take(&mut *dumps.lock().unwrap_or_else(|e| e.into_inner())).into_iter().enumerate() {
+        match dir {
+            Some(dir) => {
+                let path = std::path::Path::new(dir).join(format!("{:02}-{name}.ir", index + 1));
+                std::fs::write(&path, dump).map_err(|e| format!("Unable to write {} : {e}", path.display()))?;
+            },
+            None => eprintln!("{dump}"),
+        }
+    }
+
+    Ok(())
+}
+
+// Runs the programs without input on `jobs` threads, printing each one's output in one piece, in the
+// order they were given.
+fn run_many(program_paths: &[&str], config: Config, jobs: usize) -> Result<(), String> {
+    use std::io::Write;
+
+    let programs = program_paths.iter().map(|path| read_program_at_most(path, config.max_source_bytes).map_err(|e| format!("{path} : {e}"))).collect::<Result<Vec<_>, _>>()?;
+    let results = sac::runner::run_many(&programs, config, jobs, &interrupt_token());
+
+    let mut failed = 0;
+    for (path, result) in program_paths.iter().zip(results) {
+        let error = match result {
+            Ok(execution) => {
+                let mut stdout = std::io::stdout().lock();
+                stdout.write_all(&execution.output).and_then(|()| stdout.flush()).map_err(|e| e.to_string())?;
+                execution.error.map(|e| e.to_string())
+            },
+            Err(e) => Some(e.to_string()),
+        };
+        if let Some(e) = error {
+            eprintln!("[ERROR] {path} : {e} !");
+            failed += 1;
+        }
+    }
+
+    match failed {
+        0 => Ok(()),
+        _ => Err(format!("{failed} of {} programs failed", program_paths.len())),
+    }
+}
+
+// Ctrl-C cancels the running programs through this token rather than killing the process, so that
+// their output is flushed and the tape can be saved before exiting.
+static INTERRUPT: std::sync::OnceLock<sac::cancel::CancelToken> = std::sync::OnceLock::new();
+
+fn interrupt_token() -> sac::cancel::CancelToken {
+    #[cfg(unix)]
+    {
+        use std::ffi::c_int;
+
+        const SIGINT: c_int = 2;
+
+        extern "C" {
+            fn signal(signum: c_int, handler: extern "C" fn(c_int)) -> usize;
+            fn siginterrupt(signum: c_int, flag: c_int) -> c_int;
+        }
+
+        extern "C" fn on_interrupt(_: c_int) {
+            if let Some(token) = INTERRUPT.get() {
+                token.cancel();
+            }
+        }
+
+        if INTERRUPT.get().is_none() {
+            INTERRUPT.get_or_init(sac::cancel::CancelToken::new);
+            // Without restarts, a program blocked on `,` is interrupted too.
+            unsafe {
+                signal(SIGINT, on_interrupt);
+                siginterrupt(SIGINT, 1);
+            }
+        }
+    }
+
+    INTERRUPT.get_or_init(sac::cancel::CancelToken::new).clone()
+}
+
+// Stdin giving up on reads interrupted by Ctrl-C, which are otherwise retried.
+struct InterruptibleStdin(sac::cancel::CancelToken);
+
+impl std::io::Read for InterruptibleStdin {
+    fn read(&mut self, bytes: &mut [u8]) -> std::io::Result<usize> {
+        loop {
+            match std::io::stdin().read(bytes) {
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted && self.0.is_cancelled() => return Err(std::io::Error::other("interrupted")),
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                result => return result,
+            }
+        }
+    }
+}
+
+// Terminal settings to restore once the run is over, saved by --raw-input.
+static RAW_MODE: std::sync::Mutex<Option<RawMode>> = std::sync::Mutex::new(None);
+
+// Keys go to `,` as soon as they are pressed, until `restore_terminal`, which a panic also calls.
+fn enter_raw_mode() -> Result<(), String> {
+    let raw = RawMode::enable().map_err(|e| format!("Unable to use --raw-input : {e}"))?;
+    if !raw.is_active() {
+        eprintln!("[WARNING] The terminal couldn't be switched to raw mode, keys are read a line at a time");
+        return Ok(());
+    }
+    *RAW_MODE.lock().unwrap_or_else(|e| e.into_inner()) = Some(raw);
+
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        restore_terminal();
+        default_hook(info);
+    }));
+    Ok(())
+}
+
+fn restore_terminal() {
+    RAW_MODE.lock().unwrap_or_else(|e| e.into_inner()).take();
+}
+
+// Exits after Ctrl-C stopped the program, its output flushed and its tape saved to `dump` if asked.
+fn interrupted(interpreter: &Interpreter, dump: Option<&str>) -> ! {
+    use std::io::Write;
+
+    let _ = std::io::stdout().flush();
+    restore_terminal();
+    eprintln!();
+    if let Some(path) = dump {
+        match interpreter.save_tape(path) {
+            Ok(()) => eprintln!("[WARNING] Tape saved to {path}"),
+            Err(e) => eprintln!("[ERROR] Unable to save the tape to {path} : {e} !"),
+        }
+    }
+    eprintln!("[WARNING] Interrupted at instruction {}, memory pointer at cell {}", interpreter.instruction_pointer(), interpreter.memory_pointer());
+    process::exit(130);
+}
+
+// Buffers stdin so that the unoptimized and optimized runs see the same input, then prints the
+// output once both agreed.
+fn run_verified(program_path: &str, config: Config) -> Result<(), String> {
+    use std::io::{Read, Write};
+
+    let code = read_program_at_most(program_path, config.max_source_bytes).map_err(|e| e.to_string())?;
+    let mut input = Vec::new();
+    std::io::stdin().read_to_end(&mut input).map_err(|e| e.to_string())?;
+
+    let execution = verify::verify(&code, &input, config).map_err(|e| e.to_string())?;
+
+    let mut stdout = std::io::stdout();
+    stdout.write_all(&execution.output).and_then(|()| stdout.flush()).map_err(|e| e.to_string())?;
+
+    match execution.error {
+        Some(e) => Err(e.to_string()),
+        None => Ok(()),
+    }
+}
+
+// ./sac check [--lint] [--allow NAME]... [--analyze] [--missed-optimizations] [--tape-size N] [--max-program-bytes BYTES] [--compat classic30k|unbounded] [--format human|sarif] program.bf
+// Loads the program without running it and prints the lints that were not allowed. With
+// `--analyze`, pointer bounds warnings are printed too. `--format sarif` prints all of them, bracket
+// errors included, as a SARIF log on stdout.
+fn check(args: &[String]) -> Result<(), String> {
+    use sac::analysis::{self, Lint};
+
+    let mut program_path = None;
+    let mut config = Config::default();
+    let mut analyze = false;
+    let mut allow = Vec::new();
+    let mut sarif = false;
+    let mut missed = false;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--lint" => (), // Lints are always on.
+            "--allow" => {
+                let name = args.next().ok_or("Missing value for --allow")?;
+                allow.push(Lint::from_name(name).ok_or(format!("Unknown lint {name}"))?);
+            },
+            "--analyze" => analyze = true,
+            "--missed-optimizations" => missed = true,
+            "--compat" => {
+                let value = args.next().ok_or("Missing value for --compat")?;
+                CompatProfile::from_name(value).ok_or("--compat expects classic30k or unbounded")?.apply(&mut config);
+            },
+            "--tape-size" => {
+                let value = args.next().ok_or("Missing value for --tape-size")?;
+                config.tape_size = value.parse().map_err(|_| format!("Invalid value for --tape-size : {value}"))?;
+            },
+            "--max-program-bytes" => {
+                let value = args.next().ok_or("Missing value for --max-program-bytes")?;
+                config.max_source_bytes = Some(value.parse().map_err(|_| format!("Invalid value for --max-program-bytes : {value}"))?);
+            },
+            "--format" => sarif = match args.next().map(String::as_str) {
+                Some("human") => false,
+                Some("sarif") => true,
+                _ => return Err(String::from("--format expects human or sarif")),
+            },
+            _ if arg.starts_with('-') => return Err(format!("Unknown option {arg}")),
+            _ => program_path = Some(arg.as_str()),
+        }
+    }
+
+    let program_path = program_path.ok_or("No program provided")?;
+    if sarif {
+        return check_sarif(program_path, config, &allow, analyze);
+    }
+    let mut interpreter = Interpreter::with_config(config);
+    interpreter.load_program(program_path).map_err(|e| e.to_string())?;
+
+    for warning in analysis::lint(&interpreter, &allow) {
+        eprintln!("[WARNING] {warning}");
+    }
+    for duplicate in analysis::duplicate_labels(&interpreter) {
+        eprintln!("[WARNING] {duplicate}");
+    }
+    if analyze {
+        for warning in analysis::pointer_bounds(&interpreter) {
+            eprintln!("[WARNING] {warning}");
+        }
+    }
+    if missed {
+        for missed in interpreter.pass_reports().iter().flat_map(|report| &report.missed) {
+            eprintln!("[WARNING] {missed}");
+        }
+    }
+
+    Ok(())
+}
+
+// Fails when there is an error among the diagnostics, once the log is printed.
+#[cfg(feature = "sarif")]
+fn check_sarif(program_path: &str, config: Config, allow: &[sac::analysis::Lint], analyze: bool) -> Result<(), String> {
+    use sac::diagnostics::{self, Severity};
+
+    let code = read_program_at_most(program_path, config.max_source_bytes).map_err(|e| e.to_string())?;
+    let mut diagnostics = diagnostics::collect(&code, config, allow);
+    if !analyze {
+        diagnostics.retain(|diagnostic| ![diagnostics::POINTER_UNDERFLOW, diagnostics::POINTER_OVERFLOW].contains(&diagnostic.rule));
+    }
+    println!("{}", diagnostics::diagnostics_to_sarif(&diagnostics, &program_path.replace('\\', "/")));
+
+    match diagnostics.iter().filter(|diagnostic| diagnostic.severity == Severity::Error).count() {
+        0 => Ok(()),
+        errors => Err(format!("{errors} errors in {program_path}")),
+    }
+}
+
+#[cfg(not(feature = "sarif"))]
+fn check_sarif(_: &str, _: Config, _: &[sac::analysis::Lint], _: bool) -> Result<(), String> {
+    Err(String::from("--format sarif needs sac to be built with the sarif feature"))
+}
+
+// ./sac diff [--input FILE] [-O0|-O1] [--max-program-bytes BYTES] old.bf new.bf
+fn diff(args: &[String]) -> Result<sac::diff::Comparison, String> {
+    let mut programs = Vec::new();
+    let mut input = Vec::new();
+    let mut config = Config::default();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--input" => {
+                let path = args.next().ok_or("Missing value for --input")?;
+                input = std::fs::read(path).map_err(|e| format!("Unable to read {path} : {e}"))?;
+            },
+            "-O0" => config.opt_level = OptLevel::O0,
+            "-O1" => config.opt_level = OptLevel::O1,
+            "--max-program-bytes" => {
+                let value = args.next().ok_or("Missing value for --max-program-bytes")?;
+                config.max_source_bytes = Some(value.parse().map_err(|_| format!("Invalid value for --max-program-bytes : {value}"))?);
+            },
+            _ if arg.starts_with('-') => return Err(format!("Unknown option {arg}")),
+            _ => programs.push(arg.as_str()),
+        }
+    }
+
+    let [old, new] = programs[..] else {
+        return Err(String::from("Usage : ./sac diff [--input FILE] [-O0|-O1] [--max-program-bytes BYTES] old.bf new.bf"));
+    };
+    let read = |path| read_program_at_most(path, config.max_source_bytes).map_err(|e| e.to_string());
+    sac::diff::diff(&read(old)?, &read(new)?, &input, config).map_err(|e| e.to_string())
+}
+
+// ./sac analyze [--json] [--cfg FILE [--profile-in FILE]] program.bf
+// `--cfg` also writes the control-flow graph as Graphviz to FILE, shaded by the lcov file of
+// `--coverage-out` given to `--profile-in`.
+fn analyze(args: &[String]) -> Result<String, String> {
+    use sac::json::Value;
+
+    let mut program_path = None;
+    let mut json = false;
+    let mut cfg = None;
+    let mut profile = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--cfg" => cfg = Some(args.next().ok_or("Missing value for --cfg")?.as_str()),
+            "--profile-in" => profile = Some(args.next().ok_or("Missing value for --profile-in")?.as_str()),
+            _ if arg.starts_with('-') => return Err(format!("Unknown option {arg}")),
+            _ => program_path = Some(arg.as_str()),
+        }
+    }
+
+    let program_path = program_path.ok_or("No program provided")?;
+    let mut interpreter = Interpreter::new();
+    interpreter.load_program(program_path).map_err(|e| e.to_string())?;
+    let analysis = sac::analysis::analyze(&interpreter);
+    if profile.is_some() && cfg.is_none() {
+        return Err(String::from("--profile-in needs --cfg"));
+    }
+    if let Some(path) = cfg {
+        let runs = profile.map(|profile| read_lcov_runs(&interpreter, profile)).transpose()?;
+        let dot = sac::analysis::control_flow_dot(&interpreter, runs.as_deref());
+        std::fs::write(path, dot).map_err(|e| format!("Unable to write {path} : {e}"))?;
+    }
+
+    if json {
+        let counts = |counts: Vec<(String, usize)>| Value::Object(counts.into_iter().map(|(k, v)| (k, Value::from(v))).collect());
+        let bound = |bound: Option<i64>| bound.map_or(Value::Null, |b| Value::Number(b as f64));
+        let report = Value::Object(vec![
+            (String::from("source_bytes"), Value::from(analysis.source_bytes)),
+            (String::from("command_count"), Value::from(analysis.command_count())),
+            (String::from("commands"), counts(analysis.commands.iter().map(|(c, n)| (c.to_string(), *n)).collect())),
+            (String::from("instruction_count"), Value::from(analysis.instruction_count())),
+            (String::from("instructions"), counts(analysis.instructions.iter().map(|(k, n)| (k.to_string(), *n)).collect())),
+            (String::from("loops"), Value::from(analysis.loops)),
+            (String::from("max_depth"), Value::from(analysis.max_depth)),
+            (String::from("longest_run"), Value::from(analysis.longest_run)),
+            (String::from("inputs"), Value::from(analysis.inputs)),
+            (String::from("outputs"), Value::from(analysis.outputs)),
+            (String::from("reach"), Value::Object(vec![(String::from("low"), bound(analysis.reach.0)), (String::from("high"), bound(analysis.reach.1))])),
+            (String::from("inferred_tape_size"), analysis.inferred_tape_size.map_or(Value::Null, Value::from)),
+            (String::from("labels"), Value::Object(analysis.labels.iter().map(|label| (label.name.clone(), Value::from(label.span.line))).collect())),
+        ]);
+        return Ok(report.to_string());
+    }
+
+    let list = |counts: Vec<String>| counts.join(", ");
+    let bound = |bound: Option<i64>| bound.map_or(String::from("unknown"), |b| b.to_string());
+    Ok([
+        format!("source: {} bytes", analysis.source_bytes),
+        format!("commands: {} ({})", analysis.command_count(), list(analysis.commands.iter().map(|(c, n)| format!("{c} {n}")).collect())),
+        format!("instructions: {} ({})", analysis.instruction_count(), list(analysis.instructions.iter().map(|(k, n)| format!("{k} {n}")).collect())),
+        format!("loops: {}, maximum depth {}", analysis.loops, analysis.max_depth),
+        format!("longest run: {}", analysis.longest_run),
+        format!("input: {} `,`{}, output: {} `.`", analysis.inputs, if analysis.inputs > 0 { " (interactive)" } else { "" }, analysis.outputs),
+        format!("pointer reach: cells {} to {}", bound(analysis.reach.0), bound(analysis.reach.1)),
+        format!("inferred tape size: {}", analysis.inferred_tape_size.map_or(String::from("none"), |size| format!("{size} cells"))),
+        format!("labels: {}", if analysis.labels.is_empty() { String::from("none") } else { list(analysis.labels.iter().map(|label| format!("{} (line {})", label.name, label.span.line)).collect()) }),
+    ]
+    .join("\n"))
+}
+
+// Runs of each instruction from an lcov file, which only has them by line : each instruction gets
+// those of the line it starts on.
+fn read_lcov_runs(interpreter: &Interpreter, path: &str) -> Result<Vec<u64>, String> {
+    let lcov = std::fs::read_to_string(path).map_err(|e| format!("Unable to read {path} : {e}"))?;
+    let mut lines = std::collections::HashMap::new();
+    for record in lcov.lines().filter_map(|line| line.strip_prefix("DA:")) {
+        let mut fields = record.split(',').map(str::parse::<u64>);
+        match (fields.next(), fields.next()) {
+            (Some(Ok(line)), Some(Ok(count))) => lines.insert(line as usize, count),
+            _ => return Err(format!("Invalid lcov record in {path} : DA:{record}")),
+        };
+    }
+    Ok(interpreter.to_program().instructions.iter().map(|inst| lines.get(&inst.span.line).copied().unwrap_or(0)).collect())
+}
+
+// ./sac encode [--style naive|loops] [--from-file FILE | TEXT]
+fn encode(args: &[String]) -> Result<String, String> {
+    use sac::encode::{self, EncodeStyle};
+
+    let mut text = None;
+    let mut style = EncodeStyle::default();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--style" => {
+                let name = args.next().ok_or("Missing value for --style")?;
+                style = EncodeStyle::from_name(name).ok_or(format!("Unknown style {name}, expected naive or loops"))?;
+            },
+            "--from-file" => {
+                let path = args.next().ok_or("Missing value for --from-file")?;
+                let bytes = std::fs::read(path).map_err(|e| format!("Unable to read {path} : {e}"))?;
+                text = Some(String::from_utf8(bytes).map_err(|_| format!("{path} is not valid UTF-8"))?);
+            },
+            _ if arg.starts_with('-') => return Err(format!("Unknown option {arg}")),
+            _ => text = Some(arg.clone()),
+        }
+    }
+
+    let text = text.ok_or("Usage : ./sac encode [--style naive|loops] [--from-file FILE | TEXT]")?;
+    encode::encode(&text, style).map_err(|e| e.to_string())
+}
+
+// ./sac explain [--depth N] [-O0|-O1] program.bf
+fn explain(args: &[String]) -> Result<String, String> {
+    let mut program_path = None;
+    let mut config = Config::default();
+    let mut depth = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--depth" => {
+                let value = args.next().ok_or("Missing value for --depth")?;
+                depth = Some(value.parse().map_err(|_| format!("Invalid value for --depth : {value}"))?);
+            },
+            "-O0" => config.opt_level = OptLevel::O0,
+            "-O1" => config.opt_level = OptLevel::O1,
+            _ if arg.starts_with('-') => return Err(format!("Unknown option {arg}")),
+            _ => program_path = Some(arg.as_str()),
+        }
+    }
+
+    let program_path = program_path.ok_or("No program provided")?;
+    let mut interpreter = Interpreter::with_config(config);
+    interpreter.load_program(program_path).map_err(|e| e.to_string())?;
+    Ok(sac::explain::explain(&interpreter, depth))
+}
+
+// ./sac highlight [--line-numbers] [--max-program-bytes BYTES] program.bf
+// Colors are left out when NO_COLOR is set or stdout isn't a terminal.
+fn highlight(args: &[String]) -> Result<String, String> {
+    use std::io::IsTerminal;
+
+    use sac::highlight::{self, HighlightOptions};
+
+    let mut program_path = None;
+    let mut options = HighlightOptions::default();
+    let mut max_bytes = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--line-numbers" => options.line_numbers = true,
+            "--max-program-bytes" => {
+                let value = args.next().ok_or("Missing value for --max-program-bytes")?;
+                max_bytes = Some(value.parse().map_err(|_| format!("Invalid value for --max-program-bytes : {value}"))?);
+            },
+            _ if arg.starts_with('-') => return Err(format!("Unknown option {arg}")),
+            _ => program_path = Some(arg.as_str()),
+        }
+    }
+
+    let program_path = program_path.ok_or("No program provided")?;
+    let code = read_program_at_most(program_path, max_bytes).map_err(|e| e.to_string())?;
+    options.color = env::var_os("NO_COLOR").is_none_or(|value| value.is_empty()) && std::io::stdout().is_terminal();
+
+    Ok(highlight::highlight(&code, options))
+}
+
+// ./sac conformance [--tape-size N] [-O0|-O1]
+fn conformance(args: &[String]) -> Result<sac::conformance::Report, String> {
+    let mut config = Config::default();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-O0" => config.opt_level = OptLevel::O0,
+            "-O1" => config.opt_level = OptLevel::O1,
+            "--tape-size" => {
+                let value = args.next().ok_or("Missing value for --tape-size")?;
+                config.tape_size = value.parse().map_err(|_| format!("Invalid value for --tape-size : {value}"))?;
+            },
+            _ => return Err(format!("Unknown option {arg}")),
+        }
+    }
+
+    Ok(sac::conformance::run(config))
+}
+
+// ./sac test DIR [--filter TEXT] [--jobs N] [--max-steps N] [--timeout-ms N]
+fn test(args: &[String]) -> Result<bool, String> {
+    use std::path::Path;
+    use std::time::Duration;
+
+    use sac::runner::{self, TestLimits};
+
+    let mut dir = None;
+    let mut filter = None;
+    let mut jobs = 1usize;
+    let mut limits = TestLimits::default();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if !arg.starts_with("--") {
+            dir = Some(arg.clone());
+            continue;
+        }
+        let value = args.next().ok_or(format!("Missing value for {arg}"))?;
+        let number = || value.parse::<u64>().map_err(|_| format!("Invalid value for {arg} : {value}"));
+        match arg.as_str() {
+            "--filter" => filter = Some(value.clone()),
+            "--jobs" => jobs = number()? as usize,
+            "--max-steps" => limits.max_steps = Some(number()?),
+            "--timeout-ms" => limits.timeout = Some(Duration::from_millis(number()?)),
+            _ => return Err(format!("Unknown option {arg}")),
+        }
+    }
+
+    let dir = dir.ok_or("Usage : ./sac test DIR [--filter TEXT] [--jobs N] [--max-steps N] [--timeout-ms N]")?;
+    let cases = runner::discover(Path::new(&dir), filter.as_deref()).map_err(|e| format!("Unable to read {dir} : {e}"))?;
+    let results = runner::run_cases(&cases, limits, jobs);
+
+    for result in &results {
+        match &result.failure {
+            None => println!("PASS {} ({:.1} ms)", result.name, result.duration.as_secs_f64() * 1000.0),
+            Some(failure) => println!("FAIL {} : {failure}", result.name),
+        }
+    }
+
+    let failed = results.iter().filter(|r| !r.passed()).count();
+    println!("\n{} passed, {failed} failed", results.len() - failed);
+
+    Ok(failed == 0)
+}
+
+// ./sac serve [--bind ADDR] [--max-source-bytes N] [--max-input-bytes N] [--max-steps N]
+//             [--max-output-bytes N] [--timeout-ms N] [--max-concurrent N]
+#[cfg(feature = "serve")]
+fn serve(args: &[String]) -> Result<(), String> {
+    use std::net::TcpListener;
+    use std::time::Duration;
+
+    use sac::serve::{self, ServeConfig};
+
+    let mut bind = String::from("127.0.0.1:8080");
+    let mut config = ServeConfig::default();
+
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        let value = args.next().ok_or(format!("Missing value for {flag}"))?;
+        let number = || value.parse::<u64>().map_err(|_| format!("Invalid value for {flag} : {value}"));
+        match flag.as_str() {
+            "--bind" => bind = value.clone(),
+            "--max-source-bytes" => config.max_source_bytes = number()? as usize,
+            "--max-input-bytes" => config.max_input_bytes = number()? as usize,
+            "--max-steps" => config.max_steps = number()?,
+            "--max-output-bytes" => config.max_output_bytes = number()? as usize,
+            "--timeout-ms" => config.max_wall_time = Duration::from_millis(number()?),
+            "--max-concurrent" => config.max_concurrent = number()? as usize,
+            _ => return Err(format!("Unknown option {flag}")),
+        }
+    }
 
-    my_interpreter.interpret();
+    let listener = TcpListener::bind(&bind).map_err(|e| format!("Unable to bind {bind} : {e}"))?;
+    eprintln!("Listening on http://{bind}");
+    serve::serve(listener, config).map_err(|e| e.to_string())
 }
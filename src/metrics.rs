@@ -0,0 +1,62 @@
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use crate::error::RuntimeError;
+
+// Counters reported by the interpreter to a `Metrics` set with `Interpreter::set_metrics`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MetricKey {
+    Steps,
+    InputBytes,
+    OutputBytes, // Once encoded, as written to the output.
+    RuntimeErrors(&'static str), // By kind, one of `RuntimeError::KINDS`.
+    ParseMicros, // Loading programs, passes included.
+    RunMicros,   // In `interpret`, `resume` and `run_for`.
+}
+
+// Sink for execution counters, such as an exporter of Prometheus metrics. Counts are reported at
+// the end of each load and run, and every million steps of long runs, never for every instruction.
+// Times are only measured with the std feature.
+pub trait Metrics: Send + Sync {
+    fn incr(&self, key: MetricKey, delta: u64);
+}
+
+// Counters in atomics, for tests and embedders that read them themselves.
+#[derive(Debug, Default)]
+pub struct AtomicMetrics {
+    steps: AtomicU64,
+    input_bytes: AtomicU64,
+    output_bytes: AtomicU64,
+    runtime_errors: [AtomicU64; RuntimeError::KINDS.len()],
+    parse_micros: AtomicU64,
+    run_micros: AtomicU64,
+}
+
+impl AtomicMetrics {
+    pub fn new() -> AtomicMetrics {
+        AtomicMetrics::default()
+    }
+
+    // `None` for error kinds missing from `RuntimeError::KINDS`.
+    fn counter(&self, key: MetricKey) -> Option<&AtomicU64> {
+        match key {
+            MetricKey::Steps => Some(&self.steps),
+            MetricKey::InputBytes => Some(&self.input_bytes),
+            MetricKey::OutputBytes => Some(&self.output_bytes),
+            MetricKey::RuntimeErrors(kind) => RuntimeError::KINDS.iter().position(|&k| k == kind).map(|index| &self.runtime_errors[index]),
+            MetricKey::ParseMicros => Some(&self.parse_micros),
+            MetricKey::RunMicros => Some(&self.run_micros),
+        }
+    }
+
+    pub fn get(&self, key: MetricKey) -> u64 {
+        self.counter(key).map_or(0, |counter| counter.load(Ordering::Relaxed))
+    }
+}
+
+impl Metrics for AtomicMetrics {
+    fn incr(&self, key: MetricKey, delta: u64) {
+        if let Some(counter) = self.counter(key) {
+            counter.fetch_add(delta, Ordering::Relaxed);
+        }
+    }
+}
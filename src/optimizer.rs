@@ -0,0 +1,413 @@
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::analysis;
+use crate::interpreter::{IRInstruction, IRInstructionKind, Program, Span};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum OptLevel {
+    O0, // Straight translation of the source, only runs of `><+-` are coalesced.
+    #[default]
+    O1,
+}
+
+impl OptLevel {
+    pub const ALL: [OptLevel; 2] = [OptLevel::O0, OptLevel::O1];
+}
+
+// A rewrite of the program, run by a `Pipeline` when the program is loaded. The program it gets is
+// valid (see `Program`), and it must leave it valid. It must not assume anything about the tape
+// the program starts on, which may have been loaded with `load_tape`, or written by the programs
+// before it in a `Program::concat`.
+pub trait Pass {
+    fn name(&self) -> &str;
+    fn run(&self, program: &mut Program) -> PassStats;
+
+    // Places of the program the pass left where it nearly applied, with why it didn't, as a hint
+    // for users wondering why their code wasn't optimized. Called with the program `run` left.
+    fn missed(&self, _program: &Program) -> Vec<MissedOptimization> {
+        Vec::new()
+    }
+}
+
+// An optimization a pass didn't apply, as in "clear loop not folded: contains I/O".
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MissedOptimization {
+    pub instruction: usize,
+    pub span: Span,
+    pub message: String,
+}
+
+impl fmt::Display for MissedOptimization {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {} (instruction {})", self.message, self.span, self.instruction)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PassStats {
+    pub rewrites: usize, // Places where the pass changed the program.
+}
+
+// What a pass did to the program, as recorded by `Pipeline::run`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PassReport {
+    pub name: String,
+    pub stats: PassStats,
+    pub instructions_before: usize,
+    pub instructions_after: usize,
+    pub missed: Vec<MissedOptimization>,
+}
+
+impl fmt::Display for PassReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} : {} rewrites, {} → {} instructions", self.name, self.stats.rewrites, self.instructions_before, self.instructions_after)
+    }
+}
+
+// Called after every pass with its report and the program it left.
+pub type PassHook = Box<dyn FnMut(&PassReport, &Program) + Send>;
+
+// Passes run in order on every loaded program. In debug builds the program is validated after
+// each pass that isn't built in, and a pass breaking it panics with its name.
+#[derive(Default)]
+pub struct Pipeline {
+    passes: Vec<(Box<dyn Pass + Send>, bool)>, // With whether the pass is built in.
+    hooks: Vec<PassHook>,
+}
+
+impl Pipeline {
+    pub fn new() -> Pipeline {
+        Pipeline::default()
+    }
+
+    // The built-in passes enabled at `level`, what the interpreter runs by default.
+    pub fn for_level(level: OptLevel) -> Pipeline {
+        let mut pipeline = Pipeline::new();
+        if level >= OptLevel::O1 {
+            pipeline.passes.push((Box::new(FoldClearLoops), true));
+            pipeline.passes.push((Box::new(FoldSetConstants), true));
+            pipeline.passes.push((Box::new(FoldIfLoops), true));
+            pipeline.passes.push((Box::new(FoldRanges), true));
+        }
+        pipeline
+    }
+
+    pub fn push(&mut self, pass: Box<dyn Pass + Send>) {
+        self.passes.push((pass, false));
+    }
+
+    pub fn on_pass<F: FnMut(&PassReport, &Program) + Send + 'static>(&mut self, hook: F) {
+        self.hooks.push(Box::new(hook));
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.passes.iter().map(|(pass, _)| pass.name()).collect()
+    }
+
+    pub fn run(&mut self, program: &mut Program) -> Vec<PassReport> {
+        self.run_with(program, |_, _| (), |_| ())
+    }
+
+    // `run`, calling `before` with the name of each pass and the length of the program it gets,
+    // and `after` with its report.
+    pub(crate) fn run_with(&mut self, program: &mut Program, mut before: impl FnMut(&str, usize), mut after: impl FnMut(&PassReport)) -> Vec<PassReport> {
+        let mut reports = Vec::with_capacity(self.passes.len());
+
+        for (pass, built_in) in &self.passes {
+            let instructions_before = program.instructions.len();
+            before(pass.name(), instructions_before);
+            let stats = pass.run(program);
+            if cfg!(debug_assertions) && !built_in {
+                if let Err(e) = program.validate() {
+                    panic!("pass {} left an invalid program : {e}", pass.name());
+                }
+            }
+            let missed = pass.missed(program);
+            let report = PassReport { name: pass.name().to_string(), stats, instructions_before, instructions_after: program.instructions.len(), missed };
+            after(&report);
+            for hook in &mut self.hooks {
+                hook(&report, program);
+            }
+            reports.push(report);
+        }
+
+        reports
+    }
+}
+
+// `[-]` and `[+]` (or any odd streak, which also reaches 0 by wrapping) become a single `SetZero`.
+pub struct FoldClearLoops;
+
+impl Pass for FoldClearLoops {
+    fn name(&self) -> &str {
+        "fold-clear-loops"
+    }
+
+    fn run(&self, program: &mut Program) -> PassStats {
+        let instructions = &program.instructions;
+        let mut folded = Vec::with_capacity(instructions.len());
+        let mut stats = PassStats::default();
+        let mut index = 0usize;
+
+        while index < instructions.len() {
+            if let [open, body, close, ..] = instructions[index..] {
+                let is_clear = open.kind == IRInstructionKind::JumpIfZero
+                    && matches!(body.kind, IRInstructionKind::IncrementByte | IRInstructionKind::DecrementByte)
+                    && body.operand.unwrap() % 2 == 1
+                    && close.kind == IRInstructionKind::JumpIfNotZero;
+
+                if is_clear {
+                    folded.push(IRInstruction { kind: IRInstructionKind::SetZero, operand: None, span: Span { end: close.span.end, ..open.span } });
+                    stats.rewrites += 1;
+                    index += 3;
+                    continue;
+                }
+            }
+
+            folded.push(instructions[index]);
+            index += 1;
+        }
+
+        program.instructions = folded;
+        program.link().expect("folding whole loops keeps brackets balanced");
+        stats
+    }
+
+    // Loops that don't move the pointer nor hold other loops, and would otherwise be clear loops.
+    fn missed(&self, program: &Program) -> Vec<MissedOptimization> {
+        let instructions = &program.instructions;
+        let mut missed = Vec::new();
+
+        'loops: for (open, inst) in instructions.iter().enumerate() {
+            if inst.kind != IRInstructionKind::JumpIfZero {
+                continue;
+            }
+            let close = inst.operand.unwrap();
+            let mut delta = 0u8;
+            let mut obstacle = None;
+            for inst in &instructions[open + 1..close] {
+                match inst.kind {
+                    IRInstructionKind::IncrementByte => delta = delta.wrapping_add(inst.operand.unwrap() as u8),
+                    IRInstructionKind::DecrementByte => delta = delta.wrapping_sub(inst.operand.unwrap() as u8),
+                    IRInstructionKind::PrintByteAsChar | IRInstructionKind::ReadInputToByte => obstacle = obstacle.or(Some("contains I/O")),
+                    IRInstructionKind::Breakpoint => obstacle = obstacle.or(Some("contains a breakpoint")),
+                    IRInstructionKind::Custom(_) => obstacle = obstacle.or(Some("contains a custom instruction")),
+                    _ => continue 'loops,
+                }
+            }
+
+            let reason = match obstacle {
+                Some(obstacle) if delta % 2 == 1 => obstacle,
+                None if delta != 0 && delta.is_multiple_of(2) => "even steps may never reach 0",
+                _ => continue,
+            };
+            let span = Span { end: instructions[close].span.end, ..inst.span };
+            missed.push(MissedOptimization { instruction: open, span, message: format!("clear loop not folded: {reason}") });
+        }
+
+        missed
+    }
+}
+
+// `SetZero` directly followed by a streak of `+` or `-` on the same cell becomes a single `SetConst`,
+// as in `[-]+++++`. A streak wrapping back to 0 leaves a `SetZero`.
+pub struct FoldSetConstants;
+
+impl Pass for FoldSetConstants {
+    fn name(&self) -> &str {
+        "fold-set-constants"
+    }
+
+    fn run(&self, program: &mut Program) -> PassStats {
+        let mut folded: Vec<IRInstruction> = Vec::with_capacity(program.instructions.len());
+        let mut stats = PassStats::default();
+
+        for &inst in &program.instructions {
+            if let Some(last) = folded.last_mut().filter(|last| last.kind == IRInstructionKind::SetZero) {
+                let value = match inst.kind {
+                    IRInstructionKind::IncrementByte => Some((inst.operand.unwrap() as u8) as usize),
+                    IRInstructionKind::DecrementByte => Some((inst.operand.unwrap() as u8).wrapping_neg() as usize),
+                    _ => None,
+                };
+                if let Some(value) = value {
+                    last.span.end = inst.span.end;
+                    if value != 0 {
+                        (last.kind, last.operand) = (IRInstructionKind::SetConst, Some(value));
+                    }
+                    stats.rewrites += 1;
+                    continue;
+                }
+            }
+            folded.push(inst);
+        }
+
+        program.instructions = folded;
+        program.link().expect("no bracket is touched");
+        stats
+    }
+}
+
+// Loops whose body ends with `SetZero` on the cell they test, as in `[>+<[-]]`, run at most once :
+// their brackets become `If` and `EndIf`, which don't test the cell again. The pointer must be back
+// on the tested cell on every path, so bodies moving it, or containing loops or custom instructions
+// that may, are left alone.
+pub struct FoldIfLoops;
+
+impl Pass for FoldIfLoops {
+    fn name(&self) -> &str {
+        "fold-if-loops"
+    }
+
+    fn run(&self, program: &mut Program) -> PassStats {
+        let displacements = analysis::displacements(&program.instructions);
+        let mut stats = PassStats::default();
+
+        for (open, displacement) in displacements.into_iter().enumerate() {
+            if program.instructions[open].kind != IRInstructionKind::JumpIfZero || displacement != Some(0) {
+                continue;
+            }
+            let close = program.instructions[open].operand.unwrap();
+            if close > open + 1 && program.instructions[close - 1].kind == IRInstructionKind::SetZero {
+                program.instructions[open].kind = IRInstructionKind::If;
+                program.instructions[close].kind = IRInstructionKind::EndIf;
+                stats.rewrites += 1;
+            }
+        }
+
+        stats
+    }
+
+    // Loops left ending with a clear are the ones moving the pointer.
+    fn missed(&self, program: &Program) -> Vec<MissedOptimization> {
+        let instructions = &program.instructions;
+        let mut missed = Vec::new();
+
+        for (open, inst) in instructions.iter().enumerate() {
+            if inst.kind != IRInstructionKind::JumpIfZero {
+                continue;
+            }
+            let close = inst.operand.unwrap();
+            if close > open + 1 && instructions[close - 1].kind == IRInstructionKind::SetZero {
+                let span = Span { end: instructions[close].span.end, ..inst.span };
+                missed.push(MissedOptimization { instruction: open, span, message: String::from("if loop not folded: the pointer may not be back on the tested cell") });
+            }
+        }
+
+        missed
+    }
+}
+
+// Shortest runs folded by `FoldRanges`.
+const MIN_RANGE: usize = 4;
+
+// Runs of cells set to the same value one after the other, as in `[-]>[-]>[-]>[-]`, become a
+// `FillRange`. Runs of `+` are left alone even at the start of the program, as the tape may have
+// been loaded with `load_tape` before it runs. Runs of transfer loops moving a block of cells, as
+// in `[-<+>]>[-<+>]>...`, become a `CopyRange`. The instructions they stand for are kept after
+// them, and run instead when the range isn't within the tape, so that errors happen where they
+// would.
+pub struct FoldRanges;
+
+impl Pass for FoldRanges {
+    fn name(&self) -> &str {
+        "fold-ranges"
+    }
+
+    fn run(&self, program: &mut Program) -> PassStats {
+        let instructions = &program.instructions;
+        let mut folded = Vec::with_capacity(instructions.len());
+        let mut stats = PassStats::default();
+        let mut index = 0usize;
+
+        while index < instructions.len() {
+            if let Some((kind, covered)) = fill_range(instructions, index).or_else(|| copy_range(&instructions[index..])) {
+                let span = Span { end: instructions[index + covered - 1].span.end, ..instructions[index].span };
+                folded.push(IRInstruction { kind, operand: Some(covered), span });
+                folded.extend_from_slice(&instructions[index..index + covered]);
+                stats.rewrites += 1;
+                index += covered;
+                continue;
+            }
+
+            folded.push(instructions[index]);
+            index += 1;
+        }
+
+        program.instructions = folded;
+        program.link().expect("ranges cover whole loops");
+        stats
+    }
+}
+
+// `FillRange` for the run starting at `index`, with the number of instructions it covers.
+fn fill_range(instructions: &[IRInstruction], index: usize) -> Option<(IRInstructionKind, usize)> {
+    // Value the instruction at `index` leaves in its cell.
+    let set = |index: usize| {
+        let inst = instructions.get(index)?;
+        match inst.kind {
+            IRInstructionKind::SetZero => Some(0),
+            IRInstructionKind::SetConst => Some(inst.operand.unwrap() as u8),
+            _ => None,
+        }
+    };
+    let step = |index: usize| instructions.get(index).filter(|inst| inst.operand == Some(1)).map(|inst| inst.kind);
+
+    let value = set(index)?;
+    let direction = step(index + 1).filter(|kind| matches!(kind, IRInstructionKind::IncrementPointer | IRInstructionKind::DecrementPointer))?;
+    let mut len = 1;
+    while step(index + 2 * len - 1) == Some(direction) && set(index + 2 * len) == Some(value) {
+        len += 1;
+    }
+
+    let start_offset = if direction == IRInstructionKind::IncrementPointer { 0 } else { 1 - len as isize };
+    (len >= MIN_RANGE).then_some((IRInstructionKind::FillRange { start_offset, len, value }, 2 * len - 1))
+}
+
+// Cells a transfer loop at the start of `instructions` adds its cell to, as in `[-<+>]` or
+// `[>>+<<-]`.
+fn transfer_loop(instructions: &[IRInstruction]) -> Option<isize> {
+    let moves = |to: &IRInstruction, back: &IRInstruction| match (to.kind, back.kind) {
+        (IRInstructionKind::IncrementPointer, IRInstructionKind::DecrementPointer) if to.operand == back.operand => Some(to.operand.unwrap() as isize),
+        (IRInstructionKind::DecrementPointer, IRInstructionKind::IncrementPointer) if to.operand == back.operand => Some(-(to.operand.unwrap() as isize)),
+        _ => None,
+    };
+    let one = |inst: &IRInstruction, kind| inst.kind == kind && inst.operand == Some(1);
+
+    match instructions {
+        [open, decrement, to, increment, back, close, ..] | [open, to, increment, back, decrement, close, ..]
+            if open.kind == IRInstructionKind::JumpIfZero
+                && close.kind == IRInstructionKind::JumpIfNotZero
+                && one(decrement, IRInstructionKind::DecrementByte)
+                && one(increment, IRInstructionKind::IncrementByte) =>
+        {
+            moves(to, back)
+        },
+        _ => None,
+    }
+}
+
+// `CopyRange` for the run of transfer loops at the start of `instructions`, each moving the pointer
+// by one cell in the same direction to the next, with the number of instructions it covers.
+fn copy_range(instructions: &[IRInstruction]) -> Option<(IRInstructionKind, usize)> {
+    let shift = transfer_loop(instructions)?;
+    let direction = match instructions.get(6) {
+        Some(inst) if inst.operand == Some(1) && inst.kind == IRInstructionKind::IncrementPointer => 1,
+        Some(inst) if inst.operand == Some(1) && inst.kind == IRInstructionKind::DecrementPointer => -1,
+        _ => return None,
+    };
+    let mut len = 1;
+    while instructions.get(7 * len - 1).is_some_and(|inst| inst.operand == Some(1) && inst.kind == instructions[6].kind) && transfer_loop(&instructions[7 * len..]) == Some(shift) {
+        len += 1;
+    }
+    // Moving cells ahead of the run would add them again to the cells further on.
+    if shift.signum() == direction {
+        len = len.min(shift.unsigned_abs());
+    }
+
+    let src_offset = if direction > 0 { 0 } else { 1 - len as isize };
+    (len >= MIN_RANGE).then_some((IRInstructionKind::CopyRange { src_offset, dst_offset: src_offset + shift, len }, 7 * len - 1))
+}
@@ -0,0 +1,81 @@
+use std::io::Cursor;
+use std::panic::{self, AssertUnwindSafe};
+
+use crate::buffer::OutputBuffer;
+use crate::interpreter::Interpreter;
+
+// In-memory interpreter meant to sit behind a browser playground : all I/O stays in buffers
+// and errors, panics included, come back as messages. This is only the Rust side, the
+// wasm-bindgen exports (a `WasmInterpreter` JavaScript class with `loadProgram`, `setInput`,
+// `run`, `takeOutput` and `memorySlice`, errors thrown as exceptions) and the `wasm` feature
+// they would go behind are not written yet.
+pub struct Playground {
+    interpreter: Interpreter,
+    output: OutputBuffer,
+}
+
+fn catch<T, F: FnOnce() -> Result<T, String>>(f: F) -> Result<T, String> {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|payload| {
+        let message = if let Some(s) = payload.downcast_ref::<&str>() {
+            s.to_string()
+        } else if let Some(s) = payload.downcast_ref::<String>() {
+            s.clone()
+        } else {
+            String::from("Unknown panic")
+        };
+        Err(format!("Interpreter panicked : {message}"))
+    })
+}
+
+impl Default for Playground {
+    fn default() -> Playground {
+        Playground::new()
+    }
+}
+
+impl Playground {
+    pub fn new() -> Playground {
+        let output = OutputBuffer::new();
+        let mut interpreter = Interpreter::new();
+        interpreter.set_input(Cursor::new(Vec::new()));
+        interpreter.set_output(output.clone());
+
+        Playground { interpreter, output }
+    }
+
+    pub fn load_program(&mut self, source: &str) -> Result<(), String> {
+        catch(|| self.interpreter.load_program_from_str(source).map_err(|e| e.to_string()))
+    }
+
+    pub fn set_input(&mut self, bytes: &[u8]) {
+        self.interpreter.set_input(Cursor::new(bytes.to_vec()));
+    }
+
+    // Runs at most `max_steps` instructions and returns "halted" when the program is over or
+    // "running" when the budget ran out first, so the page can keep calling it between frames.
+    pub fn run(&mut self, max_steps: u32) -> Result<String, String> {
+        catch(|| {
+            for _ in 0..max_steps {
+                if self.interpreter.is_halted() {
+                    break;
+                }
+                self.interpreter.step().map_err(|e| e.to_string())?;
+            }
+
+            let status = if self.interpreter.is_halted() { "halted" } else { "running" };
+            Ok(String::from(status))
+        })
+    }
+
+    pub fn take_output(&mut self) -> Vec<u8> {
+        self.output.take()
+    }
+
+    // Cells outside of the tape are left out rather than reported as an error.
+    pub fn memory_slice(&self, start: usize, len: usize) -> Vec<u8> {
+        let tape_size = self.interpreter.tape_size();
+        let start = start.min(tape_size);
+        let end = start.saturating_add(len).min(tape_size);
+        self.interpreter.peek_range(start..end).unwrap_or_default().to_vec()
+    }
+}
@@ -0,0 +1,230 @@
+use std::any::Any;
+use std::fmt;
+use std::fs;
+use std::io::{self, Cursor};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::buffer::OutputBuffer;
+use crate::cancel::CancelToken;
+use crate::error::{LoadError, RuntimeError};
+use crate::interpreter::{Config, Interpreter};
+
+// Expectation file test runner : every `NAME.bf` is fed `NAME.in` (if present) and its output is
+// compared byte for byte with `NAME.out`. When `NAME.err` exists the program must fail instead,
+// with that error message.
+
+const EXCERPT_BYTES: usize = 16;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TestCase {
+    pub name: String,
+    pub program: PathBuf,
+}
+
+impl TestCase {
+    fn sibling(&self, extension: &str) -> PathBuf {
+        self.program.with_extension(extension)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct TestLimits {
+    pub max_steps: Option<u64>,
+    pub timeout: Option<Duration>,
+}
+
+impl Default for TestLimits {
+    fn default() -> TestLimits {
+        TestLimits { max_steps: None, timeout: Some(Duration::from_secs(10)) }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct TestResult {
+    pub name: String,
+    pub failure: Option<String>,
+    pub duration: Duration,
+}
+
+impl TestResult {
+    pub fn passed(&self) -> bool {
+        self.failure.is_none()
+    }
+}
+
+// What `run_many` keeps of a run : the output and how it ended, not the tape, which can be large.
+#[derive(Debug)]
+pub struct ProgramRun {
+    pub output: Vec<u8>,
+    pub error: Option<RuntimeError>,
+}
+
+#[derive(Debug)]
+pub enum RunFailure {
+    Load(LoadError),
+    // The interpreter panicked running this program, the others are not affected.
+    Panicked(String),
+}
+
+impl fmt::Display for RunFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RunFailure::Load(e) => write!(f, "{e}"),
+            RunFailure::Panicked(message) => write!(f, "Interpreter panicked : {message}"),
+        }
+    }
+}
+
+// Finds every `*.bf` under `dir`, sorted by name, keeping those whose name contains `filter`.
+pub fn discover(dir: &Path, filter: Option<&str>) -> io::Result<Vec<TestCase>> {
+    let mut cases = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+
+    while let Some(current) = pending.pop() {
+        for entry in fs::read_dir(&current)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                pending.push(path);
+            } else if path.extension().is_some_and(|e| e == "bf") {
+                let relative = path.strip_prefix(dir).unwrap_or(&path).with_extension("");
+                let name = relative.to_string_lossy().replace('\\', "/");
+                if filter.is_none_or(|f| name.contains(f)) {
+                    cases.push(TestCase { name, program: path });
+                }
+            }
+        }
+    }
+
+    cases.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(cases)
+}
+
+pub fn run_case(case: &TestCase, limits: TestLimits) -> TestResult {
+    let start = Instant::now();
+    let failure = check(case, limits).err();
+    TestResult { name: case.name.clone(), failure, duration: start.elapsed() }
+}
+
+// Runs the cases on `jobs` threads, results keeping the order of `cases`. A case panicking fails
+// on its own.
+pub fn run_cases(cases: &[TestCase], limits: TestLimits, jobs: usize) -> Vec<TestResult> {
+    let results = parallel_map(cases, jobs, |case| run_case(case, limits));
+    cases
+        .iter()
+        .zip(results)
+        .map(|(case, result)| result.unwrap_or_else(|message| TestResult { name: case.name.clone(), failure: Some(format!("interpreter panicked : {message}")), duration: Duration::ZERO }))
+        .collect()
+}
+
+// Runs each program without input on its own interpreter, on `jobs` threads, capturing their
+// outputs. Results keep the order of `programs`. Cancelling `cancel` stops the running programs and
+// the ones not started yet with a `Cancelled` error.
+pub fn run_many(programs: &[String], config: Config, jobs: usize, cancel: &CancelToken) -> Vec<Result<ProgramRun, RunFailure>> {
+    let results = parallel_map(programs, jobs, |code| {
+        let output = OutputBuffer::new();
+        let mut interpreter = Interpreter::with_config(config);
+        interpreter.set_cancel_token(cancel.clone());
+        interpreter.set_input(Cursor::new(Vec::new()));
+        interpreter.set_output(output.clone());
+        interpreter.load_program_from_str(code).map_err(RunFailure::Load)?;
+
+        let error = interpreter.interpret().err();
+        Ok(ProgramRun { output: output.take(), error })
+    });
+    results.into_iter().map(|result| result.unwrap_or_else(|message| Err(RunFailure::Panicked(message)))).collect()
+}
+
+// Maps `f` over `items` on `jobs` threads, an item on which `f` panics giving the panic message.
+fn parallel_map<T: Sync, R: Send, F: Fn(&T) -> R + Sync>(items: &[T], jobs: usize, f: F) -> Vec<Result<R, String>> {
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new((0..items.len()).map(|_| None).collect::<Vec<_>>());
+
+    thread::scope(|scope| {
+        for _ in 0..jobs.clamp(1, items.len().max(1)) {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::SeqCst);
+                let Some(item) = items.get(index) else { break };
+                let result = panic::catch_unwind(AssertUnwindSafe(|| f(item))).map_err(panic_message);
+                results.lock().unwrap_or_else(|e| e.into_inner())[index] = Some(result);
+            });
+        }
+    });
+
+    results.into_inner().unwrap_or_else(|e| e.into_inner()).into_iter().map(Option::unwrap).collect()
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        String::from("Unknown panic")
+    }
+}
+
+fn check(case: &TestCase, limits: TestLimits) -> Result<(), String> {
+    let read = |path: PathBuf| fs::read(&path).map_err(|e| format!("Unable to read {} : {e}", path.display()));
+
+    let input = if case.sibling("in").exists() { read(case.sibling("in"))? } else { Vec::new() };
+    let expected_error = case.sibling("err").exists().then(|| read(case.sibling("err"))).transpose()?;
+    let expected_output = case.sibling("out").exists().then(|| read(case.sibling("out"))).transpose()?;
+    if expected_error.is_none() && expected_output.is_none() {
+        return Err(format!("Missing {}", case.sibling("out").display()));
+    }
+
+    let output = OutputBuffer::new();
+    let mut interpreter = Interpreter::new();
+    interpreter.set_input(Cursor::new(input));
+    interpreter.set_output(output.clone());
+    if let Some(steps) = limits.max_steps {
+        interpreter.set_step_limit(steps);
+    }
+    if let Some(timeout) = limits.timeout {
+        interpreter.set_time_limit(timeout);
+    }
+
+    let result = match interpreter.load_program(&case.program.to_string_lossy()) {
+        Ok(()) => interpreter.interpret().map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+
+    match (result, expected_error) {
+        (Err(actual), Some(expected)) if actual.trim() != String::from_utf8_lossy(&expected).trim() => {
+            return Err(format!("expected error \"{}\", got \"{actual}\"", String::from_utf8_lossy(&expected).trim()));
+        },
+        (Err(_), Some(_)) => (),
+        (Ok(()), Some(expected)) => return Err(format!("expected error \"{}\", but the program succeeded", String::from_utf8_lossy(&expected).trim())),
+        (Err(actual), None) => return Err(actual),
+        (Ok(()), None) => (),
+    }
+
+    match expected_output {
+        Some(expected) => compare(&expected, &output.take()),
+        None => Ok(()),
+    }
+}
+
+fn compare(expected: &[u8], actual: &[u8]) -> Result<(), String> {
+    let Some(offset) = expected.iter().zip(actual).position(|(e, a)| e != a).or((expected.len() != actual.len()).then(|| expected.len().min(actual.len()))) else {
+        return Ok(());
+    };
+
+    let excerpt = |bytes: &[u8]| {
+        let window = &bytes[offset.min(bytes.len())..(offset + EXCERPT_BYTES).min(bytes.len())];
+        format!("\"{}\"", window.escape_ascii())
+    };
+
+    Err(format!(
+        "output differs at byte {offset} ({} bytes expected, {} produced)\n    expected : {}\n    actual   : {}",
+        expected.len(),
+        actual.len(),
+        excerpt(expected),
+        excerpt(actual)
+    ))
+}
@@ -0,0 +1,252 @@
+use std::io::{self, BufRead, BufReader, Cursor, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::buffer::OutputBuffer;
+use crate::error::RuntimeError;
+use crate::interpreter::{Config, Interpreter, TOTAL_MEMORY_SIZE};
+use crate::json::{self, Value};
+
+// Playground server : `POST /run` with `{"source": ..., "input": ..., "options": {...}}` runs the
+// program under the limits below and answers `{"output", "status", "steps", "duration"}`, the
+// output being base64 encoded and the duration in milliseconds.
+
+const MAX_HEADER_BYTES: usize = 8 * 1024;
+const SOCKET_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Clone, Debug)]
+pub struct ServeConfig {
+    pub max_source_bytes: usize,
+    pub max_input_bytes: usize,
+    pub max_steps: u64,
+    pub max_output_bytes: usize,
+    pub max_wall_time: Duration,
+    pub max_concurrent: usize,
+    pub tape_size: usize,
+}
+
+impl Default for ServeConfig {
+    fn default() -> ServeConfig {
+        ServeConfig {
+            max_source_bytes: 64 * 1024,
+            max_input_bytes: 64 * 1024,
+            max_steps: 100_000_000,
+            max_output_bytes: 64 * 1024,
+            max_wall_time: Duration::from_secs(2),
+            max_concurrent: 4,
+            tape_size: TOTAL_MEMORY_SIZE,
+        }
+    }
+}
+
+// Serves requests until the listener fails. Connections over `max_concurrent` are turned away
+// with a 503 rather than queued.
+pub fn serve(listener: TcpListener, config: ServeConfig) -> io::Result<()> {
+    let config = Arc::new(config);
+    let active = Arc::new(AtomicUsize::new(0));
+
+    for stream in listener.incoming() {
+        let Ok(mut stream) = stream else { continue };
+        stream.set_read_timeout(Some(SOCKET_TIMEOUT))?;
+        stream.set_write_timeout(Some(SOCKET_TIMEOUT))?;
+
+        if active.fetch_add(1, Ordering::SeqCst) >= config.max_concurrent {
+            active.fetch_sub(1, Ordering::SeqCst);
+            let _ = respond(&mut stream, 503, &error_body("Too many concurrent executions"));
+            continue;
+        }
+
+        let slot = Slot(Arc::clone(&active));
+        let config = Arc::clone(&config);
+        thread::spawn(move || {
+            let _slot = slot;
+            let (status, body) = match read_request(&mut stream, &config) {
+                Ok(request) => handle(&request, &config),
+                Err((status, message)) => (status, error_body(message)),
+            };
+            let _ = respond(&mut stream, status, &body);
+        });
+    }
+
+    Ok(())
+}
+
+struct Slot(Arc<AtomicUsize>);
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+struct Request {
+    method: String,
+    path: String,
+    body: Vec<u8>,
+}
+
+type HttpError = (u16, &'static str);
+
+fn read_request(stream: &mut TcpStream, config: &ServeConfig) -> Result<Request, HttpError> {
+    // Worst case every source and input byte is escaped as `\u00XX`.
+    let max_body = 6 * (config.max_source_bytes + config.max_input_bytes) + 4096;
+
+    let mut reader = BufReader::new(stream);
+    let mut header_bytes = 0usize;
+    let mut lines = Vec::new();
+
+    loop {
+        let mut line = String::new();
+        let read = (&mut reader).take((MAX_HEADER_BYTES - header_bytes) as u64).read_line(&mut line).map_err(|_| (400, "Malformed request"))?;
+        header_bytes += read;
+        if read == 0 || !line.ends_with('\n') {
+            return Err((431, "Request headers too large"));
+        }
+        let line = line.trim_end().to_string();
+        if line.is_empty() {
+            break;
+        }
+        lines.push(line);
+    }
+
+    let mut request_line = lines.first().ok_or((400, "Malformed request"))?.split(' ');
+    let (Some(method), Some(path)) = (request_line.next(), request_line.next()) else {
+        return Err((400, "Malformed request"));
+    };
+
+    let mut content_length = 0usize;
+    for line in &lines[1..] {
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().map_err(|_| (400, "Invalid Content-Length"))?;
+            }
+        }
+    }
+    if content_length > max_body {
+        return Err((413, "Request body too large"));
+    }
+
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).map_err(|_| (400, "Truncated request body"))?;
+
+    Ok(Request { method: method.to_string(), path: path.to_string(), body })
+}
+
+fn handle(request: &Request, config: &ServeConfig) -> (u16, Value) {
+    if request.path != "/run" {
+        return (404, error_body("Not found"));
+    }
+    if request.method != "POST" {
+        return (405, error_body("Only POST is allowed"));
+    }
+
+    let Ok(text) = std::str::from_utf8(&request.body) else {
+        return (400, error_body("Request body is not UTF-8"));
+    };
+    let request = match json::parse(text) {
+        Ok(request) => request,
+        Err(_) => return (400, error_body("Request body is not valid JSON")),
+    };
+
+    let Some(source) = request.get("source").and_then(Value::as_str) else {
+        return (400, error_body("Missing `source` string"));
+    };
+    let input = match request.get("input") {
+        None | Some(Value::Null) => "",
+        Some(Value::String(input)) => input,
+        Some(_) => return (400, error_body("`input` must be a string")),
+    };
+    if source.len() > config.max_source_bytes {
+        return (413, error_body("Source too large"));
+    }
+    if input.len() > config.max_input_bytes {
+        return (413, error_body("Input too large"));
+    }
+
+    // Options can only tighten the server limits.
+    let options = request.get("options");
+    let option = |name: &str| options.and_then(|o| o.get(name)).and_then(Value::as_u64);
+    let max_steps = option("max_steps").map_or(config.max_steps, |n| n.min(config.max_steps));
+    let max_output = option("max_output_bytes").map_or(config.max_output_bytes, |n| (n as usize).min(config.max_output_bytes));
+    let max_wall_time = option("timeout_ms").map_or(config.max_wall_time, |n| Duration::from_millis(n).min(config.max_wall_time));
+
+    (200, run(source, input.as_bytes(), max_steps, max_output, max_wall_time, config.tape_size))
+}
+
+fn run(source: &str, input: &[u8], max_steps: u64, max_output: usize, max_wall_time: Duration, tape_size: usize) -> Value {
+    let start = Instant::now();
+    let output = OutputBuffer::new();
+
+    let mut interpreter = Interpreter::with_config(Config { tape_size, ..Config::default() });
+    interpreter.set_input(Cursor::new(input.to_vec()));
+    interpreter.set_output(output.clone());
+    interpreter.set_step_limit(max_steps);
+    interpreter.set_output_limit(max_output);
+    interpreter.set_time_limit(max_wall_time);
+
+    let result = match interpreter.load_program_from_str(source) {
+        Ok(()) => interpreter.interpret().map_err(|e| (status(&e), e.to_string())),
+        Err(e) => Err(("error", e.to_string())),
+    };
+
+    let mut response = vec![
+        (String::from("output"), Value::from(base64(&output.take()))),
+        (String::from("status"), Value::from(result.as_ref().map_or_else(|(status, _)| *status, |_| "ok"))),
+        (String::from("steps"), Value::from(interpreter.steps())),
+        (String::from("duration"), Value::from(start.elapsed().as_secs_f64() * 1000.0)),
+    ];
+    if let Err((_, message)) = result {
+        response.push((String::from("error"), Value::from(message)));
+    }
+
+    Value::Object(response)
+}
+
+fn status(e: &RuntimeError) -> &'static str {
+    match e {
+        RuntimeError::StepLimitExceeded { .. } => "step_limit",
+        RuntimeError::OutputLimitExceeded { .. } => "output_limit",
+        RuntimeError::TimeLimitExceeded { .. } => "time_limit",
+        _ => "error",
+    }
+}
+
+fn error_body(message: &str) -> Value {
+    Value::Object(vec![(String::from("error"), Value::from(message))])
+}
+
+fn respond(stream: &mut TcpStream, status: u16, body: &Value) -> io::Result<()> {
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        413 => "Payload Too Large",
+        431 => "Request Header Fields Too Large",
+        _ => "Service Unavailable",
+    };
+    let body = body.to_string();
+
+    write!(stream, "HTTP/1.1 {status} {reason}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}", body.len())?;
+    stream.flush()
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = (chunk[0] as u32) << 16 | (*chunk.get(1).unwrap_or(&0) as u32) << 8 | *chunk.get(2).unwrap_or(&0) as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(n >> (18 - 6 * i)) as usize & 63] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
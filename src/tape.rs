@@ -0,0 +1,164 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::{Deref, DerefMut};
+
+// Storage of the interpreter's cells. A mapped tape is an anonymous memory mapping whose pages the
+// OS only commits once a cell in them is written, so huge but sparsely used tapes stay cheap.
+// Without the mmap feature, or on platforms it doesn't support, mapped tapes fall back to the heap.
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TapeBacking {
+    #[default]
+    Heap,
+    Mapped,
+}
+
+// What `>` and `<` do when they would move the pointer off the tape. `Wrap` moves it on by as many
+// cells from the other edge, so `>` on the last cell goes to cell 0.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TapeMode {
+    // Fails with `RuntimeError::PointerOverflow` or `PointerUnderflow`.
+    #[default]
+    Error,
+    // Stops the pointer on the edge cell.
+    Saturate,
+    Wrap,
+}
+
+// What `+` and `-` do when they would take a cell past 255 or below 0.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WrapPolicy {
+    // Goes on from the other end, so `-` on 0 gives 255.
+    #[default]
+    Wrap,
+    // Fails with `RuntimeError::CellOverflow` or `CellUnderflow`, leaving the cell as it was, to
+    // catch programs that aren't meant to wrap. Programs are then loaded unoptimized, as folded
+    // loops such as `[+]` or `[->+<]` would hide the wraps they make.
+    Trap,
+}
+
+pub(crate) enum Tape {
+    Heap(Vec<u8>),
+    #[cfg(all(feature = "mmap", any(target_os = "linux", target_os = "macos")))]
+    Mapped(mapped::Mapping),
+}
+
+impl Tape {
+    pub(crate) fn new(size: usize, backing: TapeBacking) -> Tape {
+        #[cfg(all(feature = "mmap", any(target_os = "linux", target_os = "macos")))]
+        if backing == TapeBacking::Mapped {
+            // Mapping fails for empty tapes, or when the address space is exhausted.
+            if let Some(mapping) = mapped::Mapping::new(size) {
+                return Tape::Mapped(mapping);
+            }
+        }
+        #[cfg(not(all(feature = "mmap", any(target_os = "linux", target_os = "macos"))))]
+        let _ = backing;
+
+        Tape::Heap(vec![0; size])
+    }
+
+    pub(crate) fn backing(&self) -> TapeBacking {
+        match self {
+            Tape::Heap(_) => TapeBacking::Heap,
+            #[cfg(all(feature = "mmap", any(target_os = "linux", target_os = "macos")))]
+            Tape::Mapped(_) => TapeBacking::Mapped,
+        }
+    }
+
+    // Sets every cell back to 0. A mapped tape is mapped anew, giving its pages back to the OS.
+    pub(crate) fn clear(&mut self) {
+        match self {
+            Tape::Heap(cells) => cells.fill(0),
+            #[cfg(all(feature = "mmap", any(target_os = "linux", target_os = "macos")))]
+            Tape::Mapped(mapping) => *self = Tape::new(mapping.len(), TapeBacking::Mapped),
+        }
+    }
+}
+
+impl Deref for Tape {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            Tape::Heap(cells) => cells,
+            #[cfg(all(feature = "mmap", any(target_os = "linux", target_os = "macos")))]
+            Tape::Mapped(mapping) => mapping.cells(),
+        }
+    }
+}
+
+impl DerefMut for Tape {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        match self {
+            Tape::Heap(cells) => cells,
+            #[cfg(all(feature = "mmap", any(target_os = "linux", target_os = "macos")))]
+            Tape::Mapped(mapping) => mapping.cells_mut(),
+        }
+    }
+}
+
+#[cfg(all(feature = "mmap", any(target_os = "linux", target_os = "macos")))]
+mod mapped {
+    use core::ffi::{c_int, c_void};
+    use core::ptr::{self, NonNull};
+
+    const PROT_READ: c_int = 1;
+    const PROT_WRITE: c_int = 2;
+    const MAP_PRIVATE: c_int = 2;
+    #[cfg(target_os = "linux")]
+    const MAP_ANONYMOUS: c_int = 0x20 | 0x4000; // With MAP_NORESERVE, so that no swap is set aside.
+    #[cfg(target_os = "macos")]
+    const MAP_ANONYMOUS: c_int = 0x1000;
+
+    extern "C" {
+        fn mmap(addr: *mut c_void, len: usize, prot: c_int, flags: c_int, fd: c_int, offset: isize) -> *mut c_void;
+        fn munmap(addr: *mut c_void, len: usize) -> c_int;
+    }
+
+    pub(crate) struct Mapping {
+        cells: NonNull<u8>,
+        len: usize,
+    }
+
+    // SAFETY: the mapping is owned by `Mapping` alone, as a `Box<[u8]>` owns its bytes.
+    unsafe impl Send for Mapping {}
+    unsafe impl Sync for Mapping {}
+
+    impl Mapping {
+        pub(crate) fn new(len: usize) -> Option<Mapping> {
+            if len == 0 {
+                return None;
+            }
+            // SAFETY: a new private anonymous mapping doesn't alias any existing memory.
+            let address = unsafe { mmap(ptr::null_mut(), len, PROT_READ | PROT_WRITE, MAP_PRIVATE | MAP_ANONYMOUS, -1, 0) };
+            if address as isize == -1 {
+                return None;
+            }
+            NonNull::new(address.cast()).map(|cells| Mapping { cells, len })
+        }
+
+        pub(crate) fn len(&self) -> usize {
+            self.len
+        }
+
+        pub(crate) fn cells(&self) -> &[u8] {
+            // SAFETY: the mapping is `len` readable bytes, zero filled by the OS, until dropped.
+            unsafe { core::slice::from_raw_parts(self.cells.as_ptr(), self.len) }
+        }
+
+        pub(crate) fn cells_mut(&mut self) -> &mut [u8] {
+            // SAFETY: as above, and the mapping is only reachable through `self`.
+            unsafe { core::slice::from_raw_parts_mut(self.cells.as_ptr(), self.len) }
+        }
+    }
+
+    impl Drop for Mapping {
+        fn drop(&mut self) {
+            // SAFETY: the mapping was created by `new` and no slice of it outlives `self`.
+            unsafe {
+                munmap(self.cells.as_ptr().cast(), self.len);
+            }
+        }
+    }
+}
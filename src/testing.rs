@@ -0,0 +1,238 @@
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::buffer::OutputBuffer;
+use crate::interpreter::{encode_output, Interpreter};
+use crate::io::{Bell, NonAscii, PrngInput};
+
+// Support for tests of programs and of the interpreter : a helper running a program on some input,
+// and for property based tests a generator of valid programs, a shrinker and a naive reference
+// interpreter giving the expected behaviour of any program.
+
+// Output of `code` run on `input` with the default configuration. Panics when the program doesn't
+// load or fails, with the error as the message.
+pub fn run(code: &str, input: &[u8]) -> Vec<u8> {
+    let output = OutputBuffer::new();
+    let mut interpreter = Interpreter::new();
+    #[cfg(feature = "std")]
+    interpreter.set_input(std::io::Cursor::new(input.to_vec()));
+    #[cfg(not(feature = "std"))]
+    interpreter.set_input(alloc::collections::VecDeque::from(input.to_vec()));
+    interpreter.set_output(output.clone());
+
+    if let Err(e) = interpreter.load_program_from_str(code) {
+        panic!("{e}");
+    }
+    if let Err(e) = interpreter.interpret() {
+        panic!("{e}");
+    }
+    output.take()
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct ProgramGenerator {
+    pub max_length: usize,
+    pub max_depth: usize,
+    pub output_density: u64, // Percent of `.` among the generated commands.
+    pub input_density: u64,  // Percent of `,`.
+    pub clear_loop_density: u64, // Percent of `[-]`, to exercise clear loop folding.
+}
+
+impl Default for ProgramGenerator {
+    fn default() -> ProgramGenerator {
+        ProgramGenerator { max_length: 64, max_depth: 6, output_density: 8, input_density: 4, clear_loop_density: 4 }
+    }
+}
+
+impl ProgramGenerator {
+    // Balanced program of at most `max_length` commands and `max_depth` nested loops.
+    pub fn generate(&self, rng: &mut PrngInput) -> String {
+        let mut code = String::new();
+        let mut depth = 0usize;
+        let length = rng.next_u64() as usize % (self.max_length + 1);
+
+        while code.len() + depth < length {
+            let roll = rng.next_u64() % 100;
+            if roll >= 100 - self.clear_loop_density && code.len() + depth + 3 <= length {
+                code.push_str("[-]");
+                continue;
+            }
+            let c = if roll < self.output_density {
+                '.'
+            } else if roll < self.output_density + self.input_density {
+                ','
+            } else {
+                match rng.next_u64() % 8 {
+                    0 | 1 => '+',
+                    2 => '-',
+                    3 => '>',
+                    4 => '<',
+                    5 | 6 if depth < self.max_depth => '[',
+                    _ if depth > 0 => ']',
+                    _ => '+',
+                }
+            };
+
+            match c {
+                '[' => depth += 1,
+                ']' => depth -= 1,
+                _ => (),
+            }
+            code.push(c);
+        }
+
+        code.extend(core::iter::repeat_n(']', depth));
+        code
+    }
+}
+
+// Smaller balanced variants of `code`, most aggressive first : dropping a whole loop, unwrapping
+// a loop, then dropping single commands.
+pub fn shrink(code: &str) -> Vec<String> {
+    let chars: Vec<char> = code.chars().collect();
+    let mut candidates = Vec::new();
+
+    let mut stack = Vec::new();
+    let mut pairs = Vec::new();
+    for (index, &c) in chars.iter().enumerate() {
+        match c {
+            '[' => stack.push(index),
+            ']' => {
+                if let Some(open) = stack.pop() {
+                    pairs.push((open, index));
+                }
+            },
+            _ => (),
+        }
+    }
+
+    for &(open, close) in &pairs {
+        candidates.push(chars[..open].iter().chain(&chars[close + 1..]).collect());
+    }
+    for &(open, close) in &pairs {
+        candidates.push(chars.iter().enumerate().filter(|&(i, _)| i != open && i != close).map(|(_, &c)| c).collect());
+    }
+    for (index, &c) in chars.iter().enumerate() {
+        if c != '[' && c != ']' {
+            candidates.push(chars.iter().enumerate().filter(|&(i, _)| i != index).map(|(_, &c)| c).collect());
+        }
+    }
+
+    candidates
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Counterexample {
+    pub seed: u64,
+    pub case: usize,
+    pub original: String,
+    pub minimal: String,
+    pub message: String,
+}
+
+impl fmt::Display for Counterexample {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "case {} of seed {:#x} failed : {}\n  original : {}\n  minimal  : {}", self.case, self.seed, self.message, self.original, self.minimal)
+    }
+}
+
+// Checks `property` on `cases` generated programs. The first failure is shrunk to a minimal
+// program that still fails.
+pub fn check<F: FnMut(&str) -> Result<(), String>>(generator: &ProgramGenerator, seed: u64, cases: usize, mut property: F) -> Result<(), Counterexample> {
+    let mut rng = PrngInput::new(seed);
+
+    for case in 0..cases {
+        let original = generator.generate(&mut rng);
+        let Err(mut message) = property(&original) else { continue };
+
+        let mut minimal = original.clone();
+        'shrinking: loop {
+            for candidate in shrink(&minimal) {
+                if let Err(e) = property(&candidate) {
+                    (minimal, message) = (candidate, e);
+                    continue 'shrinking;
+                }
+            }
+            break;
+        }
+
+        return Err(Counterexample { seed, case, original, minimal, message });
+    }
+
+    Ok(())
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReferenceRun {
+    pub output: Vec<u8>,
+    pub tape: Vec<u8>,
+    pub failed: bool, // Pointer out of the tape or input exhausted.
+}
+
+// Executes one source character at a time, with the same semantics as `Interpreter`. Returns
+// `None` when the program takes more than `max_steps` commands.
+pub fn reference_run(code: &str, input: &[u8], tape_size: usize, max_steps: u64) -> Option<ReferenceRun> {
+    let code: Vec<char> = code.chars().filter(|c| "><+-.,[]".contains(*c)).collect();
+    let mut tape = vec![0u8; tape_size];
+    let mut output = Vec::new();
+    let (mut ip, mut pointer, mut input_position, mut steps) = (0usize, 0usize, 0usize, 0u64);
+    let mut failed = false;
+
+    while ip < code.len() {
+        steps += 1;
+        if steps > max_steps {
+            return None;
+        }
+
+        match code[ip] {
+            '>' if pointer + 1 < tape_size => pointer += 1,
+            '<' if pointer > 0 => pointer -= 1,
+            '>' | '<' => {
+                failed = true;
+                break;
+            },
+            '+' => tape[pointer] = tape[pointer].wrapping_add(1),
+            '-' => tape[pointer] = tape[pointer].wrapping_sub(1),
+            '.' => output.extend_from_slice(encode_output(tape[pointer], NonAscii::Utf8, Bell::PassThrough, &mut [0; 4])),
+            ',' => match input.get(input_position) {
+                Some(&byte) => {
+                    tape[pointer] = byte;
+                    input_position += 1;
+                },
+                None => {
+                    failed = true;
+                    break;
+                },
+            },
+            '[' if tape[pointer] == 0 => ip = matching(&code, ip)?,
+            ']' if tape[pointer] != 0 => ip = matching(&code, ip)?,
+            _ => (),
+        }
+        ip += 1;
+    }
+
+    Some(ReferenceRun { output, tape, failed })
+}
+
+fn matching(code: &[char], from: usize) -> Option<usize> {
+    let forward = code[from] == '[';
+    let mut depth = 0isize;
+    let mut index = from;
+
+    loop {
+        match code[index] {
+            '[' => depth += 1,
+            ']' => depth -= 1,
+            _ => (),
+        }
+        if depth == 0 {
+            return Some(index);
+        }
+        index = if forward { index + 1 } else { index.checked_sub(1)? };
+        if index >= code.len() {
+            return None;
+        }
+    }
+}
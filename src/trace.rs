@@ -0,0 +1,140 @@
+use alloc::vec::Vec;
+
+use crate::error::TraceError;
+
+// Execution traces : the index of every instruction a run went through, in order, and the input bytes
+// it read, recorded by `Interpreter::set_trace_recording` and run again by `Interpreter::replay`.
+//
+// Format, version 1 : `SACTRACE`, the version as a little endian u32, the SHA-256 of the program
+// listing, the number of steps and of input bytes as little endian u64, the input bytes, then one
+// LEB128 varint per step : the zigzag encoded distance from the instruction after the previous step,
+// so straight-line code takes a byte per step.
+
+const MAGIC: &[u8] = b"SACTRACE";
+pub const VERSION: u32 = 1;
+const HEADER_LENGTH: usize = MAGIC.len() + 4 + 32 + 2 * 8;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Trace {
+    pub(crate) program_hash: [u8; 32],
+    steps: u64,
+    encoded: Vec<u8>,
+    input: Vec<u8>,
+}
+
+// Trace of the run so far, kept by the interpreter.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Recorder {
+    steps: u64,
+    encoded: Vec<u8>,
+    input: Vec<u8>,
+    next: usize, // Instruction after the previous step.
+}
+
+impl Recorder {
+    pub(crate) fn step(&mut self, instruction: usize) {
+        let distance = instruction as i64 - self.next as i64;
+        let mut zigzag = ((distance << 1) ^ (distance >> 63)) as u64;
+        while zigzag >= 0x80 {
+            self.encoded.push(zigzag as u8 | 0x80);
+            zigzag >>= 7;
+        }
+        self.encoded.push(zigzag as u8);
+        self.next = instruction + 1;
+        self.steps += 1;
+    }
+
+    pub(crate) fn input(&mut self, byte: u8) {
+        self.input.push(byte);
+    }
+
+    pub(crate) fn finish(self, program_hash: [u8; 32]) -> Trace {
+        Trace { program_hash, steps: self.steps, encoded: self.encoded, input: self.input }
+    }
+}
+
+impl Trace {
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    // Indices of the instructions run, in order.
+    pub fn instructions(&self) -> impl Iterator<Item = usize> + '_ {
+        let mut bytes = self.encoded.iter();
+        let mut next = 0usize;
+        core::iter::from_fn(move || {
+            let mut zigzag = 0u64;
+            let mut shift = 0;
+            loop {
+                let byte = *bytes.next()?;
+                zigzag |= ((byte & 0x7f) as u64) << shift;
+                shift += 7;
+                if byte & 0x80 == 0 || shift >= 64 {
+                    break;
+                }
+            }
+            let distance = (zigzag >> 1) as i64 ^ -((zigzag & 1) as i64);
+            let instruction = (next as i64).wrapping_add(distance) as usize;
+            next = instruction.wrapping_add(1);
+            Some(instruction)
+        })
+    }
+
+    // Bytes the run read, in order.
+    pub fn input(&self) -> &[u8] {
+        &self.input
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_LENGTH + self.input.len() + self.encoded.len());
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&VERSION.to_le_bytes());
+        bytes.extend_from_slice(&self.program_hash);
+        bytes.extend_from_slice(&self.steps.to_le_bytes());
+        bytes.extend_from_slice(&(self.input.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(&self.input);
+        bytes.extend_from_slice(&self.encoded);
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Trace, TraceError> {
+        if !bytes.starts_with(MAGIC) {
+            return Err(TraceError::NotATrace);
+        }
+        let header = bytes.get(..HEADER_LENGTH).ok_or(TraceError::Truncated)?;
+        let version = u32::from_le_bytes(header[MAGIC.len()..MAGIC.len() + 4].try_into().unwrap());
+        if version != VERSION {
+            return Err(TraceError::UnsupportedVersion { version });
+        }
+        let field = |index: usize| {
+            let start = MAGIC.len() + 4 + 32 + index * 8;
+            u64::from_le_bytes(header[start..start + 8].try_into().unwrap())
+        };
+        let (steps, input_length) = (field(0), field(1));
+
+        let rest = &bytes[HEADER_LENGTH..];
+        let input = rest.get(..input_length as usize).ok_or(TraceError::Truncated)?;
+        let trace = Trace {
+            program_hash: header[MAGIC.len() + 4..MAGIC.len() + 4 + 32].try_into().unwrap(),
+            steps,
+            encoded: rest[input.len()..].to_vec(),
+            input: input.to_vec(),
+        };
+        let ends = trace.encoded.last().is_none_or(|byte| byte & 0x80 == 0);
+        if !ends || trace.encoded.iter().filter(|byte| *byte & 0x80 == 0).count() as u64 != steps {
+            return Err(TraceError::Truncated);
+        }
+        Ok(trace)
+    }
+
+    #[cfg(feature = "std")]
+    pub fn save(&self, path: &str) -> std::io::Result<()> {
+        std::fs::write(path, self.to_bytes())
+    }
+
+    #[cfg(feature = "std")]
+    pub fn load(path: &str) -> std::io::Result<Trace> {
+        let bytes = std::fs::read(path)?;
+        Trace::from_bytes(&bytes).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, alloc::format!("{path} : {e}")))
+    }
+}
@@ -0,0 +1,121 @@
+use std::fmt;
+use std::io::Cursor;
+use std::mem;
+
+use crate::buffer::OutputBuffer;
+use crate::error::{LoadError, RuntimeError};
+use crate::interpreter::{Config, Interpreter};
+use crate::optimizer::OptLevel;
+
+// Differential execution : the same program and input run once without optimizations and once
+// with the configured level must print the same bytes, leave the same tape and halt the same way.
+
+pub struct Execution {
+    pub output: Vec<u8>,
+    pub tape: Vec<u8>,
+    pub error: Option<RuntimeError>,
+}
+
+// Loads and runs `code` on an already configured interpreter, capturing its output.
+pub fn execute(interpreter: &mut Interpreter, code: &str, input: &[u8]) -> Result<Execution, LoadError> {
+    let output = OutputBuffer::new();
+    interpreter.set_input(Cursor::new(input.to_vec()));
+    interpreter.set_output(output.clone());
+    interpreter.load_program_from_str(code)?;
+
+    let error = interpreter.interpret().err();
+    let tape = interpreter.peek_range(0..interpreter.tape_size()).unwrap().to_vec();
+
+    Ok(Execution { output: output.take(), tape, error })
+}
+
+pub struct Divergence {
+    pub opt_level: OptLevel,
+    pub reference: Execution,
+    pub optimized: Execution,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (reference, optimized, level) = (&self.reference, &self.optimized, self.opt_level);
+
+        if let Some(offset) = first_difference(&reference.output, &optimized.output) {
+            let byte = |bytes: &[u8]| bytes.get(offset).map_or(String::from("nothing"), |b| format!("{b:#04x}"));
+            return write!(f, "Outputs differ at byte {offset} (O0 : {}, {level:?} : {})", byte(&reference.output), byte(&optimized.output));
+        }
+        if let Some(cell) = first_difference(&reference.tape, &optimized.tape) {
+            return write!(f, "Tapes differ at cell {cell} (O0 : {}, {level:?} : {})", reference.tape[cell], optimized.tape[cell]);
+        }
+
+        let status = |error: &Option<RuntimeError>| error.as_ref().map_or(String::from("halted"), ToString::to_string);
+        write!(f, "Halt statuses differ (O0 : {}, {level:?} : {})", status(&reference.error), status(&optimized.error))
+    }
+}
+
+impl fmt::Debug for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+#[derive(Debug)]
+pub enum VerifyError {
+    Load(LoadError),
+    Diverged(Box<Divergence>),
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerifyError::Load(e) => write!(f, "{e}"),
+            VerifyError::Diverged(divergence) => write!(f, "Miscompilation detected : {divergence}"),
+        }
+    }
+}
+
+impl std::error::Error for VerifyError {}
+
+// Runs `code` at `O0` and at `config.opt_level`, returning the optimized execution when both agree.
+pub fn verify(code: &str, input: &[u8], config: Config) -> Result<Execution, VerifyError> {
+    let reference = execute(&mut Interpreter::with_config(Config { opt_level: OptLevel::O0, ..config }), code, input).map_err(VerifyError::Load)?;
+    let optimized = execute(&mut Interpreter::with_config(config), code, input).map_err(VerifyError::Load)?;
+
+    if agree(&reference, &optimized) {
+        Ok(optimized)
+    } else {
+        Err(VerifyError::Diverged(Box::new(Divergence { opt_level: config.opt_level, reference, optimized })))
+    }
+}
+
+// Runs `code` at every optimization level with the default configuration, failing on the first
+// level that disagrees with `O0`.
+pub fn run_all_opt_levels(code: &str, input: &[u8]) -> Result<(), VerifyError> {
+    let run = |opt_level| execute(&mut Interpreter::with_config(Config { opt_level, ..Config::default() }), code, input).map_err(VerifyError::Load);
+    let reference = run(OptLevel::O0)?;
+
+    for opt_level in OptLevel::ALL.into_iter().filter(|&level| level != OptLevel::O0) {
+        let optimized = run(opt_level)?;
+        if !agree(&reference, &optimized) {
+            return Err(VerifyError::Diverged(Box::new(Divergence { opt_level, reference, optimized })));
+        }
+    }
+
+    Ok(())
+}
+
+fn agree(reference: &Execution, optimized: &Execution) -> bool {
+    same_halt(&reference.error, &optimized.error) && reference.output == optimized.output && reference.tape == optimized.tape
+}
+
+// Instruction indices change with optimizations, so errors are compared by kind and source span.
+fn same_halt(reference: &Option<RuntimeError>, optimized: &Option<RuntimeError>) -> bool {
+    match (reference, optimized) {
+        (None, None) => true,
+        (Some(a), Some(b)) => mem::discriminant(a) == mem::discriminant(b) && a.span() == b.span(),
+        _ => false,
+    }
+}
+
+fn first_difference(a: &[u8], b: &[u8]) -> Option<usize> {
+    a.iter().zip(b).position(|(x, y)| x != y).or((a.len() != b.len()).then(|| a.len().min(b.len())))
+}
@@ -0,0 +1,253 @@
+use sac::analysis::{self, BasicBlock, BlockEdge, BoundsWarning, Edge, EdgeKind, Lint, LintWarning};
+use sac::interpreter::{Config, Interpreter, Span};
+use sac::io::OnEndOfInput;
+use sac::optimizer::OptLevel;
+
+fn warnings(code: &str, tape_size: usize) -> Vec<BoundsWarning> {
+    let mut interpreter = Interpreter::with_config(Config { tape_size, ..Config::default() });
+    interpreter.load_program_from_str(code).unwrap();
+    analysis::pointer_bounds(&interpreter)
+}
+
+#[test]
+fn definite_underflow_is_reported() {
+    assert_eq!(warnings("+>+<.\n<<.", 100), vec![BoundsWarning {
+        instruction: 5,
+        span: Span { start: 6, end: 8, line: 2, column: 1 },
+        edge: Edge::Left,
+        definite: true,
+    }]);
+}
+
+#[test]
+fn definite_overflow_depends_on_the_tape_size() {
+    let found = warnings(">>[-]>>", 4);
+    assert_eq!(found.len(), 1);
+    assert_eq!((found[0].instruction, found[0].edge, found[0].definite), (2, Edge::Right, true));
+    assert!(found[0].to_string().starts_with("The memory pointer will move past the end of the tape at line 1, column 6"));
+
+    assert!(warnings(">>[-]>>", 5).is_empty());
+}
+
+#[test]
+fn balanced_programs_are_clean() {
+    assert!(warnings("++++++++[>++++[>++>+++<<-]>+<<-]>>.>.<<", 100).is_empty());
+    assert!(warnings("+>+>+<[<]>[>]<[-<+>]", 100).is_empty());
+}
+
+#[test]
+fn loop_bodies_only_may_fail() {
+    let found = warnings(",[<.>-]", 100);
+    assert_eq!(found.len(), 1);
+    assert_eq!((found[0].instruction, found[0].edge, found[0].definite), (2, Edge::Left, false));
+    assert!(found[0].to_string().starts_with("The memory pointer may move left of cell 0"));
+
+    // After an unbalanced loop the pointer is known on one side only.
+    assert_eq!(warnings(",[>]<<", 100).len(), 1);
+}
+
+#[test]
+fn unknowable_loops_are_not_reported() {
+    // Scanning left stops on a zero cell that only exists at runtime.
+    assert!(warnings("+>+>+>,[<]<", 100).is_empty());
+    assert!(warnings(">,[[<]>-]<", 100).is_empty());
+}
+
+// Lints and their source offset, which must not depend on the optimization level.
+fn lints(code: &str, allow: &[Lint]) -> Vec<(Lint, usize)> {
+    let found = [OptLevel::O0, OptLevel::O1].map(|opt_level| {
+        let mut interpreter = Interpreter::with_config(Config { opt_level, ..Config::default() });
+        interpreter.load_program_from_str(code).unwrap();
+        analysis::lint(&interpreter, allow).into_iter().map(|w| (w.lint, w.span.start)).collect::<Vec<_>>()
+    });
+    assert_eq!(found[0], found[1], "{code}");
+    found[1].clone()
+}
+
+// Each case triggers its lint once, at the given source offset, and nothing else once allowed.
+#[test]
+fn each_lint_triggers_and_can_be_allowed() {
+    let cases = [
+        (Lint::EmptyLoop, ",[]", 1),
+        (Lint::DeadLoop, ",[>][.>]", 4),
+        (Lint::DeadStore, ">++[-]<", 1),
+        (Lint::DeadStore, "+++,.", 0),
+        (Lint::InvariantLoop, ",[>+<.]", 1),
+        (Lint::UnreachableCode, "+[>-<]\n>.", 7),
+        (Lint::WrappedProgram, "[+++]", 0),
+        (Lint::WrappedProgram, "\n[>,[.,]<]", 1),
+    ];
+
+    for (lint, code, offset) in cases {
+        let found = lints(code, &[]);
+        let expected: Vec<_> = found.iter().filter(|(l, _)| *l == lint).copied().collect();
+        assert_eq!(expected, vec![(lint, offset)], "{code}");
+        assert!(lints(code, &[lint]).iter().all(|(l, _)| *l != lint), "{code}");
+    }
+}
+
+#[test]
+fn read_loops_hang_when_the_end_of_input_leaves_them_going() {
+    let warned = |code: &str, on_end_of_input| {
+        let mut interpreter = Interpreter::with_config(Config { on_end_of_input, ..Config::default() });
+        interpreter.load_program_from_str(code).unwrap();
+        interpreter.set_input(std::collections::VecDeque::new());
+        analysis::lint(&interpreter, &[]).into_iter().filter(|w| w.lint == Lint::EndOfInputLoop).map(|w| w.span.start).collect::<Vec<_>>()
+    };
+
+    assert_eq!(warned(",[.,]", OnEndOfInput::Unchanged), [1]);
+    assert_eq!(warned(",[.,]", OnEndOfInput::MinusOne), [1]);
+    assert_eq!(warned(",[.,]", OnEndOfInput::Zero), []);
+    assert_eq!(warned(",[.,]", OnEndOfInput::Error), []);
+    assert_eq!(warned("+[>+<.,]", OnEndOfInput::Unchanged), [1]);
+    // Counting down before reading ends the loop with an unchanged cell, but not with 255.
+    assert_eq!(warned(",[-,]", OnEndOfInput::Unchanged), []);
+    assert_eq!(warned(",[-,]", OnEndOfInput::MinusOne), [1]);
+    // Reading another cell, or a loop inside, leaves the cell to the rest of the body.
+    assert_eq!(warned(",[>,<]", OnEndOfInput::Unchanged), []);
+    assert_eq!(warned(",[[>]<,]", OnEndOfInput::MinusOne), []);
+
+    let mut interpreter = Interpreter::with_config(Config { on_end_of_input: OnEndOfInput::Unchanged, ..Config::default() });
+    interpreter.load_program_from_str(",[.,]").unwrap();
+    assert_eq!(analysis::lint(&interpreter, &[])[0].to_string(), "Loop at line 1, column 2 (instruction 1) waits for a 0 from the input, so it never ends if the input runs out first [end-of-input-loop]");
+}
+
+#[test]
+fn lints_leave_common_idioms_alone() {
+    // Comment loop at the start, clear loops, scans and moves.
+    for code in ["[comment, with punctuation.]+++[->+<]>[-]<", ",[.,]", "+>+>+<[<]>[>]", "++[>++[>+<-]<-]>>."] {
+        assert_eq!(lints(code, &[]), vec![], "{code}");
+    }
+    assert_eq!(lints("[]", &[]), vec![]); // Never entered.
+}
+
+#[test]
+fn lint_warnings_name_the_lint() {
+    let mut interpreter = Interpreter::new();
+    interpreter.load_program_from_str("+[]").unwrap();
+    let warnings = analysis::lint(&interpreter, &[]);
+
+    assert_eq!(warnings[0], LintWarning { lint: Lint::EmptyLoop, instruction: 1, span: Span { start: 1, end: 3, line: 1, column: 2 } });
+    assert_eq!(warnings[0].to_string(), "Empty loop at line 1, column 2 (instruction 1) never ends once entered [empty-loop]");
+    assert_eq!(Lint::from_name("dead-store"), Some(Lint::DeadStore));
+    assert_eq!(Lint::from_name("dead_store"), None);
+}
+
+#[cfg(feature = "std")]
+fn analyze_fixture(name: &str) -> analysis::Analysis {
+    let mut interpreter = Interpreter::new();
+    interpreter.load_program(&format!("{}/tests/fixtures/{name}.bf", env!("CARGO_MANIFEST_DIR"))).unwrap();
+    analysis::analyze(&interpreter)
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn analyze_profiles_fixture_programs() {
+    let nested = analyze_fixture("nested_loops");
+    assert_eq!(nested.source_bytes, 74);
+    assert_eq!(nested.commands, vec![('>', 11), ('<', 7), ('+', 37), ('-', 5), ('.', 2), (',', 0), ('[', 5), (']', 5)]);
+    assert_eq!((nested.command_count(), nested.instruction_count()), (72, 39));
+    assert_eq!(nested.instructions[..2], [("IncrementByte", 9), ("JumpIfZero", 5)]);
+    assert_eq!((nested.loops, nested.max_depth, nested.longest_run), (5, 2, 8));
+    assert_eq!((nested.inputs, nested.outputs), (0, 2));
+    assert_eq!(nested.reach, (Some(0), Some(5)));
+
+    let cat = analyze_fixture("cat");
+    assert_eq!((cat.source_bytes, cat.command_count(), cat.instruction_count()), (6, 5, 5));
+    assert_eq!((cat.loops, cat.max_depth, cat.longest_run), (1, 1, 0));
+    assert_eq!((cat.inputs, cat.outputs), (2, 1));
+    assert_eq!(cat.reach, (Some(0), Some(0)));
+
+    let wrapping = analyze_fixture("wrapping");
+    assert_eq!(wrapping.longest_run, 256);
+    assert_eq!(wrapping.reach, (Some(0), Some(1)));
+}
+
+#[test]
+fn analyze_counts_folded_loops_and_unknown_reach() {
+    let mut interpreter = Interpreter::new();
+    interpreter.load_program_from_str("+++[-]>,[>]").unwrap();
+    let profile = analysis::analyze(&interpreter);
+
+    assert_eq!(profile.instructions, vec![("IncrementByte", 1), ("SetZero", 1), ("IncrementPointer", 2), ("ReadInputToByte", 1), ("JumpIfZero", 1), ("JumpIfNotZero", 1)]);
+    assert_eq!((profile.command_count(), profile.loops), (11, 1));
+    assert_eq!(profile.reach, (Some(0), None));
+}
+
+#[test]
+fn analyze_lists_labels_and_warns_about_duplicates() {
+    let mut interpreter = Interpreter::new();
+    interpreter.load_program_from_str("@label: init\n+\n@label: body\n[-]\n@label: init\n.").unwrap();
+    let profile = analysis::analyze(&interpreter);
+    assert_eq!(profile.labels.iter().map(|label| (label.name.as_str(), label.span.line)).collect::<Vec<_>>(), [("body", 3), ("init", 5)]);
+
+    let duplicates = analysis::duplicate_labels(&interpreter);
+    assert_eq!(duplicates.len(), 1);
+    assert_eq!(duplicates[0].to_string(), "Label init at line 1, column 1 is defined again at line 5, column 1, this definition is ignored");
+}
+
+// Reading then printing each byte until it runs out, inside an outer loop, so that nothing is folded.
+const NESTED_CFG: &str = r##"digraph cfg {
+    node [shape=box, fontname="monospace"];
+    start [shape=oval];
+    end [shape=oval];
+    b0 [label="#0..2 at 1:1\lIncrementByte 1\lJumpIfZero -> 10\l"];
+    b1 [label="#2..5 at 1:3\lIncrementPointer 1\lReadInputToByte\lJumpIfZero -> 7\l"];
+    b2 [label="#5..8 at 1:6\lPrintByteAsChar\lDecrementByte 1\lJumpIfNotZero -> 4\l"];
+    b3 [label="#8..11 at 1:9\lDecrementPointer 1\lDecrementByte 1\lJumpIfNotZero -> 1\l"];
+    start -> b0;
+    b0 -> end [label="zero"];
+    b0 -> b1 [label="nonzero"];
+    b1 -> b3 [label="zero"];
+    b1 -> b2 [label="nonzero"];
+    b2 -> b3 [label="zero"];
+    b2 -> b2 [label="nonzero", style=dashed, color="#1f77b4"];
+    b3 -> end [label="zero"];
+    b3 -> b1 [label="nonzero", style=dashed, color="#1f77b4"];
+}
+"##;
+
+// Checks the subset of the dot language `control_flow_dot` writes : node and edge statements, with
+// quoted strings that are closed and escaped.
+fn assert_valid_dot(dot: &str) {
+    let body = dot.strip_prefix("digraph cfg {\n").and_then(|body| body.strip_suffix("}\n")).expect(dot);
+    for statement in body.lines() {
+        let statement = statement.trim().strip_suffix(';').expect(statement);
+        let (target, attributes) = match statement.split_once(" [") {
+            Some((target, attributes)) => (target, Some(attributes.strip_suffix(']').expect(statement))),
+            None => (statement, None),
+        };
+        let ids: Vec<&str> = target.split(" -> ").collect();
+        assert!(ids.len() <= 2 && ids.iter().all(|id| !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric())), "{statement}");
+        let Some(attributes) = attributes else { continue };
+        let (mut quoted, mut escaped) = (false, false);
+        for c in attributes.chars() {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' if quoted => escaped = true,
+                '"' => quoted = !quoted,
+                _ => (),
+            }
+        }
+        assert!(!quoted, "{statement}");
+    }
+}
+
+#[test]
+fn control_flow_graphs_split_blocks_at_jumps() {
+    let mut interpreter = Interpreter::new();
+    interpreter.load_program_from_str("+[>,[.-]<-]").unwrap();
+    let dot = analysis::control_flow_dot(&interpreter, None);
+    assert_eq!(dot, NESTED_CFG);
+    assert_valid_dot(&dot);
+
+    // Ranges may skip the instructions they stand for, and blocks get shaded by their runs.
+    interpreter.load_program_from_str("[-]+>[-]+>[-]+>[-]+.").unwrap();
+    let blocks = analysis::basic_blocks(&interpreter.to_program().instructions);
+    let edge = |to, kind| BlockEdge { to, kind, back: false };
+    assert_eq!(blocks[0], BasicBlock { start: 0, end: 1, edges: vec![edge(Some(1), EdgeKind::Fallthrough), edge(Some(2), EdgeKind::Skip)] });
+    let runs = vec![1; interpreter.to_program().instructions.len()];
+    let dot = analysis::control_flow_dot(&interpreter, Some(&runs));
+    assert!(dot.contains("runs : 1\\l\", style=filled, fillcolor=\"#ff3737\""), "{dot}");
+    assert_valid_dot(&dot);
+}
@@ -0,0 +1,277 @@
+#![cfg(feature = "async")]
+
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::{self, Thread};
+use std::time::Duration;
+
+use sac::asynchronous::{AsyncInputSource, AsyncOutputSink};
+use sac::cancel::CancelToken;
+use sac::error::RuntimeError;
+use sac::interpreter::Interpreter;
+use sac::io::IoError;
+
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+// Tiny executor returning the future's output along with how many times it was polled.
+fn block_on<F: Future>(future: F) -> (F::Output, usize) {
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    let mut future = pin!(future);
+    let mut polls = 0;
+
+    loop {
+        polls += 1;
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return (output, polls);
+        }
+        thread::park();
+    }
+}
+
+// One direction of an in-memory duplex stream: reads wait until bytes are pushed or the
+// writer closes it.
+#[derive(Clone, Default)]
+struct Pipe(Arc<Mutex<PipeState>>);
+
+#[derive(Default)]
+struct PipeState {
+    bytes: VecDeque<u8>,
+    closed: bool,
+    waker: Option<Waker>,
+}
+
+impl Pipe {
+    fn push(&self, bytes: &[u8]) {
+        let mut state = self.0.lock().unwrap();
+        state.bytes.extend(bytes);
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+    }
+
+    fn close(&self) {
+        let mut state = self.0.lock().unwrap();
+        state.closed = true;
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+    }
+
+    fn contents(&self) -> Vec<u8> {
+        self.0.lock().unwrap().bytes.iter().copied().collect()
+    }
+}
+
+struct ReadByte<'a>(&'a Pipe);
+
+impl Future for ReadByte<'_> {
+    type Output = Result<Option<u8>, IoError>;
+
+    fn poll(self: std::pin::Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.0 .0.lock().unwrap();
+        match state.bytes.pop_front() {
+            Some(byte) => Poll::Ready(Ok(Some(byte))),
+            None if state.closed => Poll::Ready(Ok(None)),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            },
+        }
+    }
+}
+
+impl AsyncInputSource for Pipe {
+    fn read_byte(&mut self) -> impl Future<Output = Result<Option<u8>, IoError>> {
+        ReadByte(self)
+    }
+}
+
+impl AsyncOutputSink for Pipe {
+    async fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), IoError> {
+        self.push(bytes);
+        Ok(())
+    }
+}
+
+// Sink cancelling the run once it received `limit` bytes.
+struct CancelAfter {
+    token: CancelToken,
+    limit: usize,
+    received: usize,
+}
+
+impl AsyncOutputSink for CancelAfter {
+    async fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), IoError> {
+        self.received += bytes.len();
+        if self.received >= self.limit {
+            self.token.cancel();
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn echoes_over_duplex_streams() {
+    let mut interpreter = Interpreter::new();
+    interpreter.load_program_from_str(",[.,]").unwrap();
+
+    let (mut input, mut output) = (Pipe::default(), Pipe::default());
+    let writer = input.clone();
+    let feeder = thread::spawn(move || {
+        for chunk in [&b"hel"[..], b"lo", b"\0"] {
+            thread::sleep(Duration::from_millis(10));
+            writer.push(chunk);
+        }
+    });
+
+    let (result, _) = block_on(interpreter.interpret_async(&mut input, &mut output, 16));
+    feeder.join().unwrap();
+
+    result.unwrap();
+    assert_eq!(output.contents(), b"hello");
+}
+
+#[test]
+fn runs_on_another_thread() {
+    let mut interpreter = Interpreter::new();
+    interpreter.load_program_from_str(",[.,]").unwrap();
+
+    let (mut input, mut output) = (Pipe::default(), Pipe::default());
+    input.push(b"spawned\0");
+    let future = interpreter.interpret_async(&mut input, &mut output, 16);
+    // The future is `Send`, so that runtimes can move it to their worker threads.
+    let (result, _) = thread::scope(|scope| scope.spawn(|| block_on(future)).join().unwrap());
+
+    result.unwrap();
+    assert_eq!(output.contents(), b"spawned");
+}
+
+#[test]
+fn runs_like_interpret() {
+    use sac::buffer::OutputBuffer;
+    use sac::io::InvalidUtf8;
+
+    type Setup = fn(&mut Interpreter);
+    let cases: [(&str, &[u8], Setup); 4] = [
+        (",[.,]", "h\u{e9}llo\0".as_bytes(), |interpreter| interpreter.set_utf8_io(Some(InvalidUtf8::Replace))),
+        ("+[]", b"", |interpreter| interpreter.set_hang_detection(Some(64))),
+        ("++++++++[>++++++++<-]>+[.]", b"", |interpreter| {
+            interpreter.set_output_buffer_size(8);
+            interpreter.set_step_limit(200);
+        }),
+        (",.,.,.", b"ab", |interpreter| interpreter.set_history_limit(4)),
+    ];
+
+    for (code, bytes, setup) in cases {
+        let mut interpreter = Interpreter::new();
+        setup(&mut interpreter);
+        interpreter.load_program_from_str(code).unwrap();
+        let printed = OutputBuffer::new();
+        interpreter.set_input(std::io::Cursor::new(bytes.to_vec()));
+        interpreter.set_output(printed.clone());
+        let expected = (format!("{:?}", interpreter.interpret()), printed.take(), interpreter.steps());
+
+        let mut interpreter = Interpreter::new();
+        setup(&mut interpreter);
+        interpreter.load_program_from_str(code).unwrap();
+        let (input, mut output) = (Pipe::default(), Pipe::default());
+        input.push(bytes);
+        input.close();
+        let (result, _) = block_on(interpreter.interpret_async(&mut input.clone(), &mut output, 16));
+        assert_eq!((format!("{result:?}"), output.contents(), interpreter.steps()), expected, "{code}");
+    }
+}
+
+#[test]
+fn yields_periodically_in_busy_loops() {
+    let mut interpreter = Interpreter::new();
+    interpreter.load_program_from_str("++++++++[>++++++++[>++++++++[--]<-]<-]").unwrap();
+
+    let (mut input, mut output) = (Pipe::default(), Pipe::default());
+    let (result, polls) = block_on(interpreter.interpret_async(&mut input, &mut output, 10));
+
+    result.unwrap();
+    assert!(polls > 50, "only polled {polls} times");
+}
+
+#[test]
+fn cancellation_stops_a_running_loop() {
+    let token = CancelToken::new();
+    let mut interpreter = Interpreter::new();
+    interpreter.set_cancel_token(token.clone());
+    interpreter.load_program_from_str("+[.]").unwrap();
+
+    let mut output = CancelAfter { token, limit: 10, received: 0 };
+    let (result, _) = block_on(interpreter.interpret_async(&mut Pipe::default(), &mut output, 16));
+
+    assert!(matches!(result, Err(RuntimeError::Cancelled { .. })));
+    assert_eq!(output.received, 10);
+}
+
+#[test]
+fn cancellation_interrupts_a_pending_read() {
+    let token = CancelToken::new();
+    let mut interpreter = Interpreter::new();
+    interpreter.set_cancel_token(token.clone());
+    interpreter.load_program_from_str(",").unwrap();
+
+    let canceller = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(20));
+        token.cancel();
+    });
+
+    let (result, _) = block_on(interpreter.interpret_async(&mut Pipe::default(), &mut Pipe::default(), 16));
+    canceller.join().unwrap();
+
+    assert!(matches!(result, Err(RuntimeError::Cancelled { instruction: 0, .. })));
+}
+
+// Sink whose writes never complete, as a peer that stopped reading.
+struct Stalled;
+
+impl AsyncOutputSink for Stalled {
+    fn write_bytes(&mut self, _: &[u8]) -> impl Future<Output = Result<(), IoError>> {
+        std::future::pending()
+    }
+}
+
+#[test]
+fn cancellation_interrupts_a_pending_write() {
+    let token = CancelToken::new();
+    let mut interpreter = Interpreter::new();
+    interpreter.set_cancel_token(token.clone());
+    interpreter.load_program_from_str("+.").unwrap();
+
+    let canceller = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(20));
+        token.cancel();
+    });
+
+    let (result, _) = block_on(interpreter.interpret_async(&mut Pipe::default(), &mut Stalled, 16));
+    canceller.join().unwrap();
+
+    assert!(matches!(result, Err(RuntimeError::Cancelled { .. })));
+}
+
+#[test]
+fn end_of_input_matches_the_blocking_interpreter() {
+    let mut interpreter = Interpreter::new();
+    interpreter.load_program_from_str(",,").unwrap();
+
+    let input = Pipe::default();
+    input.push(b"x");
+    input.close();
+
+    let (result, _) = block_on(interpreter.interpret_async(&mut input.clone(), &mut Pipe::default(), 16));
+    assert!(matches!(result, Err(RuntimeError::EndOfInput { instruction: 1, .. })));
+}
@@ -0,0 +1,60 @@
+use sac::builder::ProgramBuilder;
+use sac::error::BuildError;
+use sac::interpreter::{IRInstructionKind, Interpreter};
+use sac::optimizer::{OptLevel, Pipeline};
+
+// Cell 2 = cell 0 × cell 1, cell 1 being restored through cell 3 on each round.
+fn multiplication(a: i32, b: i32) -> ProgramBuilder {
+    let mut builder = ProgramBuilder::new();
+    builder.add(a).move_ptr(1).add(b).move_ptr(-1);
+    let rounds = builder.loop_start();
+    builder.move_ptr(1);
+    let copy = builder.loop_start();
+    builder.move_ptr(1).add(1).move_ptr(1).add(1).move_ptr(-2).add(-1);
+    builder.loop_end(copy).move_ptr(2);
+    let restore = builder.loop_start();
+    builder.move_ptr(-2).add(1).move_ptr(2).add(-1);
+    builder.loop_end(restore).move_ptr(-3).add(-1);
+    builder.loop_end(rounds);
+    builder
+}
+
+#[test]
+fn built_programs_run_through_the_optimizer_and_interpreter() {
+    let mut program = multiplication(6, 7).build().unwrap();
+    assert_eq!(program.instructions[4].kind, IRInstructionKind::JumpIfZero);
+    assert_eq!(program.instructions[4].operand, Some(program.instructions.len() - 1));
+    let unoptimized = program.clone();
+    Pipeline::for_level(OptLevel::O1).run(&mut program);
+    assert_eq!(program.validate(), Ok(()));
+
+    for program in [&unoptimized, &program] {
+        let mut interpreter = Interpreter::new();
+        interpreter.load_ir(program).unwrap();
+        interpreter.interpret().unwrap();
+        assert_eq!(interpreter.peek_range(0..4).unwrap(), [0, 7, 42, 0]);
+    }
+
+    // Amounts wrap, and moves of 0 are left out.
+    let mut builder = ProgramBuilder::new();
+    builder.add(300).move_ptr(0).add(-257).set_zero();
+    let program = builder.build().unwrap();
+    let operands: Vec<_> = program.instructions.iter().map(|inst| (inst.kind, inst.operand)).collect();
+    assert_eq!(operands, [(IRInstructionKind::IncrementByte, Some(44)), (IRInstructionKind::DecrementByte, Some(1)), (IRInstructionKind::SetZero, None)]);
+}
+
+#[test]
+fn loops_must_be_closed_innermost_first() {
+    let mut builder = ProgramBuilder::new();
+    builder.add(1);
+    let outer = builder.loop_start();
+    let _inner = builder.loop_start();
+    builder.loop_end(outer);
+    assert_eq!(builder.build(), Err(BuildError::MisnestedLoop { instruction: 1 }));
+
+    let mut builder = ProgramBuilder::new();
+    let _outer = builder.loop_start();
+    let inner = builder.loop_start();
+    builder.loop_end(inner);
+    assert_eq!(builder.build(), Err(BuildError::UnclosedLoop { instruction: 0 }));
+}
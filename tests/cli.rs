@@ -0,0 +1,650 @@
+#![cfg(feature = "std")]
+
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+fn sac(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_sac")).args(args).output().unwrap()
+}
+
+fn program(name: &str, code: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("sac-cli-{name}-{}.bf", std::process::id()));
+    std::fs::write(&path, code).unwrap();
+    path
+}
+
+#[test]
+fn disasm_prints_the_optimized_listing() {
+    let path = program("disasm", "+++[-]");
+
+    let output = sac(&["--disasm", path.to_str().unwrap()]);
+    assert!(output.status.success());
+    let listing = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = listing.lines().collect();
+    assert_eq!(lines.len(), 2, "{listing}");
+    assert!(lines[0].starts_with("     0  IncrementByte 3"), "{listing}");
+    assert!(lines[1].starts_with("     1  SetZero"), "{listing}");
+
+    let output = sac(&["--disasm", "-O0", path.to_str().unwrap()]);
+    let listing = String::from_utf8(output.stdout).unwrap();
+    assert!(listing.contains("JumpIfZero -> 3") && !listing.contains("SetZero"), "{listing}");
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn check_analyze_prints_bounds_warnings() {
+    let path = program("analyze", "+[>]<<\n<<");
+
+    let output = sac(&["check", "--analyze", path.to_str().unwrap()]);
+    assert!(output.status.success());
+    let warnings = String::from_utf8(output.stderr).unwrap();
+    assert_eq!(warnings.lines().count(), 1, "{warnings}");
+    assert!(warnings.starts_with("[WARNING] The memory pointer may move left of cell 0 at line 1, column 5"), "{warnings}");
+
+    let output = sac(&["check", path.to_str().unwrap()]);
+    assert!(output.status.success() && output.stderr.is_empty());
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn check_prints_lints_unless_allowed() {
+    let path = program("lint", "+[]");
+
+    let output = sac(&["check", path.to_str().unwrap()]);
+    assert!(output.status.success());
+    assert!(String::from_utf8(output.stderr).unwrap().ends_with("[empty-loop]\n"));
+
+    let output = sac(&["check", "--lint", "--allow", "empty-loop", path.to_str().unwrap()]);
+    assert!(output.status.success() && output.stderr.is_empty());
+
+    let output = sac(&["check", "--allow", "no-such-lint", path.to_str().unwrap()]);
+    assert!(!output.status.success());
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn analyze_prints_text_or_json() {
+    let path = program("analyze-profile", "++[>,.<-]");
+
+    let output = sac(&["analyze", path.to_str().unwrap()]);
+    assert!(output.status.success());
+    let report = String::from_utf8(output.stdout).unwrap();
+    assert!(report.contains("loops: 1, maximum depth 1\n"), "{report}");
+    assert!(report.contains("input: 1 `,` (interactive), output: 1 `.`\n"), "{report}");
+    assert!(report.contains("pointer reach: cells 0 to 1\n"), "{report}");
+    assert!(report.contains("inferred tape size: 18 cells\n"), "{report}");
+    assert!(report.ends_with("labels: none\n"), "{report}");
+
+    let output = sac(&["analyze", "--json", path.to_str().unwrap()]);
+    let report = sac::json::parse(String::from_utf8(output.stdout).unwrap().trim()).unwrap();
+    assert_eq!(report.get("command_count").and_then(|v| v.as_u64()), Some(9));
+    assert_eq!(report.get("reach").and_then(|r| r.get("high")).and_then(|v| v.as_u64()), Some(1));
+    assert_eq!(report.get("inferred_tape_size").and_then(|v| v.as_u64()), Some(18));
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn diff_compares_two_programs() {
+    let old = program("diff-old", ",[.,]");
+    let new = program("diff-new", ",[.,]");
+    let input = program("diff-input", "abc");
+    let [old, new, input] = [&old, &new, &input].map(|p| p.to_str().unwrap());
+
+    // Both programs run out of input the same way.
+    let output = sac(&["diff", "--input", input, old, new]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "identical (3 bytes)\n");
+
+    std::fs::write(new, ",[+.,]").unwrap();
+    let output = sac(&["diff", "--input", input, old, new]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stdout).unwrap().starts_with("Outputs differ at byte 0 : expected 0x61, got 0x62"));
+
+    for path in [old, new, input] {
+        std::fs::remove_file(path).unwrap();
+    }
+}
+
+#[test]
+fn encode_prints_a_program_printing_the_text() {
+    for style in ["naive", "loops"] {
+        let output = sac(&["encode", "--style", style, "Hi\n\u{e9}"]);
+        assert!(output.status.success());
+        let path = program(&format!("encode-{style}"), &String::from_utf8(output.stdout).unwrap());
+
+        let output = sac(&[path.to_str().unwrap()]);
+        assert_eq!(output.stdout, "Hi\n\u{e9}".as_bytes());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    let text = program("encode-text", "nul\0and\nnewline");
+    let output = sac(&["encode", "--from-file", text.to_str().unwrap()]);
+    let path = program("encode-from-file", &String::from_utf8(output.stdout).unwrap());
+    assert_eq!(sac(&[path.to_str().unwrap()]).stdout, b"nul\0and\nnewline");
+    std::fs::remove_file(text).unwrap();
+    std::fs::remove_file(path).unwrap();
+
+    let output = sac(&["encode", "\u{20ac}"]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn highlight_is_plain_when_not_a_terminal() {
+    let path = program("highlight", "+[-]\n. done");
+
+    let output = sac(&["highlight", path.to_str().unwrap()]);
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"+[-]\n. done");
+
+    let output = sac(&["highlight", "--line-numbers", path.to_str().unwrap()]);
+    assert_eq!(output.stdout, b"1 | +[-]\n2 | . done");
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn non_ascii_cells_print_as_placeholders() {
+    let path = program("non-ascii", "-.+++++++++++++++++++++++++++++++++.");
+
+    assert_eq!(sac(&[path.to_str().unwrap()]).stdout, "\u{ff}\u{20}".as_bytes());
+    assert_eq!(sac(&["--non-ascii", ".", path.to_str().unwrap()]).stdout, b". ");
+    assert_eq!(sac(&["--non-ascii", "escape", path.to_str().unwrap()]).stdout, b"\\xff ");
+    assert!(!sac(&["--non-ascii", "\u{e9}", path.to_str().unwrap()]).status.success());
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn explain_prints_an_outline() {
+    let path = program("explain", "++++++++[>+++++++++<-]>.");
+
+    let output = sac(&["explain", "--depth", "0", path.to_str().unwrap()]);
+    assert!(output.status.success());
+    let outline = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(outline.lines().count(), 4, "{outline}");
+    assert_eq!(outline.lines().last(), Some("print cell 1 ('H')"));
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn many_programs_print_in_order() {
+    let paths: Vec<PathBuf> = [("a", "++++++++[>++++++++<-]>+."), ("b", "<"), ("c", "++++++++[>++++++++<-]>++."), ("d", "++++++++[>++++++++<-]>+++.")]
+        .into_iter()
+        .map(|(name, code)| program(&format!("many-{name}"), code))
+        .collect();
+    let mut args = vec!["--jobs", "4"];
+    args.extend(paths.iter().map(|p| p.to_str().unwrap()));
+
+    let output = sac(&args);
+    assert!(!output.status.success());
+    assert_eq!(output.stdout, b"ABC");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains(&format!("[ERROR] {} : Memory pointer underflow", paths[1].display())), "{stderr}");
+    assert!(stderr.contains("1 of 4 programs failed"), "{stderr}");
+
+    args.remove(3);
+    assert!(sac(&args).status.success());
+
+    for path in paths {
+        std::fs::remove_file(path).unwrap();
+    }
+}
+
+#[test]
+fn output_hash_prints_the_digest_of_the_output() {
+    let hello_world = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/hello_world.bf");
+    let sha256 = "03ba204e50d126e4674c005e04d82e84c21366780af1f43bd54a37816b6ab340";
+
+    let output = sac(&["--output-hash", "sha256", hello_world]);
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"Hello World!\n");
+    assert_eq!(String::from_utf8(output.stderr).unwrap(), format!("sha256 {sha256} (13 bytes)\n"));
+
+    let output = sac(&["--output-hash", "crc32", "--quiet", hello_world]);
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+    assert_eq!(String::from_utf8(output.stderr).unwrap(), "crc32 7d14dddd (13 bytes)\n");
+
+    let output = sac(&["--quiet", "--expect-hash", sha256, hello_world]);
+    assert!(output.status.success());
+}
+
+#[test]
+fn output_hash_mismatch_fails() {
+    let hello_world = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/hello_world.bf");
+
+    let output = sac(&["--output-hash", "crc32", "--expect-hash", "DEADBEEF", "--quiet", hello_world]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("[ERROR] Output hash mismatch : expected deadbeef, got 7d14dddd !"), "{stderr}");
+}
+
+#[cfg(unix)]
+#[test]
+fn interrupt_stops_the_program_and_saves_the_tape() {
+    use std::io::Read;
+    use std::process::Stdio;
+
+    let path = program("interrupt", "++++++++[>++++++++<-]>+.[]");
+    let dump = std::env::temp_dir().join(format!("sac-cli-interrupt-{}.tape", std::process::id()));
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_sac"))
+        .args(["--dump-tape-on-interrupt", dump.to_str().unwrap(), path.to_str().unwrap()])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    // The `A` is printed right before the endless loop.
+    let mut first = [0u8; 1];
+    child.stdout.as_mut().unwrap().read_exact(&mut first).unwrap();
+    assert_eq!(&first, b"A");
+
+    let kill = Command::new("kill").args(["-INT", &child.id().to_string()]).status().unwrap();
+    assert!(kill.success());
+    let output = child.wait_with_output().unwrap();
+
+    assert_eq!(output.status.code(), Some(130));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    // The interrupt lands on either bracket of `[]`.
+    assert!(["10", "11"].iter().any(|at| stderr.contains(&format!("[WARNING] Interrupted at instruction {at}, memory pointer at cell 1"))), "{stderr}");
+    assert_eq!(std::fs::read(&dump).unwrap(), b"SACTAPE1\x02\0\0\0\0\0\0\0\0A");
+
+    std::fs::remove_file(path).unwrap();
+    std::fs::remove_file(dump).unwrap();
+}
+
+// The listings left by each pass of the default pipeline on `tests/fixtures/clear_cells.bf` are kept
+// in `clear_cells.passes`. Run with `SAC_UPDATE_GOLDENS=1` to rewrite it.
+#[test]
+fn dump_ir_after_pass_matches_its_snapshot() {
+    let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let fixture = dir.join("clear_cells.bf");
+    let snapshot = dir.join("clear_cells.passes");
+
+    let output = sac(&["--dump-ir-after-pass", "all", fixture.to_str().unwrap()]);
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"A\nD\n");
+    let dumps = String::from_utf8(output.stderr).unwrap();
+    if std::env::var_os("SAC_UPDATE_GOLDENS").is_some() {
+        std::fs::write(&snapshot, &dumps).unwrap();
+    }
+    assert_eq!(dumps, std::fs::read_to_string(&snapshot).unwrap());
+
+    let output = sac(&["--dump-ir-after-pass", "fold-set-constants", "--disasm", fixture.to_str().unwrap()]);
+    let dump = String::from_utf8(output.stderr).unwrap();
+    assert!(dump.starts_with("; fold-set-constants : 3 rewrites, 31 → 28 instructions\n"), "{dump}");
+    assert_eq!(dump, format!("{}\n\n", dumps.split("\n\n").nth(1).unwrap()));
+
+    let dump_dir = std::env::temp_dir().join(format!("sac-cli-dump-{}", std::process::id()));
+    let output = sac(&["--dump-ir-after-pass", "all", "--dump-dir", dump_dir.to_str().unwrap(), fixture.to_str().unwrap()]);
+    assert!(output.stderr.is_empty());
+    let first = std::fs::read_to_string(dump_dir.join("01-fold-clear-loops.ir")).unwrap();
+    assert!(first.starts_with("; fold-clear-loops : 4 rewrites, 39 → 31 instructions\n"), "{first}");
+    assert!(dump_dir.join("02-fold-set-constants.ir").exists());
+    std::fs::remove_dir_all(dump_dir).unwrap();
+
+    let output = sac(&["--dump-ir-after-pass", "fold-everything", fixture.to_str().unwrap()]);
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("Unknown pass fold-everything, the passes at this level are : fold-clear-loops, fold-set-constants, fold-if-loops, fold-ranges"), "{stderr}");
+}
+
+#[test]
+fn reads_give_up_after_the_input_timeout() {
+    use std::process::Stdio;
+
+    let path = program("input-timeout", "+.,.");
+    let run = |extra: &[&str]| {
+        // Stdin stays open without anything written to it until the program exited.
+        let mut child = Command::new(env!("CARGO_BIN_EXE_sac"))
+            .args(["--input-timeout", "100ms"])
+            .args(extra)
+            .arg(&path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let stdin = child.stdin.take();
+        let output = child.wait_with_output().unwrap();
+        drop(stdin);
+        (output.stdout, String::from_utf8(output.stderr).unwrap())
+    };
+
+    let (stdout, stderr) = run(&[]);
+    assert_eq!(stdout, b"\x01");
+    assert!(stderr.contains("No input left to read at line 1, column 3"), "{stderr}");
+
+    let (stdout, stderr) = run(&["--on-input-timeout", "error"]);
+    assert_eq!(stdout, b"\x01");
+    assert!(stderr.contains("No input arrived in time at line 1, column 3"), "{stderr}");
+
+    let output = sac(&["--on-input-timeout", "eof", path.to_str().unwrap()]);
+    assert!(String::from_utf8(output.stderr).unwrap().contains("--on-input-timeout only works with --input-timeout"));
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn compare_against_reports_where_the_output_diverged() {
+    let path = program("compare", "++++++++[>++++++++<-]>+.\n+.\n+.");
+    let expected = std::env::temp_dir().join(format!("sac-cli-compare-{}.out", std::process::id()));
+
+    std::fs::write(&expected, "ABC").unwrap();
+    let output = sac(&["--compare-against", expected.to_str().unwrap(), path.to_str().unwrap()]);
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"ABC");
+
+    std::fs::write(&expected, "AXC").unwrap();
+    let output = sac(&["--compare-against", expected.to_str().unwrap(), path.to_str().unwrap()]);
+    assert!(!output.status.success());
+    assert_eq!(output.stdout, b"AB");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert_eq!(stderr, "[ERROR] Output differs at byte 1 : expected 0x58, got 0x42, printed at line 2, column 2 !\n");
+
+    std::fs::write(&expected, "XXCD").unwrap();
+    let output = sac(&["--compare-against", expected.to_str().unwrap(), "--compare-keep-going", path.to_str().unwrap()]);
+    assert_eq!(output.stdout, b"ABC");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert_eq!(stderr.lines().count(), 4, "{stderr}");
+    assert!(stderr.contains("expected 0x44, got end of output !\n[ERROR] 3 mismatches with"), "{stderr}");
+
+    std::fs::remove_file(path).unwrap();
+    std::fs::remove_file(expected).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn killed_runs_resume_from_their_last_checkpoint() {
+    use std::io::Read;
+    use std::process::Stdio;
+
+    // Echoes its input, spending about 40 000 steps on each byte.
+    let path = program("checkpoint", ",[.>++++++++++++++++++++[>++++++++++++++++++++[>++++++++++++++++++++[>+<-]<-]<-]<,]");
+    let input = std::env::temp_dir().join(format!("sac-cli-checkpoint-{}.in", std::process::id()));
+    let checkpoint = std::env::temp_dir().join(format!("sac-cli-checkpoint-{}.ckpt", std::process::id()));
+    std::fs::write(&input, "The quick brown fox jumps over the lazy dog\0").unwrap();
+    let spawn = |extra: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_sac"))
+            .args(["--checkpoint", checkpoint.to_str().unwrap(), "--checkpoint-every", "100000"])
+            .args(extra)
+            .arg(&path)
+            .stdin(std::fs::File::open(&input).unwrap())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap()
+    };
+
+    let full = Command::new(env!("CARGO_BIN_EXE_sac")).arg(&path).stdin(std::fs::File::open(&input).unwrap()).output().unwrap().stdout;
+    assert_eq!(full, b"The quick brown fox jumps over the lazy dog");
+
+    // Killed as a crash would, a few checkpoints in.
+    let mut child = spawn(&[]);
+    let mut killed = vec![0u8; 8];
+    child.stdout.as_mut().unwrap().read_exact(&mut killed).unwrap();
+    child.kill().unwrap();
+    child.stdout.as_mut().unwrap().read_to_end(&mut killed).unwrap();
+    child.wait().unwrap();
+    assert!(checkpoint.exists() && killed.len() < full.len());
+
+    let resumed = spawn(&["--resume", checkpoint.to_str().unwrap()]).wait_with_output().unwrap();
+    assert!(resumed.status.success(), "{}", String::from_utf8_lossy(&resumed.stderr));
+    // The bytes printed after the checkpoint are printed again.
+    let replayed = full.len() - resumed.stdout.len();
+    assert!(replayed <= killed.len());
+    assert_eq!([&killed[..replayed], &resumed.stdout[..]].concat(), full);
+
+    // Checkpoints only resume the program they were saved for.
+    let other = program("checkpoint-other", ",[.,]");
+    let output = sac(&["--resume", checkpoint.to_str().unwrap(), other.to_str().unwrap()]);
+    assert!(String::from_utf8(output.stderr).unwrap().contains("was saved for another program"));
+
+    for file in [path, other, input, checkpoint] {
+        std::fs::remove_file(file).unwrap();
+    }
+}
+
+#[test]
+fn detect_hangs_names_the_spinning_loop() {
+    let path = program("detect-hangs", "++.\n>+[<>]");
+
+    let output = sac(&["--detect-hangs", path.to_str().unwrap()]);
+    assert!(!output.status.success());
+    assert_eq!(output.stdout, b"\x02");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("Loop starting at line 2, column 3 (instruction 4) never ends : no progress possible, state repeats every 3 steps"), "{stderr}");
+
+    let output = sac(&["--detect-hangs", "--verify", path.to_str().unwrap()]);
+    assert!(String::from_utf8(output.stderr).unwrap().contains("can't be used with --verify"));
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn utf8_io_echoes_multi_byte_characters() {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let path = program("utf8-io", ",[.,]");
+    let cat = |args: &[&str], input: &[u8]| {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_sac")).args(args).arg(&path).stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::piped()).spawn().unwrap();
+        child.stdin.take().unwrap().write_all(input).unwrap();
+        child.wait_with_output().unwrap()
+    };
+
+    let output = cat(&["--utf8-io"], "Grüße, 世界 🦀\0".as_bytes());
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(output.stdout, "Grüße, 世界 🦀".as_bytes());
+    // Without it, each byte is printed as a code point of its own.
+    assert_eq!(cat(&[], "ü\0".as_bytes()).stdout, "Ã¼".as_bytes());
+
+    let output = cat(&["--utf8-io", "--utf8-strict"], b"ok\xc3(\0");
+    assert_eq!(output.stdout, b"ok");
+    assert!(String::from_utf8(output.stderr).unwrap().contains("Invalid UTF-8 in the input read at line 1, column 4 (instruction 3)"));
+
+    let output = sac(&["--utf8-strict", path.to_str().unwrap()]);
+    assert!(String::from_utf8(output.stderr).unwrap().contains("--utf8-strict only works with --utf8-io"));
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn recorded_traces_replay_without_input() {
+    let path = program("trace", ">,[>,]<[.<]");
+    let trace = std::env::temp_dir().join(format!("sac-cli-trace-{}.trace", std::process::id()));
+    let input = std::env::temp_dir().join(format!("sac-cli-trace-{}.in", std::process::id()));
+    std::fs::write(&input, "stressed\0").unwrap();
+
+    let recorded = Command::new(env!("CARGO_BIN_EXE_sac"))
+        .args(["--record-trace", trace.to_str().unwrap(), path.to_str().unwrap()])
+        .stdin(std::fs::File::open(&input).unwrap())
+        .output()
+        .unwrap();
+    assert!(recorded.status.success());
+    assert_eq!(recorded.stdout, b"desserts");
+
+    let replayed = Command::new(env!("CARGO_BIN_EXE_sac")).args(["--replay-trace", trace.to_str().unwrap(), path.to_str().unwrap()]).stdin(std::process::Stdio::null()).output().unwrap();
+    assert!(replayed.status.success(), "{}", String::from_utf8_lossy(&replayed.stderr));
+    assert_eq!(replayed.stdout, b"desserts");
+
+    let other = program("trace-other", "+[>,]");
+    let output = sac(&["--replay-trace", trace.to_str().unwrap(), other.to_str().unwrap()]);
+    assert!(String::from_utf8(output.stderr).unwrap().contains("The trace was recorded for another program"));
+
+    for file in [path, other, trace, input] {
+        std::fs::remove_file(file).unwrap();
+    }
+}
+
+// Runs `program` with a pseudo-terminal as stdin, typing `keys` without Enter once it started, and
+// returns its output and whether the terminal is back in canonical mode afterwards.
+#[cfg(target_os = "linux")]
+fn run_on_terminal(args: &[&str], program: &std::path::Path, keys: &[u8]) -> (Output, bool) {
+    use std::ffi::{c_char, c_int, CStr};
+    use std::fs::{File, OpenOptions};
+    use std::io::Write;
+    use std::os::fd::{AsRawFd, FromRawFd};
+    use std::process::Stdio;
+    use std::time::{Duration, Instant};
+
+    const O_RDWR: c_int = 0o2;
+    const O_NOCTTY: c_int = 0o400;
+    const ICANON: u32 = 0o2;
+
+    extern "C" {
+        fn posix_openpt(flags: c_int) -> c_int;
+        fn grantpt(fd: c_int) -> c_int;
+        fn unlockpt(fd: c_int) -> c_int;
+        fn ptsname(fd: c_int) -> *const c_char;
+        fn tcgetattr(fd: c_int, termios: *mut [u32; 15]) -> c_int;
+    }
+
+    let (mut master, terminal) = unsafe {
+        let fd = posix_openpt(O_RDWR | O_NOCTTY);
+        assert!(fd >= 0 && grantpt(fd) == 0 && unlockpt(fd) == 0);
+        let name = CStr::from_ptr(ptsname(fd)).to_str().unwrap().to_owned();
+        (File::from_raw_fd(fd), OpenOptions::new().read(true).write(true).open(name).unwrap())
+    };
+    let canonical = |terminal: &File| {
+        let mut termios = [0u32; 15];
+        assert_eq!(unsafe { tcgetattr(terminal.as_raw_fd(), &mut termios) }, 0);
+        termios[3] & ICANON != 0
+    };
+    assert!(canonical(&terminal));
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_sac")).args(args).arg(program).stdin(terminal.try_clone().unwrap()).stdout(Stdio::piped()).stderr(Stdio::piped()).spawn().unwrap();
+    std::thread::sleep(Duration::from_millis(300));
+    master.write_all(keys).unwrap();
+
+    // Without raw mode the keys wait for an Enter that never comes.
+    let start = Instant::now();
+    while child.try_wait().unwrap().is_none() {
+        if start.elapsed() > Duration::from_secs(10) {
+            child.kill().unwrap();
+            panic!("the keys never reached the program");
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    (child.wait_with_output().unwrap(), canonical(&terminal))
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn raw_input_reads_keys_at_once_and_restores_the_terminal() {
+    let echo = program("raw-input", ",.,.");
+    let (output, restored) = run_on_terminal(&["--raw-input"], &echo, b"ab");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(output.stdout, b"ab");
+    assert!(restored);
+
+    // Also after an error.
+    let failing = program("raw-input-error", ",<<");
+    let (output, restored) = run_on_terminal(&["--raw-input"], &failing, b"a");
+    assert!(String::from_utf8(output.stderr).unwrap().contains("Memory pointer underflow"));
+    assert!(restored);
+
+    std::fs::remove_file(echo).unwrap();
+    std::fs::remove_file(failing).unwrap();
+}
+
+#[test]
+fn coverage_reports_the_loop_never_entered() {
+    let path = program("coverage", "+>[-\n.]<.");
+    let lcov = std::env::temp_dir().join(format!("sac-cli-coverage-{}.info", std::process::id()));
+
+    let output = sac(&["--coverage", "--coverage-annotate", "--coverage-out", lcov.to_str().unwrap(), path.to_str().unwrap()]);
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"\x01");
+    let report = String::from_utf8(output.stderr).unwrap();
+    assert!(report.starts_with("Coverage : 5 of 8 instructions run (62.5 %)\nNot run : line 1, column 4 : -\n"), "{report}");
+    assert!(report.ends_with("+>[-\n   ^\n.]<.\n^^\n"), "{report}");
+    assert!(std::fs::read_to_string(&lcov).unwrap().contains("DA:1,1\nDA:2,1\n"));
+
+    std::fs::remove_file(path).unwrap();
+    std::fs::remove_file(lcov).unwrap();
+}
+
+#[test]
+fn analyze_writes_the_control_flow_graph_shaded_by_a_profile() {
+    let path = program("cfg", "+>[-\n.]<.");
+    let temp = |name: &str| std::env::temp_dir().join(format!("sac-cli-cfg-{}.{name}", std::process::id()));
+    let (lcov, dot) = (temp("info"), temp("dot"));
+
+    assert!(sac(&["--coverage-out", lcov.to_str().unwrap(), path.to_str().unwrap()]).status.success());
+    let output = sac(&["analyze", "--cfg", dot.to_str().unwrap(), "--profile-in", lcov.to_str().unwrap(), path.to_str().unwrap()]);
+    assert!(output.status.success());
+    let dot = std::fs::read_to_string(&dot).unwrap();
+    assert!(dot.starts_with("digraph cfg {\n"), "{dot}");
+    assert!(dot.contains("b1 -> b1 [label=\"nonzero\", style=dashed"), "{dot}");
+    assert!(dot.contains("runs : 1\\l\", style=filled"), "{dot}");
+
+    let output = sac(&["analyze", "--profile-in", lcov.to_str().unwrap(), path.to_str().unwrap()]);
+    assert_eq!(output.stderr, b"[ERROR] --profile-in needs --cfg !\n");
+
+    std::fs::remove_file(path).unwrap();
+    std::fs::remove_file(lcov).unwrap();
+    std::fs::remove_file(temp("dot")).unwrap();
+}
+
+#[test]
+fn shebang_lines_are_ignored() {
+    let path = program("shebang", "#!/usr/bin/env -S sac --compat classic30k +++[.-]\n++++++++[>++++++++<-]>+.");
+    let output = sac(&[path.to_str().unwrap()]);
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"A");
+
+    let path = program("shebang", "#!/usr/bin/sac\n<");
+    let output = sac(&[path.to_str().unwrap()]);
+    assert!(String::from_utf8(output.stderr).unwrap().contains("at line 2, column 1"));
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn check_warns_about_read_loops_under_the_profile_end_of_input() {
+    let path = program("eof-loop", ",[.,]");
+    let path = path.to_str().unwrap();
+
+    assert_eq!(sac(&["check", path]).stderr, b"");
+    let output = sac(&["check", "--compat", "unbounded", path]);
+    assert!(output.status.success());
+    let warnings = String::from_utf8(output.stderr).unwrap();
+    assert!(warnings.starts_with("[WARNING] Loop at line 1, column 2 (instruction 1) waits for a 0 from the input"), "{warnings}");
+    assert_eq!(sac(&["check", "--compat", "unbounded", "--allow", "end-of-input-loop", path]).stderr, b"");
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn check_reports_missed_optimizations() {
+    let path = program("missed", "+[-.]");
+
+    let output = sac(&["check", "--missed-optimizations", path.to_str().unwrap()]);
+    assert!(output.status.success());
+    let warnings = String::from_utf8(output.stderr).unwrap();
+    assert_eq!(warnings, "[WARNING] clear loop not folded: contains I/O at line 1, column 2 (instruction 1)\n");
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn every_command_reading_a_program_honors_the_size_limit() {
+    let path = program("limit", "++++++++[>++++++++<-]>+.");
+    let path = path.to_str().unwrap();
+
+    let mut commands = vec![vec![path], vec!["--verify", path], vec!["diff", path, path], vec!["highlight", path]];
+    if cfg!(feature = "sarif") {
+        commands.push(vec!["check", "--format", "sarif", path]);
+    }
+    for mut args in commands {
+        // The limit goes right before the (last) program.
+        args.splice(args.len() - 1..args.len() - 1, ["--max-program-bytes", "8"]);
+        let output = sac(&args);
+        assert_eq!(output.stderr, b"[ERROR] The program is larger than 8 bytes !\n", "{args:?}");
+    }
+    assert!(sac(&["highlight", "--max-program-bytes", "64", path]).status.success());
+
+    std::fs::remove_file(path).unwrap();
+}
@@ -0,0 +1,81 @@
+#![cfg(feature = "std")]
+
+use sac::conformance::{self, Cells, Edge, Eof, Report};
+use sac::buffer::OutputBuffer;
+use sac::interpreter::{Config, Engine, Interpreter};
+use sac::optimizer::OptLevel;
+use sac::{testing, verify};
+
+#[test]
+fn default_semantics() {
+    let report = conformance::run(Config::default());
+
+    assert_eq!(report, Report {
+        cells: Cells::Wrapping { bits: 8 },
+        eof: Eof::Error,
+        tape_cells: 100000,
+        left_edge: Edge::Error,
+        right_edge: Edge::Error,
+        nesting_ok: true,
+    });
+    assert_eq!(
+        report.to_string(),
+        "cells: 8-bit wrapping; EOF: error; tape: 100000 cells, left edge bounded, right edge bounded; nesting: 10000 levels OK"
+    );
+}
+
+#[test]
+fn knobs_show_up_in_the_probes() {
+    let small = conformance::run(Config { tape_size: 16, ..Config::default() });
+    assert_eq!(small.tape_cells, 16);
+    assert_eq!(small.right_edge, Edge::Error);
+
+    // Optimizations must not change any observable semantics.
+    let unoptimized = conformance::run(Config { opt_level: OptLevel::O0, ..Config::default() });
+    assert_eq!(unoptimized, conformance::run(Config::default()));
+}
+
+#[test]
+fn fixtures_match_the_reference_interpreter() {
+    for name in ["hello_world", "nested_loops", "wrapping", "cat", "bracket_error"] {
+        let code = std::fs::read_to_string(format!("tests/fixtures/{name}.bf")).unwrap();
+        let input = std::fs::read(format!("tests/fixtures/{name}.in")).unwrap_or_default();
+        let Some(expected) = testing::reference_run(&code, &input, 100000, 10_000_000) else { continue };
+
+        for opt_level in OptLevel::ALL {
+            let mut interpreter = Interpreter::with_config(Config { opt_level, ..Config::default() });
+            let Ok(actual) = verify::execute(&mut interpreter, &code, &input) else { continue };
+            assert_eq!((actual.output, actual.tape, actual.error.is_some()), (expected.output.clone(), expected.tape.clone(), expected.failed), "{name} at {opt_level:?}");
+        }
+    }
+}
+
+// Every way of getting a program into an interpreter prepares the tables the interpreter loop
+// fetches from, which must match the program it runs.
+#[test]
+fn loaded_programs_run_as_the_reference_interpreter_on_every_engine() {
+    for name in ["hello_world", "nested_loops", "wrapping", "cat"] {
+        let code = std::fs::read_to_string(format!("tests/fixtures/{name}.bf")).unwrap();
+        let input = std::fs::read(format!("tests/fixtures/{name}.in")).unwrap_or_default();
+        let expected = testing::reference_run(&code, &input, 100000, 10_000_000).unwrap();
+
+        for engine in Engine::ALL.into_iter().filter(|engine| engine.is_available()) {
+            let config = Config { engine, ..Config::default() };
+            let parsed = verify::execute(&mut Interpreter::with_config(config), &code, &input).unwrap();
+            assert_eq!((&parsed.output, &parsed.tape), (&expected.output, &expected.tape), "{name} on {engine:?}");
+
+            let mut source = Interpreter::with_config(config);
+            source.load_program_from_str(&code).unwrap();
+            assert_eq!(source.compile().unwrap().run(&input).unwrap(), expected.output, "{name} compiled on {engine:?}");
+
+            let mut loaded = Interpreter::with_config(config);
+            let output = OutputBuffer::new();
+            loaded.set_input(std::io::Cursor::new(input.clone()));
+            loaded.set_output(output.clone());
+            loaded.load_program_from_str("+[-]>>").unwrap();
+            loaded.load_ir(&source.to_program()).unwrap();
+            loaded.interpret().unwrap();
+            assert_eq!(output.take(), expected.output, "{name} from its IR on {engine:?}");
+        }
+    }
+}
@@ -0,0 +1,35 @@
+use sac::buffer::OutputBuffer;
+use sac::coverage;
+use sac::interpreter::{Interpreter, Span};
+
+// The loop after `>` is never entered, its cell being 0, like the untaken branch of an `if`.
+const BRANCH: &str = "+>[-\n.]<.";
+
+fn run(code: &str) -> Interpreter {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_coverage_tracking(true);
+    interpreter.load_program_from_str(code).unwrap();
+    interpreter.set_output(OutputBuffer::new());
+    interpreter.interpret().unwrap();
+    interpreter
+}
+
+#[test]
+fn the_untaken_loop_is_reported_as_never_run() {
+    let interpreter = run(BRANCH);
+    assert_eq!(coverage::uncovered_regions(&interpreter).unwrap(), [Span { start: 3, end: 7, line: 1, column: 4 }]);
+    assert_eq!(interpreter.coverage().unwrap().count(0), 1);
+    assert_eq!(
+        coverage::summary(&interpreter).unwrap(),
+        "Coverage : 5 of 8 instructions run (62.5 %)\nNot run : line 1, column 4 : -"
+    );
+    assert_eq!(coverage::annotate(&interpreter, false).unwrap(), "+>[-\n   ^\n.]<.\n^^\n");
+    assert_eq!(coverage::annotate(&interpreter, true).unwrap(), "+>[\x1b[1;31m-\x1b[0m\n\x1b[1;31m.\x1b[0m\x1b[1;31m]\x1b[0m<.\n");
+    assert_eq!(coverage::to_lcov(&interpreter, "branch.bf").unwrap(), "TN:\nSF:branch.bf\nDA:1,1\nDA:2,1\nLH:2\nLF:2\nend_of_record\n");
+
+    // Ranges count for the instructions they stand for.
+    let interpreter = run("[-]+>[-]+>[-]+>[-]+");
+    assert!(interpreter.listing().starts_with("     0  FillRange"));
+    assert_eq!(interpreter.coverage().unwrap().percent(), 100.0);
+    assert_eq!(coverage::summary(&Interpreter::new()), None);
+}
@@ -0,0 +1,127 @@
+#![cfg(feature = "std")]
+
+use sac::diff::{self, CheckedRun, Comparison, Equivalence, Mismatch, OutputDivergence};
+use sac::error::RuntimeError;
+use sac::interpreter::{Config, Interpreter, Span};
+
+const HELLO: &str = "++++++++[>++++[>++>+++>+++>+<<<<-]>+>+>->>+[<]<-]>>.>---.+++++++..+++.>>.<-.<.+++.------.--------.>>+.";
+
+#[test]
+fn identical_programs_report_their_output_length() {
+    // The same output written differently, with and without input.
+    let comparison = diff::diff(HELLO, &format!("comment {HELLO}"), b"", Config::default()).unwrap();
+    assert!(matches!(comparison, Comparison::Identical { bytes: 12 }));
+    assert_eq!(comparison.to_string(), "identical (12 bytes)");
+
+    let comparison = diff::diff(",[.,]", ",[.,]", b"abc\0", Config::default()).unwrap();
+    assert!(matches!(comparison, Comparison::Identical { bytes: 3 }));
+}
+
+#[test]
+fn early_divergence_shows_context() {
+    let new = HELLO.replacen(">---.", ">--.", 1); // "Hfmmp Wor..."
+    let Comparison::OutputDiffers(divergence) = diff::diff(HELLO, &new, b"", Config::default()).unwrap() else { panic!() };
+
+    assert_eq!(divergence.offset, 1);
+    assert_eq!(divergence.context, b"H");
+    assert_eq!(&divergence.old[..3], b"ell");
+    assert_eq!(&divergence.new[..3], b"fmm");
+    assert_eq!(divergence.old.len(), 9); // The diverging byte and 8 more.
+
+    let report = divergence.to_string();
+    assert!(report.starts_with("Outputs differ at byte 1 : expected 0x65, got 0x66\n"), "{report}");
+    assert!(report.contains("  old : 48 [65] 6c 6c 6f"), "{report}");
+    assert!(report.contains("|Hello Worl|\n  new : 48 [66] 6d 6d 70"), "{report}");
+}
+
+#[test]
+fn differing_lengths_diverge_where_the_shorter_output_ends() {
+    let comparison = diff::diff("+++++++[>++++++++++<-]>+..", "+++++++[>++++++++++<-]>+...", b"", Config::default()).unwrap();
+    let Comparison::OutputDiffers(divergence) = comparison else { panic!() };
+
+    assert_eq!(divergence, OutputDivergence { offset: 2, context: b"GG".to_vec(), old: Vec::new(), new: b"G".to_vec() });
+    assert!(divergence.to_string().contains("expected end of output, got 0x47"));
+    assert!(divergence.to_string().contains("  old : 47 47 []"));
+}
+
+#[test]
+fn same_output_but_different_halts_is_a_difference() {
+    let comparison = diff::diff("+.", "+.<", b"", Config::default()).unwrap();
+    let Comparison::StatusDiffers { bytes: 1, old: None, new: Some(RuntimeError::PointerUnderflow { .. }) } = comparison else { panic!("{comparison}") };
+
+    // Failing the same way at different places is not.
+    assert!(matches!(diff::diff("<", "+<", b"", Config::default()).unwrap(), Comparison::Identical { bytes: 0 }));
+}
+
+fn check(code: &str, expected: &[u8], keep_going: bool) -> (CheckedRun, Vec<u8>) {
+    let mut interpreter = Interpreter::new();
+    interpreter.load_program_from_str(code).unwrap();
+    let mut output = Vec::new();
+    let run = diff::compare_against(&mut interpreter, expected, &mut output, keep_going);
+    (run, output)
+}
+
+#[test]
+fn matching_output_passes_the_comparison() {
+    let (run, output) = check(HELLO, b"Hello World!", false);
+    assert!(run.mismatches.is_empty() && run.error.is_none(), "{run:?}");
+    assert_eq!(run.bytes, 12);
+    assert_eq!(output, b"Hello World!");
+}
+
+#[test]
+fn first_mismatch_stops_the_run_where_it_was_printed() {
+    let (run, output) = check("++++++++[>++++++++<-]>+.\n+.\n+.", b"AXC", false);
+
+    assert_eq!(run.mismatches, [Mismatch { offset: 1, expected: Some(b'X'), got: Some(b'B'), span: Some(Span { start: 26, end: 27, line: 2, column: 2 }) }]);
+    assert_eq!(output, b"AB");
+    assert_eq!(run.mismatches[0].to_string(), "Output differs at byte 1 : expected 0x58, got 0x42, printed at line 2, column 2");
+
+    // Or goes on, reporting every mismatch.
+    let (run, output) = check("++++++++[>++++++++<-]>+.\n+.\n+.", b"XXC", true);
+    assert_eq!(run.mismatches.iter().map(|m| m.offset).collect::<Vec<_>>(), [0, 1]);
+    assert_eq!(output, b"ABC");
+}
+
+#[test]
+fn length_mismatches_fail_both_ways() {
+    // The reference goes on after the program halted.
+    let (run, _) = check("++++++++[>++++++++<-]>+.", b"AB", false);
+    assert_eq!(run.mismatches, [Mismatch { offset: 1, expected: Some(b'B'), got: None, span: None }]);
+    assert_eq!(run.mismatches[0].to_string(), "Output differs at byte 1 : expected 0x42, got end of output");
+
+    // The program prints past the end of the reference.
+    let (run, output) = check("++++++++[>++++++++<-]>+..", b"A", false);
+    assert_eq!(run.mismatches, [Mismatch { offset: 1, expected: None, got: Some(b'A'), span: Some(Span { start: 24, end: 25, line: 1, column: 25 }) }]);
+    assert_eq!(output, b"AA");
+
+    // Errors are reported along with the bytes left unprinted.
+    let (run, _) = check("+.<", b"\x01\x02", false);
+    assert!(matches!(run.error, Some(RuntimeError::PointerUnderflow { .. })));
+    assert_eq!(run.mismatches.len(), 1);
+}
+
+const ROT13: &str = "-,+[-[>>++++[>++++++++<-]<+<-[>+>+>-[>>>]<[[>+<-]>>+>]<<<<<-]]>>>[-]+>--[-[<->+++[-]]]<[++++++++++++<[>-[>+>>]>[+[<+>-]>+>>]<<<<<-]>>[<+>-]>[-[-<<[-]>>]<<[<<->>-]>>]<<[<<+>>-]]<[-]<.[-]<-,+]";
+
+#[test]
+fn equivalence_is_checked_over_every_input() {
+    let inputs: &[&[u8]] = &[b"", b"\0", b"hello\0", b"Brainfuck!\0"];
+    let equivalent = diff::programs_equivalent(",[.,]", "comment >,[.[-],]", inputs, Config::default(), 100_000).unwrap();
+    assert_eq!(equivalent, Equivalence::Equivalent);
+    assert!(equivalent.is_equivalent());
+
+    // Both fail at the end of the empty input, and differ on the next one.
+    let inputs: &[&[u8]] = &[b"", b"hello\0"];
+    assert_eq!(diff::programs_equivalent(",[.,]", ROT13, inputs, Config::default(), 100_000).unwrap(), Equivalence::Differs { input: 1 });
+}
+
+#[test]
+fn programs_running_out_of_steps_are_inconclusive() {
+    let inputs: &[&[u8]] = &[b"a\0", b"b\0"];
+    // Prints the same byte as cat, then loops forever.
+    let looping = ",[.,]+[]";
+    assert_eq!(diff::programs_equivalent(",[.,]", looping, inputs, Config::default(), 1000).unwrap(), Equivalence::Inconclusive { input: 0 });
+
+    // Unless what was printed before already differs.
+    assert_eq!(diff::programs_equivalent(",[.,]", ",[+.,]+[]", inputs, Config::default(), 1000).unwrap(), Equivalence::Differs { input: 0 });
+}
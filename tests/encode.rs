@@ -0,0 +1,51 @@
+#![cfg(feature = "std")]
+
+use sac::encode::{self, EncodeStyle, Unprintable};
+use sac::interpreter::{Config, Interpreter};
+use sac::optimizer::OptLevel;
+use sac::verify;
+
+const TEXTS: [&str; 6] = ["", "Hello, World!\n", "line\nbreak\r\n", "nul\0in\0the\0middle\0", "\u{ff}caf\u{e9} \u{80}\u{7f}\u{1}", "zzzzAAAA~~ \u{0}\u{ff}\u{0}"];
+
+fn output(code: &str) -> Vec<u8> {
+    let outputs = OptLevel::ALL.map(|opt_level| {
+        let execution = verify::execute(&mut Interpreter::with_config(Config { opt_level, ..Config::default() }), code, b"").unwrap();
+        assert!(execution.error.is_none(), "{:?}", execution.error);
+        execution.output
+    });
+    assert!(outputs.iter().all(|output| *output == outputs[0]));
+    outputs[0].clone()
+}
+
+#[test]
+fn both_styles_round_trip() {
+    for style in [EncodeStyle::Naive, EncodeStyle::Loops] {
+        for text in TEXTS {
+            let code = encode::encode(text, style).unwrap();
+            assert_eq!(output(&code), text.as_bytes(), "{style:?} {text:?} : {code}");
+        }
+    }
+}
+
+#[test]
+fn every_printable_byte_round_trips() {
+    let text: String = (0..=255u8).rev().map(char::from).collect();
+    for style in [EncodeStyle::Naive, EncodeStyle::Loops] {
+        assert_eq!(output(&encode::encode(&text, style).unwrap()), text.as_bytes());
+    }
+}
+
+#[test]
+fn loops_are_shorter_than_runs() {
+    let naive = encode::encode("Hello, World!\n", EncodeStyle::Naive).unwrap();
+    let loops = encode::encode("Hello, World!\n", EncodeStyle::Loops).unwrap();
+    assert!(loops.len() < naive.len(), "{} >= {}", loops.len(), naive.len());
+
+    assert_eq!(encode::encode("AAB", EncodeStyle::Naive).unwrap(), format!("{}..+.", "+".repeat(65)));
+    assert_eq!(encode::encode("\u{ff}", EncodeStyle::Naive).unwrap(), "-.");
+}
+
+#[test]
+fn characters_above_u00ff_are_rejected() {
+    assert_eq!(encode::encode("ok \u{20ac}", EncodeStyle::Loops), Err(Unprintable { character: '\u{20ac}', index: 3 }));
+}
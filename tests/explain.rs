@@ -0,0 +1,69 @@
+use std::fs;
+use std::path::Path;
+
+use sac::explain;
+use sac::interpreter::{Config, Interpreter};
+use sac::optimizer::OptLevel;
+
+fn outline(code: &str, opt_level: OptLevel, max_depth: Option<usize>) -> String {
+    let mut interpreter = Interpreter::with_config(Config { opt_level, ..Config::default() });
+    interpreter.load_program_from_str(code).unwrap();
+    explain::explain(&interpreter, max_depth)
+}
+
+// The outline of `tests/fixtures/hello_world.bf` is kept in `hello_world.explain`, so that changes to
+// its wording show up in review. Run with `SAC_UPDATE_GOLDENS=1` to rewrite it.
+#[test]
+fn hello_world_matches_its_snapshot() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let code = fs::read_to_string(dir.join("hello_world.bf")).unwrap();
+    let snapshot = dir.join("hello_world.explain");
+
+    let outline = outline(&code, OptLevel::O1, None);
+    if std::env::var_os("SAC_UPDATE_GOLDENS").is_some() {
+        fs::write(&snapshot, format!("{outline}\n")).unwrap();
+    }
+    assert_eq!(format!("{outline}\n"), fs::read_to_string(&snapshot).unwrap());
+    assert_eq!(self::outline(&code, OptLevel::O0, None), outline);
+}
+
+#[test]
+fn multiplication_loops_take_one_line() {
+    assert_eq!(outline("++++++++[>+++++++++<-]>.", OptLevel::O1, None), [
+        "set cell 0 to 8",
+        "loop at line 1, column 9 : runs 8 times while cell 0 ≠ 0, each iteration adds 9 to cell 1 and subtracts 1 from cell 0",
+        "move to cell 1",
+        "print cell 1 ('H')",
+    ]
+    .join("\n"));
+
+    assert_eq!(outline(",[->--<]>.", OptLevel::O1, None), [
+        "read a byte into cell 0",
+        "loop at line 1, column 2 : runs while cell 0 ≠ 0, each iteration subtracts 2 from cell 1 and subtracts 1 from cell 0",
+        "move to cell 1",
+        "print cell 1",
+    ]
+    .join("\n"));
+}
+
+#[test]
+fn unknown_positions_are_relative() {
+    assert_eq!(outline("+[>+]<[-]<<.", OptLevel::O1, None), [
+        "set cell 0 to 1",
+        "loop at line 1, column 2 : runs while cell 0 ≠ 0, moving right by 1 each iteration",
+        "  add 1 to the next cell, move right by 1",
+        "set the previous cell to 0, move left by 3",
+        "print the current cell",
+    ]
+    .join("\n"));
+}
+
+#[test]
+fn depth_limits_the_nesting() {
+    let code = "+[>+[>+[-]<-]<-]";
+    assert_eq!(outline(code, OptLevel::O1, Some(0)), "set cell 0 to 1\nloop at line 1, column 2 : runs while cell 0 ≠ 0 (body not shown)");
+
+    let outline = outline(code, OptLevel::O1, Some(1));
+    assert!(outline.contains("\n  loop at line 1, column 5 : runs while cell 1 ≠ 0 (body not shown)\n"), "{outline}");
+    assert!(!outline.contains("\n    "), "{outline}");
+}
@@ -0,0 +1,89 @@
+#![cfg(feature = "std")]
+
+use std::fs;
+use std::io::Cursor;
+use std::path::Path;
+
+use sac::buffer::OutputBuffer;
+use sac::interpreter::{Config, Engine, Interpreter};
+
+// Every `tests/fixtures/NAME.bf` is run with `NAME.in` as input, and must produce `NAME.out` and,
+// when it fails, the message in `NAME.err`. Run with `SAC_UPDATE_GOLDENS=1` to rewrite them.
+
+fn run(program: &Path, config: Config) -> (Vec<u8>, Option<String>) {
+    let input = fs::read(program.with_extension("in")).unwrap_or_default();
+    let output = OutputBuffer::new();
+
+    let mut interpreter = Interpreter::with_config(config);
+    interpreter.set_input(Cursor::new(input));
+    interpreter.set_output(output.clone());
+
+    let error = match interpreter.load_program(program.to_str().unwrap()) {
+        Ok(()) => interpreter.interpret().err().map(|e| e.to_string()),
+        Err(e) => Some(e.to_string()),
+    };
+
+    (output.take(), error)
+}
+
+fn fixtures() -> Vec<std::path::PathBuf> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let mut programs: Vec<_> = fs::read_dir(&dir).unwrap().map(|e| e.unwrap().path()).filter(|p| p.extension().is_some_and(|e| e == "bf")).collect();
+    programs.sort();
+    assert!(!programs.is_empty());
+    programs
+}
+
+#[test]
+fn fixtures_match_their_goldens() {
+    let update = std::env::var_os("SAC_UPDATE_GOLDENS").is_some();
+    let programs = fixtures();
+
+    let mut failures = Vec::new();
+
+    for program in &programs {
+        let name = program.file_stem().unwrap().to_string_lossy();
+        let (output, error) = run(program, Config::default());
+        let (out_path, err_path) = (program.with_extension("out"), program.with_extension("err"));
+
+        if update {
+            fs::write(&out_path, &output).unwrap();
+            match &error {
+                Some(error) => fs::write(&err_path, format!("{error}\n")).unwrap(),
+                None => {
+                    let _ = fs::remove_file(&err_path);
+                },
+            }
+            continue;
+        }
+
+        let expected_output = fs::read(&out_path).unwrap_or_default();
+        if output != expected_output {
+            failures.push(format!("{name} : expected output {:?}, got {:?}", expected_output.escape_ascii().to_string(), output.escape_ascii().to_string()));
+        }
+        let expected_error = fs::read_to_string(&err_path).ok().map(|e| e.trim().to_string());
+        if error != expected_error {
+            failures.push(format!("{name} : expected error {expected_error:?}, got {error:?}"));
+        }
+    }
+
+    assert!(failures.is_empty(), "{} golden mismatches :\n{}", failures.len(), failures.join("\n"));
+}
+
+#[test]
+fn inferred_tape_sizes_do_not_change_the_fixtures() {
+    for program in fixtures() {
+        let inferred = run(&program, Config { infer_tape_size: true, ..Config::default() });
+        assert_eq!(inferred, run(&program, Config::default()), "{}", program.display());
+    }
+}
+
+#[test]
+fn every_engine_runs_the_fixtures_alike() {
+    for engine in Engine::ALL.into_iter().filter(|engine| engine.is_available()) {
+        for program in fixtures() {
+            let config = Config { engine, ..Config::default() };
+            assert_eq!(run(&program, config), run(&program, Config::default()), "{} with {}", program.display(), engine.name());
+        }
+    }
+}
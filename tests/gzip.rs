@@ -0,0 +1,49 @@
+#![cfg(feature = "gzip")]
+
+use std::fs;
+
+use sac::buffer::OutputBuffer;
+use sac::error::LoadError;
+use sac::gzip;
+use sac::interpreter::{Config, Interpreter};
+
+#[test]
+fn runs_a_compressed_program() {
+    let output = OutputBuffer::new();
+    let mut interpreter = Interpreter::new();
+    interpreter.set_output(output.clone());
+
+    interpreter.load_program("tests/data/hello_world.bf.gz").unwrap();
+    interpreter.interpret().unwrap();
+
+    assert_eq!(output.take(), b"Hello World!\n");
+}
+
+#[test]
+fn decompresses_dynamic_huffman_blocks() {
+    let compressed = fs::read("tests/data/mandelbrot.bf.gz").unwrap();
+
+    assert!(gzip::is_gzip(&compressed));
+    assert_eq!(gzip::decompress(&compressed).unwrap(), fs::read("programs/mandelbrot.bf").unwrap());
+}
+
+#[test]
+fn rejects_corrupted_streams() {
+    let mut compressed = fs::read("tests/data/hello_world.bf.gz").unwrap();
+    let last = compressed.len() - 5;
+    compressed[last] ^= 0xff;
+
+    assert!(gzip::decompress(&compressed).is_err());
+    assert!(gzip::decompress(&compressed[..20]).is_err());
+}
+
+#[test]
+fn stops_decompressing_past_the_size_limit() {
+    // 16 MiB of `+` in 16 KiB.
+    let compressed = fs::read("tests/data/bomb.bf.gz").unwrap();
+    assert_eq!(gzip::decompress_at_most(&compressed, 4096).unwrap_err().kind(), std::io::ErrorKind::FileTooLarge);
+
+    let mut interpreter = Interpreter::with_config(Config { max_source_bytes: Some(1 << 20), ..Config::default() });
+    assert!(matches!(interpreter.load_program("tests/data/bomb.bf.gz"), Err(LoadError::ProgramTooLarge { limit: 1048576 })));
+    interpreter.load_program("tests/data/hello_world.bf.gz").unwrap();
+}
@@ -0,0 +1,46 @@
+#![cfg(feature = "std")]
+
+use std::io::Write;
+
+use sac::hash::{crc32, HashAlgorithm, Hasher, HashingWriter};
+
+#[test]
+fn digests_match_known_vectors() {
+    let digest = |algorithm, bytes: &[u8]| {
+        let mut hasher = Hasher::new(algorithm);
+        hasher.update(bytes);
+        hasher.digest().hex
+    };
+
+    assert_eq!(digest(HashAlgorithm::Sha256, b""), "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855");
+    assert_eq!(digest(HashAlgorithm::Sha256, b"abc"), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+    assert_eq!(digest(HashAlgorithm::Sha256, &[b'a'; 1000]), "41edece42d63e8d9bf515a9ba6932e1c20cbc9f5a5d134645adb5db1b9737ea3");
+    assert_eq!(digest(HashAlgorithm::Crc32, b"123456789"), "cbf43926");
+    assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+}
+
+#[test]
+fn hashing_in_pieces_matches_hashing_at_once() {
+    let bytes: Vec<u8> = (0..300u32).map(|i| (i * 7) as u8).collect();
+    for algorithm in [HashAlgorithm::Sha256, HashAlgorithm::Crc32] {
+        let mut whole = Hasher::new(algorithm);
+        whole.update(&bytes);
+
+        let mut pieces = Hasher::new(algorithm);
+        for piece in bytes.chunks(13) {
+            pieces.update(piece);
+        }
+        assert_eq!(pieces.digest(), whole.digest());
+        assert_eq!(pieces.digest().bytes, 300);
+    }
+}
+
+#[test]
+fn hashing_writer_passes_the_output_on() {
+    let mut writer = HashingWriter::new(Vec::new(), HashAlgorithm::Crc32);
+    let mut clone = writer.clone();
+    clone.write_all(b"1234").unwrap();
+    writer.write_all(b"56789").unwrap();
+
+    assert_eq!(writer.digest().to_string(), "crc32 cbf43926 (9 bytes)");
+}
@@ -0,0 +1,46 @@
+use std::fs;
+use std::path::Path;
+
+use sac::highlight::{self, HighlightOptions};
+
+// Snapshots of the colored listing of every `tests/highlight/NAME.bf`, in `NAME.ansi` and, with line
+// numbers, in `NAME.numbered.ansi`. Run with `SAC_UPDATE_GOLDENS=1` to rewrite them.
+
+#[test]
+fn listings_match_their_snapshots() {
+    let update = std::env::var_os("SAC_UPDATE_GOLDENS").is_some();
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/highlight");
+
+    let mut programs: Vec<_> = fs::read_dir(&dir).unwrap().map(|e| e.unwrap().path()).filter(|p| p.extension().is_some_and(|e| e == "bf")).collect();
+    programs.sort();
+    assert!(!programs.is_empty());
+
+    for program in &programs {
+        let code = fs::read_to_string(program).unwrap();
+        for (extension, line_numbers) in [("ansi", false), ("numbered.ansi", true)] {
+            let listing = highlight::highlight(&code, HighlightOptions { color: true, line_numbers });
+            let snapshot = program.with_extension(extension);
+            if update {
+                fs::write(&snapshot, &listing).unwrap();
+            } else {
+                assert_eq!(listing, fs::read_to_string(&snapshot).unwrap(), "{}", snapshot.display());
+            }
+        }
+    }
+}
+
+#[test]
+fn brackets_are_colored_by_depth_and_orphans_in_red() {
+    assert_eq!(
+        highlight::highlight("[[]]]", HighlightOptions::default()),
+        "\x1b[95m[\x1b[0m\x1b[94m[]\x1b[0m\x1b[95m]\x1b[0m\x1b[1;31m]\x1b[0m"
+    );
+    assert_eq!(highlight::highlight("a +", HighlightOptions::default()), "\x1b[2ma \x1b[0m\x1b[32m+\x1b[0m");
+}
+
+#[test]
+fn plain_listing_is_the_source() {
+    let code = "+[\n-]  comment\n\n.";
+    assert_eq!(highlight::highlight(code, HighlightOptions { color: false, line_numbers: false }), code);
+    assert_eq!(highlight::highlight(code, HighlightOptions { color: false, line_numbers: true }), "1 | +[\n2 | -]  comment\n3 | \n4 | .");
+}
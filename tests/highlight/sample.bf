@@ -0,0 +1,3 @@
+Prints 2 then +
+++[>+<-]>  .  comment, with a comma
+] orphan and [[-] unclosed
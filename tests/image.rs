@@ -0,0 +1,26 @@
+use sac::buffer::OutputBuffer;
+use sac::error::ImageError;
+use sac::image;
+use sac::interpreter::Interpreter;
+
+#[test]
+fn tape_regions_and_output_become_pgm_images() {
+    // A 3x2 image from cell 1, after a cell that isn't part of it.
+    let mut interpreter = Interpreter::new();
+    interpreter.load_program_from_str("+>>++>+++>++++>+++++>++++++").unwrap();
+    interpreter.interpret().unwrap();
+    assert_eq!(interpreter.tape_as_pgm(1, 3, 2).unwrap(), b"P5\n3 2\n255\n\x00\x02\x03\x04\x05\x06");
+
+    let tape_size = interpreter.tape_size();
+    assert_eq!(interpreter.tape_as_pgm(tape_size - 1, 2, 1), Err(ImageError::OutOfRange { end: tape_size + 1, tape_size }));
+    assert_eq!(interpreter.tape_as_pgm(0, 0, 4), Err(ImageError::Empty));
+
+    // Printed pixels, white then black, as `.` gives them before encoding those above 127 as UTF-8.
+    interpreter.set_output(OutputBuffer::new());
+    interpreter.load_program_from_str("-..+..").unwrap();
+    let pixels: Vec<u8> = std::iter::from_fn(|| interpreter.run_to_output().unwrap()).collect();
+    assert_eq!(image::to_pgm(&pixels, 2, 2).unwrap(), b"P5\n2 2\n255\n\xff\xff\x00\x00");
+    let mismatch = image::to_pgm(&pixels, 3, 2).unwrap_err();
+    assert_eq!(mismatch, ImageError::SizeMismatch { width: 3, height: 2, bytes: 4 });
+    assert_eq!(mismatch.to_string(), "A 3x2 image needs 6 bytes, got 4");
+}
@@ -0,0 +1,91 @@
+#![cfg(feature = "tracing")]
+
+use std::sync::{Arc, Mutex};
+
+use sac::instrument::{Fields, Subscriber};
+use sac::interpreter::Interpreter;
+
+// Keeps a line per span entered or exited and per event, indented by the spans they are in.
+#[derive(Default)]
+struct Capture(Mutex<(Vec<String>, usize)>); // With the depth.
+
+impl Capture {
+    fn push(&self, depth: usize, name: &str, fields: &Fields<'_>) {
+        let fields: Vec<String> = fields.iter().map(|(field, value)| format!(" {field}={value}")).collect();
+        self.0.lock().unwrap().0.push(format!("{:1$}{name}{2}", "", 2 * depth, fields.concat()));
+    }
+
+    fn depth(&self, change: isize) -> usize {
+        let depth = &mut self.0.lock().unwrap().1;
+        let before = *depth;
+        *depth = depth.wrapping_add_signed(change);
+        before.min(*depth)
+    }
+
+    fn lines(&self) -> Vec<String> {
+        self.0.lock().unwrap().0.clone()
+    }
+}
+
+impl Subscriber for Capture {
+    fn enter(&self, span: &'static str, fields: &Fields<'_>) {
+        self.push(self.depth(1), span, fields);
+    }
+
+    fn exit(&self, span: &'static str, fields: &Fields<'_>) {
+        self.push(self.depth(-1), &format!("end {span}"), fields);
+    }
+
+    fn event(&self, name: &'static str, fields: &Fields<'_>) {
+        self.push(self.depth(0), name, fields);
+    }
+}
+
+#[test]
+fn loads_and_runs_are_spans_with_the_passes_inside_the_load() {
+    let capture = Arc::new(Capture::default());
+    let mut interpreter = Interpreter::new();
+    interpreter.set_output(std::io::sink());
+    interpreter.set_subscriber(capture.clone());
+
+    interpreter.load_program_from_str("+[-]>++.").unwrap();
+    interpreter.interpret().unwrap();
+
+    assert_eq!(capture.lines(), [
+        "load source_bytes=8",
+        "  jumps instructions=7",
+        "  end jumps",
+        "  pass name=\"fold-clear-loops\" instructions_before=7",
+        "  end pass instructions_after=5",
+        "  pass name=\"fold-set-constants\" instructions_before=5",
+        "  end pass instructions_after=5",
+        "  pass name=\"fold-if-loops\" instructions_before=5",
+        "  end pass instructions_after=5",
+        "  pass name=\"fold-ranges\" instructions_before=5",
+        "  end pass instructions_after=5",
+        "  jumps instructions=5",
+        "  end jumps",
+        "end load instructions=5",
+        "execute steps=0",
+        "end execute steps=5",
+    ]);
+}
+
+#[test]
+fn long_runs_report_their_steps_and_errors_carry_their_fields() {
+    let capture = Arc::new(Capture::default());
+    let mut interpreter = Interpreter::new();
+    interpreter.load_program_from_str("+[]").unwrap();
+    interpreter.set_subscriber(capture.clone());
+    interpreter.set_step_limit(2_500_000);
+
+    assert!(interpreter.interpret().is_err());
+
+    assert_eq!(capture.lines(), [
+        "execute steps=0",
+        "  steps steps=1048576",
+        "  steps steps=2097152",
+        "  runtime_error kind=\"step-limit-exceeded\" line=1 column=3 message=\"Step limit reached at line 1, column 3 (instruction 2)\"",
+        "end execute steps=2500000",
+    ]);
+}
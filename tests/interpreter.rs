@@ -0,0 +1,1166 @@
+#[cfg(feature = "std")]
+use std::time::Duration;
+
+use sac::buffer::OutputBuffer;
+use sac::error::{LoadError, RuntimeError, TapeStringError};
+use sac::interpreter::{CompatProfile, Config, Engine, FastForward, GasSchedule, InstructionHandler, Interpreter, LoopEvent, LoopInfo, Machine, Pause, RunSummary, Span, StepOutcome, StepStatus, TapeWindow};
+use sac::io::{Bell, InputMode, InputSource, InvalidUtf8, IoError, NonAscii, OnEndOfInput, OutputSink};
+use sac::optimizer::OptLevel;
+use sac::tape::{TapeMode, WrapPolicy};
+use sac::testing;
+
+fn load(code: &str) -> Interpreter {
+    let mut interpreter = Interpreter::new();
+    interpreter.load_program_from_str(code).unwrap();
+    interpreter
+}
+
+#[test]
+fn loops_reports_nesting_and_io() {
+    let interpreter = load("[[.]]");
+
+    assert_eq!(interpreter.loops(), vec![
+        LoopInfo { open: 0, close: 4, depth: 1, span: Span { start: 0, end: 5, line: 1, column: 1 }, contains_io: true },
+        LoopInfo { open: 1, close: 3, depth: 2, span: Span { start: 1, end: 4, line: 1, column: 2 }, contains_io: true },
+    ]);
+}
+
+#[test]
+fn loops_spans_skip_comments() {
+    let interpreter = load("++ a[-- b]\n[++]");
+    let loops = interpreter.loops();
+
+    assert_eq!(loops.len(), 2);
+    assert_eq!(loops[0].span, Span { start: 4, end: 10, line: 1, column: 5 });
+    assert_eq!((loops[1].span.line, loops[1].span.column), (2, 1));
+    assert!(!loops[0].contains_io);
+    assert_eq!((loops[1].open, loops[1].close, loops[1].depth), (4, 6, 1));
+}
+
+#[test]
+fn at_signs_are_comments_like_any_other() {
+    let kinds = |interpreter: &Interpreter| interpreter.to_program().instructions.iter().map(|inst| (inst.kind, inst.operand)).collect::<Vec<_>>();
+    let mut with = load("@++@++[>+@+<-]@@>@");
+    let mut without = load("++++[>++<-]>");
+    assert_eq!(kinds(&with), kinds(&without));
+    // A streak goes on across them.
+    assert_eq!(with.to_program().instructions[0].span, Span { start: 1, end: 6, line: 1, column: 2 });
+
+    with.interpret().unwrap();
+    without.interpret().unwrap();
+    assert_eq!((with.peek(1), with.memory_pointer()), (Some(8), 1));
+    assert_eq!((without.peek(1), without.memory_pointer()), (Some(8), 1));
+}
+
+#[test]
+fn pointer_underflow_reports_source_location() {
+    let mut interpreter = load("+>\n  <<");
+    let error = interpreter.interpret().unwrap_err();
+
+    match error {
+        RuntimeError::PointerUnderflow { instruction, span } => {
+            assert_eq!(instruction, 2);
+            assert_eq!((span.line, span.column), (2, 3));
+        },
+        other => panic!("unexpected error {other:?}"),
+    }
+
+    let message = error.to_string();
+    assert!(message.contains("line 2, column 3"), "{message}");
+    assert!(message.contains("left of cell 0"), "{message}");
+}
+
+struct Uart {
+    received: Vec<u8>,
+    transmitted: Vec<u8>,
+}
+
+struct UartRx(std::sync::Arc<std::sync::Mutex<Uart>>);
+struct UartTx(std::sync::Arc<std::sync::Mutex<Uart>>);
+
+impl InputSource for UartRx {
+    fn read_byte(&mut self) -> Result<Option<u8>, IoError> {
+        let mut uart = self.0.lock().unwrap();
+        Ok(if uart.received.is_empty() { None } else { Some(uart.received.remove(0)) })
+    }
+}
+
+impl OutputSink for UartTx {
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), IoError> {
+        self.0.lock().unwrap().transmitted.extend_from_slice(bytes);
+        Ok(())
+    }
+}
+
+#[test]
+fn custom_io_sources() {
+    let uart = std::sync::Arc::new(std::sync::Mutex::new(Uart { received: b"hi".to_vec(), transmitted: Vec::new() }));
+    let mut interpreter = load(",+.,+.,");
+    interpreter.set_input(UartRx(uart.clone()));
+    interpreter.set_output(UartTx(uart.clone()));
+
+    assert!(matches!(interpreter.interpret(), Err(RuntimeError::EndOfInput { instruction: 6, .. })));
+    assert_eq!(uart.lock().unwrap().transmitted, b"ij");
+}
+
+#[test]
+fn matching_brackets_resolve_both_ways() {
+    let interpreter = load("+[>[>]<\n [.]]");
+
+    for (open, close) in [(1, 10), (3, 5), (7, 9)] {
+        assert_eq!(interpreter.matching_bracket(open), Some(close));
+        assert_eq!(interpreter.matching_bracket(close), Some(open));
+    }
+    assert_eq!(interpreter.matching_bracket(0), None);
+    assert_eq!(interpreter.matching_bracket(11), None);
+
+    assert_eq!(interpreter.matching_bracket_at_offset(1), Some(12));
+    assert_eq!(interpreter.matching_bracket_at_offset(12), Some(1));
+    assert_eq!(interpreter.matching_bracket_at_offset(9), Some(11));
+    assert_eq!(interpreter.matching_bracket_at_offset(8), None);
+    assert_eq!(interpreter.matching_bracket_at_offset(10), None);
+}
+
+#[test]
+fn loop_iteration_cap_catches_runaway_loops() {
+    // The `[-]` loop iterates 10000 times in total, but never more than 100 times per run.
+    let mut interpreter = load("++++++++++[>++++++++++<-]>[>++++++++++[>++++++++++<-]>[-]<<-]");
+    interpreter.set_loop_iteration_cap(1000);
+    interpreter.interpret().unwrap();
+
+    let mut interpreter = load("+>+\n[>+<]");
+    interpreter.set_loop_iteration_cap(1000);
+    match interpreter.interpret() {
+        Err(RuntimeError::LoopCapExceeded { loop_index, span }) => {
+            assert_eq!(loop_index, 3);
+            assert_eq!((span.line, span.column), (2, 1));
+        },
+        other => panic!("unexpected result {other:?}"),
+    }
+    assert_eq!(interpreter.peek(2), Some((1000 % 256) as u8));
+}
+
+#[test]
+fn resource_limits_stop_runaway_programs() {
+    let mut interpreter = load("+[]");
+    interpreter.set_step_limit(100);
+    assert!(matches!(interpreter.interpret(), Err(RuntimeError::StepLimitExceeded { instruction: 2, .. })));
+    assert_eq!(interpreter.steps(), 100);
+
+    let mut interpreter = load("+[.]");
+    let output = OutputBuffer::new();
+    interpreter.set_output(output.clone());
+    interpreter.set_output_limit(5);
+    assert!(matches!(interpreter.interpret(), Err(RuntimeError::OutputLimitExceeded { instruction: 2, .. })));
+    assert_eq!(output.len(), 5);
+
+    #[cfg(feature = "std")]
+    {
+        let mut interpreter = load("+[]");
+        interpreter.set_time_limit(Duration::from_millis(20));
+        assert!(matches!(interpreter.interpret(), Err(RuntimeError::TimeLimitExceeded { .. })));
+    }
+}
+
+#[test]
+fn hangs_are_detected() {
+    let hang = |code: &str| {
+        let mut interpreter = load(code);
+        interpreter.set_hang_detection(Some(1000));
+        interpreter.set_step_limit(10_000);
+        match interpreter.interpret() {
+            Err(RuntimeError::NoProgress { loop_index, period, .. }) => (loop_index, period),
+            other => panic!("unexpected result {other:?}"),
+        }
+    };
+
+    assert_eq!(hang("+[]"), (1, 1));
+    assert_eq!(hang(">+[<>]"), (2, 3));
+    assert_eq!(hang("+[>+<>-<]"), (1, 7));
+    assert_eq!(hang("+[[-]+]"), (1, 2)); // `[-]+` folds into one instruction.
+
+    // Long computations and output are progress.
+    let mut interpreter = load("++++++++[>++++++++[>++++++++[>+<-]<-]<-]>>>.");
+    let output = OutputBuffer::new();
+    interpreter.set_output(output.clone());
+    interpreter.set_hang_detection(Some(10));
+    interpreter.interpret().unwrap();
+    assert_eq!(output.take(), b"\x00");
+
+    let mut interpreter = load("+[.]");
+    interpreter.set_output(OutputBuffer::new());
+    interpreter.set_hang_detection(Some(10));
+    interpreter.set_step_limit(1000);
+    assert!(matches!(interpreter.interpret(), Err(RuntimeError::StepLimitExceeded { .. })));
+}
+
+#[test]
+fn engines_share_limits_and_breakpoints() {
+    assert_eq!(Interpreter::with_config(Config { engine: Engine::Jit, ..Config::default() }).engine(), Engine::Match);
+    assert!(!Engine::Jit.is_available() && Engine::from_name("match") == Some(Engine::Match));
+
+    let mut interpreter = Interpreter::with_config(Config { engine: Engine::FnTable, breakpoint_token: Some('#'), ..Config::default() });
+    interpreter.load_program_from_str("++#[-]+[]").unwrap();
+    interpreter.set_debug_mode(true);
+    interpreter.set_step_limit(100);
+    assert!(matches!(interpreter.resume(), Ok(Pause::Breakpoint { instruction: 1, .. })));
+    assert!(matches!(interpreter.resume(), Err(RuntimeError::StepLimitExceeded { .. })));
+    assert_eq!((interpreter.engine(), interpreter.peek(0)), (Engine::FnTable, Some(1)));
+}
+
+#[test]
+fn engines_stop_on_the_same_step() {
+    let run = |engine, code: &str, limit: Option<u64>| {
+        let mut interpreter = Interpreter::with_config(Config { engine, tape_size: 100, ..Config::default() });
+        interpreter.load_program_from_str(code).unwrap();
+        if let Some(limit) = limit {
+            interpreter.set_step_limit(limit);
+        }
+        let result = interpreter.interpret().map_err(|e| e.to_string());
+        (result, interpreter.steps(), interpreter.instruction_pointer(), interpreter.memory_pointer(), interpreter.peek_range(0..100).unwrap().to_vec())
+    };
+
+    for (code, limit) in [("+[>+]", None), ("-[-[>+<-]>]", Some(100_000)), ("+[>++<<]", None), ("+[]", Some(1 << 17))] {
+        assert_eq!(run(Engine::Match, code, limit), run(Engine::FnTable, code, limit), "{code}");
+    }
+    assert_eq!(run(Engine::Match, "+[]", Some(1 << 17)).1, 1 << 17);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn metrics_count_loads_and_runs() {
+    use sac::metrics::{AtomicMetrics, MetricKey};
+    use std::sync::Arc;
+
+    let metrics = Arc::new(AtomicMetrics::new());
+    let mut interpreter = Interpreter::new();
+    interpreter.set_metrics(metrics.clone());
+    interpreter.set_input(std::io::Cursor::new(b"ab".to_vec()));
+    interpreter.set_output(OutputBuffer::new());
+
+    interpreter.load_program_from_str(",.,.++++++++[>++++++++<-]>+.").unwrap();
+    interpreter.interpret().unwrap();
+    let steps = interpreter.steps();
+    assert_eq!([MetricKey::Steps, MetricKey::InputBytes, MetricKey::OutputBytes].map(|key| metrics.get(key)), [steps, 2, 3]);
+
+    interpreter.load_program_from_str("+.<").unwrap();
+    assert!(interpreter.interpret().is_err());
+    assert_eq!(metrics.get(MetricKey::Steps), steps + 3);
+    assert_eq!(metrics.get(MetricKey::OutputBytes), 4);
+    assert_eq!(metrics.get(MetricKey::RuntimeErrors("pointer-underflow")), 1);
+
+    // Stepping reports its counts once the program halts.
+    interpreter.load_program_from_str("+>+").unwrap();
+    interpreter.step().unwrap();
+    interpreter.step().unwrap();
+    assert_eq!(metrics.get(MetricKey::Steps), steps + 3);
+    interpreter.step().unwrap();
+    assert_eq!(metrics.get(MetricKey::Steps), steps + 6);
+}
+
+#[test]
+fn stepping_tells_when_the_program_halted() {
+    let mut interpreter = load("+>");
+    assert!(!interpreter.is_halted());
+    assert_eq!(interpreter.step().unwrap(), StepStatus::Running);
+    assert!(!interpreter.is_halted());
+    assert_eq!(interpreter.step().unwrap(), StepStatus::Halted);
+    assert!(interpreter.is_halted());
+    assert_eq!(interpreter.step().unwrap(), StepStatus::Halted);
+    assert_eq!((interpreter.steps(), interpreter.memory_pointer()), (2, 1));
+}
+
+#[test]
+fn fast_forwarding_runs_whole_loop_iterations() {
+    let code = "++++++++[>++<-]>.";
+    let at_loop = || {
+        let mut interpreter = load(code);
+        interpreter.set_output(OutputBuffer::new());
+        interpreter.step().unwrap();
+        interpreter
+    };
+    let (open, close) = (1, 6);
+    let mut interpreter = load(code);
+    assert_eq!(interpreter.fast_forward_loop(5).unwrap(), None);
+
+    let mut interpreter = at_loop();
+    assert_eq!(interpreter.fast_forward_loop(5).unwrap(), Some(FastForward::Paused { cell: 3 }));
+    assert_eq!((interpreter.instruction_pointer(), interpreter.memory_pointer()), (open + 1, 0));
+    assert_eq!(interpreter.peek_range(0..2), Some(&[3, 10][..]));
+    // Only 3 iterations are left.
+    assert_eq!(interpreter.fast_forward_loop(5).unwrap(), Some(FastForward::Exited { iterations: 3 }));
+    assert_eq!(interpreter.instruction_pointer(), close + 1);
+    assert_eq!(interpreter.peek_range(0..2), Some(&[0, 16][..]));
+
+    // Ending with the last iteration asked for is no early exit.
+    let mut interpreter = at_loop();
+    assert_eq!(interpreter.fast_forward_loop(8).unwrap(), Some(FastForward::Paused { cell: 0 }));
+    assert_eq!(interpreter.instruction_pointer(), close + 1);
+
+    // Pausing evaluates no instruction twice : the steps, hooks and loop caps are those of a run.
+    let enters = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let mut interpreter = at_loop();
+    let counter = enters.clone();
+    interpreter.set_loop_hook(move |event| {
+        counter.fetch_add(matches!(event, LoopEvent::Enter { .. }) as usize, std::sync::atomic::Ordering::Relaxed);
+    });
+    for _ in 0..4 {
+        interpreter.fast_forward_loop(2).unwrap();
+    }
+    interpreter.interpret().unwrap();
+    let mut run = at_loop();
+    run.interpret().unwrap();
+    assert_eq!((interpreter.steps(), enters.load(std::sync::atomic::Ordering::Relaxed)), (run.steps(), 1));
+
+    let mut interpreter = at_loop();
+    interpreter.set_loop_iteration_cap(4);
+    interpreter.fast_forward_loop(3).unwrap();
+    assert!(matches!(interpreter.fast_forward_loop(3), Err(RuntimeError::LoopCapExceeded { .. })));
+}
+
+#[test]
+fn cells_wrap_or_trap_on_arithmetic() {
+    let run = |code: &str, wrap_policy| {
+        let mut interpreter = Interpreter::with_config(Config { wrap_policy, ..Config::default() });
+        interpreter.load_program_from_str(code).unwrap();
+        let result = interpreter.interpret();
+        (result, interpreter.peek(1).unwrap())
+    };
+
+    assert!(matches!(run(">-", WrapPolicy::Wrap), (Ok(()), 255)));
+    assert!(matches!(run(">-", WrapPolicy::Trap), (Err(RuntimeError::CellUnderflow { instruction: 1, cell: 1, .. }), 0)));
+    let overflow = run(&format!(">{}", "+".repeat(256)), WrapPolicy::Trap).0.unwrap_err();
+    assert!(matches!(overflow, RuntimeError::CellOverflow { instruction: 1, cell: 1, .. }));
+    assert_eq!(overflow.to_string(), "Cell overflow at line 1, column 2 (instruction 1) : cell 1 would go past 255");
+    // Folded, the loop would set the cell to 0 without wrapping.
+    assert!(matches!(run(">+[+]", WrapPolicy::Trap).0, Err(RuntimeError::CellOverflow { .. })));
+    assert!(matches!(run(">+++[-]+", WrapPolicy::Trap), (Ok(()), 1)));
+}
+
+#[test]
+fn gas_is_charged_per_instruction_kind() {
+    let run = |schedule| {
+        let mut interpreter = load("++[>+<-]>.");
+        interpreter.set_output(OutputBuffer::new());
+        interpreter.set_gas(1000, schedule);
+        interpreter.interpret().unwrap();
+        interpreter.gas_used()
+    };
+
+    // `++` 2, `[` once, 2 iterations of `>+<-` 4 each, `]` twice, `>` 1 and `.` 1.
+    assert_eq!(run(GasSchedule::default()), 15);
+    // `++` 3, `[` 5, 2 iterations of 1 + 3 + 1 + 3, `]` twice 5, `>` 1 and `.` 1.
+    assert_eq!(run(GasSchedule { arithmetic: 3, jump: 5, per_command: false, ..GasSchedule::default() }), 36);
+}
+
+#[test]
+fn out_of_gas_resumes_after_topping_up() {
+    let output = OutputBuffer::new();
+    let mut interpreter = load("++[>+<-]>.");
+    interpreter.set_output(output.clone());
+    interpreter.set_gas(10, GasSchedule::default());
+
+    assert!(matches!(interpreter.interpret(), Err(RuntimeError::OutOfGas { instruction: 4, .. })));
+    assert_eq!(interpreter.gas_used(), 10);
+    assert!(output.is_empty());
+
+    interpreter.add_gas(4);
+    assert!(matches!(interpreter.interpret(), Err(RuntimeError::OutOfGas { instruction: 8, .. })));
+    interpreter.add_gas(1);
+    interpreter.interpret().unwrap();
+    assert_eq!(interpreter.gas_used(), 15);
+    assert_eq!(output.take(), [2]);
+}
+
+#[test]
+fn step_back_restores_the_previous_states() {
+    let mut interpreter = load("+++>,[-<+>]");
+    // Input read once stays read, so the run after going back gets the second byte.
+    interpreter.set_input(std::collections::VecDeque::from(vec![7u8, 7]));
+    interpreter.set_history_limit(16);
+    let state = |interpreter: &Interpreter| (interpreter.instruction_pointer(), interpreter.memory_pointer(), interpreter.peek_range(0..3).unwrap().to_vec());
+
+    interpreter.step().unwrap();
+    let after_one = state(&interpreter);
+    interpreter.step().unwrap();
+    interpreter.step().unwrap();
+    assert_eq!(state(&interpreter), (3, 1, vec![3, 7, 0]));
+
+    assert!(interpreter.step_back());
+    assert_eq!(state(&interpreter), (2, 1, vec![3, 0, 0]));
+    assert!(interpreter.step_back());
+    assert_eq!(state(&interpreter), after_one);
+    assert_eq!(after_one, (1, 0, vec![3, 0, 0]));
+
+    // Running again from there gives the same result as a run that never went back.
+    interpreter.interpret().unwrap();
+    assert_eq!(state(&interpreter), (9, 1, vec![10, 0, 0]));
+}
+
+#[test]
+fn history_is_bounded() {
+    let mut interpreter = load("[-]+>[-]+>[-]+>[-]+");
+    interpreter.set_history_limit(2);
+    interpreter.interpret().unwrap();
+
+    assert!(interpreter.step_back());
+    assert!(interpreter.step_back());
+    assert!(!interpreter.step_back());
+    assert_eq!(interpreter.peek_range(0..4), Some(&[1, 1, 1, 0][..]));
+    // After the `FillRange` standing for the whole program, which runs as its instructions.
+    assert_eq!((interpreter.instruction_pointer(), interpreter.memory_pointer()), (6, 2));
+
+    let mut interpreter = load("+");
+    interpreter.step().unwrap();
+    assert!(!interpreter.step_back());
+}
+
+#[test]
+fn working_set_counts_the_cells_written() {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_cell_tracking(true);
+    let code = format!("+{}+{}-[-]", ">".repeat(500), ">".repeat(499));
+    interpreter.load_program_from_str(&code).unwrap();
+    interpreter.interpret().unwrap();
+
+    assert_eq!(interpreter.working_set_size(), 3);
+    assert_eq!(interpreter.high_water_mark(), Some(999));
+
+    let mut interpreter = load(">>>.<");
+    interpreter.set_output(OutputBuffer::new());
+    interpreter.interpret().unwrap();
+    assert_eq!((interpreter.working_set_size(), interpreter.high_water_mark()), (0, None));
+    interpreter.set_cell_tracking(true);
+    interpreter.load_program_from_str(">>>,").unwrap();
+    interpreter.set_input(std::collections::VecDeque::from(vec![0u8]));
+    interpreter.interpret().unwrap();
+    assert_eq!((interpreter.working_set_size(), interpreter.high_water_mark()), (1, Some(3)));
+}
+
+#[test]
+fn cells_read_and_written_are_told_apart() {
+    let bit = |set: &[u64], cell: usize| set[cell / 64] & 1 << (cell % 64) != 0;
+
+    let mut interpreter = Interpreter::new();
+    interpreter.set_cell_tracking(true);
+    interpreter.load_program_from_str(">>>.>>+").unwrap();
+    interpreter.set_output(OutputBuffer::new());
+    interpreter.interpret().unwrap();
+
+    let (read, written) = (interpreter.read_set().unwrap(), interpreter.write_set().unwrap());
+    assert_eq!((0..8).filter(|&cell| bit(read, cell)).collect::<Vec<_>>(), [3]);
+    assert_eq!((0..8).filter(|&cell| bit(written, cell)).collect::<Vec<_>>(), [5]);
+
+    // The loop tests cell 0, and moves it to cell 1.
+    interpreter.load_program_from_str("++[->+<]").unwrap();
+    interpreter.interpret().unwrap();
+    let (read, written) = (interpreter.read_set().unwrap(), interpreter.write_set().unwrap());
+    assert!(bit(read, 0) && !bit(read, 1));
+    assert!(bit(written, 0) && bit(written, 1));
+
+    // `,` reads the cell it targets, `+` and `-` only write theirs.
+    interpreter.load_program_from_str(">,>+>-").unwrap();
+    interpreter.set_input(std::collections::VecDeque::from(vec![b'a']));
+    interpreter.interpret().unwrap();
+    let (read, written) = (interpreter.read_set().unwrap(), interpreter.write_set().unwrap());
+    assert_eq!((0..8).filter(|&cell| bit(read, cell)).collect::<Vec<_>>(), [1]);
+    assert_eq!((0..8).filter(|&cell| bit(written, cell)).collect::<Vec<_>>(), [1, 2, 3]);
+    assert_eq!(load("+").read_set(), None);
+}
+
+#[test]
+fn read_sets_are_the_same_at_every_optimization_level() {
+    // Clears, a constant, a run of clears, a loop run once and a run of transfer loops.
+    let code = "++>+++[-]>[-]+++>>[-]>[-]>[-]>[-]<<<<<<++[>+<[-]]>>>>>>>+>+>+>+<<<[-<+>]>[-<+>]>[-<+>]>[-<+>].";
+    let read_sets = OptLevel::ALL.map(|opt_level| {
+        let mut interpreter = Interpreter::with_config(Config { opt_level, ..Default::default() });
+        interpreter.set_cell_tracking(true);
+        interpreter.set_output(OutputBuffer::new());
+        interpreter.load_program_from_str(code).unwrap();
+        interpreter.interpret().unwrap();
+        interpreter.read_set().unwrap().to_vec()
+    });
+    assert!(read_sets.iter().all(|set| *set == read_sets[0]), "{read_sets:?}");
+}
+
+#[test]
+fn prng_input_is_reproducible_from_its_seed() {
+    let run = |seed| {
+        let mut interpreter = load(",>,>,>,>,>,>,>,");
+        interpreter.set_input_prng(seed);
+        interpreter.interpret().unwrap();
+        interpreter.peek_range(0..8).unwrap().to_vec()
+    };
+
+    assert_eq!(run(42), run(42));
+    assert_ne!(run(42), run(43));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn listing_shows_folded_clear_loops() {
+    let interpreter = load("+++[-]>[>+<-]");
+    let path = std::env::temp_dir().join(format!("sac-listing-{}.txt", std::process::id()));
+    interpreter.write_listing(path.to_str().unwrap()).unwrap();
+    let listing = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let lines: Vec<&str> = listing.lines().collect();
+    assert!(lines[0].starts_with("     0  IncrementByte 3"), "{listing}");
+    assert!(lines[1].starts_with("     1  SetZero"), "{listing}");
+    assert!(lines[1].ends_with("; line 1, column 4 (3..6)"), "{listing}");
+    assert!(lines[3].starts_with("     3  JumpIfZero -> 8"), "{listing}");
+    assert!(lines[4].starts_with("     4    IncrementPointer 1"), "{listing}");
+    assert_eq!(lines.len(), 9);
+}
+
+#[test]
+fn clear_loop_then_streak_sets_a_constant() {
+    let mut interpreter = load("+++[-]+++++.");
+    let listing = interpreter.listing();
+    let lines: Vec<&str> = listing.lines().collect();
+    assert_eq!(lines.len(), 3, "{listing}");
+    assert!(lines[1].starts_with("     1  SetConst 5"), "{listing}");
+    assert!(lines[1].ends_with("(3..11)"), "{listing}");
+
+    let output = OutputBuffer::new();
+    interpreter.set_output(output.clone());
+    interpreter.interpret().unwrap();
+    assert_eq!(output.take(), [5]);
+
+    assert_eq!(load("[-]--").to_source(), "[-]--");
+    assert!(load("[-]>+").listing().contains("SetZero"));
+    assert!(load("[-]+.").listing().lines().nth(1).unwrap().contains("PrintByteAsChar"));
+}
+
+#[test]
+fn deeply_nested_loops_pair_and_run() {
+    const DEPTH: usize = 100_000;
+    let code = format!("+{}->+<{}>.", "[".repeat(DEPTH), "]".repeat(DEPTH));
+
+    let mut interpreter = load(&code);
+    for level in 0..DEPTH {
+        let (open, close) = (1 + level, 1 + 2 * DEPTH + 4 - 1 - level);
+        assert_eq!(interpreter.matching_bracket(open), Some(close));
+        assert_eq!(interpreter.matching_bracket(close), Some(open));
+    }
+
+    let loops = interpreter.loops();
+    assert_eq!(loops.len(), DEPTH);
+    assert_eq!(loops.last().unwrap().depth, DEPTH);
+
+    let output = OutputBuffer::new();
+    interpreter.set_output(output.clone());
+    interpreter.interpret().unwrap();
+    assert_eq!(output.take(), [1]);
+}
+
+#[test]
+fn loop_hook_sees_every_condition() {
+    let events = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let mut interpreter = load("+++[>+<-]>[-]");
+    let recorder = events.clone();
+    interpreter.set_loop_hook(move |event| recorder.lock().unwrap().push(event));
+    interpreter.interpret().unwrap();
+
+    assert_eq!(*events.lock().unwrap(), [
+        LoopEvent::Enter { loop_index: 1, cell: 3 },
+        LoopEvent::Exit { loop_index: 1, cell: 2 },
+        LoopEvent::Exit { loop_index: 1, cell: 1 },
+        LoopEvent::Exit { loop_index: 1, cell: 0 },
+    ]);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn saved_tapes_chain_programs() {
+    let path = std::env::temp_dir().join(format!("sac-tape-{}.bin", std::process::id()));
+    let path = path.to_str().unwrap();
+
+    let mut producer = load("+++>++++++>+");
+    producer.interpret().unwrap();
+    producer.save_tape(path).unwrap();
+
+    // Adds the first two cells into the third.
+    let mut consumer = load("[>>+<<-]>[>+<-]");
+    consumer.load_tape(path).unwrap();
+    consumer.interpret().unwrap();
+    assert_eq!(consumer.peek_range(0..4), Some(&[0, 0, 10, 0][..]));
+
+    let mut tiny = Interpreter::with_config(sac::interpreter::Config { tape_size: 2, ..Default::default() });
+    assert!(tiny.load_tape(path).is_err());
+    std::fs::remove_file(path).unwrap();
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn programs_over_the_size_limit_are_rejected_before_being_read() {
+    let path = std::env::temp_dir().join(format!("sac-huge-{}.bf", std::process::id()));
+    let path = path.to_str().unwrap();
+    // A sparse file of 64 GiB, which would run out of memory if it were read.
+    let file = std::fs::File::create(path).unwrap();
+    file.set_len(64 << 30).unwrap();
+
+    let mut interpreter = Interpreter::with_config(Config { max_source_bytes: Some(1024), ..Default::default() });
+    assert!(matches!(interpreter.load_program(path), Err(LoadError::ProgramTooLarge { limit: 1024 })));
+    file.set_len(0).unwrap();
+    std::fs::write(path, "+".repeat(1024)).unwrap();
+    interpreter.load_program(path).unwrap();
+    assert!(matches!(interpreter.load_program_from_str(&"+".repeat(1025)), Err(LoadError::ProgramTooLarge { limit: 1024 })));
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn breakpoint_tokens_pause_in_debug_mode() {
+    let config = sac::interpreter::Config { breakpoint_token: Some('!'), ..Default::default() };
+    let load = |debug| {
+        let mut interpreter = Interpreter::with_config(config);
+        interpreter.load_program_from_str("++!>+++\n!+").unwrap();
+        interpreter.set_debug_mode(debug);
+        interpreter
+    };
+
+    let mut interpreter = load(true);
+    assert_eq!(interpreter.resume().unwrap(), Pause::Breakpoint { instruction: 1, span: Span { start: 2, end: 3, line: 1, column: 3 } });
+    assert_eq!(interpreter.peek_range(0..2), Some(&[2, 0][..]));
+    assert!(matches!(interpreter.resume().unwrap(), Pause::Breakpoint { instruction: 4, .. }));
+    assert_eq!(interpreter.peek_range(0..2), Some(&[2, 3][..]));
+    assert_eq!(interpreter.resume().unwrap(), Pause::Halted);
+    assert_eq!(interpreter.peek_range(0..2), Some(&[2, 4][..]));
+
+    let mut interpreter = load(false);
+    assert_eq!(interpreter.resume().unwrap(), Pause::Halted);
+    assert_eq!(interpreter.peek_range(0..2), Some(&[2, 4][..]));
+}
+
+#[test]
+fn breakpoints_can_be_set_at_labels() {
+    let mut interpreter = load("++++\n  @label: main_loop\n[>+<-]\n@label: done\n>\n@label: done\n+.\n@label: end");
+    let labels = interpreter.labels();
+    assert_eq!(labels.iter().map(|label| label.name.as_str()).collect::<Vec<_>>(), ["main_loop", "done", "done", "end"]);
+    assert_eq!(labels[0].span, Span { start: 7, end: 24, line: 2, column: 3 });
+
+    // The later `done` wins, and `end` has no code after it.
+    let main_loop = interpreter.add_label_breakpoint("main_loop").unwrap();
+    let done = interpreter.add_label_breakpoint("done").unwrap();
+    assert_eq!((interpreter.add_label_breakpoint("end"), interpreter.add_label_breakpoint("missing")), (None, None));
+    interpreter.set_debug_mode(true);
+
+    assert_eq!(interpreter.resume().unwrap(), Pause::Breakpoint { instruction: main_loop, span: Span { start: 25, end: 26, line: 3, column: 1 } });
+    assert_eq!(interpreter.peek_range(0..2), Some(&[4, 0][..]));
+    assert!(matches!(interpreter.resume().unwrap(), Pause::Breakpoint { instruction, span: Span { line: 7, .. } } if instruction == done));
+    assert_eq!((interpreter.memory_pointer(), interpreter.peek(1)), (1, Some(4)));
+    assert_eq!(interpreter.resume().unwrap(), Pause::Halted);
+
+    interpreter.load_program_from_str("@label: start\n+").unwrap();
+    interpreter.set_debug_mode(true);
+    assert_eq!(interpreter.resume().unwrap(), Pause::Halted);
+}
+
+#[test]
+fn classic_profile_has_30000_cells_and_reads_0_at_end_of_input() {
+    let config = CompatProfile::Classic30k.config();
+    assert_eq!((config.tape_size, config.on_end_of_input), (30000, OnEndOfInput::Zero));
+
+    let mut interpreter = Interpreter::with_config(config);
+    interpreter.set_input(std::collections::VecDeque::from(b"x".to_vec()));
+    interpreter.load_program_from_str("+,>+,").unwrap();
+    interpreter.interpret().unwrap();
+    assert_eq!(interpreter.peek_range(0..2), Some(&[b'x', 0][..]));
+
+    interpreter.load_program_from_str("+[>+]").unwrap();
+    assert!(matches!(interpreter.interpret(), Err(RuntimeError::PointerOverflow { .. })));
+    assert_eq!(interpreter.memory_pointer(), 29999);
+
+    // Other interpreters leave the cell alone, or store -1.
+    for (on_end_of_input, cell) in [(OnEndOfInput::Unchanged, 7), (OnEndOfInput::MinusOne, 255)] {
+        let mut interpreter = Interpreter::with_config(Config { on_end_of_input, ..Config::default() });
+        interpreter.set_input(std::collections::VecDeque::new());
+        interpreter.load_program_from_str("+++++++,").unwrap();
+        interpreter.interpret().unwrap();
+        assert_eq!(interpreter.peek(0), Some(cell));
+    }
+}
+
+#[test]
+fn run_to_output_stops_after_each_byte() {
+    let mut interpreter = load("++++++++[>+++++++++<-]>.+++++++++++++++++++++++++++++++++.>");
+    let output = OutputBuffer::new();
+    interpreter.set_output(output.clone());
+
+    assert_eq!(interpreter.run_to_output().unwrap(), Some(b'H'));
+    assert_eq!(output.take(), b"H");
+    assert_eq!(interpreter.run_to_output().unwrap(), Some(b'i'));
+    assert_eq!(interpreter.run_to_output().unwrap(), None);
+    assert_eq!(output.take(), b"i");
+}
+
+#[test]
+fn output_maps_translate_printed_bytes() {
+    let rot13: [u8; 256] = core::array::from_fn(|byte| match byte as u8 {
+        c @ (b'a'..=b'z' | b'A'..=b'Z') => {
+            let base = if c.is_ascii_lowercase() { b'a' } else { b'A' };
+            (c - base + 13) % 26 + base
+        },
+        c => c,
+    });
+    // Prints "Hi!", as in `run_to_output_stops_after_each_byte`.
+    let mut interpreter = load("++++++++[>+++++++++<-]>.+++++++++++++++++++++++++++++++++.>>+++++[<++++++>-]<+++.");
+    let output = OutputBuffer::new();
+    interpreter.set_output(output.clone());
+    interpreter.set_output_map(rot13);
+    assert_eq!(interpreter.run_to_output().unwrap(), Some(b'U'));
+    interpreter.interpret().unwrap();
+    assert_eq!(output.take(), b"Uv!");
+
+    // The identity map turns it off.
+    interpreter.set_output_map(core::array::from_fn(|byte| byte as u8));
+    interpreter.load_program_from_str("++++++++[>++++++++<-]>+.").unwrap();
+    interpreter.interpret().unwrap();
+    assert_eq!(output.take(), b"A");
+}
+
+#[test]
+fn tape_modes_handle_the_edges() {
+    // Tapes of 4 cells, `>` on the last cell and `<` on cell 0.
+    let run = |code: &str, tape_mode: TapeMode| {
+        let mut interpreter = Interpreter::with_config(Config { tape_size: 4, tape_mode, ..Config::default() });
+        interpreter.load_program_from_str(code).unwrap();
+        interpreter.interpret().map(|()| (interpreter.memory_pointer(), interpreter.peek_range(0..4).unwrap().to_vec()))
+    };
+
+    assert!(matches!(run(">>>>", TapeMode::Error), Err(RuntimeError::PointerOverflow { instruction: 0, .. })));
+    assert!(matches!(run(">>>+>", TapeMode::Error), Err(RuntimeError::PointerOverflow { instruction: 2, .. })));
+    assert!(matches!(run("<", TapeMode::Error), Err(RuntimeError::PointerUnderflow { instruction: 0, .. })));
+
+    assert_eq!(run(">>>+>+", TapeMode::Saturate).unwrap(), (3, vec![0, 0, 0, 2]));
+    assert_eq!(run(">>>>>>+", TapeMode::Saturate).unwrap(), (3, vec![0, 0, 0, 1]));
+    assert_eq!(run("<+<<+", TapeMode::Saturate).unwrap(), (0, vec![2, 0, 0, 0]));
+
+    assert_eq!(run(">>>+>+", TapeMode::Wrap).unwrap(), (0, vec![1, 0, 0, 1]));
+    assert_eq!(run(">>>>>>+", TapeMode::Wrap).unwrap(), (2, vec![0, 0, 1, 0]));
+    assert_eq!(run("<+<<<<<+", TapeMode::Wrap).unwrap(), (2, vec![0, 0, 1, 1]));
+
+    // Ranges running off the tape fall back to the moves they stand for.
+    let clears = "+>+>+>+[-]>[-]>[-]";
+    assert!(matches!(run(clears, TapeMode::Error), Err(RuntimeError::PointerOverflow { .. })));
+    assert_eq!(run(clears, TapeMode::Saturate).unwrap(), (3, vec![1, 1, 1, 0]));
+    assert_eq!(run(clears, TapeMode::Wrap).unwrap(), (1, vec![0, 0, 1, 0]));
+}
+
+#[test]
+fn utf8_io_round_trips_text() {
+    let run = |code: &str, invalid: InvalidUtf8, input: &[u8]| {
+        let mut interpreter = load(code);
+        interpreter.set_utf8_io(Some(invalid));
+        interpreter.compile().unwrap().run(input)
+    };
+    let cat = ",[.,]";
+
+    let text = "héllo → 世界 🦀";
+    assert_eq!(run(cat, InvalidUtf8::Replace, format!("{text}\0").as_bytes()).unwrap(), text.as_bytes());
+    assert_eq!(run(cat, InvalidUtf8::Fail, format!("{text}\0").as_bytes()).unwrap(), text.as_bytes());
+
+    // A stray byte, and a character cut short by the next one.
+    assert_eq!(run(cat, InvalidUtf8::Replace, b"a\xffb\xe2\x82\0").unwrap(), "a\u{fffd}b\u{fffd}".as_bytes());
+    assert!(matches!(run(cat, InvalidUtf8::Fail, b"a\xff\0"), Err(RuntimeError::InvalidUtf8Input { instruction: 3, .. })));
+    // The input ending mid-character.
+    assert!(matches!(run(cat, InvalidUtf8::Replace, b"\xe4\xb8"), Err(RuntimeError::EndOfInput { .. })));
+
+    // 0xc3 starts a character the program never completes.
+    let truncated = format!("{}.", "-".repeat(61));
+    assert_eq!(run(&truncated, InvalidUtf8::Replace, b"").unwrap(), "\u{fffd}".as_bytes());
+    assert!(matches!(run(&truncated, InvalidUtf8::Fail, b""), Err(RuntimeError::InvalidUtf8Output { instruction: 1, .. })));
+}
+
+#[test]
+fn coverage_reports_instructions_never_run() {
+    // The loop is skipped, its cell being 0.
+    let mut interpreter = Interpreter::new();
+    interpreter.set_coverage_tracking(true);
+    interpreter.load_program_from_str("+>[-.]<.").unwrap();
+    interpreter.set_output(OutputBuffer::new());
+    assert_eq!(interpreter.coverage().unwrap().covered_count(), 0);
+
+    interpreter.interpret().unwrap();
+    let coverage = interpreter.coverage().unwrap();
+    assert_eq!(coverage.uncovered().collect::<Vec<_>>(), vec![3, 4, 5]);
+    assert!(coverage.is_covered(2) && !coverage.is_covered(8));
+    assert_eq!(coverage.percent(), 62.5);
+
+    interpreter.load_program_from_str("+").unwrap();
+    assert_eq!((interpreter.coverage().unwrap().len(), interpreter.coverage().unwrap().covered_count()), (1, 0));
+    assert_eq!(load("+").coverage(), None);
+}
+
+#[test]
+fn paused_programs_can_be_edited() {
+    // 5 times 13 is 'A', printed once a `.` is appended while the loop runs.
+    let mut interpreter = load("+++++[>+++++++++++++<-]>");
+    let output = OutputBuffer::new();
+    interpreter.set_output(output.clone());
+    assert_eq!(interpreter.run_for(10).unwrap(), StepOutcome::BudgetExhausted { steps_run: 10 });
+    interpreter.edit_program("+++++[>+++++++++++++<-]>.").unwrap();
+    interpreter.interpret().unwrap();
+    assert_eq!(output.take(), b"A");
+
+    // Edits before the instruction pointer shift it, edits from it on run next.
+    let mut interpreter = load("+.>+");
+    interpreter.set_output(output.clone());
+    interpreter.run_for(2).unwrap();
+    assert_eq!(interpreter.instruction_pointer(), 2);
+    interpreter.edit_program("-->+<+.>+").unwrap();
+    assert_eq!((interpreter.instruction_pointer(), interpreter.peek(0)), (6, Some(1)));
+    interpreter.edit_program("-->+<+.>++.").unwrap();
+    interpreter.interpret().unwrap();
+    assert_eq!(output.take(), b"\x01\x02");
+
+    // The instruction about to run can't be rewritten, nor merged with the code before it.
+    let mut interpreter = load("+>+");
+    interpreter.run_for(1).unwrap();
+    assert!(matches!(interpreter.edit_program("-<+"), Err(LoadError::EditAtInstructionPointer { instruction: 1, .. })));
+    assert!(matches!(interpreter.edit_program("++>+"), Err(LoadError::EditAtInstructionPointer { instruction: 0, .. })));
+    assert!(matches!(interpreter.edit_program("+>+]"), Err(LoadError::UnmatchedBracket { .. })));
+    assert_eq!((interpreter.to_source().as_str(), interpreter.instruction_pointer()), ("+>+", 1));
+}
+
+// Mirrors the fuzz targets with a fixed seed, so that what they found stays fixed.
+#[test]
+fn hostile_programs_never_panic() {
+    let mut rng = sac::io::PrngInput::new(7);
+    let mut byte = || rng.next_u64() as u8;
+
+    for case in 0..500 {
+        let length = byte() as usize;
+        let bytes: Vec<u8> = (0..length).map(|_| if case % 2 == 0 { b"+-<>[].,@!"[byte() as usize % 10] } else { byte() }).collect();
+
+        let mut interpreter = Interpreter::with_config(sac::interpreter::Config { tape_size: 64, ..Default::default() });
+        interpreter.set_step_limit(10_000);
+        interpreter.set_input_prng(case);
+        interpreter.set_output(OutputBuffer::new());
+        if interpreter.load_program_from_bytes(&bytes).is_ok() {
+            let _ = interpreter.interpret();
+        }
+    }
+
+    let mut interpreter = Interpreter::new();
+    assert!(matches!(interpreter.load_program_from_bytes(b"+\xff["), Err(LoadError::InvalidUtf8 { offset: 1 })));
+    assert!(matches!(interpreter.load_program_from_bytes("]".repeat(100_000).as_bytes()), Err(LoadError::UnmatchedBracket { instruction: 0, .. })));
+    assert!(matches!(interpreter.load_program_from_bytes("[".repeat(100_000).as_bytes()), Err(LoadError::UnmatchedBracket { instruction: 99_999, .. })));
+    interpreter.load_program_from_bytes(&[b'>'; 1 << 20]).unwrap();
+    assert!(matches!(interpreter.interpret(), Err(RuntimeError::PointerOverflow { .. })));
+}
+
+#[test]
+fn decimal_number_input_mode() {
+    let mut interpreter = load(",>,>,>,");
+    interpreter.set_input_mode(InputMode::DecimalNumber);
+    interpreter.set_input(std::collections::VecDeque::from(b"65 x 7,300\n2".to_vec()));
+    interpreter.interpret().unwrap();
+    assert_eq!(interpreter.peek_range(0..4), Some(&[65, 7, 44, 2][..]));
+
+    let mut interpreter = load(",");
+    interpreter.set_input_mode(InputMode::DecimalNumber);
+    interpreter.set_input(std::collections::VecDeque::from(b" \n".to_vec()));
+    assert!(matches!(interpreter.interpret(), Err(RuntimeError::EndOfInput { .. })));
+}
+
+#[test]
+fn non_ascii_cells_can_print_as_placeholders() {
+    // Cell 0 holds 200, cell 1 holds 'A'.
+    let code = "++++++++++[>++++++++++<-]>[<++>-]+++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++.<.>.";
+    let print = |non_ascii| {
+        let output = OutputBuffer::new();
+        let mut interpreter = load(code);
+        interpreter.set_non_ascii(non_ascii);
+        interpreter.set_output(output.clone());
+        interpreter.interpret().unwrap();
+        output.take()
+    };
+
+    assert_eq!(print(NonAscii::Utf8), "A\u{c8}A".as_bytes());
+    assert_eq!(print(NonAscii::Placeholder(b'.')), b"A.A");
+    assert_eq!(print(NonAscii::Placeholder(b'?')), b"A?A");
+    assert_eq!(print(NonAscii::Escape), b"A\\xc8A");
+}
+
+#[test]
+fn bells_can_be_suppressed_or_replaced() {
+    // Prints 'A', BEL and 'B'.
+    let code = "+++++++[>++++++++++<-]>-----.<+++++++.>+.";
+    let print = |bell| {
+        let output = OutputBuffer::new();
+        let mut interpreter = load(code);
+        interpreter.set_bell(bell);
+        interpreter.set_output(output.clone());
+        interpreter.interpret().unwrap();
+        output.take()
+    };
+
+    assert_eq!(print(Bell::PassThrough), b"A\x07B");
+    assert_eq!(print(Bell::Suppress), b"AB");
+    assert_eq!(print(Bell::Replace(b'*')), b"A*B");
+}
+
+struct Double;
+
+impl InstructionHandler for Double {
+    fn execute(&mut self, machine: Machine<'_>) -> Result<(), IoError> {
+        machine.tape[*machine.pointer] = machine.tape[*machine.pointer].wrapping_mul(2);
+        Ok(())
+    }
+}
+
+#[test]
+fn custom_handlers_run_registered_tokens() {
+    let mut interpreter = Interpreter::new();
+    interpreter.register_handler('*', Double);
+    interpreter.register_handler('+', Double); // Core commands can't be overridden.
+    interpreter.load_program_from_str("+++* >+ ** [-<*>]").unwrap();
+    interpreter.interpret().unwrap();
+
+    assert_eq!(interpreter.peek_range(0..2), Some(&[96, 0][..]));
+    assert_eq!(interpreter.to_source(), "+++*>+**[-<*>]");
+
+    let mut interpreter = Interpreter::new();
+    interpreter.register_handler('~', Double);
+    interpreter.load_program_from_str("+*~").unwrap();
+    interpreter.interpret().unwrap();
+    assert_eq!(interpreter.peek(0), Some(2));
+}
+
+#[test]
+fn columns_follow_tab_stops() {
+    let load = |tab_width| {
+        let mut interpreter = Interpreter::with_config(sac::interpreter::Config { tab_width, opt_level: sac::optimizer::OptLevel::O0, ..Default::default() });
+        interpreter.load_program_from_str("+\n\t[-\t]\n  \t<").unwrap();
+        interpreter
+    };
+    assert_eq!(load(1).loops()[0].span.column, 2);
+    assert_eq!(load(4).loops()[0].span.column, 5);
+    assert_eq!(load(8).loops()[0].span.column, 9);
+
+    let error = load(4).interpret().unwrap_err();
+    assert_eq!(error.span().map(|s| (s.line, s.column)), Some((3, 5)));
+    assert_eq!(load(1).interpret().unwrap_err().span().map(|s| s.column), Some(4));
+}
+
+#[test]
+fn slices_behave_like_one_run() {
+    let code = "++++[>++++++++<-]>.-[>-[>+>+<<-]>.<<-]>>>.";
+    let whole_output = OutputBuffer::new();
+    let mut whole = load(code);
+    whole.set_output(whole_output.clone());
+    whole.interpret().unwrap();
+
+    let output = OutputBuffer::new();
+    let mut sliced = load(code);
+    sliced.set_output(output.clone());
+    let mut slices = 0;
+    let summary = loop {
+        match sliced.run_for(1000).unwrap() {
+            StepOutcome::BudgetExhausted { steps_run } => assert_eq!(steps_run, 1000),
+            StepOutcome::Completed(summary) => break summary,
+            StepOutcome::NeedsInput => unreachable!(),
+        }
+        slices += 1;
+    };
+
+    assert!(slices > 3, "{slices}");
+    assert_eq!(summary, RunSummary { steps: whole.steps() });
+    assert_eq!(output.take(), whole_output.take());
+    assert_eq!(sliced.peek_range(0..5), whole.peek_range(0..5));
+    assert_eq!(sliced.run_for(1000).unwrap(), StepOutcome::Completed(summary));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn instructions_per_second_are_the_steps_over_the_run_time() {
+    let mut interpreter = load("-[>-[>>+<<-]>[-]<<-]");
+    assert_eq!(interpreter.instructions_per_second(), None);
+    assert_eq!(interpreter.run_for(100).unwrap(), StepOutcome::BudgetExhausted { steps_run: 100 });
+    let first_slice = interpreter.run_time();
+    interpreter.interpret().unwrap();
+
+    let (steps, run_time) = (interpreter.steps(), interpreter.run_time());
+    assert!(run_time > first_slice);
+    let ips = interpreter.instructions_per_second().unwrap();
+    assert!(ips > 0.0);
+    assert!((ips * run_time.as_secs_f64() - steps as f64).abs() < 1e-6 * steps as f64);
+
+    interpreter.load_program_from_str("+").unwrap();
+    assert_eq!((interpreter.run_time(), interpreter.instructions_per_second()), (Duration::ZERO, None));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn run_for_waits_for_input_that_would_block() {
+    use std::collections::VecDeque;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct Pipe(Arc<Mutex<VecDeque<u8>>>);
+
+    impl std::io::Read for Pipe {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            match self.0.lock().unwrap().pop_front() {
+                Some(byte) => {
+                    buf[0] = byte;
+                    Ok(1)
+                },
+                None => Err(std::io::ErrorKind::WouldBlock.into()),
+            }
+        }
+    }
+
+    let pipe = Pipe::default();
+    let output = OutputBuffer::new();
+    let mut interpreter = load(",[.,]");
+    interpreter.set_input(pipe.clone());
+    interpreter.set_output(output.clone());
+
+    assert_eq!(interpreter.run_for(100).unwrap(), StepOutcome::NeedsInput);
+    assert_eq!(interpreter.run_for(100).unwrap(), StepOutcome::NeedsInput);
+    pipe.0.lock().unwrap().extend(b"ab");
+    assert_eq!(interpreter.run_for(100).unwrap(), StepOutcome::NeedsInput);
+    assert_eq!(output.take(), b"ab");
+    pipe.0.lock().unwrap().extend(b"c\0");
+
+    // Each `,` is counted once however many times its read was retried.
+    assert_eq!(interpreter.run_for(100).unwrap(), StepOutcome::Completed(RunSummary { steps: 11 }));
+    assert_eq!(output.take(), b"c");
+}
+
+#[test]
+fn testing_run_captures_the_output() {
+    assert_eq!(testing::run(",[.,]", b"cat\0"), b"cat");
+    assert_eq!(testing::run("++++++++[>++++++++<-]>+.", b""), b"A");
+}
+
+#[test]
+#[should_panic(expected = "Memory pointer underflow")]
+fn testing_run_panics_on_errors() {
+    testing::run("<", b"");
+}
+
+#[test]
+fn compiled_programs_run_on_fresh_tapes() {
+    // Prints its input with every byte incremented, up to the first 0.
+    let mut interpreter = load(",[+.,]");
+    let compiled = interpreter.compile().unwrap();
+    interpreter.load_program_from_str("+.").unwrap();
+
+    assert_eq!(compiled.run(b"HAL\0").unwrap(), b"IBM");
+    assert_eq!(compiled.clone().run(b"abc\0").unwrap(), b"bcd");
+    assert_eq!(compiled.run(b"\0").unwrap(), b"");
+    assert!(matches!(compiled.run(b"x"), Err(RuntimeError::EndOfInput { .. })));
+
+    // Handlers stay with their interpreter.
+    let mut interpreter = Interpreter::new();
+    interpreter.register_handler('*', Double);
+    interpreter.load_program_from_str("+*.").unwrap();
+    assert!(interpreter.compile().is_none());
+}
+
+#[test]
+fn io_can_be_forbidden() {
+    let mut interpreter = Interpreter::with_config(sac::interpreter::Config { forbid_io: true, ..Default::default() });
+
+    let rejected = interpreter.load_program_from_str("++ multiply\n[>+++<-]>.");
+    assert!(matches!(rejected, Err(LoadError::IoForbidden { instruction: 8, span: Span { line: 2, column: 10, .. } })), "{rejected:?}");
+    assert!(matches!(interpreter.load_program_from_str(","), Err(LoadError::IoForbidden { instruction: 0, .. })));
+
+    interpreter.load_program_from_str("++[>+++<-]>").unwrap();
+    interpreter.interpret().unwrap();
+    assert_eq!(interpreter.peek(1), Some(6));
+}
+
+#[test]
+fn tape_ranges_read_as_strings() {
+    // "Hi" in cells 0 and 1, then a 0 cell and "!".
+    let mut interpreter = load(&format!("{}>{}>>{}", "+".repeat(72), "+".repeat(105), "+".repeat(33)));
+    interpreter.interpret().unwrap();
+
+    assert_eq!(interpreter.tape_as_string(0..2, false), Ok(String::from("Hi")));
+    assert_eq!(interpreter.tape_as_string(0..4, true), Ok(String::from("Hi")));
+    assert_eq!(interpreter.tape_as_string(0..4, false), Ok(String::from("Hi\0!")));
+    let size = interpreter.tape_size();
+    assert_eq!(interpreter.tape_as_string(0..size + 1, false), Err(TapeStringError::OutOfRange { end: size + 1, tape_size: size }));
+
+    let mut interpreter = load("+>-");
+    interpreter.interpret().unwrap();
+    assert_eq!(interpreter.tape_as_string(0..2, false), Err(TapeStringError::InvalidUtf8 { cell: 1 }));
+}
+
+#[test]
+fn tape_sizes_can_be_inferred_from_the_program() {
+    let mut interpreter = Interpreter::with_config(sac::interpreter::Config { infer_tape_size: true, ..Default::default() });
+
+    interpreter.load_program_from_str("+>+[>++<-]").unwrap();
+    assert_eq!(interpreter.tape_size(), 3 + 16);
+    interpreter.interpret().unwrap();
+    assert_eq!(interpreter.peek(2), Some(2));
+
+    // The pointer moves by an unknown amount, the configured size is kept.
+    interpreter.load_program_from_str("+[>+]").unwrap();
+    assert_eq!(interpreter.tape_size(), sac::interpreter::Config::default().tape_size);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn output_buffer_stays_within_its_bound_with_a_slow_sink() {
+    use std::sync::{Arc, Mutex};
+
+    // Takes its time over every write, recording the size of each.
+    #[derive(Clone, Default)]
+    struct SlowSink(Arc<Mutex<(Vec<u8>, Vec<usize>)>>);
+
+    impl std::io::Write for SlowSink {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            std::thread::sleep(Duration::from_micros(200));
+            let (bytes, writes) = &mut *self.0.lock().unwrap();
+            bytes.extend_from_slice(buf);
+            writes.push(buf.len());
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    // 1000 bytes, then a prompt before reading.
+    let sink = SlowSink::default();
+    let mut interpreter = load("++++++++[>++++++++<-]>+>++++++++++[>++++++++++[>++++++++++[<<<.>>>-]<-]<-]<+.,.");
+    interpreter.set_output_buffer_size(64);
+    interpreter.set_output(sink.clone());
+    interpreter.set_input(std::io::Cursor::new(b"!".to_vec()));
+    interpreter.interpret().unwrap();
+
+    let (bytes, writes) = std::mem::take(&mut *sink.0.lock().unwrap());
+    assert_eq!(bytes.len(), 1002);
+    assert!(bytes[..1000].iter().all(|&b| b == b'A') && bytes.ends_with(b"B!"));
+    assert!(writes.iter().all(|&size| size <= 64), "{writes:?}");
+    // Full buffers, then the rest before the `,`, and the last byte once the program halted.
+    assert_eq!(writes.len(), 1000 / 64 + 2);
+    assert_eq!(writes[writes.len() - 2..], [1000 % 64 + 1, 1]);
+}
+
+#[test]
+fn tape_windows_follow_the_pointer_and_pad_the_edges() {
+    let mut interpreter = Interpreter::with_config(sac::interpreter::Config { tape_size: 8, ..Default::default() });
+    interpreter.load_program_from_str("+>++>+++>++++<<<").unwrap();
+    interpreter.interpret().unwrap();
+
+    // At cell 0, two cells of padding come before it.
+    assert_eq!(interpreter.tape_window(5), TapeWindow { cells: vec![0, 0, 1, 2, 3], cursor: 2, first_cell: -2 });
+
+    interpreter.load_program_from_str("+>++>+++>++++>>>>+++++++").unwrap();
+    interpreter.interpret().unwrap();
+    assert_eq!(interpreter.tape_window(5), TapeWindow { cells: vec![0, 0, 7, 0, 0], cursor: 2, first_cell: 5 });
+    assert_eq!(interpreter.tape_window(4).cells, [0, 0, 7, 0]);
+    // At the last cell, and wider than the tape.
+    let window = interpreter.tape_window(20);
+    assert_eq!((window.cursor, window.first_cell), (10, -3));
+    assert_eq!(window.cells, [0, 0, 0, 1, 2, 3, 4, 0, 0, 0, 7, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+}
@@ -0,0 +1,55 @@
+#![cfg(feature = "std")]
+
+use std::collections::VecDeque;
+use std::io::{self, Read};
+
+use sac::buffer::OutputBuffer;
+use sac::interpreter::Interpreter;
+use sac::line::{LineEditor, LineInput, LineSource};
+
+struct Lines(VecDeque<&'static str>);
+
+impl LineSource for Lines {
+    fn read_line(&mut self) -> io::Result<Option<String>> {
+        Ok(self.0.pop_front().map(String::from))
+    }
+}
+
+#[test]
+fn lines_are_delivered_a_byte_at_a_time_with_a_newline() {
+    let mut input = LineInput::new(Lines(VecDeque::from(["hi", "", "é"])));
+    let mut byte = [0u8; 1];
+    let mut read = |input: &mut LineInput<Lines>| (input.read(&mut byte).unwrap() > 0).then_some(byte[0]);
+
+    assert_eq!(read(&mut input), Some(b'h'));
+    // The next line is only asked for once this one was taken whole.
+    assert_eq!(input.source().0.len(), 2);
+    assert_eq!([read(&mut input), read(&mut input)], [Some(b'i'), Some(b'\n')]);
+    assert_eq!(read(&mut input), Some(b'\n'));
+    assert_eq!(input.source().0.len(), 1);
+    assert_eq!([read(&mut input), read(&mut input), read(&mut input)], [Some(0xc3), Some(0xa9), Some(b'\n')]);
+    assert_eq!(read(&mut input), None);
+    assert_eq!(read(&mut input), None);
+
+    // Echoes the first line, up to its newline.
+    let mut interpreter = Interpreter::new();
+    interpreter.load_program_from_str(",----------[++++++++++.,----------]").unwrap();
+    let output = OutputBuffer::new();
+    interpreter.set_output(output.clone());
+    interpreter.set_input(LineInput::new(Lines(VecDeque::from(["Hello", "world"]))));
+    interpreter.interpret().unwrap();
+    assert_eq!(output.take(), b"Hello");
+}
+
+#[test]
+fn the_editor_applies_keys_and_keeps_history() {
+    // Backspace, then Left and an insertion, then Up twice, Down and an edit of the recalled line.
+    let keys: &[u8] = b"abx\x7fc\n\x1b[Dz\x1b[D\x1b[D_\r\x1b[A\x1b[A\x1b[B!\n\x04";
+    let mut editor = LineEditor::new(keys, Vec::new());
+
+    assert_eq!(editor.read_line().unwrap().as_deref(), Some("abc"));
+    assert_eq!(editor.read_line().unwrap().as_deref(), Some("_z"));
+    assert_eq!(editor.read_line().unwrap().as_deref(), Some("_z!"));
+    assert_eq!(editor.read_line().unwrap(), None);
+    assert_eq!(editor.history(), ["abc", "_z", "_z!"]);
+}
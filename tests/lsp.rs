@@ -0,0 +1,81 @@
+#![cfg(feature = "lsp")]
+
+use std::io::{BufRead, BufReader, Read};
+
+use sac::json::{self, Value};
+use sac::lsp;
+
+fn message(body: &str) -> String {
+    format!("Content-Length: {}\r\n\r\n{body}", body.len())
+}
+
+// Runs a scripted session and returns every message the server sent back.
+fn session(requests: &[&str]) -> Vec<Value> {
+    let input: String = requests.iter().map(|r| message(r)).collect();
+    let mut output = Vec::new();
+    lsp::run(input.as_bytes(), &mut output).unwrap();
+
+    let mut reader = BufReader::new(&output[..]);
+    let mut messages = Vec::new();
+    let mut header = String::new();
+    while reader.read_line(&mut header).unwrap() > 0 {
+        let length: usize = header.trim_end().trim_start_matches("Content-Length: ").parse().unwrap();
+        reader.read_line(&mut header).unwrap();
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body).unwrap();
+        messages.push(json::parse(std::str::from_utf8(&body).unwrap()).unwrap());
+        header.clear();
+    }
+    messages
+}
+
+fn diagnostics(message: &Value) -> &[Value] {
+    message.get("params").and_then(|p| p.get("diagnostics")).and_then(Value::as_array).unwrap()
+}
+
+#[test]
+fn diagnostics_appear_and_clear_after_a_fix() {
+    let messages = session(&[
+        r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{}}"#,
+        r#"{"jsonrpc":"2.0","method":"textDocument/didOpen","params":{"textDocument":{"uri":"file:///a.bf","text":"+\n[->+<"}}}"#,
+        r#"{"jsonrpc":"2.0","method":"textDocument/didChange","params":{"textDocument":{"uri":"file:///a.bf"},"contentChanges":[{"text":"+\n[->+<]"}]}}"#,
+        r#"{"jsonrpc":"2.0","method":"exit"}"#,
+    ]);
+
+    assert_eq!(messages.len(), 3);
+    assert!(messages[0].get("result").and_then(|r| r.get("capabilities")).is_some());
+
+    let opened = diagnostics(&messages[1]);
+    assert_eq!(opened.len(), 1);
+    let start = opened[0].get("range").and_then(|r| r.get("start")).unwrap();
+    assert_eq!((start.get("line").and_then(Value::as_u64), start.get("character").and_then(Value::as_u64)), (Some(1), Some(0)));
+
+    assert!(diagnostics(&messages[2]).is_empty());
+}
+
+#[test]
+fn hover_and_symbols_describe_loops() {
+    let messages = session(&[
+        r#"{"jsonrpc":"2.0","method":"textDocument/didOpen","params":{"textDocument":{"uri":"file:///b.bf","text":"[-]\n[>[-]<]"}}}"#,
+        r#"{"jsonrpc":"2.0","id":2,"method":"textDocument/hover","params":{"textDocument":{"uri":"file:///b.bf"},"position":{"line":1,"character":6}}}"#,
+        r#"{"jsonrpc":"2.0","id":3,"method":"textDocument/documentSymbol","params":{"textDocument":{"uri":"file:///b.bf"}}}"#,
+    ]);
+
+    let hover = messages[1].get("result").and_then(|r| r.get("contents")).and_then(|c| c.get("value")).and_then(Value::as_str).unwrap();
+    assert!(hover.contains("line 2, column 1"), "{hover}");
+    assert!(hover.contains("5 instructions"), "{hover}");
+
+    let symbols = messages[2].get("result").and_then(Value::as_array).unwrap();
+    let names: Vec<_> = symbols.iter().map(|s| s.get("name").and_then(Value::as_str).unwrap()).collect();
+    assert_eq!(names, ["Loop at line 1", "Loop at line 2"]);
+}
+
+#[test]
+fn lints_are_published_as_warnings() {
+    let messages = session(&[
+        r#"{"jsonrpc":"2.0","method":"textDocument/didOpen","params":{"textDocument":{"uri":"file:///c.bf","text":"+[]\n]"}}}"#,
+    ]);
+
+    let found: Vec<_> = diagnostics(&messages[0]).iter().map(|d| (d.get("code").and_then(Value::as_str).unwrap(), d.get("severity").and_then(Value::as_u64).unwrap())).collect();
+    assert_eq!(found, [("unmatched-bracket", 1), ("empty-loop", 2)]);
+}
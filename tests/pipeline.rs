@@ -0,0 +1,248 @@
+use sac::error::InvalidProgram;
+use sac::buffer::OutputBuffer;
+use sac::interpreter::{Config, IRInstruction, IRInstructionKind, Interpreter, Program, Segment, Span};
+use sac::optimizer::{MissedOptimization, OptLevel, Pass, PassReport, PassStats, Pipeline};
+
+struct StripBreakpoints;
+
+impl Pass for StripBreakpoints {
+    fn name(&self) -> &str {
+        "strip-breakpoints"
+    }
+
+    fn run(&self, program: &mut Program) -> PassStats {
+        let before = program.instructions.len();
+        program.instructions.retain(|inst| inst.kind != IRInstructionKind::Breakpoint);
+        program.link().unwrap();
+        PassStats { rewrites: before - program.instructions.len() }
+    }
+}
+
+fn instruction(kind: IRInstructionKind, operand: Option<usize>, start: usize) -> IRInstruction {
+    IRInstruction { kind, operand, span: Span { start, end: start + 1, line: 1, column: start + 1 } }
+}
+
+#[test]
+fn custom_passes_run_after_the_built_in_ones() {
+    let mut pipeline = Pipeline::for_level(OptLevel::O1);
+    pipeline.push(Box::new(StripBreakpoints));
+    assert_eq!(pipeline.names(), ["fold-clear-loops", "fold-set-constants", "fold-if-loops", "fold-ranges", "strip-breakpoints"]);
+
+    let mut interpreter = Interpreter::with_config(Config { breakpoint_token: Some('#'), ..Config::default() });
+    interpreter.set_pipeline(pipeline);
+    interpreter.load_program_from_str("+#[-]#>[-]++.").unwrap();
+
+    let listing = interpreter.listing();
+    assert!(!listing.contains("Breakpoint"), "{listing}");
+    assert_eq!(listing.lines().count(), 5, "{listing}");
+    assert_eq!(interpreter.pass_reports(), [
+        PassReport { name: String::from("fold-clear-loops"), stats: PassStats { rewrites: 2 }, instructions_before: 12, instructions_after: 8, missed: Vec::new() },
+        PassReport { name: String::from("fold-set-constants"), stats: PassStats { rewrites: 1 }, instructions_before: 8, instructions_after: 7, missed: Vec::new() },
+        PassReport { name: String::from("fold-if-loops"), stats: PassStats { rewrites: 0 }, instructions_before: 7, instructions_after: 7, missed: Vec::new() },
+        PassReport { name: String::from("fold-ranges"), stats: PassStats { rewrites: 0 }, instructions_before: 7, instructions_after: 7, missed: Vec::new() },
+        PassReport { name: String::from("strip-breakpoints"), stats: PassStats { rewrites: 2 }, instructions_before: 7, instructions_after: 5, missed: Vec::new() },
+    ]);
+}
+
+#[test]
+fn validate_checks_the_invariants() {
+    let mut program = Program {
+        instructions: vec![
+            instruction(IRInstructionKind::IncrementByte, Some(2), 0),
+            instruction(IRInstructionKind::JumpIfZero, None, 2),
+            instruction(IRInstructionKind::DecrementByte, Some(1), 3),
+            instruction(IRInstructionKind::JumpIfNotZero, None, 4),
+        ],
+        ..Program::default()
+    };
+    assert_eq!(program.validate(), Err(InvalidProgram { instruction: 3, reason: "bracket not linked to its match" }));
+    program.link().unwrap();
+    assert_eq!(program.validate(), Ok(()));
+
+    program.instructions[2] = instruction(IRInstructionKind::SetConst, Some(0), 3);
+    assert_eq!(program.validate(), Err(InvalidProgram { instruction: 2, reason: "operand out of range" }));
+
+    program.instructions.remove(2);
+    program.instructions.remove(1);
+    assert_eq!(program.link(), Err(InvalidProgram { instruction: 1, reason: "unmatched ]" }));
+}
+
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic(expected = "pass drop-last left an invalid program : Invalid program at instruction 0 : unmatched [")]
+fn invalid_programs_from_custom_passes_panic_in_debug_builds() {
+    struct DropLast;
+
+    impl Pass for DropLast {
+        fn name(&self) -> &str {
+            "drop-last"
+        }
+
+        fn run(&self, program: &mut Program) -> PassStats {
+            program.instructions.pop();
+            PassStats { rewrites: 1 }
+        }
+    }
+
+    let mut pipeline = Pipeline::new();
+    pipeline.push(Box::new(DropLast));
+    let mut interpreter = Interpreter::new();
+    interpreter.set_pipeline(pipeline);
+    let _ = interpreter.load_program_from_str("[>]");
+}
+
+#[test]
+fn hooks_see_the_program_after_each_pass() {
+    let seen = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let sink = seen.clone();
+    let mut pipeline = Pipeline::for_level(OptLevel::O1);
+    pipeline.on_pass(move |report, program| sink.lock().unwrap().push((report.to_string(), program.instructions.len())));
+
+    let mut interpreter = Interpreter::new();
+    interpreter.set_pipeline(pipeline);
+    interpreter.load_program_from_str("[-]+.").unwrap();
+
+    assert_eq!(*seen.lock().unwrap(), [
+        (String::from("fold-clear-loops : 1 rewrites, 5 → 3 instructions"), 3),
+        (String::from("fold-set-constants : 1 rewrites, 3 → 2 instructions"), 2),
+        (String::from("fold-if-loops : 0 rewrites, 2 → 2 instructions"), 2),
+        (String::from("fold-ranges : 0 rewrites, 2 → 2 instructions"), 2),
+    ]);
+}
+
+#[test]
+fn loops_ending_with_a_clear_become_ifs() {
+    let mut interpreter = Interpreter::new();
+    // The second loop moves the pointer before clearing, so it may not run only once.
+    interpreter.load_program_from_str("+[>++<[-]]>[-<+>>[-]]").unwrap();
+
+    let listing = interpreter.listing();
+    assert_eq!(listing.matches(" If -> ").count(), 1, "{listing}");
+    assert_eq!(listing.matches("EndIf -> ").count(), 1, "{listing}");
+    assert_eq!(listing.matches("JumpIfZero -> ").count(), 1, "{listing}");
+    assert_eq!(interpreter.to_source(), "+[>++<[-]]>[-<+>>[-]]");
+
+    interpreter.interpret().unwrap();
+    assert_eq!((interpreter.peek(0), interpreter.peek(1)), (Some(1), Some(1)));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn runs_of_increments_add_to_a_loaded_tape() {
+    let path = std::env::temp_dir().join(format!("sac-pipeline-tape-{}.bin", std::process::id()));
+    let path = path.to_str().unwrap();
+    let mut producer = Interpreter::new();
+    producer.load_program_from_str("+++++>++>+++").unwrap();
+    producer.interpret().unwrap();
+    producer.save_tape(path).unwrap();
+
+    let run = |opt_level| {
+        let mut interpreter = Interpreter::with_config(Config { opt_level, ..Config::default() });
+        interpreter.load_program_from_str("+>+>+>+<<<").unwrap();
+        interpreter.load_tape(path).unwrap();
+        interpreter.interpret().unwrap();
+        interpreter.peek_range(0..4).unwrap().to_vec()
+    };
+    assert_eq!(run(OptLevel::O1), [6, 3, 4, 1]);
+    assert_eq!(run(OptLevel::O1), run(OptLevel::O0));
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn fills_and_block_moves_become_ranges() {
+    // Cells 1 to 5 get 1 to 5 and are moved one cell left, then cells 6 to 9 are set to 7.
+    let code = "+>++>+++>++++>+++++<<<<[-<+>]>[-<+>]>[-<+>]>[-<+>]>[-<+>]>[-]+++++++>[-]+++++++>[-]+++++++>[-]+++++++";
+    let mut interpreter = Interpreter::new();
+    interpreter.load_program_from_str(&format!(">{code}")).unwrap();
+
+    let listing = interpreter.listing();
+    assert!(listing.contains("CopyRange +0 x5 -> -1 (covers 34)"), "{listing}");
+    assert!(listing.contains("FillRange +0 x4 = 7 (covers 7)"), "{listing}");
+    assert_eq!(interpreter.to_source(), format!(">{code}"));
+
+    interpreter.interpret().unwrap();
+    assert_eq!(interpreter.peek_range(0..11), Some(&[1, 2, 3, 4, 5, 0, 7, 7, 7, 7, 0][..]));
+    assert_eq!(interpreter.memory_pointer(), 9);
+
+    // Without the first `>`, the first transfer loop moves cell 0 out of the tape.
+    interpreter.load_program_from_str(code).unwrap();
+    let error = interpreter.interpret().unwrap_err();
+    assert_eq!(error.span().map(|span| span.start), Some(25));
+}
+
+#[test]
+fn missed_optimizations_say_why() {
+    let mut interpreter = Interpreter::new();
+    interpreter.load_program_from_str("+[-.]+[--]+[>[-]]").unwrap();
+
+    let missed: Vec<&MissedOptimization> = interpreter.pass_reports().iter().flat_map(|report| &report.missed).collect();
+    let messages: Vec<String> = missed.iter().map(|missed| missed.to_string()).collect();
+    assert_eq!(messages, [
+        "clear loop not folded: contains I/O at line 1, column 2 (instruction 1)",
+        "clear loop not folded: even steps may never reach 0 at line 1, column 7 (instruction 6)",
+        "if loop not folded: the pointer may not be back on the tested cell at line 1, column 12 (instruction 10)",
+    ]);
+    assert_eq!(missed[0].span.end, 5);
+
+    // Nothing is missed when no pass runs.
+    let mut interpreter = Interpreter::with_config(Config { opt_level: OptLevel::O0, ..Config::default() });
+    interpreter.load_program_from_str("+[-.]").unwrap();
+    assert!(interpreter.pass_reports().is_empty());
+}
+
+#[test]
+fn concatenated_programs_add_to_the_cells_written_before() {
+    let part = |code: &str| {
+        let mut interpreter = Interpreter::new();
+        interpreter.load_program_from_str(code).unwrap();
+        interpreter.to_program()
+    };
+    let run = |load: &dyn Fn(&mut Interpreter)| {
+        let mut interpreter = Interpreter::new();
+        load(&mut interpreter);
+        interpreter.interpret().unwrap();
+        interpreter.peek_range(0..4).unwrap().to_vec()
+    };
+
+    let program = Program::concat(&[&part("+++>++"), &part("<+>+>+>+<<<")]);
+    let composed = run(&|interpreter| interpreter.load_ir(&program).unwrap());
+    assert_eq!(composed, [4, 3, 1, 1]);
+    assert_eq!(composed, run(&|interpreter| interpreter.load_program_from_str("+++>++<+>+>+>+<<<").unwrap()));
+}
+
+#[test]
+fn concatenated_programs_run_as_their_concatenated_sources() {
+    // Reads two numbers, sums them, then prints the sum and clears the tape, with loops in each part.
+    let snippets = [("read.bf", ",>,[-<+>]<"), ("double.bf", "[->++<]>[-<+>]<"), ("print.bf", ".>[-]+>[-]+>[-]+>[-]+<<<<[-]")];
+    let run = |load: &dyn Fn(&mut Interpreter)| {
+        let mut interpreter = Interpreter::new();
+        interpreter.set_input(std::collections::VecDeque::from(vec![3u8, 4]));
+        let output = OutputBuffer::new();
+        interpreter.set_output(output.clone());
+        load(&mut interpreter);
+        interpreter.interpret().unwrap();
+        (output.take(), interpreter.peek_range(0..6).unwrap().to_vec())
+    };
+
+    let parts: Vec<Program> = snippets.iter().map(|(name, code)| {
+        let mut interpreter = Interpreter::new();
+        interpreter.load_program_from_str(code).unwrap();
+        interpreter.to_program().named(name)
+    }).collect();
+    let program = Program::concat(&parts.iter().collect::<Vec<_>>());
+    assert_eq!(program.validate(), Ok(()));
+    assert!(program.listing().contains("FillRange"), "{}", program.listing());
+
+    let composed = run(&|interpreter| interpreter.load_ir(&program).unwrap());
+    let concatenated = run(&|interpreter| interpreter.load_program_from_str(&snippets.map(|(_, code)| code).concat()).unwrap());
+    assert_eq!(composed, (vec![14], vec![0, 1, 1, 1, 1, 0]));
+    assert_eq!(composed, concatenated);
+
+    // Spans stay in the source of their segment.
+    let starts: Vec<usize> = program.segments.iter().map(|segment| segment.start).collect();
+    assert_eq!(starts, [0, parts[0].instructions.len(), parts[0].instructions.len() + parts[1].instructions.len()]);
+    let last = program.instructions.len() - 1;
+    assert_eq!(program.segment(last), Some(&Segment { start: starts[2], name: Some(String::from("print.bf")) }));
+    assert_eq!(program.instructions[starts[1]].span.start, 0);
+    assert_eq!(Program::concat(&[&parts[0], &Program::default()]).segments.len(), 1);
+}
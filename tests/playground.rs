@@ -0,0 +1,34 @@
+#![cfg(feature = "playground")]
+
+use sac::playground::Playground;
+
+const HELLO_WORLD: &str = "++++++++[>++++[>++>+++>+++>+<<<<-]>+>+>->>+[<]<-]>>.>---.+++++++..+++.>>.<-.<.+++.------.--------.>>+.>++.";
+
+#[test]
+fn runs_hello_world_in_slices() {
+    let mut interpreter = Playground::new();
+    interpreter.load_program(HELLO_WORLD).unwrap();
+
+    assert_eq!(interpreter.run(10).unwrap(), "running");
+    while interpreter.run(100).unwrap() == "running" {}
+
+    assert_eq!(interpreter.take_output(), b"Hello World!\n");
+    assert!(interpreter.take_output().is_empty());
+    assert_eq!(interpreter.memory_slice(0, 3), vec![0, 0, 72]);
+}
+
+#[test]
+fn echoes_input_and_reports_errors() {
+    let mut interpreter = Playground::new();
+    interpreter.load_program(",.,.").unwrap();
+    interpreter.set_input(b"ok");
+
+    assert_eq!(interpreter.run(1000).unwrap(), "halted");
+    assert_eq!(interpreter.take_output(), b"ok");
+
+    assert!(interpreter.load_program("[").unwrap_err().contains("Unmatched bracket"));
+
+    interpreter.load_program("<").unwrap();
+    assert!(interpreter.run(1).unwrap_err().contains("underflow"));
+    assert_eq!(interpreter.memory_slice(99_998, 10).len(), 2);
+}
@@ -0,0 +1,197 @@
+#![cfg(feature = "std")]
+
+use sac::error::RuntimeError;
+use sac::interpreter::{Config, Interpreter};
+use sac::io::PrngInput;
+use sac::testing::{self, ProgramGenerator};
+use sac::verify;
+
+const SEED: u64 = 0xb0a710ad;
+const CASES: usize = 500;
+const TAPE_SIZE: usize = 32;
+const INPUT: &[u8] = b"\x03property\x00";
+
+fn interpreter() -> Interpreter {
+    Interpreter::with_config(Config { tape_size: TAPE_SIZE, ..Config::default() })
+}
+
+fn assert_holds<F: FnMut(&str) -> Result<(), String>>(property: F) {
+    if let Err(counterexample) = testing::check(&ProgramGenerator::default(), SEED, CASES, property) {
+        panic!("{counterexample}");
+    }
+}
+
+#[test]
+fn parsing_never_fails_on_valid_programs() {
+    assert_holds(|code| interpreter().load_program_from_str(code).map_err(|e| e.to_string()));
+}
+
+#[test]
+fn decompiling_is_stable() {
+    assert_holds(|code| {
+        let mut first = interpreter();
+        first.load_program_from_str(code).map_err(|e| e.to_string())?;
+        let source = first.to_source();
+
+        let mut second = interpreter();
+        second.load_program_from_str(&source).map_err(|e| e.to_string())?;
+        if second.to_source() != source {
+            return Err(format!("{source} decompiles to {}", second.to_source()));
+        }
+        if testing::reference_run(code, INPUT, TAPE_SIZE, 10_000) != testing::reference_run(&source, INPUT, TAPE_SIZE, 10_000) {
+            return Err(format!("{source} behaves differently"));
+        }
+        Ok(())
+    });
+}
+
+#[test]
+fn merged_runs_match_the_reference_interpreter() {
+    assert_holds(|code| {
+        let Some(expected) = testing::reference_run(code, INPUT, TAPE_SIZE, 10_000) else { return Ok(()) };
+        let actual = verify::execute(&mut interpreter(), code, INPUT).map_err(|e| e.to_string())?;
+
+        if (actual.output, actual.tape, actual.error.is_some()) != (expected.output, expected.tape, expected.failed) {
+            return Err(String::from("differs from the reference interpreter"));
+        }
+        Ok(())
+    });
+}
+
+#[test]
+fn bounded_execution_is_deterministic() {
+    assert_holds(|code| {
+        let run = || {
+            let mut interpreter = interpreter();
+            interpreter.set_step_limit(1000);
+            let execution = verify::execute(&mut interpreter, code, INPUT).unwrap();
+            (execution.output, execution.tape, execution.error.map(|e| e.to_string()), interpreter.steps())
+        };
+        if run() != run() {
+            return Err(String::from("two runs differ"));
+        }
+        Ok(())
+    });
+}
+
+#[test]
+fn inferred_tape_sizes_behave_like_the_full_tape() {
+    assert_holds(|code| {
+        let run = |infer_tape_size| {
+            let mut interpreter = Interpreter::with_config(Config { tape_size: TAPE_SIZE, infer_tape_size, ..Config::default() });
+            interpreter.set_step_limit(10_000);
+            let mut execution = verify::execute(&mut interpreter, code, INPUT).unwrap();
+            execution.tape.resize(TAPE_SIZE, 0);
+            (execution.output, execution.tape, execution.error.map(|e| e.to_string()))
+        };
+        if run(true) != run(false) {
+            return Err(String::from("the inferred tape changes the run"));
+        }
+        Ok(())
+    });
+}
+
+#[test]
+fn if_loops_behave_like_the_loops_they_replace() {
+    // Many `[-]`, so that some loops end with one, each program run from a few random tapes.
+    let generator = ProgramGenerator { clear_loop_density: 25, ..ProgramGenerator::default() };
+    let mut rng = PrngInput::new(SEED);
+    let mut folded = 0;
+
+    let result = testing::check(&generator, SEED, CASES, |body| {
+        for _ in 0..4 {
+            let setup: String = (0..4).map(|_| "+".repeat(rng.next_u64() as usize % 256) + ">").collect();
+            let code = format!("{setup}<<<<{body}");
+            if testing::reference_run(&code, INPUT, TAPE_SIZE, 10_000).is_none() {
+                continue;
+            }
+            let mut optimized = interpreter();
+            optimized.load_program_from_str(&code).map_err(|e| e.to_string())?;
+            folded += optimized.listing().matches(" If ").count();
+            verify::verify(&code, INPUT, Config { tape_size: TAPE_SIZE, ..Config::default() }).map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    });
+
+    if let Err(counterexample) = result {
+        panic!("{counterexample}");
+    }
+    assert!(folded > 0);
+}
+
+// Random cells, then runs of `[-]+++>` and of transfer loops in random directions, which may
+// overlap or leave the tape.
+fn range_program(rng: &mut PrngInput) -> String {
+    let mut roll = |n: u64| (rng.next_u64() % n) as usize;
+    let mut code = String::new();
+    for _ in 0..8 {
+        code += &"+".repeat(roll(256));
+        code.push('>');
+    }
+    code += &"<".repeat(roll(10));
+
+    for _ in 0..4 {
+        let len = 2 + roll(8);
+        let step = if roll(2) == 0 { ">" } else { "<" };
+        let (to, back) = if roll(2) == 0 { (">", "<") } else { ("<", ">") };
+        match roll(3) {
+            0 => code += &vec![format!("[-]{}", "+".repeat(roll(256))); len].join(step),
+            1 => {
+                let shift = 1 + roll(4);
+                code += &vec![format!("[-{}+{}]", to.repeat(shift), back.repeat(shift)); len].join(step);
+            },
+            _ => code += &to.repeat(roll(6)),
+        }
+    }
+    code + "+"
+}
+
+#[test]
+fn ranges_behave_like_the_instructions_they_stand_for() {
+    let mut rng = PrngInput::new(SEED);
+    let (mut fills, mut copies, mut failures) = (0, 0, 0);
+
+    for _ in 0..CASES {
+        let code = range_program(&mut rng);
+        let mut optimized = interpreter();
+        optimized.load_program_from_str(&code).unwrap();
+        let listing = optimized.listing();
+        fills += listing.matches("FillRange").count();
+        copies += listing.matches("CopyRange").count();
+
+        let execution = verify::verify(&code, INPUT, Config { tape_size: TAPE_SIZE, ..Config::default() }).unwrap_or_else(|e| panic!("{code} : {e}"));
+        failures += execution.error.is_some() as usize;
+    }
+
+    assert!(fills > 0 && copies > 0 && failures > 0, "{fills} fills, {copies} copies, {failures} failures");
+}
+
+#[test]
+fn hang_detection_only_stops_programs_that_never_halt() {
+    assert_holds(|code| {
+        let mut interpreter = interpreter();
+        interpreter.set_hang_detection(Some(16));
+        interpreter.set_step_limit(10_000);
+        let execution = verify::execute(&mut interpreter, code, INPUT).map_err(|e| e.to_string())?;
+        match execution.error {
+            Some(RuntimeError::NoProgress { .. }) if testing::reference_run(code, INPUT, TAPE_SIZE, 1_000_000).is_some() => Err(String::from("a program that halts was stopped")),
+            _ => Ok(()),
+        }
+    });
+}
+
+#[test]
+fn failures_shrink_to_minimal_programs() {
+    // Without any input, every program reaching a `,` fails.
+    let counterexample = testing::check(&ProgramGenerator::default(), SEED, CASES, |code| {
+        let execution = verify::execute(&mut interpreter(), code, b"").map_err(|e| e.to_string())?;
+        match execution.error {
+            Some(e @ RuntimeError::EndOfInput { .. }) => Err(e.to_string()),
+            _ => Ok(()),
+        }
+    })
+    .unwrap_err();
+
+    assert_eq!(counterexample.minimal, ",");
+    assert!(counterexample.original.len() > 1);
+}
@@ -0,0 +1,103 @@
+#![cfg(feature = "std")]
+
+use std::fs;
+use std::path::PathBuf;
+
+use sac::runner::{self, RunFailure, TestLimits};
+
+fn corpus(name: &str, files: &[(&str, &[u8])]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("sac-runner-{name}-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    for (file, contents) in files {
+        let path = dir.join(file);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+    dir
+}
+
+#[test]
+fn runs_expectation_files_in_order() {
+    let dir = corpus("mixed", &[
+        ("echo.bf", b",[.,]"),
+        ("echo.in", b"abc\0"),
+        ("echo.out", b"abc"),
+        ("nested/wrong.bf", b"++++++++[>++++++++<-]>+."),
+        ("nested/wrong.out", b"B"),
+        ("unmatched.bf", b"[["),
+        ("unmatched.err", b"Unmatched bracket at line 1, column 2 (instruction 1)\n"),
+        ("runaway.bf", b"+[]"),
+        ("runaway.out", b""),
+        ("missing.bf", b"+"),
+    ]);
+
+    let cases = runner::discover(&dir, None).unwrap();
+    let names: Vec<_> = cases.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, ["echo", "missing", "nested/wrong", "runaway", "unmatched"]);
+
+    let limits = TestLimits { max_steps: Some(1000), ..TestLimits::default() };
+    let results = runner::run_cases(&cases, limits, 4);
+    let passed: Vec<_> = results.iter().map(|r| (r.name.as_str(), r.passed())).collect();
+    assert_eq!(passed, [("echo", true), ("missing", false), ("nested/wrong", false), ("runaway", false), ("unmatched", true)]);
+
+    let failure = results[2].failure.as_deref().unwrap();
+    assert!(failure.starts_with("output differs at byte 0"), "{failure}");
+    assert!(failure.contains("expected : \"B\"") && failure.contains("actual   : \"A\""), "{failure}");
+    assert!(results[3].failure.as_deref().unwrap().starts_with("Step limit reached"));
+
+    let filtered = runner::discover(&dir, Some("nested")).unwrap();
+    assert_eq!(filtered.len(), 1);
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn run_many_keeps_the_order_of_the_programs() {
+    use sac::cancel::CancelToken;
+    use sac::error::{LoadError, RuntimeError};
+    use sac::interpreter::Config;
+
+    let programs: Vec<String> = (0..12)
+        .map(|i| match i % 4 {
+            0 => format!("+++[>++++++++[>++++++++[-]<-]<-]>>{}.", "+".repeat(65 + i)),
+            1 => String::from("<"),
+            2 => String::from("[["),
+            _ => format!("{}.", "+".repeat(65 + i)),
+        })
+        .collect();
+
+    let results = runner::run_many(&programs, Config::default(), 4, &CancelToken::new());
+    assert_eq!(results.len(), 12);
+    for (i, result) in results.iter().enumerate() {
+        match (i % 4, result) {
+            (1, Ok(execution)) => assert!(matches!(execution.error, Some(RuntimeError::PointerUnderflow { .. }))),
+            (2, Err(RunFailure::Load(LoadError::UnmatchedBracket { .. }))) => (),
+            (0 | 3, Ok(execution)) => {
+                assert!(execution.error.is_none());
+                assert_eq!(execution.output, [65 + i as u8]);
+            },
+            _ => panic!("unexpected result for program {i}"),
+        }
+    }
+
+    let cancel = CancelToken::new();
+    cancel.cancel();
+    let results = runner::run_many(&programs[..4], Config::default(), 4, &cancel);
+    assert!(results.iter().all(|r| matches!(r, Ok(execution) if matches!(execution.error, Some(RuntimeError::Cancelled { .. })))
+        || matches!(r, Err(RunFailure::Load(LoadError::UnmatchedBracket { .. })))));
+}
+
+#[test]
+fn run_many_reports_a_panic_as_the_failure_of_its_program() {
+    use sac::cancel::CancelToken;
+    use sac::error::LoadError;
+    use sac::interpreter::Config;
+
+    // Without a single cell, running any program panics, while loading it can still fail.
+    let programs = [String::from("+."), String::from("[["), String::from("-")];
+    let results = runner::run_many(&programs, Config { tape_size: 0, ..Config::default() }, 2, &CancelToken::new());
+    assert!(matches!(&results[0], Err(RunFailure::Panicked(_))));
+    assert!(matches!(&results[1], Err(RunFailure::Load(LoadError::UnmatchedBracket { .. }))));
+    assert!(matches!(&results[2], Err(RunFailure::Panicked(_))));
+    assert!(results[0].as_ref().unwrap_err().to_string().starts_with("Interpreter panicked : "));
+}
@@ -0,0 +1,83 @@
+#![cfg(feature = "sarif")]
+
+use sac::diagnostics::{self, Diagnostic, Severity};
+use sac::interpreter::{Config, Span};
+use sac::json::{self, Value};
+
+fn region(result: &Value) -> (u64, u64, u64, u64) {
+    let region = result.get("locations").unwrap().as_array().unwrap()[0].get("physicalLocation").unwrap().get("region").unwrap();
+    let field = |name| region.get(name).unwrap().as_u64().unwrap();
+    (field("startLine"), field("startColumn"), field("charOffset"), field("charLength"))
+}
+
+#[test]
+fn bracket_errors_do_not_hide_lints() {
+    let diagnostics = diagnostics::collect("+[]\n]", Config::default(), &[]);
+    assert_eq!(diagnostics.len(), 2);
+    assert_eq!(diagnostics[0].rule, diagnostics::UNMATCHED_BRACKET);
+    assert_eq!(diagnostics[0].span, Span { start: 4, end: 5, line: 2, column: 1 });
+    assert_eq!(diagnostics[1].rule, "empty-loop");
+    assert_eq!(diagnostics[1].severity, Severity::Warning);
+}
+
+#[test]
+fn sarif_log_has_one_result_per_diagnostic() {
+    let diagnostics: Vec<Diagnostic> = diagnostics::collect("+[]\n]", Config::default(), &[]);
+    // Printed then parsed back, to check the log is valid JSON.
+    let log = json::parse(&diagnostics::diagnostics_to_sarif(&diagnostics, "loop.bf").to_string()).unwrap();
+    assert_eq!(log.get("version").unwrap().as_str(), Some("2.1.0"));
+
+    let run = &log.get("runs").unwrap().as_array().unwrap()[0];
+    let rules = run.get("tool").unwrap().get("driver").unwrap().get("rules").unwrap().as_array().unwrap();
+    let results = run.get("results").unwrap().as_array().unwrap();
+    assert_eq!(results.len(), 2);
+
+    let rule_id = |result: &Value| result.get("ruleId").unwrap().as_str().unwrap().to_string();
+    for result in results {
+        let index = result.get("ruleIndex").unwrap().as_u64().unwrap() as usize;
+        assert_eq!(rules[index].get("id").unwrap().as_str().unwrap(), rule_id(result));
+        let uri = result.get("locations").unwrap().as_array().unwrap()[0].get("physicalLocation").unwrap().get("artifactLocation").unwrap().get("uri").unwrap();
+        assert_eq!(uri.as_str(), Some("loop.bf"));
+    }
+
+    assert_eq!(rule_id(&results[0]), "unmatched-bracket");
+    assert_eq!(results[0].get("level").unwrap().as_str(), Some("error"));
+    assert_eq!(region(&results[0]), (2, 1, 4, 1));
+
+    assert_eq!(rule_id(&results[1]), "empty-loop");
+    assert_eq!(results[1].get("level").unwrap().as_str(), Some("warning"));
+    assert_eq!(region(&results[1]), (1, 2, 1, 2));
+}
+
+#[test]
+fn check_prints_a_sarif_log() {
+    let path = std::env::temp_dir().join(format!("sac-sarif-{}.bf", std::process::id()));
+    std::fs::write(&path, "+[]\n]").unwrap();
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_sac")).args(["check", "--format", "sarif", path.to_str().unwrap()]).output().unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    // The bracket error fails the check once the log is printed.
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr).unwrap().contains("1 errors in"));
+
+    let log = json::parse(&String::from_utf8(output.stdout).unwrap()).unwrap();
+    assert_eq!(log.get("version").unwrap().as_str(), Some("2.1.0"));
+    let run = &log.get("runs").unwrap().as_array().unwrap()[0];
+    assert_eq!(run.get("tool").unwrap().get("driver").unwrap().get("name").unwrap().as_str(), Some("sac"));
+
+    let results = run.get("results").unwrap().as_array().unwrap();
+    let summary: Vec<_> = results.iter().map(|result| {
+        let region = result.get("locations").unwrap().as_array().unwrap()[0].get("physicalLocation").unwrap().get("region").unwrap();
+        let field = |name| region.get(name).unwrap().as_u64().unwrap();
+        (
+            result.get("ruleId").unwrap().as_str().unwrap().to_string(),
+            result.get("level").unwrap().as_str().unwrap().to_string(),
+            (field("startLine"), field("startColumn"), field("endLine"), field("endColumn")),
+        )
+    }).collect();
+    assert_eq!(summary, [
+        (String::from("unmatched-bracket"), String::from("error"), (2, 1, 2, 2)),
+        (String::from("empty-loop"), String::from("warning"), (1, 2, 1, 4)),
+    ]);
+    assert!(results[1].get("message").unwrap().get("text").unwrap().as_str().unwrap().contains("empty"));
+}
@@ -0,0 +1,66 @@
+#![cfg(feature = "serve")]
+
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::thread;
+
+use sac::json::{self, Value};
+use sac::serve::{self, ServeConfig};
+
+const HELLO_WORLD: &str = "++++++++[>++++[>++>+++>+++>+<<<<-]>+>+>->>+[<]<-]>>.>---.+++++++..+++.>>.<-.<.+++.------.--------.>>+.>++.";
+
+fn start(config: ServeConfig) -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    thread::spawn(move || serve::serve(listener, config));
+    address
+}
+
+fn post(address: SocketAddr, path: &str, body: &str) -> (u16, Value) {
+    let mut stream = TcpStream::connect(address).unwrap();
+    write!(stream, "POST {path} HTTP/1.1\r\nHost: localhost\r\nContent-Length: {}\r\n\r\n{body}", body.len()).unwrap();
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+
+    let status = response[9..12].parse().unwrap();
+    let (_, body) = response.split_once("\r\n\r\n").unwrap();
+    (status, json::parse(body).unwrap())
+}
+
+#[test]
+fn runs_hello_world() {
+    let address = start(ServeConfig::default());
+    let (status, response) = post(address, "/run", &format!(r#"{{"source": "{HELLO_WORLD}", "input": ""}}"#));
+
+    assert_eq!(status, 200);
+    assert_eq!(response.get("status").and_then(Value::as_str), Some("ok"));
+    assert_eq!(response.get("output").and_then(Value::as_str), Some("SGVsbG8gV29ybGQhCg=="));
+    assert!(response.get("steps").and_then(Value::as_u64).unwrap() > 0);
+    assert!(response.get("duration").and_then(Value::as_f64).is_some());
+}
+
+#[test]
+fn reports_step_limit_violations() {
+    let address = start(ServeConfig { max_steps: 1000, ..ServeConfig::default() });
+
+    let (status, response) = post(address, "/run", r#"{"source": "+[]"}"#);
+    assert_eq!(status, 200);
+    assert_eq!(response.get("status").and_then(Value::as_str), Some("step_limit"));
+    assert_eq!(response.get("steps").and_then(Value::as_u64), Some(1000));
+
+    // Options may lower the limits, never raise them.
+    let (_, response) = post(address, "/run", r#"{"source": "+[]", "options": {"max_steps": 10}}"#);
+    assert_eq!(response.get("steps").and_then(Value::as_u64), Some(10));
+    let (_, response) = post(address, "/run", r#"{"source": "+[]", "options": {"max_steps": 1000000}}"#);
+    assert_eq!(response.get("steps").and_then(Value::as_u64), Some(1000));
+}
+
+#[test]
+fn rejects_oversized_and_malformed_requests() {
+    let address = start(ServeConfig { max_source_bytes: 4, ..ServeConfig::default() });
+
+    assert_eq!(post(address, "/run", r#"{"source": "+++++"}"#).0, 413);
+    assert_eq!(post(address, "/run", r#"{"input": "x"}"#).0, 400);
+    assert_eq!(post(address, "/elsewhere", r#"{"source": "+"}"#).0, 404);
+}
@@ -0,0 +1,48 @@
+#![cfg(all(feature = "mmap", target_pointer_width = "64", any(target_os = "linux", target_os = "macos")))]
+
+use sac::interpreter::{Config, Interpreter};
+use sac::tape::TapeBacking;
+
+const TAPE_SIZE: usize = 1 << 32;
+const STRIDE: usize = 1 << 16;
+
+#[test]
+fn huge_mapped_tapes_only_commit_touched_cells() {
+    let mut interpreter = Interpreter::with_config(Config { tape_size: TAPE_SIZE, tape_backing: TapeBacking::Mapped, ..Config::default() });
+
+    // Carries a counter 200 times STRIDE cells to the right, then marks the cell it stops on.
+    let right = ">".repeat(STRIDE);
+    let left = "<".repeat(STRIDE);
+    let code = format!("++++++++++[>++++++++++++++++++++<-]>[-[-{right}+{left}]{right}]+++");
+    interpreter.load_program_from_str(&code).unwrap();
+    interpreter.interpret().unwrap();
+
+    let far = 1 + 200 * STRIDE;
+    assert_eq!(interpreter.tape_size(), TAPE_SIZE);
+    assert_eq!(interpreter.peek(far), Some(3));
+    assert_eq!(interpreter.peek(far - STRIDE), Some(0));
+    assert_eq!(interpreter.peek(TAPE_SIZE - 1), Some(0));
+
+    // A 4 GiB heap tape would show up in the resident set.
+    #[cfg(target_os = "linux")]
+    {
+        let status = std::fs::read_to_string("/proc/self/status").unwrap();
+        let resident_kb: usize = status.lines().find_map(|l| l.strip_prefix("VmRSS:")).unwrap().trim().trim_end_matches(" kB").parse().unwrap();
+        assert!(resident_kb < 512 * 1024, "{resident_kb} kB resident");
+    }
+
+    // Reloading gives a blank tape.
+    interpreter.load_program_from_str(">").unwrap();
+    assert_eq!(interpreter.peek(far), Some(0));
+}
+
+#[test]
+fn heap_and_mapped_tapes_agree() {
+    let run = |tape_backing| {
+        let mut interpreter = Interpreter::with_config(Config { tape_size: 64, tape_backing, ..Config::default() });
+        interpreter.load_program_from_str("+++[>++<-]>[>+>+<<-]>>>-").unwrap();
+        interpreter.interpret().unwrap();
+        interpreter.peek_range(0..64).unwrap().to_vec()
+    };
+    assert_eq!(run(TapeBacking::Heap), run(TapeBacking::Mapped));
+}
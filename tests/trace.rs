@@ -0,0 +1,80 @@
+#![cfg(feature = "std")]
+
+use std::io::Cursor;
+
+use sac::buffer::OutputBuffer;
+use sac::error::{RuntimeError, TraceError};
+use sac::interpreter::Interpreter;
+use sac::trace::{Trace, VERSION};
+
+// Reverses its input, up to a 0 byte.
+const REVERSE: &str = ">,[>,]<[.<]";
+
+fn interpreter(code: &str, output: &OutputBuffer) -> Interpreter {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_trace_recording(true);
+    interpreter.load_program_from_str(code).unwrap();
+    interpreter.set_output(output.clone());
+    interpreter
+}
+
+#[test]
+fn replaying_a_trace_reproduces_the_run() {
+    let output = OutputBuffer::new();
+    let mut recorded = interpreter(REVERSE, &output);
+    recorded.set_input(Cursor::new(b"stressed\0".to_vec()));
+    recorded.interpret().unwrap();
+    assert_eq!(output.take(), b"desserts");
+
+    let trace = recorded.take_trace().unwrap();
+    assert_eq!(trace.input(), b"stressed\0");
+    assert_eq!(trace.instructions().take(4).collect::<Vec<_>>(), [0, 1, 2, 3]);
+    let bytes = trace.to_bytes();
+    assert_eq!(bytes[8..12], VERSION.to_le_bytes());
+    let trace = Trace::from_bytes(&bytes).unwrap();
+
+    // No input is given, it comes from the trace.
+    let mut replayed = Interpreter::new();
+    replayed.load_program_from_str(REVERSE).unwrap();
+    replayed.set_output(output.clone());
+    replayed.replay(&trace).unwrap();
+    assert_eq!(output.take(), b"desserts");
+    assert_eq!(replayed.memory_pointer(), recorded.memory_pointer());
+    assert_eq!(replayed.peek_range(0..16), recorded.peek_range(0..16));
+    assert_eq!(replayed.instruction_pointer(), recorded.instruction_pointer());
+}
+
+#[test]
+fn failed_runs_fail_again_when_replayed() {
+    let output = OutputBuffer::new();
+    let mut recorded = interpreter("+.,,", &output);
+    recorded.set_input(Cursor::new(b"x".to_vec()));
+    assert!(matches!(recorded.interpret(), Err(RuntimeError::EndOfInput { instruction: 3, .. })));
+    let trace = recorded.take_trace().unwrap();
+    assert_eq!((trace.steps(), output.take()), (4, b"\x01".to_vec()));
+
+    let mut replayed = Interpreter::new();
+    replayed.load_program_from_str("+.,,").unwrap();
+    replayed.set_output(output.clone());
+    assert!(matches!(replayed.replay(&trace), Err(TraceError::Runtime(RuntimeError::EndOfInput { instruction: 3, .. }))));
+    assert_eq!((output.take(), replayed.peek(0)), (b"\x01".to_vec(), Some(b'x')));
+}
+
+#[test]
+fn traces_are_checked_before_replaying() {
+    let output = OutputBuffer::new();
+    let mut recorded = interpreter(REVERSE, &output);
+    recorded.set_input(Cursor::new(b"ab\0".to_vec()));
+    recorded.interpret().unwrap();
+    let bytes = recorded.take_trace().unwrap().to_bytes();
+
+    let mut other = Interpreter::new();
+    other.load_program_from_str("+[>,]").unwrap();
+    assert!(matches!(other.replay(&Trace::from_bytes(&bytes).unwrap()), Err(TraceError::OtherProgram)));
+
+    assert!(matches!(Trace::from_bytes(b"SACTAPE1"), Err(TraceError::NotATrace)));
+    assert!(matches!(Trace::from_bytes(&bytes[..bytes.len() - 1]), Err(TraceError::Truncated)));
+    let mut newer = bytes.clone();
+    newer[8] = 2;
+    assert!(matches!(Trace::from_bytes(&newer), Err(TraceError::UnsupportedVersion { version: 2 })));
+}
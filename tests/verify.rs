@@ -0,0 +1,79 @@
+#![cfg(feature = "std")]
+
+use std::fs;
+use std::path::Path;
+
+use sac::error::RuntimeError;
+use sac::interpreter::{Config, Interpreter};
+use sac::io::PrngInput;
+use sac::optimizer::OptLevel;
+use sac::testing::ProgramGenerator;
+use sac::verify::{self, VerifyError};
+
+const SEED: u64 = 0x5eed;
+
+#[test]
+fn fixtures_agree_across_opt_levels() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().is_none_or(|e| e != "bf") {
+            continue;
+        }
+        let code = fs::read_to_string(&path).unwrap();
+        let input = fs::read(path.with_extension("in")).unwrap_or_default();
+
+        match verify::verify(&code, &input, Config::default()) {
+            Ok(_) | Err(VerifyError::Load(_)) => (),
+            Err(e) => panic!("{} : {e}", path.display()),
+        }
+    }
+}
+
+#[test]
+fn random_programs_agree_across_opt_levels() {
+    let mut rng = PrngInput::new(SEED);
+    let generator = ProgramGenerator { clear_loop_density: 10, ..ProgramGenerator::default() };
+    let mut checked = 0;
+
+    for case in 0..2000 {
+        let code = generator.generate(&mut rng);
+        let input: Vec<u8> = (0..16).map(|_| rng.next_u64() as u8).collect();
+
+        // Programs that don't halt quickly unoptimized are skipped, the optimized run never takes
+        // more steps than the unoptimized one.
+        let mut reference = Interpreter::with_config(Config { opt_level: OptLevel::O0, ..Config::default() });
+        reference.set_step_limit(100_000);
+        let execution = verify::execute(&mut reference, &code, &input).unwrap();
+        if matches!(execution.error, Some(RuntimeError::StepLimitExceeded { .. })) {
+            continue;
+        }
+
+        if let Err(e) = verify::verify(&code, &input, Config::default()) {
+            panic!("case {case} (seed {SEED:#x}) : {e}\n{code}");
+        }
+        checked += 1;
+    }
+
+    assert!(checked > 1000, "only {checked} programs halted");
+}
+
+#[test]
+fn every_opt_level_agrees_on_common_loops() {
+    let programs: [(&str, &[u8]); 5] = [
+        ("+++++[-]>++[+]<.", b""), // Clear loops.
+        ("+++[>++++<-]>[>+++<-]>.", b""), // Multiply loops.
+        (",[>+>+<<-]>>[<<+>>-]<<.>.", b"A"), // Copy through a temporary cell.
+        ("++++++++[>++++[>++>+++<<-]>+<<-]>>.>.", b""), // Nested multiplies.
+        (",[.,]", b"echo\0"),
+    ];
+
+    for (code, input) in programs {
+        if let Err(e) = verify::run_all_opt_levels(code, input) {
+            panic!("{code} : {e}");
+        }
+    }
+
+    assert!(matches!(verify::run_all_opt_levels("[", b""), Err(VerifyError::Load(_))));
+}